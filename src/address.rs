@@ -0,0 +1,308 @@
+//! Address encoding and format conversion
+//!
+//! This module converts addresses between the alternative encodings used by
+//! the supported networks (Tron hex/base58, BCH CashAddr/legacy, XRP classic/
+//! X-address, EVM lowercase/EIP-55), sharing the Base58Check primitives used
+//! by address validation elsewhere in the crate. It also fully decodes
+//! Bitcoin addresses (`decode_bitcoin`): the Base58Check checksum of legacy
+//! and P2SH addresses, and the Bech32/Bech32m checksum of segwit ones, so a
+//! typo is caught before any request is made. The checksum rules live in
+//! `core::address`, which builds without std, and are re-exported here.
+
+pub use crate::core::address::{decode_base58check, decode_bitcoin, decode_segwit, encode_base58check, to_checksum_address, BitcoinParams, ScriptType};
+use crate::core::address::evm_hex_body;
+use crate::WalletError;
+
+const BITCOIN_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const XRP_ALPHABET: &[u8; 58] = b"rpshnaf39wBUDNEGHJKLM4PQRST7VWXYZ2bcdeCg65jkm8oFqi1tuvAxyz";
+
+const CASHADDR_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const CASHADDR_PREFIX: &str = "bitcoincash";
+
+/// X-address prefix bytes for XRP Ledger mainnet (XLS-5d)
+const XADDRESS_MAINNET_PREFIX: [u8; 2] = [0x05, 0x44];
+
+/// Target format for `convert`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressFormat {
+    /// Tron Base58Check address (`T...`)
+    TronBase58,
+    /// Tron hex address (`41...`)
+    TronHex,
+    /// Bitcoin Cash CashAddr (`bitcoincash:q...`)
+    CashAddr,
+    /// Bitcoin Cash legacy Base58Check (`1...`/`3...`)
+    BchLegacy,
+    /// XRP classic address (`r...`)
+    XrpClassic,
+    /// XRP X-address with optional destination tag (`X...`)
+    XAddress,
+    /// EVM address with EIP-55 mixed-case checksum
+    Eip55,
+    /// EVM address in lowercase hex
+    EvmLowercase,
+}
+
+impl std::str::FromStr for AddressFormat {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, WalletError> {
+        match s.to_lowercase().as_str() {
+            "tron-base58" | "tron" => Ok(AddressFormat::TronBase58),
+            "tron-hex" => Ok(AddressFormat::TronHex),
+            "cashaddr" => Ok(AddressFormat::CashAddr),
+            "bch-legacy" | "legacy" => Ok(AddressFormat::BchLegacy),
+            "xrp-classic" | "classic" => Ok(AddressFormat::XrpClassic),
+            "x-address" | "xaddress" => Ok(AddressFormat::XAddress),
+            "eip55" | "checksum" => Ok(AddressFormat::Eip55),
+            "lowercase" | "evm-lowercase" => Ok(AddressFormat::EvmLowercase),
+            _ => Err(WalletError::ParseError(format!("Unsupported address format: {}", s))),
+        }
+    }
+}
+
+/// Convert an address to the requested format
+///
+/// # Arguments
+///
+/// * `address` - Address in the counterpart encoding of `target`
+/// * `target` - Desired output format
+/// * `tag` - Destination tag to embed when producing an X-address
+///
+/// # Returns
+///
+/// Returns the converted address; XRP X-address input is returned as the
+/// classic address followed by ` (tag N)` when a tag is present
+pub fn convert(address: &str, target: AddressFormat, tag: Option<u32>) -> Result<String, WalletError> {
+    let address = address.trim();
+    match target {
+        AddressFormat::TronBase58 => tron_hex_to_base58(address),
+        AddressFormat::TronHex => tron_base58_to_hex(address),
+        AddressFormat::CashAddr => bch_legacy_to_cashaddr(address),
+        AddressFormat::BchLegacy => bch_cashaddr_to_legacy(address),
+        AddressFormat::XAddress => xrp_classic_to_xaddress(address, tag),
+        AddressFormat::XrpClassic => {
+            let (classic, tag) = xrp_xaddress_to_classic(address)?;
+            Ok(match tag {
+                Some(tag) => format!("{} (tag {})", classic, tag),
+                None => classic,
+            })
+        }
+        AddressFormat::Eip55 => to_checksum_address(address).map_err(WalletError::invalid_address),
+        AddressFormat::EvmLowercase => {
+            let hex = evm_hex_body(address).map_err(WalletError::invalid_address)?;
+            Ok(format!("0x{}", hex.to_lowercase()))
+        }
+    }
+}
+
+/// Convert a Tron hex address (`41` + 20 bytes, or a 0x-prefixed EVM address) to Base58Check
+pub fn tron_hex_to_base58(address: &str) -> Result<String, WalletError> {
+    let hex_str = address.trim_start_matches("0x");
+    let bytes = hex::decode(hex_str)
+        .map_err(|_| WalletError::InvalidAddress("Tron hex address contains invalid hex characters".to_string()))?;
+
+    let payload = match bytes.len() {
+        21 if bytes[0] == 0x41 => bytes[1..].to_vec(),
+        20 => bytes,
+        _ => return Err(WalletError::InvalidAddress("Invalid Tron hex address length".to_string())),
+    };
+
+    Ok(encode_base58check(&[0x41], &payload))
+}
+
+/// Convert a Tron Base58Check address to its `41`-prefixed hex form
+pub fn tron_base58_to_hex(address: &str) -> Result<String, WalletError> {
+    let data = decode_base58check(address).map_err(WalletError::invalid_address)?;
+    if data.len() != 21 || data[0] != 0x41 {
+        return Err(WalletError::InvalidAddress("Invalid Tron address: expected 0x41 version byte".to_string()));
+    }
+
+    Ok(hex::encode(data))
+}
+
+/// Convert a Bitcoin Cash legacy address to CashAddr
+pub fn bch_legacy_to_cashaddr(address: &str) -> Result<String, WalletError> {
+    let data = decode_base58check(address).map_err(WalletError::invalid_address)?;
+    if data.len() != 21 {
+        return Err(WalletError::InvalidAddress("Invalid legacy address length".to_string()));
+    }
+
+    // CashAddr version byte: type bits (0 = P2PKH, 1 = P2SH) and size code 0 (160 bits)
+    let version_byte = match data[0] {
+        0x00 => 0u8,
+        0x05 => 1u8 << 3,
+        other => return Err(WalletError::InvalidAddress(format!("Unsupported legacy version byte {:#04x}", other))),
+    };
+
+    let mut payload = vec![version_byte];
+    payload.extend_from_slice(&data[1..]);
+    let mut words = convert_bits(&payload, 8, 5, true)?;
+
+    let checksum = cashaddr_polymod(&cashaddr_checksum_input(CASHADDR_PREFIX, &words));
+    for i in 0..8 {
+        words.push(((checksum >> (5 * (7 - i))) & 0x1f) as u8);
+    }
+
+    let encoded: String = words.iter().map(|w| CASHADDR_CHARSET[*w as usize] as char).collect();
+    Ok(format!("{}:{}", CASHADDR_PREFIX, encoded))
+}
+
+/// Convert a Bitcoin Cash CashAddr address (with or without prefix) to legacy format
+pub fn bch_cashaddr_to_legacy(address: &str) -> Result<String, WalletError> {
+    let lower = address.to_lowercase();
+    if lower != address && address.to_uppercase() != address {
+        return Err(WalletError::InvalidAddress("CashAddr must not mix upper and lower case".to_string()));
+    }
+    let body = lower.strip_prefix("bitcoincash:").unwrap_or(&lower);
+
+    let words = body
+        .bytes()
+        .map(|c| {
+            CASHADDR_CHARSET
+                .iter()
+                .position(|x| *x == c)
+                .map(|p| p as u8)
+                .ok_or_else(|| WalletError::InvalidAddress(format!("Invalid CashAddr character '{}'", c as char)))
+        })
+        .collect::<Result<Vec<u8>, WalletError>>()?;
+
+    if words.len() < 9 || cashaddr_polymod(&cashaddr_checksum_input_raw(CASHADDR_PREFIX, &words)) != 0 {
+        return Err(WalletError::InvalidAddress("Invalid CashAddr checksum".to_string()));
+    }
+
+    let payload = convert_bits(&words[..words.len() - 8], 5, 8, false)?;
+    if payload.len() != 21 {
+        return Err(WalletError::InvalidAddress("Unsupported CashAddr payload size".to_string()));
+    }
+
+    let version = match payload[0] {
+        0x00 => 0x00,
+        0x08 => 0x05,
+        other => return Err(WalletError::InvalidAddress(format!("Unsupported CashAddr version byte {:#04x}", other))),
+    };
+
+    Ok(encode_base58check(&[version], &payload[1..]))
+}
+
+/// Convert an XRP classic address to a mainnet X-address
+pub fn xrp_classic_to_xaddress(address: &str, tag: Option<u32>) -> Result<String, WalletError> {
+    let data = decode_base58check(&translate_alphabet(address, XRP_ALPHABET, BITCOIN_ALPHABET)?)
+        .map_err(WalletError::invalid_address)?;
+    if data.len() != 21 || data[0] != 0x00 {
+        return Err(WalletError::InvalidAddress("Invalid XRP classic address".to_string()));
+    }
+
+    let mut payload = data[1..].to_vec();
+    payload.push(u8::from(tag.is_some()));
+    payload.extend_from_slice(&u64::from(tag.unwrap_or(0)).to_le_bytes());
+
+    translate_alphabet(
+        &encode_base58check(&XADDRESS_MAINNET_PREFIX, &payload),
+        BITCOIN_ALPHABET,
+        XRP_ALPHABET,
+    )
+}
+
+/// Convert a mainnet X-address to its classic address and destination tag
+pub fn xrp_xaddress_to_classic(address: &str) -> Result<(String, Option<u32>), WalletError> {
+    let data = decode_base58check(&translate_alphabet(address, XRP_ALPHABET, BITCOIN_ALPHABET)?)
+        .map_err(WalletError::invalid_address)?;
+    if data.len() != 31 || data[..2] != XADDRESS_MAINNET_PREFIX {
+        return Err(WalletError::InvalidAddress("Invalid or non-mainnet X-address".to_string()));
+    }
+
+    let tag_value = u64::from_le_bytes(data[23..31].try_into().expect("slice is 8 bytes"));
+    let tag = match data[22] {
+        0 if tag_value == 0 => None,
+        1 => Some(
+            u32::try_from(tag_value)
+                .map_err(|_| WalletError::InvalidAddress("X-address tag exceeds 32 bits".to_string()))?,
+        ),
+        _ => return Err(WalletError::InvalidAddress("Invalid X-address tag flag".to_string())),
+    };
+
+    let classic = translate_alphabet(
+        &encode_base58check(&[0x00], &data[2..22]),
+        BITCOIN_ALPHABET,
+        XRP_ALPHABET,
+    )?;
+    Ok((classic, tag))
+}
+
+/// Map a base58 string between alphabets (both have 58 symbols in value order)
+fn translate_alphabet(input: &str, from: &[u8; 58], to: &[u8; 58]) -> Result<String, WalletError> {
+    input
+        .bytes()
+        .map(|c| {
+            from.iter()
+                .position(|x| *x == c)
+                .map(|p| to[p] as char)
+                .ok_or_else(|| WalletError::InvalidAddress(format!("Invalid Base58 character '{}'", c as char)))
+        })
+        .collect()
+}
+
+/// Regroup a byte slice from `from`-bit to `to`-bit words
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Result<Vec<u8>, WalletError> {
+    let mut acc: u32 = 0;
+    let mut bits: u32 = 0;
+    let mut out = Vec::new();
+    let max = (1u32 << to) - 1;
+
+    for value in data {
+        acc = (acc << from) | u32::from(*value);
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(((acc >> bits) & max) as u8);
+        }
+    }
+
+    if pad {
+        if bits > 0 {
+            out.push(((acc << (to - bits)) & max) as u8);
+        }
+    } else if bits >= from || ((acc << (to - bits)) & max) != 0 {
+        return Err(WalletError::InvalidAddress("Invalid padding in address payload".to_string()));
+    }
+
+    Ok(out)
+}
+
+/// Checksum input for encoding: prefix, separator, payload and 8 zeroed checksum words
+fn cashaddr_checksum_input(prefix: &str, words: &[u8]) -> Vec<u8> {
+    let mut input = cashaddr_checksum_input_raw(prefix, words);
+    input.extend_from_slice(&[0u8; 8]);
+    input
+}
+
+fn cashaddr_checksum_input_raw(prefix: &str, words: &[u8]) -> Vec<u8> {
+    let mut input: Vec<u8> = prefix.bytes().map(|c| c & 0x1f).collect();
+    input.push(0);
+    input.extend_from_slice(words);
+    input
+}
+
+/// BCH CashAddr 40-bit BCH code checksum
+fn cashaddr_polymod(values: &[u8]) -> u64 {
+    const GENERATORS: [u64; 5] = [
+        0x98_f2bc_8e61,
+        0x79_b76d_99e2,
+        0xf3_3e5f_b3c4,
+        0xae_2eab_e2a8,
+        0x1e_4f43_e470,
+    ];
+
+    let mut c: u64 = 1;
+    for value in values {
+        let c0 = (c >> 35) as u8;
+        c = ((c & 0x07_ffff_ffff) << 5) ^ u64::from(*value);
+        for (i, generator) in GENERATORS.iter().enumerate() {
+            if c0 & (1 << i) != 0 {
+                c ^= generator;
+            }
+        }
+    }
+    c ^ 1
+}
@@ -0,0 +1,70 @@
+//! Address book and lookalike (address poisoning) detection
+//!
+//! Address poisoning scams send a worthless transfer from an address that
+//! shares the first and last characters of one the victim uses, hoping it is
+//! copied from the transaction history next time. Wallet UIs often show only
+//! those characters, and so do people checking by eye. A balance check is
+//! often the step right before sending, so the CLI warns when the address
+//! looked up matches an `[[address_book]]` entry at both ends but differs in
+//! the middle.
+
+use crate::{config, Network};
+
+/// Characters compared at each end of an address, after its fixed prefix
+pub const LOOKALIKE_ENDS: usize = 4;
+
+/// Human-readable parts of the bech32 addresses of the Bitcoin-family networks
+const BECH32_PREFIXES: [&str; 5] = ["bc1", "tb1", "bcrt1", "ltc1", "tltc1"];
+
+/// One `[[address_book]]` entry: an address the user sends to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookEntry {
+    pub network: Network,
+    pub address: String,
+    pub name: String,
+}
+
+/// The part of `address` that differs between addresses, lowercased for hex EVM addresses
+///
+/// Strips what every address of its kind starts with: `0x`, or a bech32
+/// prefix and witness version (`bc1q`, `ltc1p`, ...).
+pub(crate) fn significant(network: Network, address: &str) -> String {
+    let address = address.trim();
+    if network.is_evm() {
+        let address = address.to_lowercase();
+        return address.strip_prefix("0x").unwrap_or(&address).to_string();
+    }
+    let lower = address.to_lowercase();
+    match BECH32_PREFIXES.iter().find(|prefix| lower.starts_with(*prefix)) {
+        Some(prefix) => lower.get(prefix.len() + 1..).unwrap_or_default().to_string(),
+        None => address.to_string(),
+    }
+}
+
+/// Whether `candidate` shares `LOOKALIKE_ENDS` characters at both ends with `known` yet is a different address
+pub fn is_lookalike(network: Network, candidate: &str, known: &str) -> bool {
+    let (candidate, known) = (significant(network, candidate), significant(network, known));
+    if candidate == known || candidate.len() != known.len() || candidate.len() <= 2 * LOOKALIKE_ENDS {
+        return false;
+    }
+    let (candidate, known) = (candidate.as_bytes(), known.as_bytes());
+    let tail = candidate.len() - LOOKALIKE_ENDS;
+    candidate[..LOOKALIKE_ENDS] == known[..LOOKALIKE_ENDS] && candidate[tail..] == known[tail..]
+}
+
+/// The first of `entries` on `network` that `address` is a lookalike of
+pub fn find_lookalike_in<'a>(
+    entries: impl IntoIterator<Item = &'a BookEntry>,
+    network: Network,
+    address: &str,
+) -> Option<&'a BookEntry> {
+    entries
+        .into_iter()
+        .find(|entry| entry.network == network && is_lookalike(network, address, &entry.address))
+}
+
+/// The configured address book entry `address` on `network` imitates, if any
+pub fn find_lookalike(network: Network, address: &str) -> Option<BookEntry> {
+    let book = config::active().address_book().unwrap_or_default();
+    find_lookalike_in(&book, network, address).cloned()
+}
@@ -0,0 +1,138 @@
+//! Vanity and burn address heuristics
+//!
+//! Reserve reports and exchange batches are often assembled from address
+//! lists nobody reads character by character. Two kinds of address deserve a
+//! second look there:
+//!
+//! - Burn addresses, which no key controls: coins sent to them are gone, so
+//!   counting them towards reserves overstates what can be spent. Besides the
+//!   well-known ones in `KNOWN_BURN`, an address that is mostly one repeated
+//!   character (`0x000…dEaD`, `1111…4oLvT2`, `bc1qqqq…`) cannot come from a
+//!   key and is taken for one.
+//! - Vanity addresses, whose long run of one character at an end (or inside)
+//!   took a vanity search to find. They are spendable, but keys from weak
+//!   vanity generators such as Profanity have been recovered by attackers, so
+//!   funds held there are at risk.
+//!
+//! A run inside the address must be two characters longer. Run lengths are set
+//! per alphabet so that a randomly generated address is flagged about once in
+//! a million or less.
+
+use crate::address_book::significant;
+use crate::Network;
+
+/// A well-known burn address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BurnAddress {
+    /// Network of the address, or `None` for one that burns on every EVM network
+    pub network: Option<Network>,
+    pub address: &'static str,
+    pub name: &'static str,
+}
+
+const fn burn(network: Option<Network>, address: &'static str, name: &'static str) -> BurnAddress {
+    BurnAddress { network, address, name }
+}
+
+/// Burn addresses that the repeated-character rule does not catch
+pub const KNOWN_BURN: &[BurnAddress] = &[
+    burn(Some(Network::Bitcoin), "1BitcoinEaterAddressDontSendf59kuE", "Bitcoin Eater"),
+    burn(Some(Network::Bitcoin), "1CounterpartyXXXXXXXXXXXXXXXUWLpVr", "Counterparty burn"),
+    burn(Some(Network::Tron), "T9yD14Nj9j7xAB4dbGeiX9h8unkKHxuWwb", "Tron zero address"),
+    burn(Some(Network::Tron), "TLsV52sRDL79HXGGm9yzwKibb6BeruhUzy", "Tron black hole"),
+    burn(None, "0xdEAD000000000000000042069420694206942069", "Shiba Inu burn"),
+];
+
+/// Why an address was flagged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressFlag {
+    /// One of `KNOWN_BURN`, by name
+    KnownBurn(&'static str),
+    /// At least half the address is `run` repetitions of `character`
+    ProbableBurn { character: char, run: usize },
+    /// A run of `run` repetitions of `character` only a vanity search finds
+    Vanity { character: char, run: usize },
+}
+
+impl AddressFlag {
+    /// Whether funds sent to the address are unspendable, so it should stay out of totals
+    pub fn is_burn(&self) -> bool {
+        !matches!(self, AddressFlag::Vanity { .. })
+    }
+}
+
+impl std::fmt::Display for AddressFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressFlag::KnownBurn(name) => write!(f, "known burn address ({})", name),
+            AddressFlag::ProbableBurn { character, run } => {
+                write!(f, "probable burn address ({} repeated '{}' characters)", run, character)
+            }
+            AddressFlag::Vanity { character, run } => {
+                write!(f, "vanity address ({} repeated '{}' characters)", run, character)
+            }
+        }
+    }
+}
+
+/// Addresses shorter than this, after their fixed prefix, are not any supported kind and are left alone
+const MIN_LENGTH: usize = 20;
+
+/// Shortest run of one character at the start or end of an address that counts as vanity
+///
+/// A hex EVM address draws from 16 characters; bech32 (32) and Base58 (58)
+/// make a run of the same length rarer.
+fn vanity_run(network: Network) -> usize {
+    match network.is_evm() {
+        true => 6,
+        false => 5,
+    }
+}
+
+/// The flag `address` on `network` deserves, if any; a burn address is not also reported as vanity
+pub fn inspect(network: Network, address: &str) -> Option<AddressFlag> {
+    let same = |known: &str| match network.is_evm() {
+        true => known.eq_ignore_ascii_case(address.trim()),
+        false => known == address.trim(),
+    };
+    let known = KNOWN_BURN.iter().find(|entry| {
+        entry.network.map_or(network.is_evm(), |burns_on| burns_on == network) && same(entry.address)
+    });
+    if let Some(entry) = known {
+        return Some(AddressFlag::KnownBurn(entry.name));
+    }
+
+    let payload = significant(network, address);
+    if payload.chars().count() < MIN_LENGTH {
+        return None;
+    }
+    let runs = runs(&payload);
+    let (character, run) = runs.iter().copied().max_by_key(|(_, run)| *run)?;
+    if run * 2 >= payload.chars().count() {
+        return Some(AddressFlag::ProbableBurn { character, run });
+    }
+
+    let threshold = vanity_run(network);
+    let at_end = [runs.first(), runs.last()]
+        .into_iter()
+        .flatten()
+        .filter(|(_, run)| *run >= threshold)
+        .max_by_key(|(_, run)| *run);
+    match at_end {
+        Some(&(character, run)) => Some(AddressFlag::Vanity { character, run }),
+        None if run >= threshold + 2 => Some(AddressFlag::Vanity { character, run }),
+        None => None,
+    }
+}
+
+/// Runs of one character in `text`, in order
+fn runs(text: &str) -> Vec<(char, usize)> {
+    let mut runs: Vec<(char, usize)> = Vec::new();
+    for character in text.chars() {
+        match runs.last_mut() {
+            Some((last, run)) if *last == character => *run += 1,
+            _ => runs.push((character, 1)),
+        }
+    }
+    runs
+}
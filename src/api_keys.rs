@@ -0,0 +1,164 @@
+//! API keys of the HTTP server: who may call it, how often and for which networks
+//!
+//! With `[[api_keys]]` entries in the config file, `serve --listen` answers
+//! `/balance` and `/graphql` only for requests that present one of the keys,
+//! as `Authorization: Bearer <key>` or `X-API-Key: <key>`. Each key can be
+//! held to a request rate and to a list of networks, so one instance can serve
+//! several teams without one of them using up the providers' limits for the
+//! rest. `/healthz` and `/readyz` stay open for the orchestrator, and without
+//! any entries the server is open as before.
+//!
+//! Quotas are token buckets: a key may burst up to `requests_per_minute`
+//! requests and regains them evenly over the minute. A GraphQL request counts
+//! once, however many fields it asks for.
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::Network;
+
+/// One key and what it may do
+#[derive(Clone, PartialEq, Eq)]
+pub struct ApiKey {
+    /// Name of the team or job holding the key, shown in errors instead of the key
+    pub name: String,
+    pub key: String,
+    /// Requests allowed per minute; unlimited when `None`
+    pub requests_per_minute: Option<u32>,
+    /// Networks the key may query; every network when `None`
+    pub networks: Option<Vec<Network>>,
+}
+
+impl std::fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("name", &self.name)
+            .field("requests_per_minute", &self.requests_per_minute)
+            .field("networks", &self.networks)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ApiKey {
+    /// Whether the key may query `network`
+    pub fn allows(&self, network: Network) -> bool {
+        self.networks.as_ref().is_none_or(|networks| networks.contains(&network))
+    }
+}
+
+/// Why a request was turned away
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Denied {
+    /// Keys are configured and the request presented none
+    MissingKey,
+    /// The presented key is not one of the configured keys
+    UnknownKey,
+    /// The key has used up its quota; a request succeeds again after `retry_after`
+    QuotaExceeded { name: String, retry_after: Duration },
+}
+
+impl std::fmt::Display for Denied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Denied::MissingKey => write!(f, "An API key is required (Authorization: Bearer <key> or X-API-Key)"),
+            Denied::UnknownKey => write!(f, "Unknown API key"),
+            Denied::QuotaExceeded { name, retry_after } => write!(
+                f,
+                "Quota of API key '{}' exceeded, retry in {}s",
+                name,
+                retry_after.as_secs_f64().ceil()
+            ),
+        }
+    }
+}
+
+/// Requests a key has left, and when that was last worked out
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    keys: Vec<ApiKey>,
+    /// Buckets by key name, so a reload that keeps a key keeps what it has used
+    buckets: HashMap<String, Bucket>,
+}
+
+/// The server's keys and the quota each has left; clones share both
+#[derive(Debug, Clone, Default)]
+pub struct Tenants {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Tenants {
+    /// Require one of `keys`; with none, every request is let through
+    pub fn new(keys: Vec<ApiKey>) -> Self {
+        let tenants = Tenants::default();
+        tenants.set_keys(keys);
+        tenants
+    }
+
+    /// Replace the keys, as after a config reload; keys that stay keep their remaining quota
+    pub fn set_keys(&self, keys: Vec<ApiKey>) {
+        let mut inner = self.lock();
+        inner.buckets.retain(|name, _| keys.iter().any(|key| key.name == *name));
+        inner.keys = keys;
+    }
+
+    /// Number of configured keys; 0 when the server is open
+    pub fn len(&self) -> usize {
+        self.lock().keys.len()
+    }
+
+    /// Whether no keys are configured, so the server is open
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Admit a request that presented `key`, taking one request from its quota
+    ///
+    /// Returns the matching key, or `None` when the server is open.
+    pub fn admit(&self, key: Option<&str>) -> Result<Option<ApiKey>, Denied> {
+        let mut inner = self.lock();
+        if inner.keys.is_empty() {
+            return Ok(None);
+        }
+        let presented = Sha256::digest(key.ok_or(Denied::MissingKey)?.as_bytes());
+        // Comparing digests keeps the time taken from telling how much of a key matched
+        let matched = inner
+            .keys
+            .iter()
+            .find(|candidate| Sha256::digest(candidate.key.as_bytes()) == presented)
+            .cloned()
+            .ok_or(Denied::UnknownKey)?;
+
+        if let Some(per_minute) = matched.requests_per_minute {
+            let capacity = f64::from(per_minute);
+            let per_second = capacity / 60.0;
+            let now = Instant::now();
+            let bucket = inner.buckets.entry(matched.name.clone()).or_insert(Bucket {
+                tokens: capacity,
+                updated: now,
+            });
+            let refilled = now.duration_since(bucket.updated).as_secs_f64() * per_second;
+            bucket.tokens = (bucket.tokens + refilled).min(capacity);
+            bucket.updated = now;
+            if bucket.tokens < 1.0 {
+                return Err(Denied::QuotaExceeded {
+                    name: matched.name,
+                    retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) / per_second),
+                });
+            }
+            bucket.tokens -= 1.0;
+        }
+        Ok(Some(matched))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
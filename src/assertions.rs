@@ -0,0 +1,269 @@
+//! Balance assertions: wallet invariants checked by `check --assertions <FILE>`
+//!
+//! An assertions file lists wallets with the bounds their native balance must
+//! stay within, and optionally how far behind the chain tip the provider may
+//! be. Keeping the file in a repository lets every deploy verify, say, that
+//! the hot wallet is funded and the fee payer is not drained:
+//!
+//! ```toml
+//! [[assertions]]
+//! name = "Hot wallet"
+//! network = "ethereum"
+//! address = "0xd8dA6BF26964aF9D7eed9e03E53415D37aA96045"
+//! min = "0.5"
+//! max = "25"
+//! max_staleness = "5m"
+//! ```
+//!
+//! `min` and `max` are inclusive amounts in the network's denomination.
+//! `max_staleness` bounds the age of the newest block the provider knows,
+//! as `30s`, `10m`, `1h` or a bare number of seconds.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{chain_stats, units, watch, BalanceSource, Network, WalletError};
+
+/// One invariant of an assertions file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assertion {
+    /// Name shown in reports; the address when the file gives none
+    pub name: String,
+    pub network: Network,
+    pub address: String,
+    /// Smallest allowed balance, in base units
+    pub min: Option<u128>,
+    /// Largest allowed balance, in base units
+    pub max: Option<u128>,
+    /// Oldest the provider's newest block may be
+    pub max_staleness: Option<Duration>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawAssertion {
+    name: Option<String>,
+    network: String,
+    address: String,
+    min: Option<String>,
+    max: Option<String>,
+    max_staleness: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawFile {
+    #[serde(default)]
+    assertions: Vec<RawAssertion>,
+}
+
+/// Parse the TOML text of an assertions file
+pub fn from_toml(text: &str) -> Result<Vec<Assertion>, WalletError> {
+    let raw: RawFile = toml::from_str(text).context("Invalid assertions file")?;
+    if raw.assertions.is_empty() {
+        return Err(WalletError::ParseError("The file lists no assertions".to_string()));
+    }
+    let assertions = raw
+        .assertions
+        .into_iter()
+        .enumerate()
+        .map(|(index, raw)| validate(raw).with_context(|| format!("Assertion {}", index + 1)))
+        .collect::<Result<_>>()?;
+    Ok(assertions)
+}
+
+/// Read the assertions file at `path`
+pub fn load(path: &Path) -> Result<Vec<Assertion>, WalletError> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(from_toml(&text).with_context(|| path.display().to_string())?)
+}
+
+fn validate(raw: RawAssertion) -> Result<Assertion> {
+    let network: Network = raw.network.parse().context("Invalid network")?;
+    let amount = |value: Option<String>, field: &str| {
+        value
+            .map(|value| {
+                units::parse_units(value.trim(), network.decimals())
+                    .with_context(|| format!("Invalid {} amount: {}", field, value))
+            })
+            .transpose()
+    };
+    let min = amount(raw.min, "min")?;
+    let max = amount(raw.max, "max")?;
+    let max_staleness = raw
+        .max_staleness
+        .map(|value| watch::parse_interval(&value).context("Invalid max_staleness"))
+        .transpose()?;
+    if min.is_none() && max.is_none() && max_staleness.is_none() {
+        return Err(anyhow::anyhow!("Nothing to check (set min, max or max_staleness)"));
+    }
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            return Err(anyhow::anyhow!("min is greater than max"));
+        }
+    }
+    let address = raw.address.trim().to_string();
+    let name = raw
+        .name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| address.clone());
+    Ok(Assertion {
+        name,
+        network,
+        address,
+        min,
+        max,
+        max_staleness,
+    })
+}
+
+/// Result of checking one assertion
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AssertionResult {
+    pub name: String,
+    pub network: String,
+    pub address: String,
+    pub passed: bool,
+    /// Balance read, when the lookup succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<String>,
+    pub denomination: String,
+    /// Age of the provider's newest block in seconds, when `max_staleness` was checked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tip_age_secs: Option<u64>,
+    /// Why the assertion failed; empty when it passed
+    pub failures: Vec<String>,
+}
+
+/// Outcome of a whole assertions file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Report {
+    /// Whether every assertion held
+    pub passed: bool,
+    pub assertions: Vec<AssertionResult>,
+}
+
+/// Check `assertions` against balances from `source`
+///
+/// Balances are fetched concurrently, and the chain tip of each network with
+/// a `max_staleness` is read once. A failed lookup fails its assertion rather
+/// than the whole check. Staleness needs the live providers: other sources
+/// fail assertions that set it.
+pub async fn evaluate(source: &BalanceSource, assertions: &[Assertion]) -> Report {
+    let queries: Vec<(Network, String)> = assertions
+        .iter()
+        .map(|assertion| (assertion.network, assertion.address.clone()))
+        .collect();
+    let (balances, tip_ages) = tokio::join!(source.get_balances(&queries, 0), tip_ages(source, assertions));
+
+    let results: Vec<AssertionResult> = assertions
+        .iter()
+        .zip(balances)
+        .map(|(assertion, balance)| check(assertion, balance.map(|balance| balance.balance), &tip_ages))
+        .collect();
+    Report {
+        passed: results.iter().all(|result| result.passed),
+        assertions: results,
+    }
+}
+
+/// Seconds since the newest block of each network some assertion bounds the staleness of
+async fn tip_ages(source: &BalanceSource, assertions: &[Assertion]) -> HashMap<Network, Result<u64, String>> {
+    let mut networks: Vec<Network> = assertions
+        .iter()
+        .filter(|assertion| assertion.max_staleness.is_some())
+        .map(|assertion| assertion.network)
+        .collect();
+    networks.sort();
+    networks.dedup();
+
+    let mut ages = HashMap::new();
+    for network in networks {
+        let age = match source {
+            BalanceSource::Live => chain_stats::tip_time(network)
+                .await
+                .map(|tip| {
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+                    now.saturating_sub(tip)
+                })
+                .map_err(|e| format!("{:#}", e)),
+            _ => Err("max_staleness needs the live providers".to_string()),
+        };
+        ages.insert(network, age);
+    }
+    ages
+}
+
+fn check(
+    assertion: &Assertion,
+    balance: Result<String, WalletError>,
+    tip_ages: &HashMap<Network, Result<u64, String>>,
+) -> AssertionResult {
+    let decimals = assertion.network.decimals();
+    let denomination = assertion.network.denomination();
+    let mut failures = Vec::new();
+
+    let balance = match balance {
+        Ok(balance) => Some(balance),
+        Err(e) => {
+            failures.push(format!("Lookup failed: {:#}", e));
+            None
+        }
+    };
+    if let Some(balance) = &balance {
+        match units::parse_units(balance, decimals) {
+            Ok(held) => {
+                if let Some(min) = assertion.min.filter(|&min| held < min) {
+                    failures.push(format!(
+                        "Balance {} {} is below the minimum of {}",
+                        balance,
+                        denomination,
+                        units::format_units(min, decimals)
+                    ));
+                }
+                if let Some(max) = assertion.max.filter(|&max| held > max) {
+                    failures.push(format!(
+                        "Balance {} {} is above the maximum of {}",
+                        balance,
+                        denomination,
+                        units::format_units(max, decimals)
+                    ));
+                }
+            }
+            Err(e) => failures.push(format!("Unreadable balance {}: {:#}", balance, e)),
+        }
+    }
+
+    let mut tip_age_secs = None;
+    if let Some(max_staleness) = assertion.max_staleness {
+        match tip_ages.get(&assertion.network) {
+            Some(Ok(age)) => {
+                tip_age_secs = Some(*age);
+                if *age > max_staleness.as_secs() {
+                    failures.push(format!(
+                        "Newest block is {}s old, more than the allowed {}s",
+                        age,
+                        max_staleness.as_secs()
+                    ));
+                }
+            }
+            Some(Err(e)) => failures.push(format!("Staleness check failed: {}", e)),
+            None => failures.push("Staleness check failed: no chain tip".to_string()),
+        }
+    }
+
+    AssertionResult {
+        name: assertion.name.clone(),
+        network: assertion.network.to_string(),
+        address: assertion.address.clone(),
+        passed: failures.is_empty(),
+        balance,
+        denomination: denomination.to_string(),
+        tip_age_secs,
+        failures,
+    }
+}
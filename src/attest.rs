@@ -0,0 +1,254 @@
+//! Proof-of-reserves attestation
+//!
+//! An attestation commits to a list of `(network, address, balance)` leaves
+//! with a SHA-256 merkle tree. Publishing only the root, and handing each
+//! holder the inclusion proof for their own leaf, lets them check their wallet
+//! was counted without seeing the rest of the list.
+//!
+//! Every leaf is salted with a random 32-byte nonce that appears only in its
+//! own proof. Without it, a holder could confirm a guessed address and
+//! balance against the sibling hashes in their proof.
+//!
+//! Hashing is domain separated (`0x00` for leaves, `0x01` for inner nodes) so a
+//! leaf can never be passed off as a node. An unpaired node at the end of a
+//! level is carried up unchanged rather than duplicated.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::units::{self, U256};
+use crate::{Network, WalletBalance, WalletError};
+
+type Hash = [u8; 32];
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// One attested wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leaf {
+    pub network: Network,
+    pub address: String,
+    /// Balance in base units
+    pub balance: U256,
+}
+
+impl Leaf {
+    /// Build a leaf from a fetched balance
+    pub fn from_balance(network: Network, balance: &WalletBalance) -> Result<Self, WalletError> {
+        Ok(Leaf {
+            network,
+            address: balance.address.clone(),
+            balance: units::parse_units_u256(&balance.balance, network.decimals())
+                .with_context(|| format!("Invalid balance for {}", balance.address))?,
+        })
+    }
+
+    fn hash(&self, nonce: &Hash) -> Hash {
+        leaf_hash(nonce, &self.network.to_string(), &self.address, self.balance)
+    }
+}
+
+/// Which side of the path a sibling hash sits on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Sibling hash needed to recompute one level of the path to the root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofStep {
+    pub side: Side,
+    pub hash: String,
+}
+
+/// Everything a holder needs to check their wallet against a published root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub network: String,
+    pub address: String,
+    /// Balance in base units, as a decimal string
+    pub balance: String,
+    /// Hex salt of this leaf alone; share it only with the wallet's holder
+    pub nonce: String,
+    pub leaf: String,
+    pub proof: Vec<ProofStep>,
+}
+
+/// Merkle root plus one inclusion proof per leaf
+#[derive(Debug, Clone, Serialize)]
+pub struct Attestation {
+    pub root: String,
+    pub leaf_count: usize,
+    /// Sum per network, formatted in whole units (informational, not committed to)
+    pub totals: BTreeMap<String, String>,
+    pub proofs: Vec<InclusionProof>,
+}
+
+/// Parse a wallet list: one `<network> <address>` pair per line
+///
+/// Blank lines and lines starting with `#` are ignored.
+pub fn parse_wallet_list(text: &str) -> Result<Vec<(Network, String)>, WalletError> {
+    let wallets = text
+        .lines()
+        .enumerate()
+        .map(|(number, line)| (number + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(network), Some(address), None) => {
+                    let network = network
+                        .parse()
+                        .with_context(|| format!("Line {}: invalid network", number))?;
+                    Ok((network, address.to_string()))
+                }
+                _ => Err(WalletError::ParseError(format!(
+                    "Line {}: expected '<network> <address>', got '{}'",
+                    number, line
+                ))
+                .into()),
+            }
+        })
+        .collect::<Result<_>>()?;
+    Ok(wallets)
+}
+
+/// Build the merkle tree over `leaves`, in the given order
+pub fn attest(leaves: &[Leaf]) -> Result<Attestation, WalletError> {
+    if leaves.is_empty() {
+        return Err(WalletError::Other("Cannot attest an empty wallet list".to_string()));
+    }
+
+    let random = SystemRandom::new();
+    let nonces: Vec<Hash> = leaves
+        .iter()
+        .map(|_| {
+            let mut nonce = [0u8; 32];
+            random.fill(&mut nonce).map(|()| nonce)
+        })
+        .collect::<Result<_, _>>()
+        .map_err(|_| anyhow::anyhow!("No secure random numbers available"))?;
+    let levels = build_levels(leaves.iter().zip(&nonces).map(|(leaf, nonce)| leaf.hash(nonce)).collect());
+    let root = levels.last().expect("at least one level")[0];
+
+    let mut sums: BTreeMap<Network, U256> = BTreeMap::new();
+    for leaf in leaves {
+        let sum = sums.entry(leaf.network).or_default();
+        *sum = sum.saturating_add(leaf.balance);
+    }
+
+    Ok(Attestation {
+        root: hex::encode(root),
+        leaf_count: leaves.len(),
+        totals: sums
+            .into_iter()
+            .map(|(network, sum)| {
+                (
+                    network.to_string(),
+                    format!("{} {}", units::format_units_u256(sum, network.decimals()), network.denomination()),
+                )
+            })
+            .collect(),
+        proofs: leaves
+            .iter()
+            .enumerate()
+            .map(|(index, leaf)| InclusionProof {
+                network: leaf.network.to_string(),
+                address: leaf.address.clone(),
+                balance: leaf.balance.to_string(),
+                nonce: hex::encode(nonces[index]),
+                leaf: hex::encode(levels[0][index]),
+                proof: proof_path(&levels, index),
+            })
+            .collect(),
+    })
+}
+
+/// Check that `proof` leads from its leaf data to `root` (hex)
+pub fn verify_proof(proof: &InclusionProof, root: &str) -> Result<bool, WalletError> {
+    let balance = U256::from_dec_str(&proof.balance).context("Invalid balance in proof")?;
+    let nonce = decode_hash(&proof.nonce).context("Invalid nonce in proof")?;
+    let leaf = leaf_hash(&nonce, &proof.network, &proof.address, balance);
+    if hex::encode(leaf) != proof.leaf.to_lowercase() {
+        return Ok(false);
+    }
+
+    let mut current = leaf;
+    for step in &proof.proof {
+        let sibling = decode_hash(&step.hash)?;
+        current = match step.side {
+            Side::Left => node_hash(&sibling, &current),
+            Side::Right => node_hash(&current, &sibling),
+        };
+    }
+    Ok(current == decode_hash(root)?)
+}
+
+fn leaf_hash(nonce: &Hash, network: &str, address: &str, balance: U256) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(nonce);
+    hasher.update(network.as_bytes());
+    hasher.update([0]);
+    hasher.update(address.as_bytes());
+    hasher.update([0]);
+    hasher.update(balance.to_string().as_bytes());
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// All tree levels, from the leaves up to the single root
+fn build_levels(leaves: Vec<Hash>) -> Vec<Vec<Hash>> {
+    let mut levels = vec![leaves];
+    while levels.last().expect("at least one level").len() > 1 {
+        let next = levels
+            .last()
+            .expect("at least one level")
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node_hash(left, right),
+                [single] => *single,
+                _ => unreachable!("chunks(2) yields one or two items"),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+fn proof_path(levels: &[Vec<Hash>], mut index: usize) -> Vec<ProofStep> {
+    let mut steps = Vec::new();
+    for level in &levels[..levels.len() - 1] {
+        let sibling = index ^ 1;
+        // A carried-up node has no sibling at this level
+        if let Some(hash) = level.get(sibling) {
+            steps.push(ProofStep {
+                side: if sibling < index { Side::Left } else { Side::Right },
+                hash: hex::encode(hash),
+            });
+        }
+        index /= 2;
+    }
+    steps
+}
+
+fn decode_hash(value: &str) -> Result<Hash> {
+    hex::decode(value.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow::anyhow!("'{}' is not a 32-byte hex hash", value))
+}
@@ -0,0 +1,320 @@
+//! Cold storage audit checklist
+//!
+//! `audit` runs every check worth doing before trusting an address with
+//! savings, or when reviewing one that already holds them: the address parses
+//! and its checksum is valid, its type is what the owner expects, the balance
+//! reads back, nothing is moving, it is neither an exchange wallet nor an
+//! address book lookalike, and an independent explorer agrees on the balance.
+//! Each check passes, warns, fails or is skipped; the audit passes when no
+//! check fails.
+
+use serde::Serialize;
+
+use crate::{address, address_book, bitcoin_wallet, evm_rpc, exchanges, units, verify, BalanceSource, Network};
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    /// Worth a look, but not a reason to fail the audit
+    Warn,
+    Fail,
+    /// Not applicable to the network or the balance source
+    Skip,
+}
+
+/// One line of the checklist
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Check {
+    /// Stable identifier: `address`, `checksum`, `type`, `balance`, `activity`, `screening` or `cross_check`
+    pub id: &'static str,
+    pub status: CheckStatus,
+    /// What was found, in English
+    pub detail: String,
+}
+
+impl Check {
+    fn new(id: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Check {
+            id,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Every check run for one address, in checklist order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditReport {
+    pub network: String,
+    pub address: String,
+    pub passed: bool,
+    pub checks: Vec<Check>,
+}
+
+impl AuditReport {
+    /// Number of checks with `status`
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|check| check.status == status).count()
+    }
+}
+
+/// What an address decodes to, independent of the network's API
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Decoded {
+    Evm,
+    Bech32 { version: u8, program_len: usize },
+    Base58 { version: u8 },
+}
+
+/// Bech32 prefixes of the segwit networks
+const BECH32_PREFIXES: &[&str] = &["bc1", "tb1", "ltc1"];
+
+fn decode(network: Network, address: &str) -> anyhow::Result<Decoded> {
+    if network.is_evm() {
+        return Ok(Decoded::Evm);
+    }
+    let lower = address.to_lowercase();
+    if BECH32_PREFIXES.iter().any(|prefix| lower.starts_with(prefix)) {
+        let (_, version, program) = address::decode_segwit(address)?;
+        return Ok(Decoded::Bech32 {
+            version,
+            program_len: program.len(),
+        });
+    }
+    let data = address::decode_base58check(address)?;
+    Ok(Decoded::Base58 { version: data[0] })
+}
+
+/// Checksum check of the address exactly as the user typed it
+pub fn check_checksum(network: Network, input: &str) -> Check {
+    match decode(network, input) {
+        Ok(Decoded::Evm) => {
+            let body = input.trim_start_matches("0x").trim_start_matches("0X");
+            let letters: Vec<char> = body.chars().filter(char::is_ascii_alphabetic).collect();
+            if letters.iter().all(char::is_ascii_lowercase) || letters.iter().all(char::is_ascii_uppercase) {
+                return Check::new(
+                    "checksum",
+                    CheckStatus::Warn,
+                    "No EIP-55 checksum (single-case address); a typo would go unnoticed",
+                );
+            }
+            match address::to_checksum_address(input) {
+                Ok(expected) if expected[2..] == *body => Check::new("checksum", CheckStatus::Pass, "EIP-55 checksum valid"),
+                Ok(expected) => Check::new(
+                    "checksum",
+                    CheckStatus::Fail,
+                    format!("EIP-55 checksum mismatch (expected {})", expected),
+                ),
+                Err(e) => Check::new("checksum", CheckStatus::Fail, e.to_string()),
+            }
+        }
+        Ok(Decoded::Bech32 { .. }) => Check::new("checksum", CheckStatus::Pass, "Bech32 checksum valid"),
+        Ok(Decoded::Base58 { .. }) => Check::new("checksum", CheckStatus::Pass, "Base58Check checksum valid"),
+        Err(e) => Check::new("checksum", CheckStatus::Fail, e.to_string()),
+    }
+}
+
+/// Script type from the encoding alone; `None` for EVM addresses, which need the chain
+pub fn script_type(network: Network, address: &str) -> Option<String> {
+    let kind = match decode(network, address).ok()? {
+        Decoded::Evm => return None,
+        Decoded::Bech32 { version: 0, program_len: 20 } => "P2WPKH (native segwit)".to_string(),
+        Decoded::Bech32 { version: 0, program_len: 32 } => "P2WSH (native segwit script)".to_string(),
+        Decoded::Bech32 { version: 1, program_len: 32 } => "P2TR (taproot)".to_string(),
+        Decoded::Bech32 { version, .. } => format!("Witness v{} program", version),
+        // Bitcoin, testnet, Litecoin and Dogecoin version bytes
+        Decoded::Base58 { version: 0x00 | 0x6f | 0x30 | 0x1e } => "P2PKH (legacy)".to_string(),
+        Decoded::Base58 { version: 0x05 | 0xc4 | 0x32 | 0x16 } => "P2SH (script hash)".to_string(),
+        Decoded::Base58 { version: 0x41 } => "Tron account".to_string(),
+        Decoded::Base58 { version } => format!("Base58Check version 0x{:02x}", version),
+    };
+    Some(kind)
+}
+
+/// Run the audit of `input` on `network`, reading balances from `source`
+///
+/// Checks that need a block explorer or RPC node (contract detection,
+/// activity, cross-check) are skipped unless `source` is the live one.
+pub async fn run(network: Network, input: &str, source: &BalanceSource) -> AuditReport {
+    // A bad EIP-55 checksum is the checksum check's to report, not a reason to skip the rest
+    let candidate = match network.is_evm() {
+        true => input.to_lowercase(),
+        false => input.to_string(),
+    };
+    let address = match crate::normalize_address(network, &candidate) {
+        Ok(address) => address,
+        Err(e) => {
+            let mut checks = vec![Check::new("address", CheckStatus::Fail, format!("{:#}", e))];
+            for id in ["checksum", "type", "balance", "activity", "screening", "cross_check"] {
+                checks.push(Check::new(id, CheckStatus::Skip, "Address is not valid"));
+            }
+            return report(network, input.to_string(), checks);
+        }
+    };
+    let live = *source == BalanceSource::Live;
+
+    let (kind, balance, activity, cross_check) = tokio::join!(
+        check_type(network, &address, live),
+        check_balance(network, &address, source),
+        check_activity(network, &address, live),
+        check_cross(network, &address, live),
+    );
+    let checks = vec![
+        Check::new("address", CheckStatus::Pass, format!("Valid {} address", network)),
+        check_checksum(network, input),
+        kind,
+        balance,
+        activity,
+        check_screening(network, &address),
+        cross_check,
+    ];
+    report(network, address, checks)
+}
+
+fn report(network: Network, address: String, checks: Vec<Check>) -> AuditReport {
+    AuditReport {
+        network: network.to_string(),
+        address,
+        passed: checks.iter().all(|check| check.status != CheckStatus::Fail),
+        checks,
+    }
+}
+
+fn needs_live(id: &'static str) -> Check {
+    Check::new(id, CheckStatus::Skip, "Needs the live provider")
+}
+
+async fn check_type(network: Network, address: &str, live: bool) -> Check {
+    if let Some(kind) = script_type(network, address) {
+        return Check::new("type", CheckStatus::Pass, kind);
+    }
+    if !network.is_evm() {
+        return Check::new("type", CheckStatus::Skip, "Address does not decode (see the checksum check)");
+    }
+    if !live {
+        return needs_live("type");
+    }
+    let rpc_url = evm_rpc::rpc_url(network).expect("EVM networks have an RPC URL");
+    match evm_rpc::has_code(&rpc_url, address).await {
+        Ok(true) => Check::new("type", CheckStatus::Pass, "Contract account (e.g. a multisig)"),
+        Ok(false) => Check::new("type", CheckStatus::Pass, "Externally owned account"),
+        Err(e) => Check::new("type", CheckStatus::Warn, format!("Could not read contract code: {:#}", e)),
+    }
+}
+
+async fn check_balance(network: Network, address: &str, source: &BalanceSource) -> Check {
+    match source.get_balance(network, address).await {
+        Ok(balance) if units::parse_units(&balance.balance, network.decimals()).is_ok_and(|value| value == 0) => {
+            Check::new("balance", CheckStatus::Warn, format!("Empty: 0 {}", balance.denomination))
+        }
+        Ok(balance) => Check::new("balance", CheckStatus::Pass, format!("{} {}", balance.balance, balance.denomination)),
+        Err(e) => Check::new("balance", CheckStatus::Fail, format!("Could not read the balance: {:#}", e)),
+    }
+}
+
+async fn check_activity(network: Network, address: &str, live: bool) -> Check {
+    if let Some(chain) = network.bitcoin_chain() {
+        if !live {
+            return needs_live("activity");
+        }
+        return match bitcoin_wallet::get_activity_from(&chain.api_base(), address).await {
+            Ok(activity) if activity.unconfirmed_txs > 0 => Check::new(
+                "activity",
+                CheckStatus::Warn,
+                format!(
+                    "{} unconfirmed transaction(s) in the mempool, {} confirmed",
+                    activity.unconfirmed_txs, activity.confirmed_txs
+                ),
+            ),
+            Ok(activity) => Check::new(
+                "activity",
+                CheckStatus::Pass,
+                format!("{} confirmed transaction(s), none pending", activity.confirmed_txs),
+            ),
+            Err(e) => Check::new("activity", CheckStatus::Warn, format!("Could not read activity: {:#}", e)),
+        };
+    }
+    if !network.is_evm() {
+        return Check::new("activity", CheckStatus::Skip, format!("Not available on {}", network));
+    }
+    if !live {
+        return needs_live("activity");
+    }
+    let rpc_url = evm_rpc::rpc_url(network).expect("EVM networks have an RPC URL");
+    let counts = tokio::try_join!(
+        evm_rpc::transaction_count(&rpc_url, address, "latest"),
+        evm_rpc::transaction_count(&rpc_url, address, "pending"),
+    );
+    match counts {
+        Ok((sent, pending)) if pending > sent => Check::new(
+            "activity",
+            CheckStatus::Warn,
+            format!("{} outgoing transaction(s) pending, {} sent", pending - sent, sent),
+        ),
+        Ok((sent, _)) => Check::new(
+            "activity",
+            CheckStatus::Pass,
+            format!("{} outgoing transaction(s) sent, none pending", sent),
+        ),
+        Err(e) => Check::new("activity", CheckStatus::Warn, format!("Could not read activity: {:#}", e)),
+    }
+}
+
+fn check_screening(network: Network, address: &str) -> Check {
+    if let Some(entry) = address_book::find_lookalike(network, address) {
+        return Check::new(
+            "screening",
+            CheckStatus::Fail,
+            format!(
+                "Imitates address book entry \"{}\" ({}): possible address poisoning",
+                entry.name, entry.address
+            ),
+        );
+    }
+    if let Some(exchange) = exchanges::identify(network, address) {
+        return Check::new(
+            "screening",
+            CheckStatus::Warn,
+            format!("Shared {} exchange wallet, not cold storage", exchange),
+        );
+    }
+    Check::new(
+        "screening",
+        CheckStatus::Pass,
+        "Not a known exchange wallet or address book lookalike",
+    )
+}
+
+async fn check_cross(network: Network, address: &str, live: bool) -> Check {
+    if !verify::is_supported(network) {
+        return Check::new("cross_check", CheckStatus::Skip, format!("No independent provider for {}", network));
+    }
+    if !live {
+        return needs_live("cross_check");
+    }
+    let decimals = network.decimals();
+    match verify::verify_balance(network, address).await {
+        Ok(verification) if verification.agrees() => {
+            let at = verification.block.map(|block| format!(" at block {}", block)).unwrap_or_default();
+            Check::new(
+                "cross_check",
+                CheckStatus::Pass,
+                format!("{} and {} agree{}", verification.primary_source, verification.secondary_source, at),
+            )
+        }
+        Ok(verification) => Check::new(
+            "cross_check",
+            CheckStatus::Fail,
+            format!(
+                "{} reports {}, {} reports {}",
+                verification.primary_source,
+                units::format_units_u256(verification.primary, decimals),
+                verification.secondary_source,
+                units::format_units_u256(verification.secondary, decimals)
+            ),
+        ),
+        Err(e) => Check::new("cross_check", CheckStatus::Warn, format!("Could not cross-check: {:#}", e)),
+    }
+}
@@ -0,0 +1,388 @@
+//! Bitcoin HD wallet (BIP32) address derivation
+//!
+//! This module derives addresses from extended public keys (xpub/ypub/zpub and
+//! their testnet counterparts) and simple output descriptors, entirely offline.
+//! `scan` then walks the receive and change chains against Esplora to total
+//! the wallet's balance, stopping each chain after `gap_limit` unused addresses.
+
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use k256::elliptic_curve::ff::PrimeField;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::{FieldBytes, ProjectivePoint, PublicKey, Scalar};
+use ripemd::Ripemd160;
+use serde::Serialize;
+use sha2::{Digest, Sha256, Sha512};
+use std::ops::Range;
+
+use crate::address::{decode_base58check, encode_base58check};
+use crate::bitcoin_wallet::{self, Chain};
+use crate::WalletError;
+
+/// Path template used when a plain extended key is given (external chain)
+pub const DEFAULT_PATH: &str = "0/*";
+
+/// Consecutive unused addresses after which a chain is considered exhausted (BIP44)
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+
+/// Script type of a derived address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AddressType {
+    /// Legacy pay-to-pubkey-hash (BIP44, `1...`)
+    P2pkh,
+    /// Nested segwit pay-to-witness-pubkey-hash in P2SH (BIP49, `3...`)
+    P2shP2wpkh,
+    /// Native segwit pay-to-witness-pubkey-hash (BIP84, `bc1q...`)
+    P2wpkh,
+}
+
+impl std::fmt::Display for AddressType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressType::P2pkh => write!(f, "p2pkh"),
+            AddressType::P2shP2wpkh => write!(f, "p2sh-p2wpkh"),
+            AddressType::P2wpkh => write!(f, "p2wpkh"),
+        }
+    }
+}
+
+/// An address derived from an extended public key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DerivedAddress {
+    /// Derivation path relative to the extended key (e.g. `0/5`)
+    pub path: String,
+    pub address: String,
+    pub address_type: AddressType,
+}
+
+/// Extended public key as defined by BIP32
+#[derive(Debug, Clone)]
+struct ExtendedPubKey {
+    key: PublicKey,
+    chain_code: [u8; 32],
+    testnet: bool,
+}
+
+/// Derive addresses from an extended public key or output descriptor
+///
+/// # Arguments
+///
+/// * `input` - An xpub/ypub/zpub (or tpub/upub/vpub) key, or a descriptor such as
+///   `wpkh([d34db33f/84'/0'/0']xpub.../0/*)`
+/// * `path` - Path template used when `input` does not carry its own; must end in `*`
+/// * `range` - Child indexes substituted for `*`
+///
+/// # Returns
+///
+/// Returns one `DerivedAddress` per index in `range`
+pub fn derive_addresses(input: &str, path: &str, range: Range<u32>) -> Result<Vec<DerivedAddress>, WalletError> {
+    let (key_str, address_type, descriptor_path) = parse_input(input.trim())?;
+    let (xpub, key_type) = parse_extended_key(key_str)?;
+    let address_type = address_type.unwrap_or(key_type);
+    let template = parse_path_template(descriptor_path.unwrap_or(path))?;
+
+    // Everything before the wildcard is shared by all derived children
+    let mut parent = xpub;
+    for index in &template {
+        parent = parent.derive_child(*index)?;
+    }
+    let prefix: Vec<String> = template.iter().map(|i| i.to_string()).collect();
+
+    range
+        .map(|index| {
+            let child = parent.derive_child(index)?;
+            let mut path = prefix.clone();
+            path.push(index.to_string());
+            Ok(DerivedAddress {
+                path: path.join("/"),
+                address: encode_address(&child, address_type)?,
+                address_type,
+            })
+        })
+        .collect()
+}
+
+/// A derived address that has seen transactions, with its confirmed balance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScannedAddress {
+    #[serde(flatten)]
+    pub derived: DerivedAddress,
+    /// Confirmed balance in BTC
+    pub balance: String,
+    /// Confirmed plus mempool transactions
+    pub tx_count: u64,
+}
+
+/// Result of scanning an extended public key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct XpubScan {
+    pub network: String,
+    /// Confirmed balance of all scanned addresses in BTC
+    pub balance: String,
+    /// Addresses looked up, used or not
+    pub scanned: u32,
+    /// Used addresses in derivation order, receive chain first
+    pub addresses: Vec<ScannedAddress>,
+}
+
+/// Total the balance of an extended public key or descriptor on `chain`
+///
+/// A plain key is scanned on its receive (`0/*`) and change (`1/*`) chains, a
+/// descriptor on its own path. Each chain is read in chunks of addresses
+/// (`bitcoin_wallet::lookup_plan`, paced against the public API) and ends
+/// once `gap_limit` addresses in a row have no transactions.
+///
+/// # Arguments
+///
+/// * `input` - Extended public key or descriptor, as for `derive_addresses`
+/// * `chain` - Bitcoin chain the key belongs to
+/// * `gap_limit` - Unused addresses in a row that end a chain; at least 1
+pub async fn scan(input: &str, chain: Chain, gap_limit: u32) -> Result<XpubScan, WalletError> {
+    if gap_limit == 0 {
+        return Err(WalletError::Other("Gap limit must be at least 1".to_string()));
+    }
+    let paths: &[&str] = match parse_input(input.trim())?.2 {
+        Some(_) => &[DEFAULT_PATH],
+        None => &["0/*", "1/*"],
+    };
+    let first = derive_addresses(input, DEFAULT_PATH, 0..1)?;
+    bitcoin_wallet::validate_address_on(chain, &first[0].address)
+        .with_context(|| format!("The extended key is not a {} key", chain.network()))?;
+
+    let (api_base, chunk_size, pause) = bitcoin_wallet::lookup_plan(chain);
+    let mut scan = XpubScan {
+        network: chain.network().to_string(),
+        balance: String::new(),
+        scanned: 0,
+        addresses: Vec::new(),
+    };
+    let mut total_sats = 0u64;
+    for path in paths {
+        let mut unused = 0;
+        let mut start = 0u32;
+        while unused < gap_limit {
+            if scan.scanned > 0 {
+                tokio::time::sleep(pause).await;
+            }
+            let end = start.saturating_add(chunk_size as u32).min(HARDENED_OFFSET);
+            if start >= end {
+                break;
+            }
+            let chunk = derive_addresses(input, path, start..end)?;
+            start = end;
+
+            let mut tasks = tokio::task::JoinSet::new();
+            for (offset, derived) in chunk.iter().enumerate() {
+                let (api_base, address) = (api_base.clone(), derived.address.clone());
+                tasks.spawn(async move { (offset, bitcoin_wallet::get_usage_from(&api_base, &address).await) });
+            }
+            let mut usage: Vec<Option<Result<_>>> = chunk.iter().map(|_| None).collect();
+            while let Some(joined) = tasks.join_next().await {
+                let (offset, result) = joined.context("Address lookup task failed")?;
+                usage[offset] = Some(result);
+            }
+
+            for (derived, result) in chunk.into_iter().zip(usage) {
+                let (sats, activity) = result
+                    .unwrap_or_else(|| Err(anyhow::anyhow!("Address lookup task failed")))
+                    .with_context(|| format!("Failed to look up {} ({})", derived.address, derived.path))?;
+                scan.scanned += 1;
+                let tx_count = activity.confirmed_txs + activity.unconfirmed_txs;
+                if tx_count == 0 {
+                    unused += 1;
+                    if unused == gap_limit {
+                        break;
+                    }
+                    continue;
+                }
+                unused = 0;
+                total_sats += sats;
+                scan.addresses.push(ScannedAddress {
+                    derived,
+                    balance: format!("{:.8}", sats as f64 / 100_000_000.0),
+                    tx_count,
+                });
+            }
+        }
+    }
+    scan.balance = format!("{:.8}", total_sats as f64 / 100_000_000.0);
+    Ok(scan)
+}
+
+/// Split descriptor syntax into the key expression, script type and path
+fn parse_input(input: &str) -> Result<(&str, Option<AddressType>, Option<&str>)> {
+    // Descriptor checksums (`#abcd1234`) are not verified, only stripped
+    let input = input.split('#').next().unwrap_or_default();
+
+    let (inner, address_type) = if let Some(rest) = input.strip_prefix("sh(wpkh(") {
+        let inner = rest
+            .strip_suffix("))")
+            .ok_or_else(|| anyhow::anyhow!("Unterminated sh(wpkh(...)) descriptor"))?;
+        (inner, Some(AddressType::P2shP2wpkh))
+    } else if let Some(rest) = input.strip_prefix("wpkh(") {
+        let inner = rest
+            .strip_suffix(')')
+            .ok_or_else(|| anyhow::anyhow!("Unterminated wpkh(...) descriptor"))?;
+        (inner, Some(AddressType::P2wpkh))
+    } else if let Some(rest) = input.strip_prefix("pkh(") {
+        let inner = rest
+            .strip_suffix(')')
+            .ok_or_else(|| anyhow::anyhow!("Unterminated pkh(...) descriptor"))?;
+        (inner, Some(AddressType::P2pkh))
+    } else if input.contains('(') {
+        return Err(anyhow::anyhow!(
+            "Unsupported descriptor (supported: pkh, wpkh, sh(wpkh))"
+        ));
+    } else {
+        (input, None)
+    };
+
+    // Drop key origin information such as `[d34db33f/84'/0'/0']`
+    let inner = match inner.strip_prefix('[') {
+        Some(rest) => rest
+            .split_once(']')
+            .map(|(_, key)| key)
+            .ok_or_else(|| anyhow::anyhow!("Unterminated key origin in descriptor"))?,
+        None => inner,
+    };
+
+    match inner.split_once('/') {
+        Some((key, path)) => Ok((key, address_type, Some(path))),
+        None => Ok((inner, address_type, None)),
+    }
+}
+
+/// Decode a base58check extended public key and its implied address type
+fn parse_extended_key(key: &str) -> Result<(ExtendedPubKey, AddressType)> {
+    if ["xprv", "yprv", "zprv", "tprv", "uprv", "vprv"]
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+    {
+        return Err(anyhow::anyhow!(
+            "Private extended keys are not accepted; pass the matching public key"
+        ));
+    }
+
+    let data = decode_base58check(key).context("Invalid extended public key encoding")?;
+    if data.len() != 78 {
+        return Err(anyhow::anyhow!("Invalid extended public key length"));
+    }
+
+    let version = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+    let (address_type, testnet) = match version {
+        0x0488_B21E => (AddressType::P2pkh, false),      // xpub
+        0x049D_7CB2 => (AddressType::P2shP2wpkh, false), // ypub
+        0x04B2_4746 => (AddressType::P2wpkh, false),     // zpub
+        0x0435_87CF => (AddressType::P2pkh, true),       // tpub
+        0x044A_5262 => (AddressType::P2shP2wpkh, true),  // upub
+        0x045F_1CF6 => (AddressType::P2wpkh, true),      // vpub
+        _ => return Err(anyhow::anyhow!("Unknown extended key version {:#010x}", version)),
+    };
+
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&data[13..45]);
+    let key = PublicKey::from_sec1_bytes(&data[45..78])
+        .map_err(|_| anyhow::anyhow!("Extended key contains an invalid public key"))?;
+
+    Ok((
+        ExtendedPubKey {
+            key,
+            chain_code,
+            testnet,
+        },
+        address_type,
+    ))
+}
+
+/// Parse a path template like `0/*` into its fixed prefix
+fn parse_path_template(path: &str) -> Result<Vec<u32>> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let (last, prefix) = segments
+        .split_last()
+        .ok_or_else(|| anyhow::anyhow!("Derivation path cannot be empty"))?;
+
+    if *last != "*" {
+        return Err(anyhow::anyhow!("Derivation path must end with '*' (e.g. 0/*)"));
+    }
+
+    prefix
+        .iter()
+        .map(|segment| {
+            if segment.ends_with('\'') || segment.ends_with('h') {
+                return Err(anyhow::anyhow!(
+                    "Hardened step '{}' cannot be derived from a public key",
+                    segment
+                ));
+            }
+            let index: u32 = segment
+                .parse()
+                .with_context(|| format!("Invalid derivation path step '{}'", segment))?;
+            if index >= HARDENED_OFFSET {
+                return Err(anyhow::anyhow!("Derivation index {} is out of range", index));
+            }
+            Ok(index)
+        })
+        .collect()
+}
+
+impl ExtendedPubKey {
+    /// Non-hardened public child key derivation (BIP32 CKDpub)
+    fn derive_child(&self, index: u32) -> Result<Self> {
+        if index >= HARDENED_OFFSET {
+            return Err(anyhow::anyhow!("Cannot derive hardened child from a public key"));
+        }
+
+        let mut mac = Hmac::<Sha512>::new_from_slice(&self.chain_code)
+            .expect("HMAC accepts keys of any length");
+        mac.update(self.key.to_encoded_point(true).as_bytes());
+        mac.update(&index.to_be_bytes());
+        let digest = mac.finalize().into_bytes();
+        let (tweak_bytes, chain_code_bytes) = digest.split_at(32);
+
+        let tweak = Option::<Scalar>::from(Scalar::from_repr(*FieldBytes::from_slice(tweak_bytes)))
+            .ok_or_else(|| anyhow::anyhow!("Derived key at index {} is invalid", index))?;
+        let point = ProjectivePoint::GENERATOR * tweak + self.key.to_projective();
+        let key = PublicKey::from_affine(point.to_affine())
+            .map_err(|_| anyhow::anyhow!("Derived key at index {} is invalid", index))?;
+
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(chain_code_bytes);
+
+        Ok(Self {
+            key,
+            chain_code,
+            testnet: self.testnet,
+        })
+    }
+}
+
+/// Encode the public key of `xpub` as an address of the given type
+fn encode_address(xpub: &ExtendedPubKey, address_type: AddressType) -> Result<String> {
+    let pubkey_hash = hash160(xpub.key.to_encoded_point(true).as_bytes());
+
+    match address_type {
+        AddressType::P2pkh => {
+            let version = if xpub.testnet { 0x6f } else { 0x00 };
+            Ok(encode_base58check(&[version], &pubkey_hash))
+        }
+        AddressType::P2shP2wpkh => {
+            let mut redeem_script = vec![0x00, 0x14];
+            redeem_script.extend_from_slice(&pubkey_hash);
+            let version = if xpub.testnet { 0xc4 } else { 0x05 };
+            Ok(encode_base58check(&[version], &hash160(&redeem_script)))
+        }
+        AddressType::P2wpkh => {
+            let hrp = if xpub.testnet { bech32::hrp::TB } else { bech32::hrp::BC };
+            bech32::segwit::encode(hrp, bech32::segwit::VERSION_0, &pubkey_hash)
+                .map_err(|e| anyhow::anyhow!("Failed to encode segwit address: {}", e))
+        }
+    }
+}
+
+/// RIPEMD160(SHA256(data))
+fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(Sha256::digest(data)).into()
+}
@@ -1,26 +1,85 @@
 //! Bitcoin wallet balance checking functionality
 //!
 //! This module provides functions to check Bitcoin wallet balances
-//! using the Blockchain.com API.
+//! using the Blockchain.com API. Signet and testnet4 are served by
+//! mempool.space's Esplora endpoints.
 
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::Instrument;
 
-use crate::WalletBalance;
+use crate::http::HttpResponse;
+use crate::provider::BalanceProvider;
+use crate::units::{self, U256};
+use crate::{address, config, http, Network, WalletBalance, WalletError};
 
 // const BLOCKCHAIN_INFO_API: &str = "https://blockchain.info";
-const BLOCKCHAIN_INFO_API: &str = "https://blockstream.info/api";
+pub(crate) const BLOCKCHAIN_INFO_API: &str = "https://blockstream.info/api";
+const TESTNET4_API: &str = "https://mempool.space/testnet4/api";
+const SIGNET_API: &str = "https://mempool.space/signet/api";
+
+/// Bitcoin chain a request targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chain {
+    Mainnet,
+    Testnet4,
+    Signet,
+}
+
+impl Chain {
+    /// Esplora API base URL for this chain; mainnet's comes from the active config
+    pub(crate) fn api_base(&self) -> String {
+        match self {
+            Chain::Mainnet => config::active().bitcoin_api().to_string(),
+            Chain::Testnet4 => TESTNET4_API.to_string(),
+            Chain::Signet => SIGNET_API.to_string(),
+        }
+    }
+
+    /// The `Network` this chain is exposed as
+    pub fn network(&self) -> Network {
+        match self {
+            Chain::Mainnet => Network::Bitcoin,
+            Chain::Testnet4 => Network::BitcoinTestnet4,
+            Chain::Signet => Network::BitcoinSignet,
+        }
+    }
+
+    /// Address encoding of this chain
+    pub fn address_params(&self) -> address::BitcoinParams {
+        match self {
+            Chain::Mainnet => address::BitcoinParams::MAINNET,
+            // Signet shares testnet's address encoding
+            Chain::Testnet4 | Chain::Signet => address::BitcoinParams::TESTNET,
+        }
+    }
+}
 
 //  Response structure from Blockstream.info API
 #[derive(Debug, Deserialize)]
 struct BlockstreamResponse {
     chain_stats: ChainStats,
+    #[serde(default)]
+    mempool_stats: ChainStats,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct ChainStats {
     funded_txo_sum: u64,  // Total received (in satoshis)
     spent_txo_sum: u64,   // Total spent (in satoshis)
+    #[serde(default)]
+    tx_count: u64,
+}
+
+/// Transactions touching an address, as counted by Esplora
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressActivity {
+    pub confirmed_txs: u64,
+    /// Transactions still in the mempool
+    pub unconfirmed_txs: u64,
 }
 
 /// Get Bitcoin wallet balance for a given address
@@ -32,22 +91,192 @@ struct ChainStats {
 /// # Returns
 ///
 /// Returns a `WalletBalance` containing the balance in BTC
-pub async fn get_balance(address: &str) -> Result<WalletBalance> {
+pub async fn get_balance(address: &str) -> Result<WalletBalance, WalletError> {
+    get_balance_on(Chain::Mainnet, address).await
+}
+
+/// Get the wallet balance of `address` on a specific Bitcoin `chain`
+#[tracing::instrument(
+    level = "debug",
+    name = "bitcoin_balance",
+    skip_all,
+    fields(network = %chain.network(), address = %address)
+)]
+pub async fn get_balance_on(chain: Chain, address: &str) -> Result<WalletBalance, WalletError> {
+    validate_address_on(chain, address)?;
+
+    let balance_sats = get_balance_sats_from(&chain.api_base(), address).await?;
+    let balance_btc = balance_sats as f64 / 100_000_000.0;
+
+    Ok(WalletBalance::new(
+        address.to_string(),
+        format!("{:.8}", balance_btc),
+        chain.network().to_string(),
+        chain.network().denomination().to_string(),
+    ))
+}
+
+/// Balance of a Bitcoin mainnet `address` with its unconfirmed change (`get_balance_with_pending_on`)
+pub async fn get_balance_with_pending(address: &str) -> Result<WalletBalance, WalletError> {
+    get_balance_with_pending_on(Chain::Mainnet, address).await
+}
+
+/// `get_balance_on`, plus the net effect of the address's mempool transactions
+///
+/// Esplora reports unconfirmed funding and spending in `mempool_stats`; their
+/// difference is set as `unconfirmed_balance`, signed, so an incoming deposit
+/// shows before it is mined. `balance` stays the confirmed amount.
+#[tracing::instrument(
+    level = "debug",
+    name = "bitcoin_balance_with_pending",
+    skip_all,
+    fields(network = %chain.network(), address = %address)
+)]
+pub async fn get_balance_with_pending_on(chain: Chain, address: &str) -> Result<WalletBalance, WalletError> {
+    validate_address_on(chain, address)?;
+
+    let data = parse_address_response(request_address(&chain.api_base(), address).await?)?;
+    let confirmed = data.chain_stats.funded_txo_sum.saturating_sub(data.chain_stats.spent_txo_sum);
+    let mut balance = WalletBalance::new(
+        address.to_string(),
+        format!("{:.8}", confirmed as f64 / 100_000_000.0),
+        chain.network().to_string(),
+        chain.network().denomination().to_string(),
+    );
+    balance.unconfirmed_balance = Some(units::format_delta(
+        U256::from(data.mempool_stats.spent_txo_sum),
+        U256::from(data.mempool_stats.funded_txo_sum),
+        chain.network().decimals(),
+    ));
+    Ok(balance)
+}
+
+/// Confirmed balance in satoshis from an Esplora-compatible API at `api_base`
+pub(crate) async fn get_balance_sats_from(api_base: &str, address: &str) -> Result<u64> {
+    let response = request_address(api_base, address).await?;
+    sats_from_response(response)
+}
+
+/// Confirmed and mempool transaction counts of `address` from `api_base`
+pub(crate) async fn get_activity_from(api_base: &str, address: &str) -> Result<AddressActivity> {
+    Ok(get_usage_from(api_base, address).await?.1)
+}
+
+/// Confirmed balance in satoshis and transaction counts of `address` from one request
+pub(crate) async fn get_usage_from(api_base: &str, address: &str) -> Result<(u64, AddressActivity)> {
+    let data = parse_address_response(request_address(api_base, address).await?)?;
+    let activity = AddressActivity {
+        confirmed_txs: data.chain_stats.tx_count,
+        unconfirmed_txs: data.mempool_stats.tx_count,
+    };
+    Ok((data.chain_stats.funded_txo_sum.saturating_sub(data.chain_stats.spent_txo_sum), activity))
+}
+
+/// Addresses looked up concurrently per chunk of a batch against the public API
+const PUBLIC_CHUNK_SIZE: usize = 5;
+/// Pause between chunks against the public API, which rate-limits bursts
+const PUBLIC_CHUNK_PAUSE: Duration = Duration::from_millis(500);
+/// Addresses per chunk against a configured `batch_api`, which is assumed unthrottled
+const BATCH_API_CHUNK_SIZE: usize = 50;
+
+/// Balances of several Bitcoin mainnet addresses, in input order
+///
+/// The addresses are looked up in chunks: small ones with a pause in between
+/// against the public API, or large ones back to back when a local Esplora
+/// instance is configured as `bitcoin.batch_api`. A lookup answered with 429
+/// is retried under the shared `http::RetryPolicy`, so a long list completes
+/// instead of failing once the provider starts throttling. Invalid addresses
+/// fail without a request.
+pub async fn get_balances(addresses: &[String]) -> Vec<Result<WalletBalance, WalletError>> {
+    let mut results: Vec<Option<Result<WalletBalance, WalletError>>> = addresses.iter().map(|_| None).collect();
+    let (sender, mut received) = tokio::sync::mpsc::unbounded_channel();
+    stream_balances(addresses, move |index, result| {
+        let _ = sender.send((index, result));
+    })
+    .await;
+    while let Some((index, result)) = received.recv().await {
+        results[index] = Some(result);
+    }
+    results
+        .into_iter()
+        .map(|result| result.unwrap_or_else(|| Err(WalletError::Other("Balance lookup task failed".to_string()))))
+        .collect()
+}
+
+/// `get_balances`, handing each result to `report` with its index as soon as it is in
+#[tracing::instrument(
+    level = "info",
+    name = "bitcoin_batch",
+    skip_all,
+    fields(network = "bitcoin", addresses = addresses.len())
+)]
+pub(crate) async fn stream_balances(
+    addresses: &[String],
+    report: impl Fn(usize, Result<WalletBalance, WalletError>) + Clone + Send + Sync + 'static,
+) {
+    let (api_base, chunk_size, pause) = lookup_plan(Chain::Mainnet);
+
+    for (number, chunk) in addresses.chunks(chunk_size).enumerate() {
+        if number > 0 {
+            tokio::time::sleep(pause).await;
+        }
+        let mut tasks = tokio::task::JoinSet::new();
+        for (offset, address) in chunk.iter().enumerate() {
+            let (api_base, address) = (api_base.clone(), address.trim().to_string());
+            let (index, report) = (number * chunk_size + offset, report.clone());
+            tasks.spawn(async move {
+                let result = get_balance_from(&api_base, &address).await;
+                report(index, result.map_err(WalletError::from))
+            }
+            .in_current_span());
+        }
+        while tasks.join_next().await.is_some() {}
+    }
+}
+
+/// API base, chunk size and pause between chunks for many lookups on `chain`
+pub(crate) fn lookup_plan(chain: Chain) -> (String, usize, Duration) {
+    match config::active().bitcoin_batch_api() {
+        Some(api) if chain == Chain::Mainnet => (api.to_string(), BATCH_API_CHUNK_SIZE, Duration::ZERO),
+        _ => (chain.api_base(), PUBLIC_CHUNK_SIZE, PUBLIC_CHUNK_PAUSE),
+    }
+}
+
+/// Mainnet balance of `address` from `api_base`
+async fn get_balance_from(api_base: &str, address: &str) -> Result<WalletBalance> {
     validate_address(address)?;
+    let sats = get_balance_sats_from(api_base, address).await?;
 
-    let url = format!("{}/address/{}", BLOCKCHAIN_INFO_API, address);
+    Ok(WalletBalance::new(
+        address.to_string(),
+        format!("{:.8}", sats as f64 / 100_000_000.0),
+        Network::Bitcoin.to_string(),
+        Network::Bitcoin.denomination().to_string(),
+    ))
+}
+
+async fn request_address(api_base: &str, address: &str) -> Result<HttpResponse> {
+    let url = format!("{}/address/{}", api_base, address);
+
+    let client = http::client();
+    http::send(
+        client
+            .get(&url)
+            .header("User-Agent", "wallet-balance-cli/0.1.0"),
+    )
+    .await
+    .with_context(|| format!("Failed to send request to Esplora API {}", http::loggable(api_base)))
+}
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .header("User-Agent", "wallet-balance-cli/0.1.0")
-        .send()
-        .await
-        .context("Failed to send request to Blockstream API")?;
+fn sats_from_response(response: HttpResponse) -> Result<u64> {
+    let data = parse_address_response(response)?;
+    Ok(data.chain_stats.funded_txo_sum.saturating_sub(data.chain_stats.spent_txo_sum))
+}
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
+fn parse_address_response(response: HttpResponse) -> Result<BlockstreamResponse> {
+    if !response.status.is_success() {
+        let status = response.status;
+        let body = response.text();
         return Err(anyhow::anyhow!(
             "API failed: {} - {}",
             status,
@@ -55,40 +284,175 @@ pub async fn get_balance(address: &str) -> Result<WalletBalance> {
         ));
     }
 
-    let data: BlockstreamResponse = response
+    response
         .json()
-        .await
-        .context("Failed to parse JSON from Blockstream")?;
+        .context("Failed to parse JSON from the Esplora API")
+}
 
-    let balance_sats = data.chain_stats.funded_txo_sum.saturating_sub(data.chain_stats.spent_txo_sum);
-    let balance_btc = balance_sats as f64 / 100_000_000.0;
+/// An unspent output as listed by Esplora's `/address/:address/utxo`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Utxo {
+    pub txid: String,
+    pub vout: u32,
+    /// Value in satoshis
+    pub value: u64,
+    #[serde(default)]
+    pub status: UtxoStatus,
+}
 
-    Ok(WalletBalance::new(
-        address.to_string(),
-        format!("{:.8}", balance_btc),
-        "bitcoin".to_string(),
-        "BTC".to_string(),
-    ))
+/// Whether the transaction creating an output is in a block yet
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct UtxoStatus {
+    pub confirmed: bool,
+    pub block_height: Option<u64>,
+}
+
+impl Utxo {
+    /// The `txid:vout` outpoint
+    pub fn outpoint(&self) -> String {
+        format!("{}:{}", self.txid, self.vout)
+    }
+
+    /// Confirmations at chain tip `tip_height`; 0 while the output is in the mempool
+    pub fn confirmations(&self, tip_height: u64) -> u64 {
+        match (self.status.confirmed, self.status.block_height) {
+            (true, Some(height)) => tip_height.saturating_sub(height) + 1,
+            _ => 0,
+        }
+    }
+}
+
+/// Unspent outputs of a Bitcoin mainnet `address`
+pub async fn get_utxos(address: &str) -> Result<Vec<Utxo>, WalletError> {
+    get_utxos_on(Chain::Mainnet, address).await
+}
+
+/// Unspent outputs of `address` on a specific Bitcoin `chain`
+pub async fn get_utxos_on(chain: Chain, address: &str) -> Result<Vec<Utxo>, WalletError> {
+    validate_address_on(chain, address)?;
+    Ok(get_utxos_from(&chain.api_base(), address).await?)
+}
+
+/// Current block height of `chain`, for turning UTXO heights into confirmations
+pub async fn get_tip_height_on(chain: Chain) -> Result<u64, WalletError> {
+    Ok(get_tip_height_from(&chain.api_base()).await?)
+}
+
+/// Unspent outputs of `address` from an Esplora-compatible API at `api_base`
+pub(crate) async fn get_utxos_from(api_base: &str, address: &str) -> Result<Vec<Utxo>> {
+    let url = format!("{}/address/{}/utxo", api_base, address);
+
+    let client = http::client();
+    let response = http::send(
+        client
+            .get(&url)
+            .header("User-Agent", "wallet-balance-cli/0.1.0"),
+    )
+    .await
+    .with_context(|| format!("Failed to send request to Esplora API {}", http::loggable(api_base)))?;
+
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("API failed: {} - {}", response.status, response.text()));
+    }
+
+    response.json().context("Failed to parse UTXOs from the Esplora API")
 }
 
-fn validate_address(address: &str) -> Result<()> {
+pub(crate) fn validate_address(address: &str) -> Result<(), WalletError> {
+    validate_address_on(Chain::Mainnet, address)
+}
+
+/// Fully decode `address` for `chain`: Base58Check for legacy/P2SH, Bech32/Bech32m for segwit
+pub(crate) fn validate_address_on(chain: Chain, address: &str) -> Result<(), WalletError> {
     if address.is_empty() {
-        return Err(anyhow::anyhow!("Bitcoin address cannot be empty"));
+        return Err(WalletError::InvalidAddress("Bitcoin address cannot be empty".to_string()));
     }
 
-    // Basic validation: Bitcoin addresses are typically 26-35 characters
-    if address.len() < 26 || address.len() > 62 {
-        return Err(anyhow::anyhow!("Invalid Bitcoin address length"));
+    address::decode_bitcoin(&chain.address_params(), address)
+        .map(|_| ())
+        .map_err(|e| WalletError::InvalidAddress(format!("Invalid Bitcoin address: {}: {}", address, e)))
+}
+
+/// Get fee rate estimates from the mainnet Esplora API
+///
+/// # Returns
+///
+/// Returns a map from confirmation target (in blocks) to fee rate in sat/vB
+pub async fn get_fee_estimates() -> Result<HashMap<u32, f64>, WalletError> {
+    get_fee_estimates_on(Chain::Mainnet).await
+}
+
+/// Fee rate estimates for a specific Bitcoin `chain`
+pub async fn get_fee_estimates_on(chain: Chain) -> Result<HashMap<u32, f64>, WalletError> {
+    Ok(get_fee_estimates_from(&chain.api_base()).await?)
+}
+
+/// Fee rate estimates from an Esplora-compatible API at `api_base`
+pub(crate) async fn get_fee_estimates_from(api_base: &str) -> Result<HashMap<u32, f64>> {
+    let url = format!("{}/fee-estimates", api_base);
+
+    let client = http::client();
+    let response = http::send(
+        client
+            .get(&url)
+            .header("User-Agent", "wallet-balance-cli/0.1.0"),
+    )
+    .await
+    .with_context(|| format!("Failed to send request to Esplora API {}", http::loggable(api_base)))?;
+
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("API failed: {}", response.status));
     }
 
-    // Check if starts with valid prefix (1, 3, or bc1)
-    if !address.starts_with('1') 
-        && !address.starts_with('3') 
-        && !address.starts_with("bc1") {
-        return Err(anyhow::anyhow!(
-            "Invalid Bitcoin address format (must start with 1, 3, or bc1)"
-        ));
+    let estimates: HashMap<String, f64> = response
+        .json()
+        .context("Failed to parse fee estimates from the Esplora API")?;
+
+    Ok(estimates
+        .into_iter()
+        .filter_map(|(target, rate)| target.parse().ok().map(|target| (target, rate)))
+        .collect())
+}
+
+/// Chain tip height from an Esplora-compatible API at `api_base`; also a cheap reachability probe
+pub(crate) async fn get_tip_height_from(api_base: &str) -> Result<u64> {
+    let url = format!("{}/blocks/tip/height", api_base);
+
+    let client = http::client();
+    let response = http::send(
+        client
+            .get(&url)
+            .header("User-Agent", "wallet-balance-cli/0.1.0"),
+    )
+    .await
+    .with_context(|| format!("Failed to send request to Esplora API {}", http::loggable(api_base)))?;
+
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("API failed: {}", response.status));
     }
 
-    Ok(())
-}
\ No newline at end of file
+    response
+        .text()
+        .trim()
+        .parse()
+        .context("Failed to parse tip height from the Esplora API")
+}
+
+/// `BalanceProvider` for one Bitcoin chain
+pub struct BitcoinProvider(pub Chain);
+
+#[async_trait]
+impl BalanceProvider for BitcoinProvider {
+    fn network(&self) -> Network {
+        self.0.network()
+    }
+
+    fn normalize_address(&self, address: &str) -> Result<String, WalletError> {
+        validate_address_on(self.0, address)?;
+        Ok(address.to_string())
+    }
+
+    async fn get_balance(&self, address: &str) -> Result<WalletBalance, WalletError> {
+        get_balance_on(self.0, address).await
+    }
+}
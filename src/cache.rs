@@ -0,0 +1,505 @@
+//! Cache of live balance lookups (`--cache`, `[cache]`)
+//!
+//! With a cache installed, a native balance fetched from a provider is kept
+//! for the cache's TTL, and lookups of the same address on the same network
+//! within it are answered from the cache instead. Backends implement
+//! `CacheBackend`:
+//!
+//! - `memory`: inside the process, for `serve`, `--watch` and JSONL sessions
+//! - `disk`: files in the cache directory, shared by the runs on one machine
+//! - `redis://[:password@]host[:port][/db]`: a Redis server
+//! - `memcached://host[:port]`: a memcached server
+//!
+//! Redis and memcached let the pods of a horizontally scaled `serve`
+//! deployment share one cache, so an address that several of them are asked
+//! for reaches the provider once per TTL rather than once per pod. Both are
+//! spoken over plain TCP with their text protocols.
+//!
+//! A backend that fails is logged and bypassed: the lookup goes to the
+//! provider as if nothing were cached. Cache hits show up in the `-v` session
+//! report under the backend's name.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream};
+use tokio::net::TcpStream;
+
+use crate::config::ConfigPaths;
+use crate::{evm_wallet, http, Network, WalletBalance, WalletError};
+
+/// How long entries are kept when `--cache-ttl` and `[cache] ttl` are not given
+pub const DEFAULT_TTL: Duration = Duration::from_secs(30);
+
+/// Prefix of every key, so a shared Redis or memcached can hold other data too
+const KEY_PREFIX: &str = "wallet-balance:v1";
+
+/// Longest a Redis or memcached exchange may take before the backend counts as failed
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Storage for cached values
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    /// Where the entries live, as in the spec that opened the backend
+    fn describe(&self) -> String;
+
+    /// The value stored under `key`, unless there is none or it has expired
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Store `value` under `key` for `ttl`
+    async fn set(&self, key: &str, value: &[u8], ttl: Duration) -> Result<()>;
+}
+
+/// Which backend to open
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheSpec {
+    /// Cache nothing
+    None,
+    Memory,
+    /// Files in the cache directory
+    Disk,
+    /// `redis://` URL
+    Redis(String),
+    /// `memcached://` URL
+    Memcached(String),
+}
+
+impl std::str::FromStr for CacheSpec {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, WalletError> {
+        let s = s.trim();
+        match s.to_lowercase().as_str() {
+            "none" | "off" => return Ok(CacheSpec::None),
+            "memory" => return Ok(CacheSpec::Memory),
+            "disk" => return Ok(CacheSpec::Disk),
+            _ => {}
+        }
+        if s.starts_with("redis://") {
+            RedisCache::new(s)?;
+            return Ok(CacheSpec::Redis(s.to_string()));
+        }
+        if s.starts_with("memcached://") {
+            MemcachedCache::new(s)?;
+            return Ok(CacheSpec::Memcached(s.to_string()));
+        }
+        Err(WalletError::ParseError(format!(
+            "Unsupported cache: {} (expected none, memory, disk, redis://host or memcached://host)",
+            s
+        )))
+    }
+}
+
+impl std::fmt::Display for CacheSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheSpec::None => write!(f, "none"),
+            CacheSpec::Memory => write!(f, "memory"),
+            CacheSpec::Disk => write!(f, "disk"),
+            CacheSpec::Redis(url) | CacheSpec::Memcached(url) => write!(f, "{}", redact(url)),
+        }
+    }
+}
+
+impl CacheSpec {
+    /// Open the backend, or nothing for `None`; `Disk` keeps its files in the standard cache directory
+    pub fn open(&self) -> Result<Option<Arc<dyn CacheBackend>>, WalletError> {
+        Ok(match self {
+            CacheSpec::None => None,
+            CacheSpec::Memory => Some(Arc::new(MemoryCache::default())),
+            CacheSpec::Disk => Some(Arc::new(DiskCache::new(ConfigPaths::discover()?.cache_dir.join("balances")))),
+            CacheSpec::Redis(url) => Some(Arc::new(RedisCache::new(url)?)),
+            CacheSpec::Memcached(url) => Some(Arc::new(MemcachedCache::new(url)?)),
+        })
+    }
+}
+
+/// `url` without its password
+fn redact(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(mut url) if url.password().is_some() => {
+            let _ = url.set_password(Some("***"));
+            url.to_string()
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// Entries kept in the process
+#[derive(Default)]
+pub struct MemoryCache {
+    entries: Mutex<HashMap<String, (Instant, Vec<u8>)>>,
+}
+
+/// Entries the memory cache holds before it drops the expired ones
+const MEMORY_PRUNE_AT: usize = 4_096;
+
+#[async_trait]
+impl CacheBackend for MemoryCache {
+    fn describe(&self) -> String {
+        "memory".to_string()
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Ok(entries
+            .get(key)
+            .filter(|(expires, _)| *expires > Instant::now())
+            .map(|(_, value)| value.clone()))
+    }
+
+    async fn set(&self, key: &str, value: &[u8], ttl: Duration) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        if entries.len() >= MEMORY_PRUNE_AT {
+            entries.retain(|_, (expires, _)| *expires > now);
+        }
+        entries.insert(key.to_string(), (now + ttl, value.to_vec()));
+        Ok(())
+    }
+}
+
+/// One file per entry in a directory: the expiry in Unix milliseconds on the first line, then the value
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    pub fn new(dir: PathBuf) -> Self {
+        DiskCache { dir }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(hex::encode(Sha256::digest(key.as_bytes())))
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[async_trait]
+impl CacheBackend for DiskCache {
+    fn describe(&self) -> String {
+        "disk".to_string()
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let contents = match tokio::fs::read(self.path(key)).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).context("Failed to read the cache entry"),
+        };
+        let Some(newline) = contents.iter().position(|&byte| byte == b'\n') else {
+            return Ok(None);
+        };
+        let expires: u64 = match std::str::from_utf8(&contents[..newline]).ok().and_then(|line| line.parse().ok()) {
+            Some(expires) => expires,
+            None => return Ok(None),
+        };
+        Ok((expires > unix_millis()).then(|| contents[newline + 1..].to_vec()))
+    }
+
+    async fn set(&self, key: &str, value: &[u8], ttl: Duration) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let mut contents = format!("{}\n", unix_millis() + ttl.as_millis() as u64).into_bytes();
+        contents.extend_from_slice(value);
+        // Written aside and renamed, so a concurrent run never reads half an entry
+        let path = self.path(key);
+        let partial = path.with_extension(format!("{}.tmp", std::process::id()));
+        tokio::fs::write(&partial, contents)
+            .await
+            .context("Failed to write the cache entry")?;
+        tokio::fs::rename(&partial, &path)
+            .await
+            .context("Failed to write the cache entry")
+    }
+}
+
+/// `host:port` of a `scheme://` cache URL, with the URL parsed
+fn server_address(url: &str, default_port: u16) -> Result<(String, reqwest::Url), WalletError> {
+    let parsed = reqwest::Url::parse(url).with_context(|| format!("Invalid cache URL: {}", url))?;
+    let host = parsed
+        .host_str()
+        .filter(|host| !host.is_empty())
+        .ok_or_else(|| WalletError::ParseError(format!("Invalid cache URL: {} (no host)", url)))?;
+    Ok((format!("{}:{}", host, parsed.port().unwrap_or(default_port)), parsed))
+}
+
+/// A connection kept between exchanges; `None` until the first one or after a failure
+type Connection = tokio::sync::Mutex<Option<BufStream<TcpStream>>>;
+
+/// Run `exchange` on the kept connection, opening one first if needed
+///
+/// A failed or timed-out exchange drops the connection, so the next one
+/// starts on a fresh socket rather than in the middle of a stale reply.
+async fn with_connection<T>(
+    connection: &Connection,
+    address: &str,
+    open: impl AsyncFnOnce(&mut BufStream<TcpStream>) -> Result<()>,
+    exchange: impl AsyncFnOnce(&mut BufStream<TcpStream>) -> Result<T>,
+) -> Result<T> {
+    let mut kept = connection.lock().await;
+    let result = tokio::time::timeout(REMOTE_TIMEOUT, async {
+        if kept.is_none() {
+            let mut stream = BufStream::new(TcpStream::connect(address).await?);
+            open(&mut stream).await?;
+            *kept = Some(stream);
+        }
+        exchange(kept.as_mut().expect("connected above")).await
+    })
+    .await
+    .unwrap_or_else(|_| Err(anyhow::anyhow!("{} did not answer within {:?}", address, REMOTE_TIMEOUT)));
+    if result.is_err() {
+        *kept = None;
+    }
+    result
+}
+
+/// A Redis server, spoken to in RESP
+pub struct RedisCache {
+    url: String,
+    address: String,
+    password: Option<String>,
+    database: u32,
+    connection: Connection,
+}
+
+impl RedisCache {
+    /// Parse `redis://[:password@]host[:port][/db]`; connecting waits for the first exchange
+    pub fn new(url: &str) -> Result<Self, WalletError> {
+        let (address, parsed) = server_address(url, 6379)?;
+        let database = match parsed.path().trim_matches('/') {
+            "" => 0,
+            db => db
+                .parse()
+                .map_err(|_| WalletError::ParseError(format!("Invalid Redis database: {} in {}", db, url)))?,
+        };
+        Ok(RedisCache {
+            url: url.to_string(),
+            address,
+            password: parsed.password().map(str::to_string),
+            database,
+            connection: Connection::default(),
+        })
+    }
+
+    async fn command<T>(
+        &self,
+        arguments: &[&[u8]],
+        read: impl AsyncFnOnce(&mut BufStream<TcpStream>) -> Result<T>,
+    ) -> Result<T> {
+        let (password, database) = (self.password.clone(), self.database.to_string());
+        with_connection(
+            &self.connection,
+            &self.address,
+            async |stream| {
+                if let Some(password) = &password {
+                    send_resp(stream, &[b"AUTH", password.as_bytes()]).await?;
+                    expect_resp_ok(stream).await?;
+                }
+                if database != "0" {
+                    send_resp(stream, &[b"SELECT", database.as_bytes()]).await?;
+                    expect_resp_ok(stream).await?;
+                }
+                Ok(())
+            },
+            async |stream| {
+                send_resp(stream, arguments).await?;
+                read(stream).await
+            },
+        )
+        .await
+    }
+}
+
+async fn send_resp(stream: &mut BufStream<TcpStream>, arguments: &[&[u8]]) -> Result<()> {
+    let mut command = format!("*{}\r\n", arguments.len()).into_bytes();
+    for argument in arguments {
+        command.extend_from_slice(format!("${}\r\n", argument.len()).as_bytes());
+        command.extend_from_slice(argument);
+        command.extend_from_slice(b"\r\n");
+    }
+    stream.write_all(&command).await?;
+    Ok(stream.flush().await?)
+}
+
+async fn read_line(stream: &mut BufStream<TcpStream>) -> Result<String> {
+    let mut line = String::new();
+    if stream.read_line(&mut line).await? == 0 {
+        return Err(anyhow::anyhow!("Connection closed by the cache server"));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+async fn expect_resp_ok(stream: &mut BufStream<TcpStream>) -> Result<()> {
+    match read_line(stream).await? {
+        line if line.starts_with('+') => Ok(()),
+        line => Err(anyhow::anyhow!("Redis answered {}", line.trim_start_matches('-'))),
+    }
+}
+
+/// A bulk string reply: `None` for the null reply of a missing key
+async fn read_resp_bulk(stream: &mut BufStream<TcpStream>) -> Result<Option<Vec<u8>>> {
+    let line = read_line(stream).await?;
+    let Some(length) = line.strip_prefix('$') else {
+        return Err(anyhow::anyhow!("Redis answered {}", line.trim_start_matches('-')));
+    };
+    let length: i64 = length.parse().context("Invalid Redis reply")?;
+    if length < 0 {
+        return Ok(None);
+    }
+    let mut value = vec![0; length as usize + 2];
+    stream.read_exact(&mut value).await?;
+    value.truncate(length as usize);
+    Ok(Some(value))
+}
+
+#[async_trait]
+impl CacheBackend for RedisCache {
+    fn describe(&self) -> String {
+        redact(&self.url)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.command(&[b"GET", key.as_bytes()], read_resp_bulk).await
+    }
+
+    async fn set(&self, key: &str, value: &[u8], ttl: Duration) -> Result<()> {
+        let millis = ttl.as_millis().max(1).to_string();
+        self.command(&[b"SET", key.as_bytes(), value, b"PX", millis.as_bytes()], expect_resp_ok)
+            .await
+    }
+}
+
+/// A memcached server, spoken to in its text protocol
+pub struct MemcachedCache {
+    url: String,
+    address: String,
+    connection: Connection,
+}
+
+impl MemcachedCache {
+    /// Parse `memcached://host[:port]`; connecting waits for the first exchange
+    pub fn new(url: &str) -> Result<Self, WalletError> {
+        let (address, _) = server_address(url, 11211)?;
+        Ok(MemcachedCache {
+            url: url.to_string(),
+            address,
+            connection: Connection::default(),
+        })
+    }
+}
+
+#[async_trait]
+impl CacheBackend for MemcachedCache {
+    fn describe(&self) -> String {
+        self.url.clone()
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let request = format!("get {}\r\n", key);
+        with_connection(&self.connection, &self.address, async |_| Ok(()), async |stream| {
+            stream.write_all(request.as_bytes()).await?;
+            stream.flush().await?;
+            let line = read_line(stream).await?;
+            if line == "END" {
+                return Ok(None);
+            }
+            // VALUE <key> <flags> <bytes>
+            let length: usize = match line.split(' ').collect::<Vec<_>>()[..] {
+                ["VALUE", _, _, length, ..] => length.parse().context("Invalid memcached reply")?,
+                _ => return Err(anyhow::anyhow!("memcached answered {}", line)),
+            };
+            let mut value = vec![0; length + 2];
+            stream.read_exact(&mut value).await?;
+            value.truncate(length);
+            match read_line(stream).await?.as_str() {
+                "END" => Ok(Some(value)),
+                line => Err(anyhow::anyhow!("memcached answered {}", line)),
+            }
+        })
+        .await
+    }
+
+    async fn set(&self, key: &str, value: &[u8], ttl: Duration) -> Result<()> {
+        // Whole seconds, at least one: memcached reads 0 as "never expires"
+        let mut request = format!("set {} 0 {} {}\r\n", key, ttl.as_secs().max(1), value.len()).into_bytes();
+        request.extend_from_slice(value);
+        request.extend_from_slice(b"\r\n");
+        with_connection(&self.connection, &self.address, async |_| Ok(()), async |stream| {
+            stream.write_all(&request).await?;
+            stream.flush().await?;
+            match read_line(stream).await?.as_str() {
+                "STORED" => Ok(()),
+                line => Err(anyhow::anyhow!("memcached answered {}", line)),
+            }
+        })
+        .await
+    }
+}
+
+static CACHE: RwLock<Option<(Arc<dyn CacheBackend>, Duration)>> = RwLock::new(None);
+
+/// Install the cache live lookups go through, keeping entries for `ttl`; `None` turns caching off
+pub fn set(cache: Option<Arc<dyn CacheBackend>>, ttl: Duration) {
+    *CACHE.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = cache.map(|cache| (cache, ttl));
+}
+
+/// The installed cache and its TTL, if any
+pub fn active() -> Option<(Arc<dyn CacheBackend>, Duration)> {
+    CACHE.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// Key of the native balance of the normalized `address` on `network`
+pub fn balance_key(network: Network, address: &str) -> String {
+    format!("{}:balance:{}:{}", KEY_PREFIX, chain_key(network), address)
+}
+
+/// Key of the balance of the normalized `address` read pinned to the tip of EVM `network`, with its block
+pub fn pinned_balance_key(network: Network, address: &str) -> String {
+    format!("{}:pinned-balance:{}:{}", KEY_PREFIX, chain_key(network), address)
+}
+
+/// `network` in a key; `custom-evm` names whichever chain `--rpc-url` points at,
+/// so it carries a hash of that endpoint to keep two custom chains apart
+fn chain_key(network: Network) -> String {
+    match evm_wallet::custom_chain().filter(|_| network == Network::CustomEvm) {
+        Some(chain) => format!("{}-{}", network, &hex::encode(Sha256::digest(chain.default_rpc.as_bytes()))[..16]),
+        None => network.to_string(),
+    }
+}
+
+/// The balance cached under `key`, if the installed cache has one
+pub(crate) async fn cached_balance(key: &str) -> Option<WalletBalance> {
+    let (cache, _) = active()?;
+    match cache.get(key).await {
+        Ok(Some(value)) => {
+            let balance = serde_json::from_slice(&value).ok()?;
+            http::record_cache_hit(&cache.describe());
+            Some(balance)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            tracing::warn!("Cache {} unavailable, fetching from the provider: {:#}", cache.describe(), e);
+            None
+        }
+    }
+}
+
+/// Keep `balance` under `key` in the installed cache, if any
+pub(crate) async fn store_balance(key: &str, balance: &WalletBalance) {
+    let Some((cache, ttl)) = active() else {
+        return;
+    };
+    let value = serde_json::to_vec(balance).expect("balances serialize");
+    if let Err(e) = cache.set(key, &value, ttl).await {
+        tracing::warn!("Cache {} unavailable, not storing the balance: {:#}", cache.describe(), e);
+    }
+}
@@ -0,0 +1,175 @@
+//! Chain summary for `stats -n <NETWORK>`
+//!
+//! When a balance looks stale or a deposit has not confirmed, the first
+//! questions are whether the provider is at the chain tip, what fees are
+//! going for and how busy the mempool is. This module answers them from the
+//! APIs the balance lookups already use: Esplora for the Bitcoin chains and
+//! JSON-RPC for the EVM networks.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{bitcoin_wallet, evm_rpc, http, units, Network, WalletError};
+
+/// Bitcoin confirmation targets reported, in blocks
+const BITCOIN_FEE_TARGETS: [u32; 3] = [1, 6, 144];
+
+/// One fee rate of a chain summary
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeeRate {
+    /// Confirmation target in blocks; `None` for an EVM gas price
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_blocks: Option<u32>,
+    pub rate: String,
+    /// `sat/vB` or `gwei`
+    pub unit: String,
+}
+
+/// Transactions waiting to be mined
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MempoolStats {
+    pub tx_count: u64,
+    /// Total virtual size in vbytes (Bitcoin)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vsize: Option<u64>,
+}
+
+/// Head height, fees and mempool of one network
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChainStats {
+    pub network: String,
+    pub height: u64,
+    pub fees: Vec<FeeRate>,
+    /// `None` when the provider does not expose its mempool
+    pub mempool: Option<MempoolStats>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraMempool {
+    count: u64,
+    vsize: u64,
+}
+
+/// Summary of `network`; Bitcoin chains and EVM networks are supported
+pub async fn get_chain_stats(network: Network) -> Result<ChainStats, WalletError> {
+    if let Some(chain) = network.bitcoin_chain() {
+        return Ok(bitcoin_stats(network, &chain.api_base()).await?);
+    }
+    if let Some(rpc_url) = evm_rpc::rpc_url(network) {
+        return Ok(evm_stats(network, &rpc_url).await?);
+    }
+    Err(WalletError::UnsupportedNetwork(format!(
+        "Chain statistics are not available for {} (supported: Bitcoin and EVM networks)",
+        network
+    )))
+}
+
+/// Unix time of the newest block `network`'s provider knows; Bitcoin chains and EVM networks are supported
+///
+/// A provider stuck behind the chain serves stale balances, so how long ago
+/// this block was mined bounds how out of date a balance read now can be.
+pub async fn tip_time(network: Network) -> Result<u64, WalletError> {
+    if let Some(chain) = network.bitcoin_chain() {
+        return Ok(esplora_tip_time(&chain.api_base()).await?);
+    }
+    if let Some(rpc_url) = evm_rpc::rpc_url(network) {
+        let block = evm_rpc::call(&rpc_url, "eth_getBlockByNumber", vec![json!("latest"), json!(false)]).await?;
+        return Ok(evm_rpc::parse_quantity(&block["timestamp"])? as u64);
+    }
+    Err(WalletError::UnsupportedNetwork(format!(
+        "The chain tip time is not available for {} (supported: Bitcoin and EVM networks)",
+        network
+    )))
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraBlock {
+    timestamp: u64,
+}
+
+/// Timestamp of the newest of the blocks Esplora lists at `/blocks`
+async fn esplora_tip_time(api_base: &str) -> Result<u64> {
+    let url = format!("{}/blocks", api_base);
+
+    let client = http::client();
+    let response = http::send(client.get(&url).header("User-Agent", "wallet-balance-cli/0.1.0"))
+        .await
+        .with_context(|| format!("Failed to send request to Esplora API {}", http::loggable(api_base)))?;
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("API failed: {}", response.status));
+    }
+    let blocks: Vec<EsploraBlock> = response.json().context("Failed to parse blocks from the Esplora API")?;
+    blocks
+        .first()
+        .map(|block| block.timestamp)
+        .ok_or_else(|| anyhow::anyhow!("The Esplora API listed no blocks"))
+}
+
+async fn bitcoin_stats(network: Network, api_base: &str) -> Result<ChainStats> {
+    let (height, estimates, mempool) = tokio::try_join!(
+        bitcoin_wallet::get_tip_height_from(api_base),
+        bitcoin_wallet::get_fee_estimates_from(api_base),
+        esplora_mempool(api_base),
+    )?;
+    let fees = BITCOIN_FEE_TARGETS
+        .iter()
+        .filter_map(|target| {
+            estimates.get(target).map(|rate| FeeRate {
+                target_blocks: Some(*target),
+                rate: format!("{:.1}", rate),
+                unit: "sat/vB".to_string(),
+            })
+        })
+        .collect();
+    Ok(ChainStats {
+        network: network.to_string(),
+        height,
+        fees,
+        mempool: Some(MempoolStats {
+            tx_count: mempool.count,
+            vsize: Some(mempool.vsize),
+        }),
+    })
+}
+
+async fn esplora_mempool(api_base: &str) -> Result<EsploraMempool> {
+    let url = format!("{}/mempool", api_base);
+
+    let client = http::client();
+    let response = http::send(client.get(&url).header("User-Agent", "wallet-balance-cli/0.1.0"))
+        .await
+        .with_context(|| format!("Failed to send request to Esplora API {}", http::loggable(api_base)))?;
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("API failed: {}", response.status));
+    }
+    response.json().context("Failed to parse mempool stats from the Esplora API")
+}
+
+async fn evm_stats(network: Network, rpc_url: &str) -> Result<ChainStats> {
+    let (height, gas_price, pool) = tokio::join!(
+        evm_rpc::block_number(rpc_url),
+        evm_rpc::gas_price(rpc_url),
+        evm_rpc::call(rpc_url, "txpool_status", vec![]),
+    );
+    Ok(ChainStats {
+        network: network.to_string(),
+        height: height?,
+        fees: vec![FeeRate {
+            target_blocks: None,
+            rate: units::format_units(gas_price?, 9),
+            unit: "gwei".to_string(),
+        }],
+        // Most public endpoints do not serve the txpool namespace
+        mempool: pool.ok().and_then(|status| txpool_pending(&status)),
+    })
+}
+
+/// Pending transaction count of a `txpool_status` result
+fn txpool_pending(status: &Value) -> Option<MempoolStats> {
+    let pending = evm_rpc::parse_quantity(status.get("pending")?).ok()?;
+    Some(MempoolStats {
+        tx_count: pending.try_into().ok()?,
+        vsize: None,
+    })
+}
@@ -0,0 +1,113 @@
+//! Fault injection in the HTTP transport (`chaos` feature)
+//!
+//! With a `Chaos` installed, every attempt `http::send` makes may be replaced
+//! by a synthetic failure instead of reaching the provider: a 429 rate limit,
+//! a timeout after the `--timeout` has elapsed, or a 200 response whose body
+//! is cut off mid-document. The faults go through the same retry policy and
+//! session accounting as real ones, so projects embedding the library can
+//! exercise their own retry and alerting logic against them:
+//!
+//! ```no_run
+//! use wallet_balance::chaos::{self, Chaos};
+//!
+//! chaos::set(Some("429=0.2,timeout=0.05,malformed=0.05".parse().unwrap()));
+//! ```
+//!
+//! The CLI takes the same spec with the hidden `--chaos` flag.
+
+use std::sync::RwLock;
+
+use crate::WalletError;
+
+/// Probability of each injected fault per request attempt
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Chaos {
+    /// Answer with `429 Too Many Requests`
+    pub rate_limited: f64,
+    /// Fail as timed out once the per-attempt timeout has passed
+    pub timeout: f64,
+    /// Answer `200 OK` with a truncated JSON body
+    pub malformed: f64,
+}
+
+/// A fault `roll` picked for one attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Fault {
+    RateLimited,
+    Timeout,
+    Malformed,
+}
+
+/// Body of an injected malformed response: the start of a JSON document, cut off
+pub(crate) const MALFORMED_BODY: &[u8] = br#"{"chaos":"truncated"#;
+
+static CHAOS: RwLock<Option<Chaos>> = RwLock::new(None);
+
+/// Install the fault probabilities `http::send` applies; `None` turns injection off
+pub fn set(chaos: Option<Chaos>) {
+    *CHAOS.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = chaos;
+}
+
+/// The installed fault probabilities, if any
+pub fn active() -> Option<Chaos> {
+    *CHAOS.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Pick the fault, if any, to inject into the next attempt
+pub(crate) fn roll() -> Option<Fault> {
+    let chaos = active()?;
+    let roll = crate::http::random_fraction();
+    let mut threshold = 0.0;
+    for (probability, fault) in [
+        (chaos.rate_limited, Fault::RateLimited),
+        (chaos.timeout, Fault::Timeout),
+        (chaos.malformed, Fault::Malformed),
+    ] {
+        threshold += probability;
+        if roll < threshold {
+            return Some(fault);
+        }
+    }
+    None
+}
+
+impl std::str::FromStr for Chaos {
+    type Err = WalletError;
+
+    /// Parse `429=P,timeout=P,malformed=P`, each optional, with probabilities summing to at most 1
+    fn from_str(s: &str) -> Result<Self, WalletError> {
+        let mut chaos = Chaos::default();
+        for entry in s.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (fault, probability) = entry
+                .split_once('=')
+                .ok_or_else(|| {
+                    WalletError::ParseError(format!("Invalid chaos entry: {} (expected e.g. 429=0.1)", entry))
+                })?;
+            let probability: f64 = probability
+                .trim()
+                .parse()
+                .map_err(|_| WalletError::ParseError(format!("Invalid chaos probability: {}", probability)))?;
+            if !(0.0..=1.0).contains(&probability) {
+                return Err(WalletError::ParseError(format!(
+                    "Chaos probability {} is not between 0 and 1",
+                    probability
+                )));
+            }
+            match fault.trim().to_lowercase().as_str() {
+                "429" | "rate-limit" => chaos.rate_limited = probability,
+                "timeout" => chaos.timeout = probability,
+                "malformed" => chaos.malformed = probability,
+                other => {
+                    return Err(WalletError::ParseError(format!(
+                        "Unsupported chaos fault: {} (expected 429, timeout or malformed)",
+                        other
+                    )))
+                }
+            }
+        }
+        if chaos.rate_limited + chaos.timeout + chaos.malformed > 1.0 {
+            return Err(WalletError::ParseError("Chaos probabilities add up to more than 1".to_string()));
+        }
+        Ok(chaos)
+    }
+}
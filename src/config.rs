@@ -0,0 +1,798 @@
+//! Configuration file: locations, `config init` and the settings it holds
+//!
+//! Paths follow each platform's conventions via the `directories` crate:
+//!
+//! | Platform | Config file |
+//! |----------|-------------|
+//! | Linux    | `$XDG_CONFIG_HOME/wallet-balance/config.toml` (`~/.config/...`) |
+//! | macOS    | `~/Library/Application Support/wallet-balance/config.toml` |
+//! | Windows  | `%APPDATA%\wallet-balance\config\config.toml` |
+//!
+//! Older releases documented `~/.wallet-balance.toml` and, on every platform,
+//! `~/.config/wallet-balance/config.toml`; `init` moves such a file into place
+//! instead of writing a fresh one.
+//!
+//! The parsed file is a `Config`. The CLI loads it with `load_active`, which
+//! installs it with `set_active`; wallet modules read their endpoints from
+//! `active()`, which falls back to the built-in defaults for anything the file
+//! leaves out. Long-running modes re-read the same file with `reload`.
+//! Environment variables (`ENV_OVERRIDES`) take precedence over the file, so
+//! CI jobs and containers can point at private endpoints without one.
+
+use anyhow::{Context, Result};
+use directories::{BaseDirs, ProjectDirs};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::address_book::BookEntry;
+use crate::api_keys::ApiKey;
+use crate::cache::{self, CacheSpec};
+use crate::exchanges::ExchangeAddress;
+use crate::sink::Sink;
+use crate::subscriptions::{self, WebhookPolicy};
+use crate::provider::{self, ProviderRegistry};
+use crate::{bitcoin_wallet, evm_wallet, history, litecoin_wallet, unstoppable, watch, Network, WalletError};
+
+/// File name of the config file inside the config directory
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Service name of exported spans when `[telemetry]` sets none
+pub const DEFAULT_SERVICE_NAME: &str = "wallet-balance";
+
+/// Contents written by `config init`
+pub const DEFAULT_CONFIG: &str = r#"# wallet-balance configuration
+#
+# Created by `wallet-balance config init`. Every setting is optional:
+# anything left out keeps its built-in default, and command-line flags
+# always take precedence over this file.
+#
+# Run `wallet-balance config init` again at any time to print where the
+# config, data and cache directories live on this machine.
+
+# Network and address checked by a bare `wallet-balance` run; -n and -a
+# override them
+# default_network = "bitcoin"
+# default_address = "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"
+
+# Deliver the output of every run here instead of stdout: a file path, a
+# webhook URL (POST), or an s3:// or gs:// object; --output-to overrides it
+# output_to = "s3://reports/wallet-balance/latest.json"
+
+# RPC endpoints of the EVM networks
+[rpc]
+# ethereum = "https://cloudflare-eth.com"
+# polygon = "https://polygon-rpc.com"
+# arbitrum = "https://arb1.arbitrum.io/rpc"
+# optimism = "https://mainnet.optimism.io"
+# base = "https://mainnet.base.org"
+# bsc = "https://bsc-dataseed.bnbchain.org"
+# avalanche = "https://api.avax.network/ext/bc/C/rpc"
+# fantom = "https://rpcapi.fantom.network"
+# gnosis = "https://rpc.gnosischain.com"
+
+# Endpoints an EVM network fails over to, in order, when its [rpc] endpoint
+# fails to connect, answers 5xx or rate limits. Without an entry, networks on
+# their built-in endpoint fail over to public ones; an [rpc] endpoint of your
+# own fails over only to what is listed here
+[rpc_fallbacks]
+# ethereum = ["https://ethereum-rpc.publicnode.com", "https://eth.drpc.org"]
+
+[bitcoin]
+# Esplora-compatible explorer API for mainnet
+# api = "https://blockstream.info/api"
+# Esplora instance for batch lookups, e.g. a local one without rate limits
+# batch_api = "http://127.0.0.1:3002"
+
+[litecoin]
+# Esplora-compatible explorer API for Litecoin
+# api = "https://litecoinspace.org/api"
+
+[tron]
+# TronGrid API key, sent as TRON-PRO-API-KEY for higher rate limits
+# api_key = ""
+
+[unstoppable]
+# Resolution API key, needed to look up Unstoppable Domains names (.crypto, .nft, .x, ...)
+# api_key = ""
+# api = "https://api.unstoppabledomains.com/resolve"
+
+[etherscan]
+# Etherscan API key; `history` lists EVM transactions through the V2 API with
+# it, and only ERC-20 transfers read from RPC logs without
+# api_key = ""
+# api = "https://api.etherscan.io/v2/api"
+
+# Exchange wallets to warn about, on top of the built-in list; an address
+# ending in * matches every address with that prefix
+# [[exchange_addresses]]
+# network = "ethereum"
+# address = "0x28C6c06298d514Db089934071355E5743bf21d60"
+# exchange = "Binance"
+
+# Addresses you send to; looking up an address that matches one at both ends
+# but differs in the middle (address poisoning) prints a warning
+# [[address_book]]
+# network = "ethereum"
+# address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+# name = "Savings"
+
+# Keys `serve --listen` requires on /balance and /graphql; without any entry
+# the server is open. requests_per_minute and networks are optional limits
+# [[api_keys]]
+# name = "payments"
+# key = "change-me"
+# requests_per_minute = 120
+# networks = ["bitcoin", "ethereum"]
+
+# Cache of live balance lookups: none, memory, disk, redis://host[:port][/db]
+# or memcached://host[:port]. Share a Redis or memcached between the pods of a
+# scaled-out `serve` so each address reaches the provider once per ttl
+# [cache]
+# backend = "redis://localhost:6379"
+# ttl = "30s"
+
+# OpenTelemetry collector `serve` and `--watch` send their request spans to,
+# over OTLP/HTTP (needs a build with the `otel` feature)
+# [telemetry]
+# otlp_endpoint = "http://localhost:4318"
+# service_name = "wallet-balance"
+
+# Webhooks of `serve --listen`. A webhook URL whose host resolves to a
+# loopback, private or link-local address is refused unless listed here, and
+# each API key may hold max_per_owner subscriptions (100 by default)
+# [webhooks]
+# allowed_hosts = ["hooks.internal", "127.0.0.1"]
+# max_per_owner = 100
+"#;
+
+/// Settings read from the config file; every field is optional
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Network used when `--network` is not given
+    pub default_network: Option<String>,
+    /// Address (or comma-separated addresses) checked when `--address` is not given
+    pub default_address: Option<String>,
+    /// Destination of the output when `--output-to` is not given
+    pub output_to: Option<String>,
+    pub rpc: RpcEndpoints,
+    pub rpc_fallbacks: RpcFallbacks,
+    pub bitcoin: BitcoinSettings,
+    pub litecoin: LitecoinSettings,
+    pub tron: TronSettings,
+    pub unstoppable: UnstoppableSettings,
+    pub etherscan: EtherscanSettings,
+    /// `[[exchange_addresses]]`: extra exchange wallets to warn about
+    pub exchange_addresses: Vec<ExchangeAddressEntry>,
+    /// `[[address_book]]`: addresses the user sends to, checked for lookalikes
+    pub address_book: Vec<AddressBookEntry>,
+    /// `[[api_keys]]`: keys the HTTP server requires, with their limits
+    pub api_keys: Vec<ApiKeyEntry>,
+    pub cache: CacheSettings,
+    pub telemetry: TelemetrySettings,
+    pub webhooks: WebhookSettings,
+}
+
+/// `[rpc]`: JSON-RPC endpoints of the EVM networks
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcEndpoints {
+    pub ethereum: Option<String>,
+    pub polygon: Option<String>,
+    pub arbitrum: Option<String>,
+    pub optimism: Option<String>,
+    pub base: Option<String>,
+    pub bsc: Option<String>,
+    pub avalanche: Option<String>,
+    pub fantom: Option<String>,
+    pub gnosis: Option<String>,
+}
+
+/// `[rpc_fallbacks]`: endpoints an EVM network fails over to, in order, replacing the built-in ones
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcFallbacks {
+    pub ethereum: Option<Vec<String>>,
+    pub polygon: Option<Vec<String>>,
+    pub arbitrum: Option<Vec<String>>,
+    pub optimism: Option<Vec<String>>,
+    pub base: Option<Vec<String>>,
+    pub bsc: Option<Vec<String>>,
+    pub avalanche: Option<Vec<String>>,
+    pub fantom: Option<Vec<String>>,
+    pub gnosis: Option<Vec<String>>,
+}
+
+/// `[bitcoin]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BitcoinSettings {
+    /// Esplora API base URL for mainnet
+    pub api: Option<String>,
+    /// Esplora instance that batch lookups switch to instead of `api`
+    pub batch_api: Option<String>,
+}
+
+/// `[litecoin]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LitecoinSettings {
+    /// Esplora-compatible explorer API
+    pub api: Option<String>,
+}
+
+/// `[tron]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TronSettings {
+    pub api_key: Option<String>,
+}
+
+/// `[unstoppable]`: the Unstoppable Domains Resolution API
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UnstoppableSettings {
+    pub api_key: Option<String>,
+    /// Resolution API base URL
+    pub api: Option<String>,
+}
+
+/// `[etherscan]`: the Etherscan V2 API, used by `history` on EVM networks
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EtherscanSettings {
+    pub api_key: Option<String>,
+    /// API endpoint
+    pub api: Option<String>,
+}
+
+/// `[cache]`: where live lookups are cached, and for how long
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheSettings {
+    /// `none`, `memory`, `disk`, or a `redis://` or `memcached://` URL
+    pub backend: Option<String>,
+    /// How long a balance is kept, as `30s`, `5m` or `1h`
+    pub ttl: Option<String>,
+}
+
+/// `[telemetry]`: OpenTelemetry export of `serve` and `--watch`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetrySettings {
+    /// OTLP/HTTP base URL of the collector
+    pub otlp_endpoint: Option<String>,
+    /// Service name the spans are reported under
+    pub service_name: Option<String>,
+}
+
+/// `[webhooks]`: where `serve` may send balance-change webhooks
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookSettings {
+    /// Hosts allowed even though they resolve to a non-public address
+    pub allowed_hosts: Vec<String>,
+    /// Subscriptions each API key may hold
+    pub max_per_owner: Option<usize>,
+}
+
+/// `[[exchange_addresses]]`: one exchange wallet, or address prefix ending in `*`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExchangeAddressEntry {
+    pub network: String,
+    pub address: String,
+    pub exchange: String,
+}
+
+/// `[[address_book]]`: one named address
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AddressBookEntry {
+    pub network: String,
+    pub address: String,
+    pub name: String,
+}
+
+/// `[[api_keys]]`: one key of the HTTP server
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKeyEntry {
+    pub name: String,
+    pub key: String,
+    pub requests_per_minute: Option<u32>,
+    pub networks: Option<Vec<String>>,
+}
+
+impl Config {
+    /// Parse the TOML text of a config file
+    pub fn from_toml(text: &str) -> Result<Self, WalletError> {
+        let config: Config = toml::from_str(text).context("Invalid config file")?;
+        config.default_network()?;
+        config.output_to()?;
+        config.exchange_addresses()?;
+        config.address_book()?;
+        config.api_keys()?;
+        config.cache()?;
+        config.cache_ttl()?;
+        config.otlp_endpoint()?;
+        config.check_rpc_fallbacks()?;
+        config.webhook_policy()?;
+        Ok(config)
+    }
+
+    /// Read the config file at `path`, which must exist
+    pub fn load(path: &Path) -> Result<Self, WalletError> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Config::from_toml(&text).with_context(|| path.display().to_string())?)
+    }
+
+    /// Read the config file from its standard location, or the defaults when there is none
+    pub fn load_default() -> Result<Self, WalletError> {
+        let path = ConfigPaths::discover()?.config_file;
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        Config::load(&path)
+    }
+
+    /// `default_network`, if one is configured
+    pub fn default_network(&self) -> Result<Option<Network>, WalletError> {
+        match self.default_network.as_deref().map(str::trim).filter(|name| !name.is_empty()) {
+            Some(name) => Ok(name.parse().map(Some).context("Invalid default_network")?),
+            None => Ok(None),
+        }
+    }
+
+    /// `output_to`, if one is configured
+    pub fn output_to(&self) -> Result<Option<Sink>, WalletError> {
+        match self.output_to.as_deref().map(str::trim).filter(|target| !target.is_empty()) {
+            Some(target) => Ok(target.parse().map(Some).context("Invalid output_to")?),
+            None => Ok(None),
+        }
+    }
+
+    /// `default_address`, if one is configured
+    pub fn default_address(&self) -> Option<&str> {
+        self.default_address.as_deref().map(str::trim).filter(|address| !address.is_empty())
+    }
+
+    /// The `[[exchange_addresses]]` entries, checked for a known network and non-empty fields
+    pub fn exchange_addresses(&self) -> Result<Vec<ExchangeAddress>, WalletError> {
+        let entries = self
+            .exchange_addresses
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let network = entry
+                    .network
+                    .parse()
+                    .with_context(|| format!("exchange_addresses entry {}: invalid network", index + 1))?;
+                let (address, exchange) = (entry.address.trim(), entry.exchange.trim());
+                if address.is_empty() || address == "*" || exchange.is_empty() {
+                    return Err(WalletError::ParseError(format!(
+                        "exchange_addresses entry {}: address and exchange must not be empty",
+                        index + 1
+                    ))
+                    .into());
+                }
+                Ok(ExchangeAddress {
+                    network,
+                    address: address.to_string().into(),
+                    exchange: exchange.to_string().into(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(entries)
+    }
+
+    /// The `[[address_book]]` entries, checked for a known network and non-empty fields
+    pub fn address_book(&self) -> Result<Vec<BookEntry>, WalletError> {
+        let entries = self
+            .address_book
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let network = entry
+                    .network
+                    .parse()
+                    .with_context(|| format!("address_book entry {}: invalid network", index + 1))?;
+                let (address, name) = (entry.address.trim(), entry.name.trim());
+                if address.is_empty() || name.is_empty() {
+                    return Err(WalletError::ParseError(format!(
+                        "address_book entry {}: address and name must not be empty",
+                        index + 1
+                    ))
+                    .into());
+                }
+                Ok(BookEntry {
+                    network,
+                    address: address.to_string(),
+                    name: name.to_string(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(entries)
+    }
+
+    /// The `[[api_keys]]` entries, checked for unique names and keys, a non-zero rate and known networks
+    pub fn api_keys(&self) -> Result<Vec<ApiKey>, WalletError> {
+        let mut keys: Vec<ApiKey> = Vec::new();
+        for (index, entry) in self.api_keys.iter().enumerate() {
+            let invalid = |reason: &str| WalletError::ParseError(format!("api_keys entry {}: {}", index + 1, reason));
+            let (name, key) = (entry.name.trim(), entry.key.trim());
+            if name.is_empty() || key.is_empty() {
+                return Err(invalid("name and key must not be empty"));
+            }
+            if keys.iter().any(|other| other.name == name || other.key == key) {
+                return Err(invalid("name and key must differ from every other entry's"));
+            }
+            if entry.requests_per_minute == Some(0) {
+                return Err(invalid("requests_per_minute must be at least 1"));
+            }
+            let networks = match &entry.networks {
+                Some(names) if names.is_empty() => return Err(invalid("networks must not be empty")),
+                Some(names) => Some(
+                    names
+                        .iter()
+                        .map(|network| network.parse())
+                        .collect::<Result<Vec<Network>, _>>()
+                        .with_context(|| format!("api_keys entry {}: invalid network", index + 1))?,
+                ),
+                None => None,
+            };
+            keys.push(ApiKey {
+                name: name.to_string(),
+                key: key.to_string(),
+                requests_per_minute: entry.requests_per_minute,
+                networks,
+            });
+        }
+        Ok(keys)
+    }
+
+    /// `[cache] backend`; nothing is cached when it is not configured
+    pub fn cache(&self) -> Result<CacheSpec, WalletError> {
+        match self.cache.backend.as_deref().map(str::trim).filter(|backend| !backend.is_empty()) {
+            Some(backend) => Ok(backend.parse().context("Invalid cache backend")?),
+            None => Ok(CacheSpec::None),
+        }
+    }
+
+    /// `[cache] ttl`, `cache::DEFAULT_TTL` when not configured
+    pub fn cache_ttl(&self) -> Result<Duration, WalletError> {
+        match self.cache.ttl.as_deref() {
+            Some(ttl) => Ok(watch::parse_interval(ttl).context("Invalid cache ttl")?),
+            None => Ok(cache::DEFAULT_TTL),
+        }
+    }
+
+    /// `[telemetry] otlp_endpoint`, if one is configured
+    pub fn otlp_endpoint(&self) -> Result<Option<&str>, WalletError> {
+        let Some(endpoint) = self.telemetry.otlp_endpoint.as_deref().map(str::trim).filter(|url| !url.is_empty())
+        else {
+            return Ok(None);
+        };
+        let url = reqwest::Url::parse(endpoint).with_context(|| format!("Invalid otlp_endpoint: {}", endpoint))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(WalletError::ParseError(format!(
+                "Invalid otlp_endpoint: {} (expected an http:// or https:// URL)",
+                endpoint
+            )));
+        }
+        Ok(Some(endpoint))
+    }
+
+    /// `[telemetry] service_name`, `wallet-balance` when not configured
+    pub fn service_name(&self) -> &str {
+        self.telemetry
+            .service_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(DEFAULT_SERVICE_NAME)
+    }
+
+    /// The `[webhooks]` settings, checked for empty hosts and a zero limit
+    pub fn webhook_policy(&self) -> Result<WebhookPolicy, WalletError> {
+        if self.webhooks.allowed_hosts.iter().any(|host| host.trim().is_empty()) {
+            return Err(WalletError::ParseError("webhooks: allowed_hosts must not contain empty hosts".to_string()));
+        }
+        if self.webhooks.max_per_owner == Some(0) {
+            return Err(WalletError::ParseError("webhooks: max_per_owner must be at least 1".to_string()));
+        }
+        Ok(WebhookPolicy {
+            allowed_hosts: self.webhooks.allowed_hosts.iter().map(|host| host.trim().to_string()).collect(),
+            max_per_owner: self.webhooks.max_per_owner.unwrap_or(subscriptions::DEFAULT_MAX_PER_OWNER),
+        })
+    }
+
+    /// JSON-RPC endpoint of an EVM `network`, or `None` for non-EVM networks
+    pub fn evm_rpc(&self, network: Network) -> Option<&str> {
+        let default = evm_wallet::chain(network)?.default_rpc;
+        let configured = match network {
+            Network::Ethereum => &self.rpc.ethereum,
+            Network::Polygon => &self.rpc.polygon,
+            Network::Arbitrum => &self.rpc.arbitrum,
+            Network::Optimism => &self.rpc.optimism,
+            Network::Base => &self.rpc.base,
+            Network::Bsc => &self.rpc.bsc,
+            Network::Avalanche => &self.rpc.avalanche,
+            Network::Fantom => &self.rpc.fantom,
+            Network::Gnosis => &self.rpc.gnosis,
+            _ => &None,
+        };
+        Some(configured.as_deref().unwrap_or(default))
+    }
+
+    /// `evm_rpc` followed by the endpoints to fail over to, or `None` for non-EVM networks
+    ///
+    /// The fallbacks are the `[rpc_fallbacks]` entry of the network, else the
+    /// chain's built-in public endpoints. An `[rpc]` endpoint of its own, often
+    /// a private node, gets no built-in fallbacks, so lookups never leak to a
+    /// public endpoint unless the config lists one.
+    pub fn evm_rpcs(&self, network: Network) -> Option<Vec<&str>> {
+        let primary = self.evm_rpc(network)?;
+        let (configured, fallbacks) = match network {
+            Network::Ethereum => (&self.rpc.ethereum, &self.rpc_fallbacks.ethereum),
+            Network::Polygon => (&self.rpc.polygon, &self.rpc_fallbacks.polygon),
+            Network::Arbitrum => (&self.rpc.arbitrum, &self.rpc_fallbacks.arbitrum),
+            Network::Optimism => (&self.rpc.optimism, &self.rpc_fallbacks.optimism),
+            Network::Base => (&self.rpc.base, &self.rpc_fallbacks.base),
+            Network::Bsc => (&self.rpc.bsc, &self.rpc_fallbacks.bsc),
+            Network::Avalanche => (&self.rpc.avalanche, &self.rpc_fallbacks.avalanche),
+            Network::Fantom => (&self.rpc.fantom, &self.rpc_fallbacks.fantom),
+            Network::Gnosis => (&self.rpc.gnosis, &self.rpc_fallbacks.gnosis),
+            _ => (&None, &None),
+        };
+        let fallbacks: Vec<&str> = match (fallbacks, configured) {
+            (Some(fallbacks), _) => fallbacks.iter().map(|url| url.trim()).collect(),
+            (None, Some(_)) => Vec::new(),
+            (None, None) => evm_wallet::chain(network)?.fallback_rpcs.to_vec(),
+        };
+        let mut endpoints = vec![primary];
+        endpoints.extend(fallbacks.into_iter().filter(|url| !url.is_empty() && *url != primary));
+        Some(endpoints)
+    }
+
+    /// Check that every `[rpc_fallbacks]` entry is an http(s) URL
+    fn check_rpc_fallbacks(&self) -> Result<(), WalletError> {
+        for network in Network::ALL {
+            for url in self.evm_rpcs(network).unwrap_or_default().into_iter().skip(1) {
+                let valid = reqwest::Url::parse(url).is_ok_and(|parsed| matches!(parsed.scheme(), "http" | "https"));
+                if !valid {
+                    return Err(WalletError::ParseError(format!(
+                        "Invalid rpc_fallbacks.{} endpoint: {} (expected an http:// or https:// URL)",
+                        network, url
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Esplora API base URL for Bitcoin mainnet
+    pub fn bitcoin_api(&self) -> &str {
+        self.bitcoin.api.as_deref().unwrap_or(bitcoin_wallet::BLOCKCHAIN_INFO_API)
+    }
+
+    /// Esplora instance for Bitcoin mainnet batches, if one is configured
+    pub fn bitcoin_batch_api(&self) -> Option<&str> {
+        self.bitcoin.batch_api.as_deref().filter(|api| !api.is_empty())
+    }
+
+    /// Esplora API for Litecoin, litecoinspace.org when not configured
+    pub fn litecoin_api(&self) -> &str {
+        self.litecoin.api.as_deref().filter(|api| !api.is_empty()).unwrap_or(litecoin_wallet::LITECOINSPACE_API)
+    }
+
+    /// TronGrid API key, if one is configured
+    pub fn trongrid_api_key(&self) -> Option<&str> {
+        self.tron.api_key.as_deref().filter(|key| !key.is_empty())
+    }
+
+    /// Unstoppable Domains Resolution API key, if one is configured
+    pub fn unstoppable_api_key(&self) -> Option<&str> {
+        self.unstoppable.api_key.as_deref().filter(|key| !key.is_empty())
+    }
+
+    /// Unstoppable Domains Resolution API base URL
+    pub fn unstoppable_api(&self) -> &str {
+        self.unstoppable.api.as_deref().unwrap_or(unstoppable::RESOLUTION_API)
+    }
+
+    /// Etherscan API key, if one is configured
+    pub fn etherscan_api_key(&self) -> Option<&str> {
+        self.etherscan.api_key.as_deref().filter(|key| !key.is_empty())
+    }
+
+    /// Etherscan V2 API endpoint
+    pub fn etherscan_api(&self) -> &str {
+        self.etherscan.api.as_deref().unwrap_or(history::ETHERSCAN_API)
+    }
+}
+
+/// Environment variables that override config file settings, with the setting each one replaces
+pub const ENV_OVERRIDES: [(&str, &str); 14] = [
+    ("WALLET_BALANCE_ETH_RPC", "rpc.ethereum"),
+    ("WALLET_BALANCE_POLYGON_RPC", "rpc.polygon"),
+    ("WALLET_BALANCE_ARBITRUM_RPC", "rpc.arbitrum"),
+    ("WALLET_BALANCE_OPTIMISM_RPC", "rpc.optimism"),
+    ("WALLET_BALANCE_BASE_RPC", "rpc.base"),
+    ("WALLET_BALANCE_BSC_RPC", "rpc.bsc"),
+    ("WALLET_BALANCE_AVALANCHE_RPC", "rpc.avalanche"),
+    ("WALLET_BALANCE_FANTOM_RPC", "rpc.fantom"),
+    ("WALLET_BALANCE_GNOSIS_RPC", "rpc.gnosis"),
+    ("WALLET_BALANCE_BTC_API", "bitcoin.api"),
+    ("WALLET_BALANCE_BTC_BATCH_API", "bitcoin.batch_api"),
+    ("WALLET_BALANCE_TRONGRID_KEY", "tron.api_key"),
+    ("WALLET_BALANCE_UD_KEY", "unstoppable.api_key"),
+    ("WALLET_BALANCE_ETHERSCAN_KEY", "etherscan.api_key"),
+];
+
+impl Config {
+    /// Apply `ENV_OVERRIDES` from the process environment
+    pub fn apply_env_overrides(&mut self) {
+        self.apply_overrides(|name| std::env::var(name).ok());
+    }
+
+    /// Apply `ENV_OVERRIDES`, reading each variable with `lookup`; empty values are ignored
+    pub fn apply_overrides(&mut self, lookup: impl Fn(&str) -> Option<String>) {
+        for (variable, setting) in ENV_OVERRIDES {
+            let Some(value) = lookup(variable).filter(|value| !value.trim().is_empty()) else {
+                continue;
+            };
+            let slot = match setting {
+                "rpc.ethereum" => &mut self.rpc.ethereum,
+                "rpc.polygon" => &mut self.rpc.polygon,
+                "rpc.arbitrum" => &mut self.rpc.arbitrum,
+                "rpc.optimism" => &mut self.rpc.optimism,
+                "rpc.base" => &mut self.rpc.base,
+                "rpc.bsc" => &mut self.rpc.bsc,
+                "rpc.avalanche" => &mut self.rpc.avalanche,
+                "rpc.fantom" => &mut self.rpc.fantom,
+                "rpc.gnosis" => &mut self.rpc.gnosis,
+                "bitcoin.api" => &mut self.bitcoin.api,
+                "bitcoin.batch_api" => &mut self.bitcoin.batch_api,
+                "tron.api_key" => &mut self.tron.api_key,
+                "unstoppable.api_key" => &mut self.unstoppable.api_key,
+                "etherscan.api_key" => &mut self.etherscan.api_key,
+                _ => unreachable!("ENV_OVERRIDES names a known setting"),
+            };
+            *slot = Some(value.trim().to_string());
+        }
+    }
+}
+
+static ACTIVE: RwLock<Option<Arc<Config>>> = RwLock::new(None);
+
+/// The `path` given to the last `load_active`
+static SOURCE: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Install `config` as the settings every wallet module reads
+pub fn set_active(config: Config) {
+    *ACTIVE.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Arc::new(config));
+}
+
+/// The installed settings, or the defaults when none were installed
+pub fn active() -> Arc<Config> {
+    ACTIVE
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+/// Load the config file at `path` (or the standard location), apply `ENV_OVERRIDES`
+/// and install the result along with a fresh provider registry
+///
+/// On error the previously installed settings stay in place.
+pub fn load_active(path: Option<&Path>) -> Result<(), WalletError> {
+    let mut config = match path {
+        Some(path) => Config::load(path)?,
+        None => Config::load_default()?,
+    };
+    config.apply_env_overrides();
+    *SOURCE.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = path.map(Path::to_path_buf);
+    set_active(config);
+    provider::set_registry(ProviderRegistry::with_defaults());
+    Ok(())
+}
+
+/// Re-read the file the last `load_active` read, picking up edited endpoints and keys
+///
+/// Lookups already in flight finish with the settings they started with.
+pub fn reload() -> Result<(), WalletError> {
+    let path = SOURCE.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+    load_active(path.as_deref())
+}
+
+/// Where the CLI keeps its files on this machine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigPaths {
+    pub config_file: PathBuf,
+    pub data_dir: PathBuf,
+    pub cache_dir: PathBuf,
+    /// Pre-`directories` locations checked for migration, in priority order
+    pub legacy_files: Vec<PathBuf>,
+}
+
+impl ConfigPaths {
+    /// Resolve the per-OS locations for the current user
+    pub fn discover() -> Result<Self, WalletError> {
+        let project = ProjectDirs::from("", "", "wallet-balance")
+            .ok_or_else(|| anyhow::anyhow!("Could not determine the home directory"))?;
+        let config_file = project.config_dir().join(CONFIG_FILE_NAME);
+
+        let legacy_files = BaseDirs::new()
+            .map(|base| {
+                let home = base.home_dir();
+                vec![
+                    home.join(".config").join("wallet-balance").join(CONFIG_FILE_NAME),
+                    home.join(".wallet-balance.toml"),
+                ]
+            })
+            .unwrap_or_default()
+            .into_iter()
+            // On Linux the XDG legacy path is the current one
+            .filter(|legacy| *legacy != config_file)
+            .collect();
+
+        Ok(ConfigPaths {
+            config_file,
+            data_dir: project.data_dir().to_path_buf(),
+            cache_dir: project.cache_dir().to_path_buf(),
+            legacy_files,
+        })
+    }
+}
+
+/// What `init` did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InitOutcome {
+    /// Wrote `DEFAULT_CONFIG`
+    Created,
+    /// Moved an existing legacy file into place
+    Migrated { from: PathBuf },
+    /// A config file was already present and was left alone
+    AlreadyExists,
+}
+
+/// Create the config file (and data/cache directories) if they do not exist yet
+///
+/// A legacy config file is moved into place rather than replaced. With
+/// `force`, an existing config file is overwritten with the defaults.
+pub fn init(paths: &ConfigPaths, force: bool) -> Result<InitOutcome, WalletError> {
+    for dir in [&paths.data_dir, &paths.cache_dir] {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    if let Some(parent) = paths.config_file.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    if paths.config_file.exists() && !force {
+        return Ok(InitOutcome::AlreadyExists);
+    }
+
+    if !paths.config_file.exists() {
+        if let Some(legacy) = paths.legacy_files.iter().find(|legacy| legacy.is_file()) {
+            move_file(legacy, &paths.config_file)?;
+            return Ok(InitOutcome::Migrated { from: legacy.clone() });
+        }
+    }
+
+    std::fs::write(&paths.config_file, DEFAULT_CONFIG)
+        .with_context(|| format!("Failed to write {}", paths.config_file.display()))?;
+    Ok(InitOutcome::Created)
+}
+
+/// Rename, falling back to copy-and-delete across filesystems
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to).with_context(|| format!("Failed to copy {} to {}", from.display(), to.display()))?;
+    std::fs::remove_file(from).with_context(|| format!("Failed to remove {}", from.display()))
+}
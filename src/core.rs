@@ -0,0 +1,11 @@
+//! Validation and encoding rules that build without std
+//!
+//! Firmware and embedded signers can depend on this crate with
+//! `default-features = false` and get exactly the checks the CLI enforces:
+//! Base58Check, Bech32/Bech32m and EIP-55 checksums, and the conversion
+//! between decimal balances and integer base units. Everything here needs
+//! only `core` and `alloc` and never touches the network; the std modules
+//! (`address`, `units`) re-export these items rather than keep their own copies.
+
+pub mod address;
+pub mod units;
@@ -0,0 +1,160 @@
+//! Address checksums: Base58Check, Bech32/Bech32m segwit and EIP-55
+//!
+//! These are the rules the CLI applies before any request is made, so a
+//! signer that validates with them rejects exactly the addresses the CLI does.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use anyhow::Result;
+use base58::{FromBase58, ToBase58};
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+/// Render an EVM address with its EIP-55 mixed-case checksum
+pub fn to_checksum_address(address: &str) -> Result<String> {
+    let hex_lower = evm_hex_body(address)?.to_lowercase();
+    let hash = Keccak256::digest(hex_lower.as_bytes());
+
+    let checksummed: String = hex_lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if c.is_ascii_alphabetic() && nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    Ok(format!("0x{}", checksummed))
+}
+
+/// Strip the 0x prefix of an EVM address and check it is 20 bytes of hex
+pub(crate) fn evm_hex_body(address: &str) -> Result<&str> {
+    let body = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+        .unwrap_or(address);
+    if body.len() != 40 || !body.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow::anyhow!("Invalid EVM address (expected 40 hex characters)"));
+    }
+    Ok(body)
+}
+
+/// Double SHA256 Base58Check encoding of `version || payload`
+pub fn encode_base58check(version: &[u8], payload: &[u8]) -> String {
+    let mut data = version.to_vec();
+    data.extend_from_slice(payload);
+    let checksum = Sha256::digest(Sha256::digest(&data));
+    data.extend_from_slice(&checksum[..4]);
+    data.to_base58()
+}
+
+/// Decode Base58Check and verify its checksum, returning `version || payload`
+pub fn decode_base58check(encoded: &str) -> Result<Vec<u8>> {
+    let mut data = encoded
+        .from_base58()
+        .map_err(|_| anyhow::anyhow!("Invalid Base58 encoding"))?;
+    if data.len() < 5 {
+        return Err(anyhow::anyhow!("Base58Check payload too short"));
+    }
+
+    let checksum = data.split_off(data.len() - 4);
+    if Sha256::digest(Sha256::digest(&data))[..4] != checksum[..] {
+        return Err(anyhow::anyhow!("Invalid Base58Check checksum"));
+    }
+
+    Ok(data)
+}
+
+/// Output script an address pays to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptType {
+    /// Legacy pay-to-pubkey-hash (`1...`)
+    P2pkh,
+    /// Pay-to-script-hash (`3...`)
+    P2sh,
+    /// Native segwit v0 key hash (`bc1q...`, 20-byte program)
+    P2wpkh,
+    /// Native segwit v0 script hash (`bc1q...`, 32-byte program)
+    P2wsh,
+    /// Taproot (`bc1p...`)
+    P2tr,
+    /// A witness version without a defined output type yet (v2-v16)
+    Witness(u8),
+}
+
+/// Address encoding parameters of a Bitcoin chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitcoinParams {
+    /// Base58Check version byte of P2PKH addresses
+    pub p2pkh_version: u8,
+    /// Base58Check version byte of P2SH addresses
+    pub p2sh_version: u8,
+    /// Bech32 human-readable part of segwit addresses
+    pub hrp: &'static str,
+}
+
+impl BitcoinParams {
+    pub const MAINNET: BitcoinParams = BitcoinParams { p2pkh_version: 0x00, p2sh_version: 0x05, hrp: "bc" };
+    /// Testnet3, testnet4 and signet share one encoding
+    pub const TESTNET: BitcoinParams = BitcoinParams { p2pkh_version: 0x6f, p2sh_version: 0xc4, hrp: "tb" };
+}
+
+/// Decode a Bitcoin `address` for the chain of `params` and return the script it pays to
+///
+/// Legacy and P2SH addresses must carry a valid Base58Check checksum and a
+/// 20-byte hash; segwit addresses must be Bech32 (v0) or Bech32m (v1 and
+/// later, BIP-350) with the chain's prefix and a valid program length.
+pub fn decode_bitcoin(params: &BitcoinParams, address: &str) -> Result<ScriptType> {
+    let separator = address.rfind('1').unwrap_or(0);
+    let segwit = decode_segwit(address);
+    if !address[..separator].eq_ignore_ascii_case(params.hrp) {
+        if let Ok((hrp, _, _)) = segwit {
+            return Err(anyhow::anyhow!(
+                "Segwit address for another network ({}1..., expected {}1...)",
+                hrp,
+                params.hrp
+            ));
+        }
+    } else {
+        let (_, version, program) = segwit?;
+        return match (version, program.len()) {
+            (0, 20) => Ok(ScriptType::P2wpkh),
+            (0, 32) => Ok(ScriptType::P2wsh),
+            (1, 32) => Ok(ScriptType::P2tr),
+            // Other lengths are valid for later versions, just not yet defined
+            (version, _) => Ok(ScriptType::Witness(version)),
+        };
+    }
+
+    let data = decode_base58check(address)?;
+    if data.len() != 21 {
+        return Err(anyhow::anyhow!(
+            "Invalid legacy address payload: {} bytes (expected a 20-byte hash)",
+            data.len() - 1
+        ));
+    }
+    match data[0] {
+        version if version == params.p2pkh_version => Ok(ScriptType::P2pkh),
+        version if version == params.p2sh_version => Ok(ScriptType::P2sh),
+        version => Err(anyhow::anyhow!(
+            "Address version byte 0x{:02x} belongs to another network (expected 0x{:02x} or 0x{:02x})",
+            version,
+            params.p2pkh_version,
+            params.p2sh_version
+        )),
+    }
+}
+
+/// Decode a segwit address (BIP-173/BIP-350) into its lowercase prefix, witness version and program
+///
+/// The checksum variant must match the version: Bech32 for v0, Bech32m after.
+pub fn decode_segwit(address: &str) -> Result<(String, u8, Vec<u8>)> {
+    let (hrp, version, program) = bech32::segwit::decode(address)
+        .map_err(|e| anyhow::anyhow!("Invalid bech32 checksum or encoding: {}", e))?;
+    Ok((hrp.to_lowercase(), version.to_u8(), program))
+}
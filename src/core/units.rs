@@ -0,0 +1,294 @@
+//! Conversion between decimal balance strings and integer base units
+//!
+//! Integer base units (satoshi, wei, sun) compare without floating point
+//! rounding; `U256` holds EVM balances at their full 256-bit width.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use anyhow::{Context, Result};
+
+/// Format an integer amount of base units as a decimal string
+///
+/// Trailing fractional zeros are trimmed, so `1_500_000` with 6 decimals is `"1.5"`.
+pub fn format_units(value: u128, decimals: u32) -> String {
+    format_units_u256(U256::from(value), decimals)
+}
+
+/// `format_units` for amounts at the full width of EVM balances
+pub fn format_units_u256(value: U256, decimals: u32) -> String {
+    let digits = value.to_string();
+    if digits == "0" {
+        return digits;
+    }
+
+    let decimals = decimals as usize;
+    let digits = format!("{:0>width$}", digits, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        return whole.to_string();
+    }
+    format!("{}.{}", whole, fraction)
+}
+
+/// `part` as a percentage of `whole` with 4 decimal places, e.g. `"12.5000"`; `None` when `whole` is zero
+///
+/// Computed in floating point: a share is read at a glance, not reconciled.
+pub fn percent_of(part: U256, whole: U256) -> Option<String> {
+    if whole.is_zero() {
+        return None;
+    }
+    let part: f64 = part.to_string().parse().ok()?;
+    let whole: f64 = whole.to_string().parse().ok()?;
+    Some(format!("{:.4}", part / whole * 100.0))
+}
+
+/// Signed difference from `before` to `after` in whole units, e.g. `"+0.5"`, `"-2"` or `"0"`
+pub fn format_delta(before: U256, after: U256, decimals: u32) -> String {
+    match after.cmp(&before) {
+        core::cmp::Ordering::Equal => "0".to_string(),
+        core::cmp::Ordering::Greater => format!("+{}", format_units_u256(after.abs_diff(before), decimals)),
+        core::cmp::Ordering::Less => format!("-{}", format_units_u256(after.abs_diff(before), decimals)),
+    }
+}
+
+/// Unsigned 256-bit integer, the width of EVM balances and `uint256` return values
+///
+/// Only what balance handling needs: parsing hex quantities and ABI words,
+/// comparison and differences, narrowing to `u128` and decimal formatting via
+/// `Display`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct U256([u64; 4]); // Most significant limb first, so the derived order is numeric
+
+impl U256 {
+    pub const ZERO: U256 = U256([0; 4]);
+    pub const MAX: U256 = U256([u64::MAX; 4]);
+
+    /// Parse a hex quantity such as `"0x1a"` (the `0x` prefix is optional)
+    pub fn from_hex(hex_str: &str) -> Result<Self> {
+        let digits = hex_str.trim().trim_start_matches("0x").trim_start_matches("0X");
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow::anyhow!("Invalid hex quantity '{}'", hex_str));
+        }
+        let digits = digits.trim_start_matches('0');
+        if digits.len() > 64 {
+            return Err(anyhow::anyhow!("Hex quantity '{}' does not fit in 256 bits", hex_str));
+        }
+
+        let padded = format!("{:0>64}", digits);
+        let mut limbs = [0u64; 4];
+        for (limb, chunk) in limbs.iter_mut().zip(padded.as_bytes().chunks(16)) {
+            let chunk = core::str::from_utf8(chunk).expect("hex digits are ASCII");
+            *limb = u64::from_str_radix(chunk, 16).expect("16 hex digits fit in u64");
+        }
+        Ok(U256(limbs))
+    }
+
+    /// A 32-byte big-endian word, as ABI-encoded `uint256` values are
+    pub fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        let mut limbs = [0u64; 4];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks(8)) {
+            *limb = u64::from_be_bytes(chunk.try_into().expect("8-byte chunk"));
+        }
+        U256(limbs)
+    }
+
+    /// The value as a `u128`, or `None` if it is larger
+    pub fn to_u128(&self) -> Option<u128> {
+        let [a, b, c, d] = self.0;
+        (a == 0 && b == 0).then(|| (u128::from(c) << 64) | u128::from(d))
+    }
+
+    /// Parse a string of decimal digits such as `"1500000"`
+    pub fn from_dec_str(digits: &str) -> Result<Self> {
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(anyhow::anyhow!("Invalid integer '{}'", digits));
+        }
+        let mut limbs = [0u64; 4];
+        for digit in digits.bytes() {
+            let mut carry = u128::from(digit - b'0');
+            for limb in limbs.iter_mut().rev() {
+                let current = u128::from(*limb) * 10 + carry;
+                *limb = current as u64;
+                carry = current >> 64;
+            }
+            if carry != 0 {
+                return Err(anyhow::anyhow!("'{}' does not fit in 256 bits", digits));
+            }
+        }
+        Ok(U256(limbs))
+    }
+
+    /// `self + other`, or `U256::MAX` if the sum does not fit
+    pub fn saturating_add(self, other: U256) -> U256 {
+        let mut limbs = [0u64; 4];
+        let mut carry = false;
+        for index in (0..4).rev() {
+            let (sum, over) = self.0[index].overflowing_add(other.0[index]);
+            let (sum, over_again) = sum.overflowing_add(u64::from(carry));
+            limbs[index] = sum;
+            carry = over || over_again;
+        }
+        match carry {
+            true => U256::MAX,
+            false => U256(limbs),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == U256::ZERO
+    }
+
+    /// `|self - other|`
+    pub fn abs_diff(self, other: U256) -> U256 {
+        let (high, low) = match self >= other {
+            true => (self.0, other.0),
+            false => (other.0, self.0),
+        };
+        let mut limbs = [0u64; 4];
+        let mut borrow = false;
+        for index in (0..4).rev() {
+            let (difference, under) = high[index].overflowing_sub(low[index]);
+            let (difference, under_again) = difference.overflowing_sub(u64::from(borrow));
+            limbs[index] = difference;
+            borrow = under || under_again;
+        }
+        U256(limbs)
+    }
+}
+
+impl From<u128> for U256 {
+    fn from(value: u128) -> Self {
+        U256([0, 0, (value >> 64) as u64, value as u64])
+    }
+}
+
+impl From<u64> for U256 {
+    fn from(value: u64) -> Self {
+        U256([0, 0, 0, value])
+    }
+}
+
+impl core::fmt::Display for U256 {
+    /// Decimal digits, computed 19 at a time by long division
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        const CHUNK: u128 = 10_000_000_000_000_000_000; // 10^19, the largest power of ten in a u64
+
+        let mut limbs = self.0;
+        let mut chunks = Vec::new();
+        while limbs != [0; 4] {
+            let mut remainder: u128 = 0;
+            for limb in limbs.iter_mut() {
+                let current = (remainder << 64) | u128::from(*limb);
+                *limb = (current / CHUNK) as u64;
+                remainder = current % CHUNK;
+            }
+            chunks.push(remainder as u64);
+        }
+
+        let Some((most_significant, rest)) = chunks.split_last() else {
+            return f.pad("0");
+        };
+        let mut digits = most_significant.to_string();
+        for chunk in rest.iter().rev() {
+            digits.push_str(&format!("{:019}", chunk));
+        }
+        f.pad(&digits)
+    }
+}
+
+/// Parse a decimal string such as `"0.015"` into integer base units
+///
+/// Fails if the amount has more significant fractional digits than `decimals`.
+pub fn parse_units(amount: &str, decimals: u32) -> Result<u128> {
+    let amount = amount.trim();
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(anyhow::anyhow!("Amount cannot be empty"));
+    }
+    if !whole.chars().all(|c| c.is_ascii_digit()) || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow::anyhow!("Invalid amount '{}'", amount));
+    }
+
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(anyhow::anyhow!(
+            "Amount '{}' has more than {} decimal places",
+            amount,
+            decimals
+        ));
+    }
+
+    let scale = 10u128
+        .checked_pow(decimals)
+        .ok_or_else(|| anyhow::anyhow!("Amount '{}' is too large: {} decimals do not fit in 128 bits", amount, decimals))?;
+    let whole_units = if whole.is_empty() {
+        0
+    } else {
+        whole
+            .parse::<u128>()
+            .with_context(|| format!("Invalid amount '{}'", amount))?
+    };
+    let fraction_units = if fraction.is_empty() {
+        0
+    } else {
+        let padded = format!("{:0<width$}", fraction, width = decimals as usize);
+        padded
+            .parse::<u128>()
+            .with_context(|| format!("Invalid amount '{}'", amount))?
+    };
+
+    whole_units
+        .checked_mul(scale)
+        .and_then(|units| units.checked_add(fraction_units))
+        .ok_or_else(|| anyhow::anyhow!("Amount '{}' is too large", amount))
+}
+
+/// `parse_units` for amounts at the full width of EVM balances
+pub fn parse_units_u256(amount: &str, decimals: u32) -> Result<U256> {
+    let trimmed = amount.trim();
+    let (whole, fraction) = trimmed.split_once('.').unwrap_or((trimmed, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(anyhow::anyhow!("Amount cannot be empty"));
+    }
+    if !whole.chars().all(|c| c.is_ascii_digit()) || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow::anyhow!("Invalid amount '{}'", trimmed));
+    }
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(anyhow::anyhow!("Amount '{}' has more than {} decimal places", trimmed, decimals));
+    }
+    // 10^78 exceeds 2^256, so no more places can scale a non-zero amount
+    if decimals > 78 {
+        return Err(anyhow::anyhow!("Amount '{}' is too large", trimmed));
+    }
+
+    let whole = if whole.is_empty() { "0" } else { whole };
+    let digits = format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+    U256::from_dec_str(&digits).with_context(|| format!("Amount '{}' is too large", trimmed))
+}
+
+/// Parse an integer that may use scientific notation, e.g. `2.014e18`
+///
+/// This is the number syntax EIP-681 allows for `value` and `uint256` parameters.
+pub fn parse_scientific(number: &str) -> Result<u128> {
+    let number = number.trim();
+    let (mantissa, exponent) = match number.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (
+            mantissa,
+            exponent
+                .parse::<u32>()
+                .with_context(|| format!("Invalid exponent in '{}'", number))?,
+        ),
+        None => (number, 0),
+    };
+    if 10u128.checked_pow(exponent).is_none() {
+        return Err(anyhow::anyhow!("Amount '{}' is too large", number));
+    }
+
+    parse_units(mantissa, exponent)
+        .with_context(|| format!("'{}' is not a whole number of base units", number))
+}
+
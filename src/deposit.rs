@@ -0,0 +1,305 @@
+//! Tracking of ETH deposits from Ethereum to its rollups (`deposit-status`)
+//!
+//! A deposit through a canonical bridge is credited on L2 by a transaction
+//! the rollup derives from the L1 logs, so its hash can be rebuilt from the
+//! L1 receipt without a bridge indexer:
+//!
+//! * Optimism and Base: the `OptimismPortal` emits `TransactionDeposited`, from
+//!   which the L2 deposit transaction (type `0x7e`) is rebuilt, with a source
+//!   hash of the L1 block hash and log index.
+//! * Arbitrum One: `depositEth` makes the bridge emit `MessageDelivered` (kind
+//!   12) and the inbox `InboxMessageDelivered` with the recipient and value;
+//!   the L2 deposit transaction (type `0x64`) is keyed by the message number.
+//!
+//! The deposit is credited once that transaction has an L2 receipt. Arbitrum
+//! token gateway deposits are retryable tickets and are not tracked.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha3::{Digest, Keccak256};
+
+use crate::units::{self, U256};
+use crate::{evm_rpc, Network, WalletBalance, WalletError};
+
+/// `OptimismPortal` on Ethereum for Optimism
+pub const OPTIMISM_PORTAL: &str = "0xbeb5fc579115071764c7423a4f12edde41f106ed";
+/// `L1StandardBridge` on Ethereum for Optimism
+pub const OPTIMISM_STANDARD_BRIDGE: &str = "0x99c9fc46f92e8a1c0dec1b1747d010903e884be1";
+/// `OptimismPortal` on Ethereum for Base
+pub const BASE_PORTAL: &str = "0x49048044d57e1c92a77f79988d21fa8faf74e97e";
+/// `L1StandardBridge` on Ethereum for Base
+pub const BASE_STANDARD_BRIDGE: &str = "0x3154cf16ccdb4c6d922629664174b904d80f2c35";
+/// Arbitrum One `Bridge` on Ethereum, which emits `MessageDelivered`
+pub const ARBITRUM_BRIDGE: &str = "0x8315177ab297ba92a06054ce80a67ed4dbd7ed3a";
+/// Arbitrum One `Inbox` on Ethereum, which emits `InboxMessageDelivered`
+pub const ARBITRUM_INBOX: &str = "0x4dbd4fc535ac27206064b68ffcf827b0a60bab3f";
+
+/// Chain id of Arbitrum One, part of its deposit transactions
+const ARBITRUM_CHAIN_ID: u64 = 42161;
+/// `MessageDelivered` kind of an ETH deposit
+const ARBITRUM_ETH_DEPOSIT: u8 = 12;
+
+/// Where an L1 deposit stands on its L2
+#[derive(Debug, Clone, Serialize)]
+pub struct DepositStatus {
+    pub l1_tx: String,
+    pub l1_block: u64,
+    /// Rollup the deposit goes to
+    pub network: String,
+    pub recipient: String,
+    /// ETH deposited
+    pub amount: String,
+    /// Hash of the L2 transaction that credits the deposit
+    pub l2_tx: String,
+    pub credited: bool,
+    /// L2 block of the crediting transaction, once credited
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l2_block: Option<u64>,
+    /// The recipient's current balance on the rollup
+    pub l2_balance: WalletBalance,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Receipt {
+    #[serde(default)]
+    status: Option<String>,
+    block_number: String,
+    #[serde(default)]
+    logs: Vec<Log>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Log {
+    address: String,
+    topics: Vec<String>,
+    data: String,
+    log_index: String,
+    block_hash: String,
+}
+
+/// A deposit decoded from its L1 receipt
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Deposit {
+    network: Network,
+    recipient: String,
+    amount: U256,
+    l2_tx: String,
+}
+
+/// Look up the L1 deposit `l1_tx` and whether its rollup has credited it
+pub async fn status(l1_tx: &str) -> Result<DepositStatus, WalletError> {
+    let l1_tx = l1_tx.trim().to_lowercase();
+    if !l1_tx.strip_prefix("0x").is_some_and(|hash| hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())) {
+        return Err(WalletError::ParseError(format!(
+            "Invalid transaction hash: {} (expected 0x and 64 hex digits)",
+            l1_tx
+        )));
+    }
+    let l1_rpc = evm_rpc::rpc_url(Network::Ethereum).context("No ethereum RPC endpoint configured")?;
+    let receipt = evm_rpc::call(&l1_rpc, "eth_getTransactionReceipt", vec![json!(l1_tx)]).await?;
+    if receipt.is_null() {
+        return Err(WalletError::Other(format!("{} has no receipt on ethereum: it is unknown or still pending", l1_tx)));
+    }
+    let receipt: Receipt = serde_json::from_value(receipt).context("Failed to parse the L1 receipt")?;
+    if receipt.status.as_deref() == Some("0x0") {
+        return Err(WalletError::Other(format!("{} reverted on ethereum, so nothing was deposited", l1_tx)));
+    }
+    let deposit = decode(&receipt)?
+        .ok_or_else(|| anyhow::anyhow!("{} is not an ETH deposit to the Arbitrum, Optimism or Base bridge", l1_tx))?;
+
+    let l2_rpc = evm_rpc::rpc_url(deposit.network)
+        .with_context(|| format!("No {} RPC endpoint configured", deposit.network))?;
+    let l2_receipt = evm_rpc::call(&l2_rpc, "eth_getTransactionReceipt", vec![json!(deposit.l2_tx)]).await?;
+    let l2_block = match l2_receipt.get("blockNumber") {
+        Some(number) => Some(evm_rpc::parse_quantity(number)? as u64),
+        None => None,
+    };
+    let l2_balance = crate::get_balance(deposit.network, &deposit.recipient).await?;
+
+    Ok(DepositStatus {
+        l1_tx,
+        l1_block: evm_rpc::parse_quantity(&json!(receipt.block_number))? as u64,
+        network: deposit.network.to_string(),
+        recipient: l2_balance.address.clone(),
+        amount: units::format_units_u256(deposit.amount, Network::Ethereum.decimals()),
+        l2_tx: deposit.l2_tx,
+        credited: l2_receipt.is_object(),
+        l2_block,
+        l2_balance,
+    })
+}
+
+fn decode(receipt: &Receipt) -> Result<Option<Deposit>> {
+    let emitted = |contract: &str, signature: &str| {
+        let topic = event_topic(signature);
+        receipt.logs.iter().find(move |log| {
+            log.address.eq_ignore_ascii_case(contract) && log.topics.first().is_some_and(|t| t.eq_ignore_ascii_case(&topic))
+        })
+    };
+
+    for (network, portal, standard_bridge) in [
+        (Network::Optimism, OPTIMISM_PORTAL, OPTIMISM_STANDARD_BRIDGE),
+        (Network::Base, BASE_PORTAL, BASE_STANDARD_BRIDGE),
+    ] {
+        let Some(log) = emitted(portal, "TransactionDeposited(address,address,uint256,bytes)") else {
+            continue;
+        };
+        let (l2_tx, to, mint) = op_deposit_tx(log)?;
+        // Through the standard bridge the portal deposit goes to the L2 messenger; the event names the real recipient
+        let (recipient, amount) = match emitted(standard_bridge, "ETHDepositInitiated(address,address,uint256,bytes)") {
+            Some(initiated) => (topic_address(initiated, 2)?, U256::from_be_bytes(word(&hex_data(&initiated.data)?, 0)?)),
+            None => (to, mint),
+        };
+        return Ok(Some(Deposit {
+            network,
+            recipient,
+            amount,
+            l2_tx,
+        }));
+    }
+
+    let delivered = emitted(
+        ARBITRUM_BRIDGE,
+        "MessageDelivered(uint256,bytes32,address,uint8,address,bytes32,uint256,uint64)",
+    );
+    let message = emitted(ARBITRUM_INBOX, "InboxMessageDelivered(uint256,bytes)");
+    let (Some(delivered), Some(message)) = (delivered, message) else {
+        return Ok(None);
+    };
+    let fields = hex_data(&delivered.data)?;
+    if word(&fields, 1)?[31] != ARBITRUM_ETH_DEPOSIT {
+        return Err(anyhow::anyhow!("Only ETH deposits (depositEth) are tracked on arbitrum"));
+    }
+    let request_id = topic_bytes(delivered, 1)?;
+    // The bridge records the sender aliased, which is what the L2 transaction is from
+    let sender = word(&fields, 2)?[12..].to_vec();
+    let payload = abi_bytes(&hex_data(&message.data)?)?;
+    if payload.len() != 52 {
+        return Err(anyhow::anyhow!("Unexpected ETH deposit message of {} bytes", payload.len()));
+    }
+    let (recipient, value) = payload.split_at(20);
+
+    let fields = [
+        rlp_uint(&ARBITRUM_CHAIN_ID.to_be_bytes()),
+        rlp_bytes(&request_id),
+        rlp_bytes(&sender),
+        rlp_bytes(recipient),
+        rlp_uint(value),
+    ];
+    Ok(Some(Deposit {
+        network: Network::Arbitrum,
+        recipient: format!("0x{}", hex::encode(recipient)),
+        amount: U256::from_be_bytes(value.try_into().expect("split at 20 of 52 bytes")),
+        l2_tx: typed_tx_hash(0x64, &fields),
+    }))
+}
+
+/// Hash, recipient and minted ETH of the L2 deposit transaction for a `TransactionDeposited` log
+fn op_deposit_tx(log: &Log) -> Result<(String, String, U256)> {
+    if topic_bytes(log, 3)? != [0u8; 32] {
+        return Err(anyhow::anyhow!("Unsupported deposit version {}", log.topics[3]));
+    }
+    // Version 0 packs mint (32 bytes), value (32), gas limit (8), is-creation (1) and the calldata
+    let opaque = abi_bytes(&hex_data(&log.data)?)?;
+    if opaque.len() < 73 {
+        return Err(anyhow::anyhow!("Deposit data is only {} bytes", opaque.len()));
+    }
+    let (mint, value, gas, is_creation, data) =
+        (&opaque[..32], &opaque[32..64], &opaque[64..72], opaque[72] != 0, &opaque[73..]);
+
+    let log_index = U256::from_hex(&log.log_index)?.to_u128().unwrap_or_default();
+    let mut position = [0u8; 32];
+    position[16..].copy_from_slice(&log_index.to_be_bytes());
+    let l1_hash = [hex_data(&log.block_hash)?, position.to_vec()].concat();
+    let source_hash = keccak(&[[0u8; 32].as_slice(), &keccak(&l1_hash)].concat());
+
+    let from = topic_bytes(log, 1)?[12..].to_vec();
+    let to = topic_bytes(log, 2)?[12..].to_vec();
+    let fields = [
+        rlp_bytes(&source_hash),
+        rlp_bytes(&from),
+        rlp_bytes(if is_creation { &[] } else { &to }),
+        rlp_uint(mint),
+        rlp_uint(value),
+        rlp_uint(gas),
+        // Never a system transaction: those are the rollup's own
+        rlp_uint(&[]),
+        rlp_bytes(data),
+    ];
+    let hash = typed_tx_hash(0x7e, &fields);
+    Ok((hash, format!("0x{}", hex::encode(to)), U256::from_be_bytes(mint.try_into().expect("32-byte slice"))))
+}
+
+fn keccak(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+fn event_topic(signature: &str) -> String {
+    format!("0x{}", hex::encode(keccak(signature.as_bytes())))
+}
+
+/// `keccak256(type || rlp(fields))`, the hash of an EIP-2718 typed transaction
+fn typed_tx_hash(tx_type: u8, fields: &[Vec<u8>]) -> String {
+    format!("0x{}", hex::encode(keccak(&[vec![tx_type], rlp_list(fields)].concat())))
+}
+
+fn hex_data(data: &str) -> Result<Vec<u8>> {
+    hex::decode(data.trim_start_matches("0x")).with_context(|| format!("Invalid hex data: {}", data))
+}
+
+fn topic_bytes(log: &Log, index: usize) -> Result<Vec<u8>> {
+    let topic = log.topics.get(index).ok_or_else(|| anyhow::anyhow!("Log is missing topic {}", index))?;
+    hex_data(topic)
+}
+
+fn topic_address(log: &Log, index: usize) -> Result<String> {
+    Ok(format!("0x{}", hex::encode(&topic_bytes(log, index)?[12..])))
+}
+
+/// The `index`th 32-byte word of ABI-encoded `data`
+fn word(data: &[u8], index: usize) -> Result<[u8; 32]> {
+    data.get(index * 32..(index + 1) * 32)
+        .and_then(|word| word.try_into().ok())
+        .ok_or_else(|| anyhow::anyhow!("ABI data ends before word {}", index))
+}
+
+/// The `bytes` value ABI-encoded as the only field of `data`
+fn abi_bytes(data: &[u8]) -> Result<Vec<u8>> {
+    let offset = U256::from_be_bytes(word(data, 0)?).to_u128().unwrap_or(u128::MAX) as usize;
+    let length = data
+        .get(offset..offset.saturating_add(32))
+        .map(|length| U256::from_be_bytes(length.try_into().expect("32-byte slice")).to_u128().unwrap_or(u128::MAX))
+        .ok_or_else(|| anyhow::anyhow!("ABI bytes offset {} is out of range", offset))? as usize;
+    data.get(offset + 32..(offset + 32).saturating_add(length))
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| anyhow::anyhow!("ABI bytes of length {} overrun the data", length))
+}
+
+fn rlp_length(length: usize, offset: u8) -> Vec<u8> {
+    if length < 56 {
+        return vec![offset + length as u8];
+    }
+    let be: Vec<u8> = length.to_be_bytes().into_iter().skip_while(|&byte| byte == 0).collect();
+    [vec![offset + 55 + be.len() as u8], be].concat()
+}
+
+fn rlp_bytes(bytes: &[u8]) -> Vec<u8> {
+    match bytes {
+        [byte] if *byte < 0x80 => vec![*byte],
+        _ => [rlp_length(bytes.len(), 0x80), bytes.to_vec()].concat(),
+    }
+}
+
+/// RLP of a big-endian unsigned integer, which drops its leading zeros
+fn rlp_uint(be: &[u8]) -> Vec<u8> {
+    let start = be.iter().position(|&byte| byte != 0).unwrap_or(be.len());
+    rlp_bytes(&be[start..])
+}
+
+fn rlp_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload = items.concat();
+    [rlp_length(payload.len(), 0xc0), payload].concat()
+}
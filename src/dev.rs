@@ -0,0 +1,103 @@
+//! Local developer node support (`--dev`)
+//!
+//! Points any network at a node on the developer's machine instead of the
+//! public APIs: an Esplora/electrs instance in front of a regtest `bitcoind`,
+//! `anvil`/`hardhat` for EVM networks, or a private `java-tron` node. Address
+//! checks are relaxed to what the node itself accepts (regtest `bcrt1`
+//! addresses, any 20-byte hex address) and chain ids are not enforced.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{bitcoin_wallet, ethereum_wallet, evm_rpc, http, units, Network, WalletBalance, WalletError};
+
+/// Default anvil/hardhat JSON-RPC endpoint
+pub const DEFAULT_EVM_NODE: &str = "http://127.0.0.1:8545";
+/// Default electrs/Esplora REST endpoint for regtest
+pub const DEFAULT_BITCOIN_NODE: &str = "http://127.0.0.1:3002";
+/// Default java-tron full node HTTP endpoint
+pub const DEFAULT_TRON_NODE: &str = "http://127.0.0.1:8090";
+
+#[derive(Debug, Deserialize)]
+struct TronAccount {
+    #[serde(default)]
+    balance: u64,
+}
+
+/// Local node URL used for `network` when none is given
+pub fn default_node_url(network: Network) -> &'static str {
+    if network.bitcoin_chain().is_some() {
+        DEFAULT_BITCOIN_NODE
+    } else if network.is_tron() {
+        DEFAULT_TRON_NODE
+    } else {
+        DEFAULT_EVM_NODE
+    }
+}
+
+/// Get the balance of `address` from a local node
+///
+/// # Arguments
+///
+/// * `network` - Network whose API the node speaks
+/// * `node_url` - Node base URL, or `None` for the network's default local port
+/// * `address` - Address to check
+///
+/// # Returns
+///
+/// Returns a `WalletBalance` formatted the same way as the live module's output
+pub async fn get_balance(
+    network: Network,
+    node_url: Option<&str>,
+    address: &str,
+) -> Result<WalletBalance, WalletError> {
+    let node_url = node_url.unwrap_or_else(|| default_node_url(network));
+    let address = address.trim();
+    if address.is_empty() {
+        return Err(WalletError::InvalidAddress("Address cannot be empty".to_string()));
+    }
+
+    if matches!(network, Network::Dogecoin | Network::Litecoin) {
+        return Err(WalletError::UnsupportedNetwork(format!("Dev mode does not support {} yet", network)));
+    }
+
+    let (address, balance) = if network.bitcoin_chain().is_some() {
+        let sats = bitcoin_wallet::get_balance_sats_from(node_url, address).await?;
+        (address.to_string(), format!("{:.8}", sats as f64 / 100_000_000.0))
+    } else if network.is_tron() {
+        let sun = tron_account_balance(node_url, address).await?;
+        (address.to_string(), format!("{:.6}", sun as f64 / 1_000_000.0))
+    } else {
+        let address = ethereum_wallet::normalize_address(address)?;
+        let wei = evm_rpc::get_balance_at(node_url, &address, None).await?;
+        (address, units::format_units_u256(wei, network.decimals()))
+    };
+
+    Ok(WalletBalance::new(
+        address,
+        balance,
+        network.to_string(),
+        network.denomination().to_string(),
+    ))
+}
+
+/// `POST /wallet/getaccount` on a java-tron node; unknown accounts return `{}`
+async fn tron_account_balance(node_url: &str, address: &str) -> Result<u64> {
+    let url = format!("{}/wallet/getaccount", node_url.trim_end_matches('/'));
+
+    let client = http::client();
+    let response = http::send(
+        client
+            .post(&url)
+            .json(&json!({ "address": address, "visible": true })),
+    )
+    .await
+    .with_context(|| format!("Failed to send request to {}", node_url))?;
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("Tron node request failed: {}", response.status));
+    }
+
+    let account: TronAccount = response.json().context("Failed to parse JSON from Tron node")?;
+    Ok(account.balance)
+}
@@ -0,0 +1,138 @@
+//! Dogecoin wallet balance checking functionality
+//!
+//! Balances come from the Blockcypher API. Dogecoin uses Bitcoin's base58check
+//! address format with its own version bytes, and 8 decimal places (koinu).
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use base58::FromBase58;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::provider::BalanceProvider;
+use crate::{http, Network, WalletBalance, WalletError};
+
+pub(crate) const BLOCKCYPHER_DOGE_API: &str = "https://api.blockcypher.com/v1/doge/main";
+
+/// Version byte of P2PKH addresses (`D...`)
+const P2PKH_VERSION: u8 = 0x1e;
+/// Version byte of P2SH addresses (`9...` / `A...`)
+const P2SH_VERSION: u8 = 0x16;
+
+#[derive(Debug, Deserialize)]
+struct BalanceResponse {
+    /// Confirmed balance in koinu
+    balance: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChainResponse {
+    height: u64,
+}
+
+/// Get Dogecoin wallet balance for a given address
+///
+/// # Arguments
+///
+/// * `address` - Dogecoin address to check
+///
+/// # Returns
+///
+/// Returns a `WalletBalance` containing the confirmed balance in DOGE
+#[tracing::instrument(level = "debug", name = "dogecoin_balance", skip_all, fields(address = %address))]
+pub async fn get_balance(address: &str) -> Result<WalletBalance, WalletError> {
+    let address = address.trim();
+    validate_address(address)?;
+
+    let balance_koinu = get_balance_koinu(address).await?;
+    let balance_doge = balance_koinu as f64 / 100_000_000.0;
+
+    Ok(WalletBalance::new(
+        address.to_string(),
+        format!("{:.8}", balance_doge),
+        "dogecoin".to_string(),
+        "DOGE".to_string(),
+    ))
+}
+
+/// Confirmed balance in koinu from Blockcypher
+async fn get_balance_koinu(address: &str) -> Result<u64> {
+    let url = format!("{}/addrs/{}/balance", BLOCKCYPHER_DOGE_API, address);
+
+    let client = http::client();
+    let response = http::send(client.get(&url))
+        .await
+        .context("Failed to send request to Blockcypher API")?;
+
+    if !response.status.is_success() {
+        let status = response.status;
+        let body = response.text();
+        return Err(anyhow::anyhow!("Blockcypher API failed: {} - {}", status, body));
+    }
+
+    let data: BalanceResponse = response
+        .json()
+        .context("Failed to parse JSON from Blockcypher")?;
+    Ok(data.balance)
+}
+
+pub(crate) fn validate_address(address: &str) -> Result<(), WalletError> {
+    if address.is_empty() {
+        return Err(WalletError::InvalidAddress("Dogecoin address cannot be empty".to_string()));
+    }
+    if address.len() != 34 || !address.starts_with(['D', 'A', '9']) {
+        return Err(WalletError::InvalidAddress(
+            "Invalid Dogecoin address: must be 34 chars starting with 'D' (or 'A'/'9' for P2SH)".to_string(),
+        ));
+    }
+
+    let decoded = address
+        .from_base58()
+        .map_err(|_| WalletError::InvalidAddress("Invalid Base58 encoding".to_string()))?;
+    if decoded.len() != 25 {
+        return Err(WalletError::InvalidAddress("Invalid decoded length".to_string()));
+    }
+    if decoded[0] != P2PKH_VERSION && decoded[0] != P2SH_VERSION {
+        return Err(WalletError::InvalidAddress("Invalid Dogecoin version byte".to_string()));
+    }
+
+    let (payload, provided_checksum) = decoded.split_at(21);
+    let expected_checksum = &Sha256::digest(Sha256::digest(payload))[..4];
+    if provided_checksum != expected_checksum {
+        return Err(WalletError::InvalidAddress("Invalid address checksum".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Height of the latest block, used as a cheap reachability probe
+pub(crate) async fn get_tip_height() -> Result<u64> {
+    let client = http::client();
+    let response = http::send(client.get(BLOCKCYPHER_DOGE_API)).await?;
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("Blockcypher API failed: {}", response.status));
+    }
+
+    let chain: ChainResponse = response.json().context("Failed to parse JSON")?;
+    Ok(chain.height)
+}
+
+/// `BalanceProvider` for Dogecoin
+pub struct DogecoinProvider;
+
+#[async_trait]
+impl BalanceProvider for DogecoinProvider {
+    fn network(&self) -> Network {
+        Network::Dogecoin
+    }
+
+    fn normalize_address(&self, address: &str) -> Result<String, WalletError> {
+        let address = address.trim();
+        validate_address(address)?;
+        Ok(address.to_string())
+    }
+
+    async fn get_balance(&self, address: &str) -> Result<WalletBalance, WalletError> {
+        get_balance(address).await
+    }
+}
@@ -0,0 +1,286 @@
+//! Drift of a portfolio from its target allocation (`drift`)
+//!
+//! Market makers and treasuries hold each asset against a target, either an
+//! amount ("keep 2.5 BTC") or a share of the portfolio's value ("40% in
+//! ETH"). A targets file lists them per network:
+//!
+//! ```toml
+//! [[targets]]
+//! network = "bitcoin"
+//! amount = "2.5"
+//!
+//! [[targets]]
+//! network = "ethereum"
+//! share = "40%"
+//! ```
+//!
+//! `report` sets the per-network totals of a portfolio (`portfolio::totals`)
+//! against those targets and gives, for every asset, how far it is over or
+//! under and the trade that would bring it back. Amount targets are compared
+//! in base units and need no prices. Share targets need every held asset
+//! priced in one fiat currency, since a share of the whole only exists once
+//! the assets are in one unit. Nothing is traded: the report is read-only.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+use crate::portfolio::Totals;
+use crate::pricing::{self, FiatCurrency};
+use crate::{units, Network, WalletError};
+
+/// What an asset should amount to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    /// An amount, in base units
+    Amount(u128),
+    /// A percentage of the portfolio's value
+    Share(f64),
+}
+
+/// One line of a targets file
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetEntry {
+    pub network: Network,
+    pub target: Target,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawTarget {
+    network: String,
+    amount: Option<String>,
+    share: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawFile {
+    #[serde(default)]
+    targets: Vec<RawTarget>,
+}
+
+/// Parse the TOML text of a targets file
+pub fn from_toml(text: &str) -> Result<Vec<TargetEntry>, WalletError> {
+    let raw: RawFile = toml::from_str(text).context("Invalid targets file")?;
+    if raw.targets.is_empty() {
+        return Err(WalletError::ParseError("The file lists no targets".to_string()));
+    }
+    let targets: Vec<TargetEntry> = raw
+        .targets
+        .into_iter()
+        .enumerate()
+        .map(|(index, raw)| validate(raw).with_context(|| format!("Target {}", index + 1)))
+        .collect::<Result<_>>()?;
+
+    let mut seen = BTreeSet::new();
+    if let Some(twice) = targets.iter().find(|entry| !seen.insert(entry.network)) {
+        return Err(WalletError::ParseError(format!("{} has more than one target", twice.network)));
+    }
+    let shares: f64 = targets
+        .iter()
+        .filter_map(|entry| match entry.target {
+            Target::Share(share) => Some(share),
+            Target::Amount(_) => None,
+        })
+        .sum();
+    if shares > 100.0 + 1e-9 {
+        return Err(WalletError::ParseError(format!("The shares add up to {}%, more than 100%", shares)));
+    }
+    Ok(targets)
+}
+
+/// Read the targets file at `path`
+pub fn load(path: &Path) -> Result<Vec<TargetEntry>, WalletError> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(from_toml(&text).with_context(|| path.display().to_string())?)
+}
+
+fn validate(raw: RawTarget) -> Result<TargetEntry> {
+    let network: Network = raw.network.parse().context("Invalid network")?;
+    let target = match (raw.amount, raw.share) {
+        (Some(amount), None) => Target::Amount(
+            units::parse_units(&amount, network.decimals()).with_context(|| format!("Invalid amount: {}", amount))?,
+        ),
+        (None, Some(share)) => {
+            let percent: f64 = share
+                .trim()
+                .trim_end_matches('%')
+                .trim()
+                .parse()
+                .ok()
+                .filter(|percent: &f64| (0.0..=100.0).contains(percent))
+                .with_context(|| format!("Invalid share: {} (expected a percentage from 0 to 100)", share))?;
+            Target::Share(percent)
+        }
+        _ => return Err(anyhow::anyhow!("Give either an amount or a share")),
+    };
+    Ok(TargetEntry { network, target })
+}
+
+/// Fiat prices of the networks' coins, which share targets are weighed with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prices {
+    pub currency: FiatCurrency,
+    pub per_network: HashMap<Network, f64>,
+}
+
+/// Price every network of `networks` that has a market price, with one request
+pub async fn fetch_prices(networks: impl IntoIterator<Item = Network>, currency: FiatCurrency) -> Result<Prices, WalletError> {
+    let ids: BTreeMap<Network, &str> = networks
+        .into_iter()
+        .filter_map(|network| pricing::coingecko_id(network).map(|id| (network, id)))
+        .collect();
+    let unique: BTreeSet<&str> = ids.values().copied().collect();
+    let quoted = match unique.is_empty() {
+        true => HashMap::new(),
+        false => pricing::get_prices(&unique.into_iter().collect::<Vec<_>>(), currency).await?,
+    };
+    let per_network = ids
+        .into_iter()
+        .filter_map(|(network, id)| quoted.get(id).map(|price| (network, *price)))
+        .collect();
+    Ok(Prices { currency, per_network })
+}
+
+/// Side of a rebalancing trade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// Trade that brings one asset to its target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Trade {
+    pub side: Side,
+    pub amount: String,
+    /// Value of the trade, when the asset is priced
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fiat_value: Option<String>,
+}
+
+/// How one asset stands against its target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AssetDrift {
+    pub network: String,
+    pub denomination: String,
+    pub held: String,
+    /// Target amount; `None` for an asset held without a target
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Share of the portfolio's value held, in percent, when everything is priced
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub held_share: Option<String>,
+    /// Target share in percent, given or (for an amount) implied by the prices
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_share: Option<String>,
+    /// Held minus target, signed: `+` is over-exposure, `-` under-exposure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drift: Option<String>,
+    /// `None` when on target or without one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trade: Option<Trade>,
+}
+
+/// Every asset's drift and, with prices, the portfolio's value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DriftReport {
+    pub assets: Vec<AssetDrift>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub portfolio_value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fiat_currency: Option<String>,
+}
+
+/// Set `totals` against `targets`, weighing share targets with `prices`
+///
+/// Assets are listed in `Network` order: every held one, and every targeted
+/// one even when none of it is held. Share targets fail without a price for
+/// every held asset, as the portfolio's value would be unknown.
+pub fn report(totals: &Totals, targets: &[TargetEntry], prices: Option<&Prices>) -> Result<DriftReport, WalletError> {
+    let mut held: BTreeMap<Network, u128> = BTreeMap::new();
+    for total in &totals.networks {
+        let network: Network = total.network.parse()?;
+        held.insert(network, units::parse_units(&total.balance, network.decimals())?);
+    }
+    let price = |network: Network| prices.and_then(|prices| prices.per_network.get(&network).copied());
+    let value = |network: Network, amount: u128| {
+        price(network).map(|price| amount as f64 / 10f64.powi(network.decimals() as i32) * price)
+    };
+
+    // Unknown as soon as one held asset has no price
+    let portfolio_value: Option<f64> = prices.and_then(|_| {
+        held.iter()
+            .filter(|(_, amount)| **amount > 0)
+            .map(|(network, amount)| value(*network, *amount))
+            .sum()
+    });
+
+    let by_network: BTreeMap<Network, Target> = targets.iter().map(|entry| (entry.network, entry.target)).collect();
+    let networks: BTreeSet<Network> = held.keys().chain(by_network.keys()).copied().collect();
+    let mut assets = Vec::with_capacity(networks.len());
+    for network in networks {
+        let amount = held.get(&network).copied().unwrap_or_default();
+        let target = match by_network.get(&network) {
+            Some(Target::Amount(target)) => Some(*target),
+            Some(Target::Share(share)) => Some(share_amount(network, *share, portfolio_value, price(network), prices)?),
+            None => None,
+        };
+        let share_of = |amount: u128| {
+            let total = portfolio_value.filter(|total| *total > 0.0)?;
+            value(network, amount).map(|value| format!("{:.2}", value / total * 100.0))
+        };
+        let trade = target.filter(|target| *target != amount).map(|target| Trade {
+            side: if amount < target { Side::Buy } else { Side::Sell },
+            amount: units::format_units(amount.abs_diff(target), network.decimals()),
+            fiat_value: value(network, amount.abs_diff(target)).map(|value| format!("{:.2}", value)),
+        });
+        assets.push(AssetDrift {
+            network: network.to_string(),
+            denomination: network.denomination().to_string(),
+            held: units::format_units(amount, network.decimals()),
+            target: target.map(|target| units::format_units(target, network.decimals())),
+            held_share: share_of(amount),
+            target_share: match by_network.get(&network) {
+                Some(Target::Share(share)) => Some(format!("{:.2}", share)),
+                _ => target.and_then(share_of),
+            },
+            drift: target.map(|target| units::format_delta(target.into(), amount.into(), network.decimals())),
+            trade,
+        });
+    }
+
+    Ok(DriftReport {
+        assets,
+        portfolio_value: portfolio_value.map(|value| format!("{:.2}", value)),
+        fiat_currency: portfolio_value.and(prices).map(|prices| prices.currency.to_string()),
+    })
+}
+
+/// Amount of `network`'s coin, in base units, worth `share` percent of the portfolio
+fn share_amount(
+    network: Network,
+    share: f64,
+    portfolio_value: Option<f64>,
+    price: Option<f64>,
+    prices: Option<&Prices>,
+) -> Result<u128, WalletError> {
+    let Some(prices) = prices else {
+        return Err(WalletError::Other(format!(
+            "The {} target is a share of the portfolio's value, which needs prices (use --convert)",
+            network
+        )));
+    };
+    let (Some(total), Some(price)) = (portfolio_value, price.filter(|price| *price > 0.0)) else {
+        return Err(WalletError::Other(format!(
+            "Cannot weigh the {} target: not every asset has a {} price",
+            network, prices.currency
+        )));
+    };
+    let amount = total * share / 100.0 / price;
+    let decimals = network.decimals();
+    Ok(units::parse_units(&format!("{:.*}", decimals as usize, amount), decimals)?)
+}
@@ -0,0 +1,161 @@
+//! Health check of the endpoints the CLI reads from
+//!
+//! When a lookup fails it is rarely obvious whether the local network, the
+//! config or the provider is to blame. `configured()` lists every endpoint
+//! the active config points the networks at: each EVM network's RPC endpoint
+//! and its fallbacks, the Esplora instances of the Bitcoin-family networks
+//! (and the Bitcoin batch instance, if any), Blockcypher for Dogecoin and
+//! TronGrid. `check` probes them all concurrently with the cheapest request
+//! that proves the endpoint answers (`eth_blockNumber`, `/blocks/tip/height`,
+//! `/wallet/getnowblock`) and reports each one's latency and tip height.
+//!
+//! EVM endpoints are probed one by one, without failing over to the next, so
+//! a fallback that is down shows up before it is needed.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::time::Instant;
+use tokio::task::JoinSet;
+
+use crate::{bitcoin_wallet, config, dogecoin_wallet, evm_rpc, evm_wallet, http, tron_wallet, Network};
+
+/// API an endpoint speaks, which decides how it is probed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EndpointKind {
+    /// EVM JSON-RPC
+    EvmRpc,
+    /// Esplora REST API (Bitcoin, its test networks, Litecoin)
+    Esplora,
+    /// Blockcypher (Dogecoin)
+    Blockcypher,
+    /// TronGrid (Tron, Shasta)
+    TronGrid,
+}
+
+impl std::fmt::Display for EndpointKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            EndpointKind::EvmRpc => "evm-rpc",
+            EndpointKind::Esplora => "esplora",
+            EndpointKind::Blockcypher => "blockcypher",
+            EndpointKind::TronGrid => "trongrid",
+        })
+    }
+}
+
+/// One endpoint a network is read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    pub network: Network,
+    pub kind: EndpointKind,
+    pub url: String,
+    /// Which of the network's endpoints this is: `primary`, `fallback 1`, ..., or `batch`
+    pub role: String,
+}
+
+/// Outcome of probing one endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointHealth {
+    pub network: String,
+    pub kind: EndpointKind,
+    /// The endpoint without query string or credentials
+    pub url: String,
+    pub role: String,
+    pub ok: bool,
+    pub latency_ms: u128,
+    /// Tip height the endpoint reported
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Every endpoint of `networks` under the active config, in network order
+///
+/// `custom-evm` is listed only once `--rpc-url` has given it an endpoint.
+pub fn configured(networks: &[Network]) -> Vec<Endpoint> {
+    let config = config::active();
+    let mut endpoints = Vec::new();
+    for &network in networks {
+        let mut add = |kind, url: String, role: String| {
+            endpoints.push(Endpoint { network, kind, url, role });
+        };
+        if network == Network::CustomEvm && evm_wallet::custom_chain().is_none() {
+            continue;
+        }
+        if let Some(urls) = config.evm_rpcs(network) {
+            for (index, url) in urls.into_iter().enumerate() {
+                let role = match index {
+                    0 => "primary".to_string(),
+                    n => format!("fallback {}", n),
+                };
+                add(EndpointKind::EvmRpc, url.to_string(), role);
+            }
+            continue;
+        }
+        match network.bitcoin_chain() {
+            Some(chain) => {
+                add(EndpointKind::Esplora, chain.api_base(), "primary".to_string());
+                if let Some(batch_api) = config.bitcoin_batch_api().filter(|_| network == Network::Bitcoin) {
+                    add(EndpointKind::Esplora, batch_api.to_string(), "batch".to_string());
+                }
+            }
+            None if network == Network::Litecoin => {
+                add(EndpointKind::Esplora, config.litecoin_api().to_string(), "primary".to_string())
+            }
+            None if network == Network::Dogecoin => add(
+                EndpointKind::Blockcypher,
+                dogecoin_wallet::BLOCKCYPHER_DOGE_API.to_string(),
+                "primary".to_string(),
+            ),
+            None => add(
+                EndpointKind::TronGrid,
+                tron_wallet::api_url(network).to_string(),
+                "primary".to_string(),
+            ),
+        }
+    }
+    endpoints
+}
+
+/// Probe `endpoints` concurrently and return their health in the same order
+///
+/// Probes go through `http::send` like any request, so each attempt is cut
+/// off after `http::timeout()` and retried under the installed `RetryPolicy`;
+/// install one without retries to time a single attempt.
+pub async fn check(endpoints: Vec<Endpoint>) -> Vec<EndpointHealth> {
+    let mut probes = JoinSet::new();
+    for (index, endpoint) in endpoints.into_iter().enumerate() {
+        probes.spawn(async move {
+            let started = Instant::now();
+            let outcome = probe(&endpoint).await;
+            let health = EndpointHealth {
+                network: endpoint.network.to_string(),
+                kind: endpoint.kind,
+                url: http::loggable(&endpoint.url),
+                role: endpoint.role,
+                ok: outcome.is_ok(),
+                latency_ms: started.elapsed().as_millis(),
+                height: outcome.as_ref().ok().copied(),
+                // The root cause only: the context repeats the URL, credentials and all
+                error: outcome.err().map(|e| e.root_cause().to_string()),
+            };
+            (index, health)
+        });
+    }
+
+    let mut results: Vec<(usize, EndpointHealth)> = probes.join_all().await;
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, health)| health).collect()
+}
+
+/// Tip height of the chain behind `endpoint`, from the cheapest request it answers
+async fn probe(endpoint: &Endpoint) -> Result<u64> {
+    match endpoint.kind {
+        EndpointKind::EvmRpc => evm_rpc::block_number_on(&endpoint.url).await,
+        EndpointKind::Esplora => bitcoin_wallet::get_tip_height_from(&endpoint.url).await,
+        EndpointKind::Blockcypher => dogecoin_wallet::get_tip_height().await,
+        EndpointKind::TronGrid => tron_wallet::get_tip_height(endpoint.network).await,
+    }
+}
@@ -0,0 +1,136 @@
+//! ERC-1155 multi-token balances
+//!
+//! An ERC-1155 contract holds many token ids, each with its own balance and
+//! no decimals. `balanceOfBatch` reads any number of ids for a holder in one
+//! `eth_call`, which the ERC-20 path (one balance per contract) cannot express.
+
+use anyhow::{Context, Result};
+use serde_json::json;
+
+use crate::units::U256;
+use crate::{ethereum_wallet, evm_rpc, Network, WalletError};
+
+/// `balanceOfBatch(address[],uint256[])` selector
+const BALANCE_OF_BATCH_SELECTOR: &str = "4e1273f4";
+
+/// Balance of one token id, in units (ERC-1155 ids have no decimals)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenIdBalance {
+    pub id: u128,
+    pub balance: U256,
+}
+
+/// Parse a token id given in decimal or `0x` hex
+pub fn parse_token_id(id: &str) -> Result<u128, WalletError> {
+    let id = id.trim();
+    let parsed = match id.strip_prefix("0x").or_else(|| id.strip_prefix("0X")) {
+        Some(hex_id) => u128::from_str_radix(hex_id, 16),
+        None => id.parse(),
+    };
+    parsed.map_err(|_| {
+        WalletError::ParseError(format!(
+            "Invalid token id '{}' (expected a decimal or 0x hex number up to 128 bits)",
+            id
+        ))
+    })
+}
+
+/// Balances of `ids` held by `holder` in the ERC-1155 `contract`, in `ids` order
+pub async fn get_balances(
+    network: Network,
+    contract: &str,
+    holder: &str,
+    ids: &[u128],
+) -> Result<Vec<TokenIdBalance>, WalletError> {
+    let rpc_url = evm_rpc::rpc_url(network).ok_or_else(|| {
+        WalletError::UnsupportedNetwork(format!("ERC-1155 balances are only supported on EVM networks, not {}", network))
+    })?;
+    if ids.is_empty() {
+        return Err(WalletError::Other("At least one token id is required".to_string()));
+    }
+    let contract = normalize(contract).context("Invalid token contract")?;
+    let holder = normalize(holder)?;
+
+    let result = evm_rpc::call(
+        &rpc_url,
+        "eth_call",
+        vec![json!({ "to": contract, "data": encode_balance_of_batch(&holder, ids) }), json!("latest")],
+    )
+    .await?;
+    let output = result
+        .as_str()
+        .and_then(|hex_str| hex::decode(hex_str.trim_start_matches("0x")).ok())
+        .ok_or_else(|| anyhow::anyhow!("balanceOfBatch returned {} instead of hex data", result))?;
+    if output.is_empty() {
+        return Err(WalletError::Other(format!("No contract at {}", contract)));
+    }
+
+    let balances = decode_uint_array(&output).context("Unexpected balanceOfBatch result")?;
+    if balances.len() != ids.len() {
+        return Err(WalletError::ParseError(format!(
+            "balanceOfBatch returned {} balances for {} ids",
+            balances.len(),
+            ids.len()
+        )));
+    }
+    Ok(ids
+        .iter()
+        .zip(balances)
+        .map(|(&id, balance)| TokenIdBalance { id, balance })
+        .collect())
+}
+
+fn normalize(address: &str) -> Result<String> {
+    let address = ethereum_wallet::normalize_address(address.trim())?;
+    ethereum_wallet::validate_address(&address)?;
+    Ok(address)
+}
+
+/// Calldata for `balanceOfBatch([holder; n], ids)`
+fn encode_balance_of_batch(holder: &str, ids: &[u128]) -> String {
+    let word = |value: u128| format!("{:064x}", value);
+    let count = ids.len() as u128;
+    let holder_word = format!("{:0>64}", holder.trim_start_matches("0x").to_lowercase());
+
+    // Heads: offsets of the two arrays, then each array as length + elements
+    let mut data = format!("0x{}{}{}", BALANCE_OF_BATCH_SELECTOR, word(0x40), word(0x40 + 32 * (1 + count)));
+    data.push_str(&word(count));
+    for _ in ids {
+        data.push_str(&holder_word);
+    }
+    data.push_str(&word(count));
+    for &id in ids {
+        data.push_str(&word(id));
+    }
+    data
+}
+
+/// Decode an ABI `uint256[]` return value at full width
+fn decode_uint_array(output: &[u8]) -> Result<Vec<U256>> {
+    let read = |at: usize| -> Result<U256> {
+        let word = at
+            .checked_add(32)
+            .and_then(|end| output.get(at..end))
+            .ok_or_else(|| anyhow::anyhow!("Result truncated at byte {}", at))?;
+        Ok(U256::from_be_bytes(word.try_into().expect("32 bytes")))
+    };
+    // Offsets and lengths index the output, so they must fit a usize
+    let position = |at: usize| -> Result<usize> {
+        read(at)?
+            .to_u128()
+            .and_then(|value| usize::try_from(value).ok())
+            .ok_or_else(|| anyhow::anyhow!("Offset or length at byte {} is out of range", at))
+    };
+    let offset = position(0)?;
+    let count = position(offset)?;
+    (0..count)
+        .map(|index| {
+            let at = index
+                .checked_add(1)
+                .and_then(|slot| slot.checked_mul(32))
+                .and_then(|delta| offset.checked_add(delta))
+                .ok_or_else(|| anyhow::anyhow!("Element {} is out of range", index))?;
+            read(at)
+        })
+        .collect()
+}
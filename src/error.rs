@@ -0,0 +1,116 @@
+//! The library's error type
+//!
+//! Public functions return `WalletError`, so callers can act on the cause of
+//! a failure, e.g. back off on `RateLimited`, retry on `NetworkUnreachable`
+//! or ask the user to fix an `InvalidAddress`, without parsing messages:
+//!
+//! ```no_run
+//! use wallet_balance::{Network, WalletError};
+//!
+//! # async fn example() {
+//! match wallet_balance::get_balance(Network::Bitcoin, "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa").await {
+//!     Ok(balance) => println!("{} {}", balance.balance, balance.denomination),
+//!     Err(WalletError::RateLimited { provider }) => eprintln!("{} is throttling us", provider),
+//!     Err(e) => eprintln!("{}", e),
+//! }
+//! # }
+//! ```
+//!
+//! Inside the crate, request code builds `anyhow` errors with context as
+//! before. The typed causes are raised where they happen (the transport, the
+//! JSON-RPC client, the address validators) and travel inside those errors;
+//! `From<anyhow::Error>` recovers them at the public boundary. Variants that
+//! carry a message keep the whole context chain in it.
+
+use crate::{LookupTimedOut, NotConfigured};
+
+/// Why a library call failed
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum WalletError {
+    /// The address does not parse, or fails its checksum, on the network
+    #[error("{0}")]
+    InvalidAddress(String),
+
+    /// The provider could not be reached or did not answer within the timeout
+    #[error("{0}")]
+    NetworkUnreachable(String),
+
+    /// A JSON-RPC endpoint answered with an error object
+    #[error("RPC error {code}: {message}")]
+    RpcError { code: i64, message: String },
+
+    /// The provider still answered 429 Too Many Requests after every retry
+    #[error("Rate limited by {provider} (429 Too Many Requests)")]
+    RateLimited { provider: String },
+
+    /// A response, file or value was not in the expected format
+    #[error("{0}")]
+    ParseError(String),
+
+    /// The operation is not available on the network
+    #[error("{0}")]
+    UnsupportedNetwork(String),
+
+    /// The network needs an endpoint or key nobody has set
+    #[error(transparent)]
+    NotConfigured(#[from] NotConfigured),
+
+    /// The lookup was abandoned when its batch's deadline passed
+    #[error(transparent)]
+    TimedOut(#[from] LookupTimedOut),
+
+    /// Any other failure, such as an unexpected HTTP status
+    #[error("{0}")]
+    Other(String),
+}
+
+impl WalletError {
+    /// `InvalidAddress` with the message of `error`, for `map_err` on validation results
+    pub(crate) fn invalid_address(error: impl std::fmt::Display) -> Self {
+        WalletError::InvalidAddress(format!("{:#}", error))
+    }
+
+    /// Whether trying the same call again later may succeed
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            WalletError::NetworkUnreachable(_) | WalletError::RateLimited { .. } | WalletError::TimedOut(_)
+        )
+    }
+}
+
+impl From<anyhow::Error> for WalletError {
+    /// The first typed cause in the chain, with the chain's full message; `Other` if there is none
+    fn from(error: anyhow::Error) -> Self {
+        let message = || format!("{:#}", error);
+        for cause in error.chain() {
+            if let Some(typed) = cause.downcast_ref::<WalletError>() {
+                return match typed {
+                    WalletError::InvalidAddress(_) => WalletError::InvalidAddress(message()),
+                    WalletError::NetworkUnreachable(_) => WalletError::NetworkUnreachable(message()),
+                    WalletError::ParseError(_) => WalletError::ParseError(message()),
+                    WalletError::UnsupportedNetwork(_) => WalletError::UnsupportedNetwork(message()),
+                    WalletError::Other(_) => WalletError::Other(message()),
+                    structured => structured.clone(),
+                };
+            }
+            if let Some(not_configured) = cause.downcast_ref::<NotConfigured>() {
+                return WalletError::NotConfigured(*not_configured);
+            }
+            if cause.is::<LookupTimedOut>() {
+                return WalletError::TimedOut(LookupTimedOut);
+            }
+            if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                return match e.is_decode() {
+                    true => WalletError::ParseError(message()),
+                    false => WalletError::NetworkUnreachable(message()),
+                };
+            }
+            if cause.is::<serde_json::Error>() || cause.is::<toml::de::Error>() {
+                return WalletError::ParseError(message());
+            }
+        }
+        WalletError::Other(message())
+    }
+}
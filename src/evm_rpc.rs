@@ -0,0 +1,358 @@
+//! Minimal JSON-RPC client shared by features that talk to EVM nodes
+//!
+//! Every EVM read goes through `call`: the native balances of `evm_wallet`
+//! (for the table chains and `custom-evm` alike) as well as the gas price,
+//! block and token calls that would otherwise be copied into each feature.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::units::U256;
+use crate::{config, http, BlockRef, Network, TokenInfo, WalletError};
+
+/// JSON-RPC request structure
+#[derive(Debug, Serialize)]
+struct JsonRpcRequest<'a> {
+    jsonrpc: &'a str,
+    method: &'a str,
+    params: Vec<Value>,
+    id: u64,
+}
+
+/// JSON-RPC response structure
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse {
+    result: Option<Value>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcError {
+    code: i32,
+    message: String,
+}
+
+/// Send a JSON-RPC request and return its `result` value
+///
+/// When `rpc_url` is the endpoint of an EVM network with fallbacks
+/// (`Config::evm_rpcs`), an endpoint that cannot be reached, answers 5xx or
+/// rate limits, after `http::send`'s own retries, hands the request to the
+/// next one; the error of the last endpoint is returned. Which endpoint
+/// served a failed-over request is logged at `info` level, for `-v`.
+#[tracing::instrument(level = "info", name = "rpc", skip_all, fields(rpc_method = %method))]
+pub(crate) async fn call(rpc_url: &str, method: &str, params: Vec<Value>) -> Result<Value> {
+    if rpc_url.is_empty() {
+        return Err(anyhow::anyhow!("No RPC endpoint for {}: --network custom-evm needs --rpc-url", method));
+    }
+    let request = JsonRpcRequest {
+        jsonrpc: "2.0",
+        method,
+        params,
+        id: 1,
+    };
+
+    let endpoints = endpoints(rpc_url);
+    let mut index = 0;
+    loop {
+        let endpoint = http::loggable(&endpoints[index]);
+        match call_endpoint(&endpoints[index], &request).await {
+            Ok(result) => {
+                if index > 0 {
+                    tracing::info!(rpc_method = %method, %endpoint, "served by fallback endpoint");
+                }
+                return Ok(result);
+            }
+            Err(EndpointError::Unavailable(e)) if index + 1 < endpoints.len() => {
+                let next = http::loggable(&endpoints[index + 1]);
+                // The root cause only: the context repeats the URL, credentials and all
+                tracing::info!(%endpoint, error = %e.root_cause(), %next, "endpoint unavailable, failing over");
+                index += 1;
+            }
+            Err(EndpointError::Unavailable(e) | EndpointError::Failed(e)) => return Err(e),
+        }
+    }
+}
+
+/// Latest block number from `rpc_url` alone, without failing over, to tell each endpoint's health apart
+pub(crate) async fn block_number_on(rpc_url: &str) -> Result<u64> {
+    let request = JsonRpcRequest {
+        jsonrpc: "2.0",
+        method: "eth_blockNumber",
+        params: vec![],
+        id: 1,
+    };
+    let result = call_endpoint(rpc_url, &request)
+        .await
+        .map_err(|(EndpointError::Unavailable(e) | EndpointError::Failed(e))| e)?;
+    parse_quantity(&result)?
+        .try_into()
+        .context("Block number out of range")
+}
+
+/// Why `call_endpoint` failed
+enum EndpointError {
+    /// The endpoint is down or rate limiting: the next one may answer
+    Unavailable(anyhow::Error),
+    /// The request itself failed, and would on any endpoint
+    Failed(anyhow::Error),
+}
+
+/// EIP-1474 code of a JSON-RPC "limit exceeded" error, which public endpoints use for rate limiting
+const LIMIT_EXCEEDED: i32 = -32005;
+
+/// `rpc_url` and the endpoints to fail over to when it is the primary endpoint of an EVM network
+fn endpoints(rpc_url: &str) -> Vec<String> {
+    let config = config::active();
+    Network::ALL
+        .into_iter()
+        .filter_map(|network| config.evm_rpcs(network))
+        .find(|endpoints| endpoints.first() == Some(&rpc_url))
+        .unwrap_or_else(|| vec![rpc_url])
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Send `request` to one endpoint
+async fn call_endpoint(rpc_url: &str, request: &JsonRpcRequest<'_>) -> Result<Value, EndpointError> {
+    let method = request.method;
+    let client = http::client();
+    let response = http::send(
+        client
+            .post(rpc_url)
+            .header("Content-Type", "application/json")
+            .json(request),
+    )
+    .await
+    .with_context(|| format!("Failed to send {} request to {}", method, rpc_url))
+    .map_err(EndpointError::Unavailable)?;
+
+    if !response.status.is_success() {
+        let error = anyhow::anyhow!("RPC request failed with status: {}", response.status);
+        return Err(match response.status.is_server_error() {
+            true => EndpointError::Unavailable(error),
+            false => EndpointError::Failed(error),
+        });
+    }
+
+    let rpc_response: JsonRpcResponse = response
+        .json()
+        .with_context(|| format!("Failed to parse JSON response for {}", method))
+        .map_err(EndpointError::Failed)?;
+
+    if let Some(error) = rpc_response.error {
+        let limited = error.code == LIMIT_EXCEEDED;
+        let error = WalletError::RpcError {
+            code: error.code.into(),
+            message: error.message,
+        }
+        .into();
+        return Err(match limited {
+            true => EndpointError::Unavailable(error),
+            false => EndpointError::Failed(error),
+        });
+    }
+
+    rpc_response
+        .result
+        .ok_or_else(|| EndpointError::Failed(WalletError::ParseError(format!("No result in {} response", method)).into()))
+}
+
+/// Current gas price in wei (`eth_gasPrice`)
+pub(crate) async fn gas_price(rpc_url: &str) -> Result<u128> {
+    let result = call(rpc_url, "eth_gasPrice", vec![]).await?;
+    parse_quantity(&result)
+}
+
+/// Latest block number (`eth_blockNumber`)
+pub(crate) async fn block_number(rpc_url: &str) -> Result<u64> {
+    let result = call(rpc_url, "eth_blockNumber", vec![]).await?;
+    parse_quantity(&result)?
+        .try_into()
+        .context("Block number out of range")
+}
+
+/// Number and hash of the block `depth` blocks below the current tip
+pub(crate) async fn block_at_depth(rpc_url: &str, depth: u64) -> Result<BlockRef> {
+    let tag = match depth {
+        0 => "latest".to_string(),
+        _ => format!("0x{:x}", block_number(rpc_url).await?.saturating_sub(depth)),
+    };
+    block_by_tag(rpc_url, &tag).await
+}
+
+/// Number and hash of the block `tag` names: a `0x` height or a tag such as `finalized`
+pub(crate) async fn block_by_tag(rpc_url: &str, tag: &str) -> Result<BlockRef> {
+    let block = call(rpc_url, "eth_getBlockByNumber", vec![json!(tag), json!(false)]).await?;
+    if block.is_null() {
+        let height = tag.strip_prefix("0x").and_then(|hex| u64::from_str_radix(hex, 16).ok());
+        return Err(match height {
+            Some(height) => anyhow::anyhow!("Block {} does not exist yet", height),
+            None => anyhow::anyhow!("The node has no {} block", tag),
+        });
+    }
+
+    let number = parse_quantity(&block["number"])?
+        .try_into()
+        .context("Block number out of range")?;
+    let hash = block["hash"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Block {} has no hash", number))?
+        .to_string();
+    Ok(BlockRef { number, hash })
+}
+
+/// Native balance in wei at `block`, or at `latest` when `None` (`eth_getBalance`)
+pub(crate) async fn get_balance_at(rpc_url: &str, address: &str, block: Option<u64>) -> Result<U256> {
+    let block = match block {
+        Some(number) => format!("0x{:x}", number),
+        None => "latest".to_string(),
+    };
+    let result = call(rpc_url, "eth_getBalance", vec![json!(address), json!(block)]).await?;
+    parse_quantity_u256(&result)
+}
+
+/// Native balance in wei at a block tag such as `pending` (`eth_getBalance`)
+pub(crate) async fn get_balance_at_tag(rpc_url: &str, address: &str, tag: &str) -> Result<U256> {
+    let result = call(rpc_url, "eth_getBalance", vec![json!(address), json!(tag)]).await?;
+    parse_quantity_u256(&result)
+}
+
+/// Transactions sent from `address` at `block` (`eth_getTransactionCount`), e.g. `latest` or `pending`
+pub(crate) async fn transaction_count(rpc_url: &str, address: &str, block: &str) -> Result<u64> {
+    let result = call(rpc_url, "eth_getTransactionCount", vec![json!(address), json!(block)]).await?;
+    parse_quantity(&result)?
+        .try_into()
+        .context("Transaction count out of range")
+}
+
+/// Whether `address` has contract code deployed (`eth_getCode` is not empty)
+pub(crate) async fn has_code(rpc_url: &str, address: &str) -> Result<bool> {
+    let result = call(rpc_url, "eth_getCode", vec![json!(address), json!("latest")]).await?;
+    let code = result
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Expected hex code, got {}", result))?;
+    Ok(!code.trim_start_matches("0x").is_empty())
+}
+
+// ERC-20 function selectors: first 4 bytes of keccak256 of the signature
+const BALANCE_OF_SELECTOR: &str = "0x70a08231"; // balanceOf(address)
+const DECIMALS_SELECTOR: &str = "0x313ce567"; // decimals()
+const SYMBOL_SELECTOR: &str = "0x95d89b41"; // symbol()
+const TOTAL_SUPPLY_SELECTOR: &str = "0x18160ddd"; // totalSupply()
+
+/// Denomination used when a token's `symbol()` is missing or unreadable
+const FALLBACK_SYMBOL: &str = "TOKEN";
+
+/// ERC-20 balance of `holder` in the token's base units at `block` (`eth_call` of `balanceOf`)
+///
+/// Reading a block older than the node's pruning window needs an archive node.
+pub(crate) async fn erc20_balance_at(rpc_url: &str, token: &str, holder: &str, block: u64) -> Result<U256> {
+    let result = token_call(rpc_url, token, &balance_of_data(holder), Some(block)).await?;
+    parse_quantity_u256(&result)
+}
+
+/// ERC-20 balance of `holder` in the token's base units at the latest block
+pub(crate) async fn erc20_balance(rpc_url: &str, token: &str, holder: &str) -> Result<U256> {
+    let result = token_call(rpc_url, token, &balance_of_data(holder), None).await?;
+    parse_quantity_u256(&result)
+}
+
+/// Total supply of an ERC-20 `token` in base units at the latest block
+pub(crate) async fn erc20_total_supply(rpc_url: &str, token: &str) -> Result<U256> {
+    let result = token_call(rpc_url, token, TOTAL_SUPPLY_SELECTOR, None).await?;
+    parse_quantity_u256(&result)
+}
+
+/// Symbol and decimals of an ERC-20 `token`, read concurrently
+///
+/// The decimals are required to scale a balance; a token without a readable
+/// `symbol()` falls back to the generic `TOKEN` denomination.
+pub(crate) async fn erc20_token(rpc_url: &str, token: &str) -> Result<TokenInfo> {
+    let (decimals, symbol) = tokio::join!(
+        token_call(rpc_url, token, DECIMALS_SELECTOR, None),
+        token_call(rpc_url, token, SYMBOL_SELECTOR, None),
+    );
+
+    let decimals = parse_quantity(&decimals?)?;
+    // 10^77 is the largest power of ten a uint256 balance can reach
+    if decimals > 77 {
+        return Err(anyhow::anyhow!("Token {} reports {} decimals (expected at most 77)", token, decimals));
+    }
+    let symbol = symbol
+        .ok()
+        .and_then(|result| decode_symbol(result.as_str()?))
+        .unwrap_or_else(|| FALLBACK_SYMBOL.to_string());
+    Ok(TokenInfo { symbol, decimals: decimals as u32 })
+}
+
+/// `balanceOf(address)` calldata: the selector followed by the holder left-padded to 32 bytes
+fn balance_of_data(holder: &str) -> String {
+    format!("{}{:0>64}", BALANCE_OF_SELECTOR, holder.trim_start_matches("0x").to_lowercase())
+}
+
+/// `eth_call` of `data` against `token` at `block`, or at `latest` when `None`
+async fn token_call(rpc_url: &str, token: &str, data: &str, block: Option<u64>) -> Result<Value> {
+    let tag = match block {
+        Some(number) => format!("0x{:x}", number),
+        None => "latest".to_string(),
+    };
+    let result = call(rpc_url, "eth_call", vec![json!({ "to": token, "data": data }), json!(tag)]).await?;
+
+    // A call to an address without code succeeds with empty return data
+    if result.as_str() == Some("0x") {
+        return Err(match block {
+            Some(number) => anyhow::anyhow!("No contract at {} at block {}", token, number),
+            None => anyhow::anyhow!("No contract at {}", token),
+        });
+    }
+    Ok(result)
+}
+
+/// Decode a `symbol()` result: an ABI `string`, or the `bytes32` some early tokens return
+///
+/// Control characters are dropped, since the contract decides what is printed.
+fn decode_symbol(result: &str) -> Option<String> {
+    let bytes = hex::decode(result.trim_start_matches("0x")).ok()?;
+    let raw = match bytes.len() {
+        32 => bytes.split(|&b| b == 0).next()?.to_vec(),
+        _ => {
+            let word = |at: usize| -> Option<usize> {
+                let chunk = bytes.get(at..at + 32)?;
+                // Offsets and lengths beyond a few bytes cannot be valid here
+                chunk[..28].iter().all(|&b| b == 0).then(|| {
+                    chunk[28..].iter().fold(0usize, |value, &b| (value << 8) | b as usize)
+                })
+            };
+            let offset = word(0)?;
+            let length = word(offset)?;
+            bytes.get(offset + 32..offset + 32 + length)?.to_vec()
+        }
+    };
+    let symbol: String = String::from_utf8(raw).ok()?.chars().filter(|c| !c.is_control()).collect();
+    let symbol = symbol.trim();
+    (!symbol.is_empty()).then(|| symbol.to_string())
+}
+
+/// Parse a hex-encoded JSON-RPC quantity such as `"0x1a"`
+pub(crate) fn parse_quantity(value: &Value) -> Result<u128> {
+    let hex_str = value
+        .as_str()
+        .ok_or_else(|| WalletError::ParseError(format!("Expected a hex quantity, got {}", value)))?;
+    u128::from_str_radix(hex_str.trim_start_matches("0x"), 16)
+        .map_err(|e| WalletError::ParseError(format!("Failed to parse hex quantity '{}': {}", hex_str, e)).into())
+}
+
+/// Parse a hex-encoded JSON-RPC quantity or `uint256` word at full 256-bit width
+pub(crate) fn parse_quantity_u256(value: &Value) -> Result<U256> {
+    let hex_str = value
+        .as_str()
+        .ok_or_else(|| WalletError::ParseError(format!("Expected a hex quantity, got {}", value)))?;
+    U256::from_hex(hex_str)
+}
+
+/// RPC endpoint of an EVM network from the active config, or `None` for non-EVM networks
+pub(crate) fn rpc_url(network: Network) -> Option<String> {
+    config::active().evm_rpc(network).map(str::to_string)
+}
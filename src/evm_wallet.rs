@@ -0,0 +1,288 @@
+//! Native and ERC-20 balances on any EVM chain
+//!
+//! The EVM networks differ only in their RPC endpoint, chain id and native
+//! coin, so one implementation serves them all from the `CHAINS` table. The
+//! per-network modules (`ethereum_wallet`, `base_wallet`, ...) keep their
+//! functions as thin wrappers over this one; supporting another EVM chain is a
+//! row in the table plus its `Network` variant. Chains without a row are
+//! reached as `Network::CustomEvm` through an endpoint installed at runtime
+//! with `set_custom_chain` (`--network custom-evm --rpc-url ...`).
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::sync::OnceLock;
+
+use crate::provider::BalanceProvider;
+use crate::{address, config, evm_rpc, units, Network, TokenInfo, WalletBalance, WalletError};
+
+/// One EVM chain: where to reach it and what its native coin is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvmChain {
+    pub network: Network,
+    /// Short name used in error messages
+    pub name: &'static str,
+    /// EIP-155 chain id
+    pub chain_id: u64,
+    /// Public JSON-RPC endpoint used unless the config names another
+    pub default_rpc: &'static str,
+    /// Public endpoints tried in order when `default_rpc` is down or rate limiting
+    pub fallback_rpcs: &'static [&'static str],
+    /// Ticker of the native coin
+    pub symbol: &'static str,
+    /// Decimal places of the native coin's base unit
+    pub decimals: u32,
+}
+
+const fn evm(
+    network: Network,
+    name: &'static str,
+    chain_id: u64,
+    default_rpc: &'static str,
+    symbol: &'static str,
+) -> EvmChain {
+    EvmChain {
+        network,
+        name,
+        chain_id,
+        default_rpc,
+        fallback_rpcs: &[],
+        symbol,
+        decimals: 18,
+    }
+}
+
+impl EvmChain {
+    const fn fallbacks(self, fallback_rpcs: &'static [&'static str]) -> EvmChain {
+        EvmChain { fallback_rpcs, ..self }
+    }
+}
+
+/// Every supported EVM chain
+pub const CHAINS: &[EvmChain] = &[
+    evm(Network::Ethereum, "Ethereum", 1, "https://cloudflare-eth.com", "ETH")
+        .fallbacks(&["https://ethereum-rpc.publicnode.com", "https://eth.drpc.org"]),
+    evm(Network::Base, "Base", 8453, "https://mainnet.base.org", "ETH")
+        .fallbacks(&["https://base-rpc.publicnode.com", "https://base.drpc.org"]),
+    evm(Network::Arbitrum, "Arbitrum", 42161, "https://arb1.arbitrum.io/rpc", "ETH")
+        .fallbacks(&["https://arbitrum-one-rpc.publicnode.com", "https://arbitrum.drpc.org"]),
+    evm(Network::Optimism, "Optimism", 10, "https://mainnet.optimism.io", "ETH")
+        .fallbacks(&["https://optimism-rpc.publicnode.com", "https://optimism.drpc.org"]),
+    evm(Network::Polygon, "Polygon", 137, "https://polygon-rpc.com", "MATIC")
+        .fallbacks(&["https://polygon-bor-rpc.publicnode.com", "https://polygon.drpc.org"]),
+    evm(Network::Bsc, "BSC", 56, "https://bsc-dataseed.bnbchain.org", "BNB")
+        .fallbacks(&["https://bsc-rpc.publicnode.com", "https://bsc.drpc.org"]),
+    evm(Network::Avalanche, "Avalanche", 43114, "https://api.avax.network/ext/bc/C/rpc", "AVAX")
+        .fallbacks(&["https://avalanche-c-chain-rpc.publicnode.com", "https://avalanche.drpc.org"]),
+    evm(Network::Fantom, "Fantom", 250, "https://rpcapi.fantom.network", "FTM")
+        .fallbacks(&["https://fantom-rpc.publicnode.com", "https://fantom.drpc.org"]),
+    evm(Network::Gnosis, "Gnosis", 100, "https://rpc.gnosischain.com", "xDAI")
+        .fallbacks(&["https://gnosis-rpc.publicnode.com", "https://gnosis.drpc.org"]),
+    // Test networks: their coins carry a `t` prefix, like tBTC, so they are never taken for real funds
+    evm(Network::Sepolia, "Sepolia", 11155111, "https://ethereum-sepolia-rpc.publicnode.com", "tETH")
+        .fallbacks(&["https://sepolia.drpc.org"]),
+    evm(Network::BaseSepolia, "Base Sepolia", 84532, "https://sepolia.base.org", "tETH")
+        .fallbacks(&["https://base-sepolia-rpc.publicnode.com"]),
+    evm(Network::ArbitrumSepolia, "Arbitrum Sepolia", 421614, "https://sepolia-rollup.arbitrum.io/rpc", "tETH")
+        .fallbacks(&["https://arbitrum-sepolia-rpc.publicnode.com"]),
+    evm(Network::OptimismSepolia, "Optimism Sepolia", 11155420, "https://sepolia.optimism.io", "tETH")
+        .fallbacks(&["https://optimism-sepolia-rpc.publicnode.com"]),
+    evm(Network::PolygonAmoy, "Polygon Amoy", 80002, "https://rpc-amoy.polygon.technology", "tMATIC")
+        .fallbacks(&["https://polygon-amoy-bor-rpc.publicnode.com"]),
+    evm(Network::BscTestnet, "BSC Testnet", 97, "https://bsc-testnet-rpc.publicnode.com", "tBNB")
+        .fallbacks(&["https://data-seed-prebsc-1-s1.bnbchain.org:8545"]),
+    evm(Network::AvalancheFuji, "Avalanche Fuji", 43113, "https://api.avax-test.network/ext/bc/C/rpc", "tAVAX")
+        .fallbacks(&["https://avalanche-fuji-c-chain-rpc.publicnode.com"]),
+    evm(Network::FantomTestnet, "Fantom Testnet", 4002, "https://rpc.testnet.fantom.network", "tFTM")
+        .fallbacks(&["https://fantom-testnet-rpc.publicnode.com"]),
+    evm(Network::GnosisChiado, "Gnosis Chiado", 10200, "https://rpc.chiadochain.net", "txDAI")
+        .fallbacks(&["https://gnosis-chiado-rpc.publicnode.com"]),
+];
+
+/// `Network::CustomEvm` before `set_custom_chain`: no endpoint, so every request fails
+const CUSTOM_UNSET: EvmChain = evm(Network::CustomEvm, "custom EVM", 0, "", "ETH");
+
+static CUSTOM: OnceLock<EvmChain> = OnceLock::new();
+
+/// Serve `Network::CustomEvm` from `rpc_url`, with `symbol` as its native coin
+///
+/// The chain is installed once per process and lives until it exits; its
+/// chain id is unknown (0), so payment URIs never resolve to it.
+pub fn set_custom_chain(rpc_url: &str, symbol: &str) -> Result<(), WalletError> {
+    let url = reqwest::Url::parse(rpc_url).with_context(|| format!("Invalid RPC URL: {}", rpc_url))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(WalletError::ParseError(format!("Invalid RPC URL: {} (expected http:// or https://)", rpc_url)));
+    }
+    let symbol = symbol.trim();
+    if symbol.is_empty() || !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(WalletError::ParseError(format!(
+            "Invalid symbol: {:?} (expected letters and digits, e.g. ETH)",
+            symbol
+        )));
+    }
+
+    let chain = EvmChain {
+        default_rpc: String::from(rpc_url).leak(),
+        symbol: symbol.to_string().leak(),
+        ..CUSTOM_UNSET
+    };
+    CUSTOM
+        .set(chain)
+        .map_err(|_| WalletError::Other("A custom EVM chain is already installed".to_string()))
+}
+
+/// The chain installed with `set_custom_chain`, if any
+pub fn custom_chain() -> Option<&'static EvmChain> {
+    CUSTOM.get()
+}
+
+/// The `CHAINS` entry of `network` (or the custom chain), or `None` for non-EVM networks
+pub fn chain(network: Network) -> Option<&'static EvmChain> {
+    if network == Network::CustomEvm {
+        return Some(custom_chain().unwrap_or(&CUSTOM_UNSET));
+    }
+    CHAINS.iter().find(|chain| chain.network == network)
+}
+
+/// The `CHAINS` entry with EIP-155 id `chain_id`
+pub fn chain_by_id(chain_id: u64) -> Option<&'static EvmChain> {
+    CHAINS.iter().find(|chain| chain.chain_id == chain_id)
+}
+
+impl EvmChain {
+    /// RPC endpoint from the active config, defaulting to `default_rpc`
+    pub fn rpc_url(&self) -> String {
+        config::active().evm_rpc(self.network).unwrap_or(self.default_rpc).to_string()
+    }
+
+    /// `rpc_url` followed by the endpoints `evm_rpc::call` fails over to, in order
+    pub fn rpc_urls(&self) -> Vec<String> {
+        config::active()
+            .evm_rpcs(self.network)
+            .unwrap_or_else(|| vec![self.default_rpc])
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// Native balance of `address` on `chain`, in whole coins
+#[tracing::instrument(
+    level = "debug",
+    name = "evm_balance",
+    skip_all,
+    fields(network = %chain.network, address = %address)
+)]
+pub async fn get_balance(chain: &EvmChain, address: &str) -> Result<WalletBalance, WalletError> {
+    let address = normalize_address(chain, address)?;
+
+    let balance_wei = evm_rpc::get_balance_at(&chain.rpc_url(), &address, None)
+        .await
+        .with_context(|| format!("Failed to read balance from {} RPC", chain.name))?;
+
+    Ok(WalletBalance::new(
+        address,
+        units::format_units_u256(balance_wei, chain.decimals),
+        chain.network.to_string(),
+        chain.symbol.to_string(),
+    ))
+}
+
+/// Validate `address` and render it with its EIP-55 checksum, adding the `0x` prefix if missing
+///
+/// Mixed-case input carries a checksum, which must match: a mistyped
+/// character is caught here instead of querying the wrong account.
+/// Single-case input has no checksum to verify.
+pub(crate) fn normalize_address(chain: &EvmChain, address: &str) -> Result<String, WalletError> {
+    if address.is_empty() {
+        return Err(WalletError::InvalidAddress(format!("{} address cannot be empty", chain.name)));
+    }
+
+    let body = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+        .unwrap_or(address);
+    let lowercase = format!("0x{}", body.to_lowercase());
+    validate_address(chain, &lowercase)?;
+
+    let checksummed = address::to_checksum_address(&lowercase)?;
+    let mixed_case = body.contains(|c: char| c.is_ascii_lowercase()) && body.contains(|c: char| c.is_ascii_uppercase());
+    if mixed_case && checksummed[2..] != *body {
+        return Err(WalletError::InvalidAddress(format!(
+            "Invalid EIP-55 checksum in {} address {} (a character may be mistyped; the checksummed form of the same hex is {})",
+            chain.name,
+            address,
+            checksummed
+        )));
+    }
+    Ok(checksummed)
+}
+
+/// Check that a normalized `address` is `0x` followed by 40 hex characters
+pub(crate) fn validate_address(chain: &EvmChain, address: &str) -> Result<(), WalletError> {
+    if !address.starts_with("0x") {
+        return Err(WalletError::InvalidAddress(format!("{} address must start with 0x", chain.name)));
+    }
+
+    if address.len() != 42 {
+        return Err(WalletError::InvalidAddress(format!(
+            "Invalid {} address length (expected 42 characters)",
+            chain.name
+        )));
+    }
+
+    if !address[2..].chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(WalletError::InvalidAddress(format!("{} address contains invalid hex characters", chain.name)));
+    }
+
+    Ok(())
+}
+
+/// ERC-20 balance of `wallet_address` in the `token_address` token, scaled by the token's own `decimals()`
+pub async fn get_erc20_balance(
+    chain: &EvmChain,
+    token_address: &str,
+    wallet_address: &str,
+) -> Result<String, WalletError> {
+    let token_address = normalize_address(chain, token_address)?;
+    let wallet_address = normalize_address(chain, wallet_address)?;
+
+    let rpc_url = chain.rpc_url();
+    let (balance, token) = tokio::try_join!(
+        evm_rpc::erc20_balance(&rpc_url, &token_address, &wallet_address),
+        evm_rpc::erc20_token(&rpc_url, &token_address),
+    )?;
+    Ok(units::format_units_u256(balance, token.decimals))
+}
+
+/// Symbol and decimals of an ERC-20 token on `chain`
+pub async fn get_erc20_token(chain: &EvmChain, token_address: &str) -> Result<TokenInfo, WalletError> {
+    let token_address = normalize_address(chain, token_address)?;
+    Ok(evm_rpc::erc20_token(&chain.rpc_url(), &token_address).await?)
+}
+
+/// `BalanceProvider` for one of the `CHAINS`, or the custom chain
+///
+/// The chain is looked up on every call, so the registry may be built before
+/// `set_custom_chain` runs.
+pub struct EvmProvider(pub Network);
+
+impl EvmProvider {
+    fn chain(&self) -> &'static EvmChain {
+        chain(self.0).expect("EvmProvider serves an EVM network")
+    }
+}
+
+#[async_trait]
+impl BalanceProvider for EvmProvider {
+    fn network(&self) -> Network {
+        self.0
+    }
+
+    fn normalize_address(&self, address: &str) -> Result<String, WalletError> {
+        normalize_address(self.chain(), address)
+    }
+
+    async fn get_balance(&self, address: &str) -> Result<WalletBalance, WalletError> {
+        get_balance(self.chain(), address).await
+    }
+}
@@ -0,0 +1,86 @@
+//! Known exchange deposit and hot-wallet addresses
+//!
+//! Exchanges pool their customers' funds in shared wallets, so the on-chain
+//! balance of such an address is the exchange's, not what it owes any one
+//! customer. Someone who looks up the address they deposited to and finds a
+//! balance that has nothing to do with theirs is usually looking at one of
+//! these; the CLI warns when an address is known to belong to an exchange.
+//!
+//! `BUILT_IN` lists widely published exchange wallets. The config file's
+//! `[[exchange_addresses]]` entries extend it, and an address ending in `*`
+//! there matches every address with that prefix.
+
+use std::borrow::Cow;
+
+use crate::{config, Network};
+
+/// An address, or address prefix, known to belong to an exchange
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExchangeAddress {
+    pub network: Network,
+    /// Full address, or a prefix followed by `*`
+    pub address: Cow<'static, str>,
+    pub exchange: Cow<'static, str>,
+}
+
+const fn known(network: Network, address: &'static str, exchange: &'static str) -> ExchangeAddress {
+    ExchangeAddress {
+        network,
+        address: Cow::Borrowed(address),
+        exchange: Cow::Borrowed(exchange),
+    }
+}
+
+/// Exchange wallets shipped with the CLI, grouped by network
+pub const BUILT_IN: &[ExchangeAddress] = &[
+    known(Network::Bitcoin, "34xp4vRoCGJym3xR7yCVPFHoCNxv4Twseo", "Binance"),
+    known(Network::Bitcoin, "bc1qm34lsc65zpw79lxes69zkqmk6ee3ewf0j77s3h", "Binance"),
+    known(Network::Bitcoin, "bc1qgdjqv0av3q56jvd82tkdjpy7gdp9ut8tlqmgrpmv24sq90ecnvqqjwvw97", "Bitfinex"),
+    known(Network::Bitcoin, "bc1ql49ydapnjafl5t2cp9zqpjwe6pdgmxy98859v2", "Robinhood"),
+    known(Network::Ethereum, "0xBE0eB53F46cd790Cd13851d5EFf43D12404d33E8", "Binance"),
+    known(Network::Ethereum, "0xF977814e90dA44bFA03b6295A0616a897441aceC", "Binance"),
+    known(Network::Ethereum, "0x28C6c06298d514Db089934071355E5743bf21d60", "Binance"),
+    known(Network::Ethereum, "0x21a31Ee1afC51d94C2eFcCAa2092aD1028285549", "Binance"),
+    known(Network::Ethereum, "0xDFd5293D8e347dFe59E90eFd55b2956a1343963d", "Binance"),
+    known(Network::Ethereum, "0x71660c4005BA85c37ccec55d0C4493E66Fe775d3", "Coinbase"),
+    known(Network::Ethereum, "0x503828976D22510aad0201ac7EC88293211D23Da", "Coinbase"),
+    known(Network::Ethereum, "0xddfAbCdc4D8FfC6d5beaf154f18B778f892A0740", "Coinbase"),
+    known(Network::Ethereum, "0xA9D1e08C7793af67e9d92fe308d5697FB81d3E43", "Coinbase"),
+    known(Network::Ethereum, "0x2910543Af39abA0Cd09dBb2D50200b3E800A63D2", "Kraken"),
+];
+
+impl ExchangeAddress {
+    /// Whether `address` on `network` is this address, or starts with this prefix
+    ///
+    /// Hex EVM addresses compare case-insensitively, so checksummed and
+    /// lowercase spellings match alike.
+    pub fn matches(&self, network: Network, address: &str) -> bool {
+        if self.network != network {
+            return false;
+        }
+        let address = address.trim();
+        let same = |a: &str, b: &str| match network.is_evm() {
+            true => a.eq_ignore_ascii_case(b),
+            false => a == b,
+        };
+        match self.address.strip_suffix('*') {
+            Some(prefix) => address.get(..prefix.len()).is_some_and(|start| same(start, prefix)),
+            None => same(address, &self.address),
+        }
+    }
+}
+
+/// The first of `entries` that `address` on `network` matches
+pub fn identify_in<'a>(
+    entries: impl IntoIterator<Item = &'a ExchangeAddress>,
+    network: Network,
+    address: &str,
+) -> Option<&'a ExchangeAddress> {
+    entries.into_iter().find(|entry| entry.matches(network, address))
+}
+
+/// Name of the exchange `address` on `network` belongs to, checking the configured entries before `BUILT_IN`
+pub fn identify(network: Network, address: &str) -> Option<String> {
+    let configured = config::active().exchange_addresses().unwrap_or_default();
+    identify_in(configured.iter().chain(BUILT_IN), network, address).map(|entry| entry.exchange.to_string())
+}
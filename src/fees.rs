@@ -0,0 +1,51 @@
+//! Transaction fee estimation
+//!
+//! Rough estimates of what a simple native-asset transfer costs right now,
+//! used to answer "can this wallet pay X, fees included?".
+
+use anyhow::Result;
+
+use crate::{bitcoin_wallet, evm_rpc, Network, WalletError};
+
+/// Typical virtual size of a 1-input, 2-output P2WPKH transaction
+const BITCOIN_TRANSFER_VBYTES: f64 = 141.0;
+
+/// Confirmation target used for the Bitcoin fee rate
+const BITCOIN_FEE_TARGET_BLOCKS: u32 = 6;
+
+/// Gas used by a plain native-asset transfer
+const EVM_TRANSFER_GAS: u128 = 21_000;
+
+/// Bandwidth cost of a TRX transfer with no free bandwidth left (~268 bytes at 1000 sun/byte)
+const TRON_TRANSFER_FEE_SUN: u128 = 268_000;
+
+/// Estimate the fee of a simple transfer on `network`
+///
+/// # Returns
+///
+/// Returns the fee in the network's base unit (satoshi, wei or sun). L2 fees
+/// only cover execution gas, not the L1 data fee.
+pub async fn estimate_transfer_fee(network: Network) -> Result<u128, WalletError> {
+    if let Some(rpc_url) = evm_rpc::rpc_url(network) {
+        let gas_price = evm_rpc::gas_price(&rpc_url).await?;
+        return gas_price.checked_mul(EVM_TRANSFER_GAS).ok_or_else(|| {
+            WalletError::ParseError(format!("Gas price {} wei is too large for a transfer fee", gas_price))
+        });
+    }
+
+    if let Some(chain) = network.bitcoin_chain() {
+        let estimates = bitcoin_wallet::get_fee_estimates_on(chain).await?;
+        // Fall back to the most expensive rate offered if the target is missing
+        let rate = estimates
+            .get(&BITCOIN_FEE_TARGET_BLOCKS)
+            .copied()
+            .or_else(|| estimates.values().copied().reduce(f64::max))
+            .ok_or_else(|| anyhow::anyhow!("No Bitcoin fee estimates available"))?;
+        return Ok((rate * BITCOIN_TRANSFER_VBYTES).ceil() as u128);
+    }
+
+    match network {
+        Network::Tron | Network::TronShasta => Ok(TRON_TRANSFER_FEE_SUN),
+        _ => Err(WalletError::UnsupportedNetwork(format!("Fee estimation is not supported on {}", network))),
+    }
+}
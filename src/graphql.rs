@@ -0,0 +1,620 @@
+//! GraphQL endpoint of the HTTP server (`serve --listen <addr> --graphql`)
+//!
+//! `POST /graphql` answers queries over the same lookups as `/balance`, so a
+//! dashboard can fetch everything it shows in one request instead of one REST
+//! call per number. Only the subset of GraphQL those lookups need is
+//! implemented: operations with variables, aliases, arguments and nested
+//! selections, but no fragments, directives or introspection. Field names are
+//! the snake_case keys of the REST JSON; a key a result does not carry reads
+//! as `null`. A JSON array of requests is answered with an array of responses.
+//!
+//! Query fields:
+//!
+//! * `balance(network, address)` - native balance
+//! * `balances(queries: [{network, address}])` - several balances, fetched like a batch run
+//! * `tokens(address, network = "bitcoin")` - rune balances of a Bitcoin address
+//! * `history(network, address, blocks: [Int])` - native EVM balance at each block
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use tokio::task::JoinSet;
+
+use crate::{dev, evm_rpc, runes, sanitize, units, BalanceSource, Network};
+
+/// Most blocks one `history` field may read
+pub const MAX_HISTORY_BLOCKS: usize = 100;
+
+/// One GraphQL request as POSTed in JSON
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Request {
+    pub query: String,
+    #[serde(default)]
+    pub variables: Option<Map<String, Value>>,
+    #[serde(default)]
+    pub operation_name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Punct(char),
+    Name(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Punct(c) => write!(f, "\"{}\"", c),
+            Token::Name(name) => write!(f, "\"{}\"", name),
+            Token::Int(n) => write!(f, "{}", n),
+            Token::Float(n) => write!(f, "{}", n),
+            Token::Str(s) => write!(f, "{:?}", s),
+        }
+    }
+}
+
+/// Argument value, before variables are substituted
+#[derive(Debug, Clone)]
+enum Input {
+    Variable(String),
+    Const(Value),
+    List(Vec<Input>),
+    Object(Vec<(String, Input)>),
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    /// Key of the field in the response: its alias, or else its name
+    key: String,
+    name: String,
+    arguments: Vec<(String, Input)>,
+    selections: Vec<Field>,
+}
+
+#[derive(Debug, Clone)]
+struct Operation {
+    name: Option<String>,
+    variables: Vec<(String, Option<Input>)>,
+    selections: Vec<Field>,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            // Commas are insignificant in GraphQL, like whitespace
+            _ if c.is_whitespace() || c == ',' || c == '\u{feff}' => i += 1,
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '{' | '}' | '(' | ')' | '[' | ']' | ':' | '!' | '$' | '=' => {
+                tokens.push(Token::Punct(c));
+                i += 1;
+            }
+            '.' => return Err(anyhow::anyhow!("Fragments are not supported")),
+            '@' => return Err(anyhow::anyhow!("Directives are not supported")),
+            '"' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None | Some('\n') => return Err(anyhow::anyhow!("Unterminated string")),
+                        Some('"') => break,
+                        Some('\\') => {
+                            let escaped = match chars.get(i + 1) {
+                                Some('n') => '\n',
+                                Some('t') => '\t',
+                                Some('r') => '\r',
+                                Some('b') => '\u{8}',
+                                Some('f') => '\u{c}',
+                                Some(&c @ ('"' | '\\' | '/')) => c,
+                                Some('u') => {
+                                    let hex: String = chars.iter().skip(i + 2).take(4).collect();
+                                    i += 4;
+                                    u32::from_str_radix(&hex, 16)
+                                        .ok()
+                                        .and_then(char::from_u32)
+                                        .ok_or_else(|| anyhow::anyhow!("Invalid unicode escape \\u{}", hex))?
+                                }
+                                _ => return Err(anyhow::anyhow!("Invalid escape in string")),
+                            };
+                            value.push(escaped);
+                            i += 2;
+                        }
+                        Some(&c) => {
+                            value.push(c);
+                            i += 1;
+                        }
+                    }
+                }
+                tokens.push(Token::Str(value));
+                i += 1;
+            }
+            _ if c == '_' || c.is_ascii_alphabetic() => {
+                let start = i;
+                while i < chars.len() && (chars[i] == '_' || chars[i].is_ascii_alphanumeric()) {
+                    i += 1;
+                }
+                tokens.push(Token::Name(chars[start..i].iter().collect()));
+            }
+            _ if c == '-' || c.is_ascii_digit() => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || matches!(chars[i], '.' | 'e' | 'E' | '+' | '-')) {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let token = match text.parse::<i64>() {
+                    Ok(n) => Token::Int(n),
+                    Err(_) => Token::Float(text.parse().map_err(|_| anyhow::anyhow!("Invalid number {}", text))?),
+                };
+                tokens.push(token);
+            }
+            _ => return Err(anyhow::anyhow!("Unexpected character {:?}", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self.peek().cloned().ok_or_else(|| anyhow::anyhow!("Unexpected end of query"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, punct: char) -> bool {
+        let found = self.peek() == Some(&Token::Punct(punct));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, punct: char) -> Result<()> {
+        match self.next()? {
+            Token::Punct(c) if c == punct => Ok(()),
+            token => Err(anyhow::anyhow!("Expected \"{}\", found {}", punct, token)),
+        }
+    }
+
+    fn name(&mut self) -> Result<String> {
+        match self.next()? {
+            Token::Name(name) => Ok(name),
+            token => Err(anyhow::anyhow!("Expected a name, found {}", token)),
+        }
+    }
+
+    fn operation(&mut self) -> Result<Operation> {
+        let mut operation = Operation {
+            name: None,
+            variables: Vec::new(),
+            selections: Vec::new(),
+        };
+        if !self.eat('{') {
+            match self.name()?.as_str() {
+                "query" => {}
+                "mutation" | "subscription" => return Err(anyhow::anyhow!("Only queries are supported")),
+                "fragment" => return Err(anyhow::anyhow!("Fragments are not supported")),
+                other => return Err(anyhow::anyhow!("Unexpected \"{}\"", other)),
+            }
+            if let Some(Token::Name(_)) = self.peek() {
+                operation.name = Some(self.name()?);
+            }
+            if self.eat('(') {
+                while !self.eat(')') {
+                    self.expect('$')?;
+                    let name = self.name()?;
+                    self.expect(':')?;
+                    self.skip_type()?;
+                    let default = match self.eat('=') {
+                        true => Some(self.value()?),
+                        false => None,
+                    };
+                    operation.variables.push((name, default));
+                }
+            }
+            self.expect('{')?;
+        }
+        operation.selections = self.selections()?;
+        Ok(operation)
+    }
+
+    /// Variable types are not checked, only skipped
+    fn skip_type(&mut self) -> Result<()> {
+        if self.eat('[') {
+            self.skip_type()?;
+            self.expect(']')?;
+        } else {
+            self.name()?;
+        }
+        self.eat('!');
+        Ok(())
+    }
+
+    /// Fields up to the `}` closing a selection set whose `{` was consumed
+    fn selections(&mut self) -> Result<Vec<Field>> {
+        let mut fields = Vec::new();
+        while !self.eat('}') {
+            fields.push(self.field()?);
+        }
+        if fields.is_empty() {
+            return Err(anyhow::anyhow!("Selection sets cannot be empty"));
+        }
+        Ok(fields)
+    }
+
+    fn field(&mut self) -> Result<Field> {
+        let key = self.name()?;
+        let name = match self.eat(':') {
+            true => self.name()?,
+            false => key.clone(),
+        };
+        let mut arguments = Vec::new();
+        if self.eat('(') {
+            while !self.eat(')') {
+                let argument = self.name()?;
+                self.expect(':')?;
+                arguments.push((argument, self.value()?));
+            }
+        }
+        let selections = match self.eat('{') {
+            true => self.selections()?,
+            false => Vec::new(),
+        };
+        Ok(Field {
+            key,
+            name,
+            arguments,
+            selections,
+        })
+    }
+
+    fn value(&mut self) -> Result<Input> {
+        Ok(match self.next()? {
+            Token::Punct('$') => Input::Variable(self.name()?),
+            Token::Punct('[') => {
+                let mut items = Vec::new();
+                while !self.eat(']') {
+                    items.push(self.value()?);
+                }
+                Input::List(items)
+            }
+            Token::Punct('{') => {
+                let mut entries = Vec::new();
+                while !self.eat('}') {
+                    let key = self.name()?;
+                    self.expect(':')?;
+                    entries.push((key, self.value()?));
+                }
+                Input::Object(entries)
+            }
+            Token::Name(name) => Input::Const(match name.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "null" => Value::Null,
+                // Enum values are passed on as their names
+                _ => Value::String(name),
+            }),
+            Token::Int(n) => Input::Const(json!(n)),
+            Token::Float(n) => Input::Const(json!(n)),
+            Token::Str(s) => Input::Const(Value::String(s)),
+            token => return Err(anyhow::anyhow!("Expected a value, found {}", token)),
+        })
+    }
+}
+
+fn parse_document(source: &str) -> Result<Vec<Operation>> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+    };
+    let mut operations = Vec::new();
+    while parser.peek().is_some() {
+        operations.push(parser.operation()?);
+    }
+    if operations.is_empty() {
+        return Err(anyhow::anyhow!("The query has no operation"));
+    }
+    Ok(operations)
+}
+
+fn substitute(input: &Input, variables: &HashMap<String, Value>) -> Result<Value> {
+    Ok(match input {
+        Input::Variable(name) => variables
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Variable ${} is not defined", name))?,
+        Input::Const(value) => value.clone(),
+        Input::List(items) => Value::Array(items.iter().map(|item| substitute(item, variables)).collect::<Result<_>>()?),
+        Input::Object(entries) => Value::Object(
+            entries
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), substitute(value, variables)?)))
+                .collect::<Result<_>>()?,
+        ),
+    })
+}
+
+/// Answer a JSON request body: one request object, or an array of them
+///
+/// With `networks`, fields naming any other network fail instead of being looked up.
+pub async fn respond(source: &BalanceSource, networks: Option<&[Network]>, body: Value) -> Value {
+    let Value::Array(requests) = body else {
+        return execute_value(source, networks, body).await;
+    };
+    let mut tasks = JoinSet::new();
+    for (index, request) in requests.into_iter().enumerate() {
+        let (source, networks) = (source.clone(), networks.map(<[Network]>::to_vec));
+        tasks.spawn(async move { (index, execute_value(&source, networks.as_deref(), request).await) });
+    }
+    let mut responses: Vec<Value> = vec![Value::Null; tasks.len()];
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok((index, response)) = joined {
+            responses[index] = response;
+        }
+    }
+    Value::Array(responses)
+}
+
+async fn execute_value(source: &BalanceSource, networks: Option<&[Network]>, request: Value) -> Value {
+    match serde_json::from_value(request).context("Invalid GraphQL request") {
+        Ok(request) => execute(source, networks, request).await,
+        Err(e) => json!({ "errors": [{ "message": format!("{:#}", e) }] }),
+    }
+}
+
+/// Execute one request, fetching its top-level fields concurrently
+///
+/// A field that fails is `null` in `data` and has an entry in `errors`; the
+/// other fields are still answered. With `networks`, fields naming any other
+/// network fail.
+pub async fn execute(source: &BalanceSource, networks: Option<&[Network]>, request: Request) -> Value {
+    let fields = match prepare(request) {
+        Ok(fields) => fields,
+        Err(e) => return json!({ "errors": [{ "message": format!("{:#}", e) }] }),
+    };
+
+    let mut tasks = JoinSet::new();
+    for (index, (field, arguments)) in fields.iter().cloned().enumerate() {
+        let (source, networks) = (source.clone(), networks.map(<[Network]>::to_vec));
+        tasks.spawn(async move {
+            let result = resolve(&source, networks.as_deref(), &field.name, &arguments).await;
+            (index, result.and_then(|value| select(value, &field.selections)))
+        });
+    }
+    let mut results: Vec<Option<Result<Value>>> = fields.iter().map(|_| None).collect();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok((index, result)) = joined {
+            results[index] = Some(result);
+        }
+    }
+
+    let mut data = Map::new();
+    let mut errors = Vec::new();
+    for ((field, _), result) in fields.iter().zip(results) {
+        match result.unwrap_or_else(|| Err(anyhow::anyhow!("Field task failed"))) {
+            Ok(value) => {
+                data.insert(field.key.clone(), value);
+            }
+            Err(e) => {
+                data.insert(field.key.clone(), Value::Null);
+                errors.push(json!({ "message": format!("{:#}", e), "path": [field.key] }));
+            }
+        }
+    }
+    let mut response = json!({ "data": data });
+    if !errors.is_empty() {
+        response["errors"] = Value::Array(errors);
+    }
+    response
+}
+
+/// Top-level fields of the selected operation with their arguments substituted
+fn prepare(request: Request) -> Result<Vec<(Field, Map<String, Value>)>> {
+    let mut operations = parse_document(&request.query)?;
+    let operation = match &request.operation_name {
+        Some(name) => {
+            let index = operations
+                .iter()
+                .position(|operation| operation.name.as_deref() == Some(name))
+                .ok_or_else(|| anyhow::anyhow!("No operation named \"{}\"", name))?;
+            operations.swap_remove(index)
+        }
+        None if operations.len() == 1 => operations.remove(0),
+        None => return Err(anyhow::anyhow!("operationName is required when the query has several operations")),
+    };
+
+    let supplied = request.variables.unwrap_or_default();
+    let mut variables = HashMap::new();
+    for (name, default) in &operation.variables {
+        let value = match (supplied.get(name), default) {
+            (Some(value), _) => value.clone(),
+            (None, Some(default)) => substitute(default, &HashMap::new())?,
+            (None, None) => Value::Null,
+        };
+        variables.insert(name.clone(), value);
+    }
+
+    operation
+        .selections
+        .into_iter()
+        .map(|field| {
+            let arguments = field
+                .arguments
+                .iter()
+                .map(|(name, input)| Ok((name.clone(), substitute(input, &variables)?)))
+                .collect::<Result<_>>()?;
+            Ok((field, arguments))
+        })
+        .collect()
+}
+
+/// Keep the selected subfields of `value`, element by element for lists
+fn select(value: Value, selections: &[Field]) -> Result<Value> {
+    if selections.is_empty() {
+        let composite = match &value {
+            Value::Object(_) => true,
+            Value::Array(items) => items.iter().any(Value::is_object),
+            _ => false,
+        };
+        if composite {
+            return Err(anyhow::anyhow!("Objects need a selection of subfields"));
+        }
+        return Ok(value);
+    }
+    match value {
+        Value::Array(items) => Ok(Value::Array(
+            items.into_iter().map(|item| select(item, selections)).collect::<Result<_>>()?,
+        )),
+        Value::Object(object) => {
+            let mut selected = Map::new();
+            for field in selections {
+                if !field.arguments.is_empty() {
+                    return Err(anyhow::anyhow!("Field \"{}\" takes no arguments", field.name));
+                }
+                let value = object.get(&field.name).cloned().unwrap_or(Value::Null);
+                selected.insert(field.key.clone(), select(value, &field.selections)?);
+            }
+            Ok(Value::Object(selected))
+        }
+        Value::Null => Ok(Value::Null),
+        _ => Err(anyhow::anyhow!("Scalar fields have no subfields")),
+    }
+}
+
+fn string_argument(arguments: &Map<String, Value>, name: &str) -> Result<String> {
+    arguments
+        .get(name)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("Missing string argument \"{}\"", name))
+}
+
+/// The `network` argument, or `default` when it is absent; one outside `networks` is refused
+fn network_argument(
+    arguments: &Map<String, Value>,
+    default: Option<Network>,
+    networks: Option<&[Network]>,
+) -> Result<Network> {
+    let network = match (arguments.get("network").filter(|value| !value.is_null()), default) {
+        (None, Some(network)) => network,
+        _ => string_argument(arguments, "network")?.parse()?,
+    };
+    match networks.is_none_or(|networks| networks.contains(&network)) {
+        true => Ok(network),
+        false => Err(anyhow::anyhow!("This API key may not query {}", network)),
+    }
+}
+
+fn address_argument(arguments: &Map<String, Value>) -> Result<String> {
+    Ok(sanitize::sanitize_address(&string_argument(arguments, "address")?).address)
+}
+
+async fn resolve(
+    source: &BalanceSource,
+    networks: Option<&[Network]>,
+    name: &str,
+    arguments: &Map<String, Value>,
+) -> Result<Value> {
+    match name {
+        "balance" => {
+            let network = network_argument(arguments, None, networks)?;
+            let balance = source.get_balance(network, &address_argument(arguments)?).await?;
+            Ok(serde_json::to_value(balance)?)
+        }
+        "balances" => {
+            let queries = arguments
+                .get("queries")
+                .and_then(Value::as_array)
+                .ok_or_else(|| anyhow::anyhow!("Missing list argument \"queries\""))?
+                .iter()
+                .map(|query| {
+                    let query = query
+                        .as_object()
+                        .ok_or_else(|| anyhow::anyhow!("Each query needs a network and an address"))?;
+                    Ok((network_argument(query, None, networks)?, address_argument(query)?))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let results = source.get_balances(&queries, 0).await;
+            // A failed lookup keeps its place in the list, with the reason instead of a balance
+            let rows = queries.iter().zip(results).map(|((network, address), result)| match result {
+                Ok(balance) => serde_json::to_value(balance).expect("balances serialize"),
+                Err(e) => json!({ "address": address, "network": network.to_string(), "error": format!("{:#}", e) }),
+            });
+            Ok(Value::Array(rows.collect()))
+        }
+        "tokens" => {
+            let network = network_argument(arguments, Some(Network::Bitcoin), networks)?;
+            if network != Network::Bitcoin {
+                return Err(anyhow::anyhow!("Token listings are only supported on bitcoin, not {}", network));
+            }
+            if *source != BalanceSource::Live {
+                return Err(anyhow::anyhow!("Token listings need the live provider"));
+            }
+            Ok(serde_json::to_value(runes::get_balances(&address_argument(arguments)?).await?)?)
+        }
+        "history" => history(source, networks, arguments).await,
+        _ => Err(anyhow::anyhow!("Cannot query field \"{}\" on type \"Query\"", name)),
+    }
+}
+
+/// Native EVM balance at each of the `blocks`, which needs an archive node for old blocks
+async fn history(source: &BalanceSource, networks: Option<&[Network]>, arguments: &Map<String, Value>) -> Result<Value> {
+    let network = network_argument(arguments, None, networks)?;
+    let rpc_url = match source {
+        BalanceSource::Live => evm_rpc::rpc_url(network),
+        BalanceSource::Dev { node_url } if network.is_evm() => {
+            Some(node_url.clone().unwrap_or_else(|| dev::DEFAULT_EVM_NODE.to_string()))
+        }
+        BalanceSource::Dev { .. } => None,
+        BalanceSource::Mock => return Err(anyhow::anyhow!("Balance history needs the live provider or --dev")),
+    }
+    .ok_or_else(|| anyhow::anyhow!("Balance history is only supported on EVM networks, not {}", network))?;
+    let address = crate::normalize_address(network, &address_argument(arguments)?)?;
+    let blocks = arguments
+        .get("blocks")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow::anyhow!("Missing list argument \"blocks\""))?
+        .iter()
+        .map(|block| block.as_u64().ok_or_else(|| anyhow::anyhow!("Block numbers must be non-negative integers")))
+        .collect::<Result<Vec<u64>>>()?;
+    if blocks.len() > MAX_HISTORY_BLOCKS {
+        return Err(anyhow::anyhow!("At most {} blocks per history field", MAX_HISTORY_BLOCKS));
+    }
+
+    let mut reads = JoinSet::new();
+    for (index, block) in blocks.iter().copied().enumerate() {
+        let (rpc_url, address) = (rpc_url.clone(), address.clone());
+        reads.spawn(async move { (index, evm_rpc::get_balance_at(&rpc_url, &address, Some(block)).await) });
+    }
+    let mut balances = vec![None; blocks.len()];
+    while let Some(joined) = reads.join_next().await {
+        let (index, wei) = joined.context("Balance read task failed")?;
+        balances[index] = Some(units::format_units_u256(wei?, network.decimals()));
+    }
+    let points = blocks
+        .iter()
+        .zip(balances)
+        .map(|(block, balance)| json!({ "block": block, "balance": balance, "denomination": network.denomination() }));
+    Ok(Value::Array(points.collect()))
+}
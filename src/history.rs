@@ -0,0 +1,491 @@
+//! Recent transactions of an address (`history`)
+//!
+//! Each network family lists transactions from a different source:
+//!
+//! * Bitcoin: Esplora's `/address/:address/txs`, which returns the mempool
+//!   transactions and the newest confirmed ones, then
+//!   `/txs/chain/:last_seen_txid` for older pages.
+//! * EVM, with an Etherscan API key (`etherscan.api_key` or
+//!   `WALLET_BALANCE_ETHERSCAN_KEY`): the `txlist` action of the Etherscan V2
+//!   API, which covers every chain by id.
+//! * EVM, without a key: ERC-20 `Transfer` logs to or from the address read
+//!   with `eth_getLogs`, a window of `LOG_WINDOW` blocks per page. Plain
+//!   native transfers emit no logs, so only token transfers show up this way.
+//!
+//! Every transaction is reported with the signed net change it made to the
+//! address (fees included where the source knows them). A page ends with an
+//! opaque cursor for `before`, to continue with older transactions.
+//!
+//! The same Esplora transaction lists give Bitcoin balances as of an earlier
+//! block (`get_balance_at_height`).
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+
+use crate::units::{self, U256};
+use crate::{bitcoin_wallet, config, evm_rpc, evm_wallet, http, BlockRef, Network, WalletBalance, WalletError};
+
+/// Default number of transactions per page
+pub const DEFAULT_LIMIT: usize = 25;
+
+/// Blocks of `Transfer` logs scanned per page without an Etherscan key
+pub const LOG_WINDOW: u64 = 10_000;
+
+/// Default Etherscan V2 API endpoint
+pub const ETHERSCAN_API: &str = "https://api.etherscan.io/v2/api";
+
+/// Confirmed transactions per Esplora page
+const ESPLORA_PAGE: usize = 25;
+
+/// One transaction as it affected the address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Transaction {
+    pub hash: String,
+    /// Block the transaction is in, `None` while unconfirmed
+    pub block: Option<u64>,
+    /// Block time in Unix seconds, when the source reports it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    /// Signed net change to the address, e.g. `+0.5` or `-0.0021`
+    pub change: String,
+    /// Asset the change is in: the native coin, or a token's symbol
+    pub denomination: String,
+}
+
+/// A page of an address's transactions, newest first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HistoryPage {
+    pub network: String,
+    pub address: String,
+    /// Where the transactions came from: `esplora`, `etherscan` or `logs`
+    pub source: String,
+    pub transactions: Vec<Transaction>,
+    /// Cursor to pass as `before` for the next, older page; `None` on the last one
+    pub next: Option<String>,
+}
+
+/// Up to `limit` transactions of `address` on `network`, older than the cursor `before`
+///
+/// Bitcoin's unconfirmed transactions are all listed on the first page, ahead
+/// of the `limit` confirmed ones. A page of `Transfer` logs may run over
+/// `limit` to finish the block it stopped in.
+pub async fn get_history(
+    network: Network,
+    address: &str,
+    limit: usize,
+    before: Option<&str>,
+) -> Result<HistoryPage, WalletError> {
+    if limit == 0 {
+        return Err(WalletError::Other("--limit must be at least 1".to_string()));
+    }
+    let address = crate::normalize_address(network, address.trim())?;
+    let (source, transactions, next) = if let Some(chain) = network.bitcoin_chain() {
+        let (transactions, next) = esplora_history(&chain.api_base(), chain.network(), &address, limit, before).await?;
+        ("esplora", transactions, next)
+    } else if let Some(rpc_url) = evm_rpc::rpc_url(network) {
+        let settings = config::active();
+        match (settings.etherscan_api_key(), evm_wallet::chain(network)) {
+            (Some(api_key), Some(chain)) if network != Network::CustomEvm => {
+                let api = settings.etherscan_api().to_string();
+                let page = etherscan_history(&api, api_key, chain.chain_id, network, &address, limit, before).await?;
+                ("etherscan", page.0, page.1)
+            }
+            _ => {
+                let (transactions, next) = log_history(&rpc_url, &address, limit, before).await?;
+                ("logs", transactions, next)
+            }
+        }
+    } else {
+        return Err(WalletError::UnsupportedNetwork(format!("history is not supported on {} yet", network)));
+    };
+
+    Ok(HistoryPage {
+        network: network.to_string(),
+        address,
+        source: source.to_string(),
+        transactions,
+        next,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraTx {
+    txid: String,
+    #[serde(default)]
+    vin: Vec<EsploraInput>,
+    #[serde(default)]
+    vout: Vec<EsploraOutput>,
+    #[serde(default)]
+    status: EsploraStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraInput {
+    prevout: Option<EsploraOutput>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraOutput {
+    scriptpubkey_address: Option<String>,
+    value: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct EsploraStatus {
+    confirmed: bool,
+    block_height: Option<u64>,
+    block_time: Option<u64>,
+}
+
+/// Pages of Esplora transactions until `limit` confirmed ones are in, and the cursor after them
+async fn esplora_history(
+    api_base: &str,
+    network: Network,
+    address: &str,
+    limit: usize,
+    before: Option<&str>,
+) -> Result<(Vec<Transaction>, Option<String>)> {
+    let mut transactions = Vec::new();
+    let mut confirmed = 0;
+    let mut cursor = before.map(str::to_string);
+    loop {
+        let path = match &cursor {
+            Some(txid) => format!("/address/{}/txs/chain/{}", address, txid),
+            None => format!("/address/{}/txs", address),
+        };
+        let page = esplora_page(api_base, &path).await?;
+        let full = page.iter().filter(|tx| tx.status.confirmed).count() >= ESPLORA_PAGE;
+        let mut stopped = false;
+        for tx in page {
+            if tx.status.confirmed {
+                if confirmed == limit {
+                    stopped = true;
+                    break;
+                }
+                confirmed += 1;
+                cursor = Some(tx.txid.clone());
+            }
+            transactions.push(Transaction {
+                change: esplora_change(&tx, address, network.decimals()),
+                hash: tx.txid,
+                block: tx.status.block_height,
+                timestamp: tx.status.block_time,
+                denomination: network.denomination().to_string(),
+            });
+        }
+        if confirmed == limit {
+            // A full page may have more behind it; a short one ended with the oldest
+            return Ok((transactions, cursor.filter(|_| stopped || full)));
+        }
+        if !full {
+            return Ok((transactions, None));
+        }
+    }
+}
+
+/// One page of Esplora's transaction list at `path`
+async fn esplora_page(api_base: &str, path: &str) -> Result<Vec<EsploraTx>> {
+    let url = format!("{}{}", api_base, path);
+    let response = http::send(http::client().get(&url).header("User-Agent", "wallet-balance-cli/0.1.0"))
+        .await
+        .with_context(|| format!("Failed to send request to Esplora API {}", http::loggable(api_base)))?;
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("API failed: {} - {}", response.status, response.text()));
+    }
+    response.json().context("Failed to parse transactions from the Esplora API")
+}
+
+/// Outputs paid to `address` minus the inputs it spent, in whole coins
+fn esplora_change(tx: &EsploraTx, address: &str, decimals: u32) -> String {
+    let (spent, received) = esplora_flows(tx, address);
+    units::format_delta(U256::from(spent), U256::from(received), decimals)
+}
+
+/// Satoshis of `address` a transaction spent, and paid to it
+fn esplora_flows(tx: &EsploraTx, address: &str) -> (u64, u64) {
+    let is_ours = |output: &EsploraOutput| output.scriptpubkey_address.as_deref() == Some(address);
+    let received: u64 = tx.vout.iter().filter(|output| is_ours(output)).map(|output| output.value).sum();
+    let spent: u64 = tx
+        .vin
+        .iter()
+        .filter_map(|input| input.prevout.as_ref())
+        .filter(|output| is_ours(output))
+        .map(|output| output.value)
+        .sum();
+    (spent, received)
+}
+
+/// Times the Bitcoin balance at a height is recomputed when a block lands mid-read
+const AT_HEIGHT_ATTEMPTS: usize = 3;
+
+/// Confirmed balance of `address` on a Bitcoin network as of block `height` (`--at-height`)
+///
+/// Esplora has no balance-at-height endpoint, so the balance is worked back
+/// from the current one: the net change of every transaction mined above
+/// `height` is undone, newest first. The cost grows with the activity since
+/// `height`. If the address's confirmed transaction count moves during the
+/// walk, it starts over. `block` in the result is the block at `height`.
+pub async fn get_balance_at_height(network: Network, address: &str, height: u64) -> Result<WalletBalance, WalletError> {
+    let chain = network
+        .bitcoin_chain()
+        .ok_or_else(|| anyhow::anyhow!("--at-height is only supported on Bitcoin networks, not {}", network))?;
+    let api_base = chain.api_base();
+    let address = crate::normalize_address(network, address.trim())?;
+    let hash = esplora_block_hash(&api_base, height).await?;
+
+    for _ in 0..AT_HEIGHT_ATTEMPTS {
+        let (current, before) = bitcoin_wallet::get_usage_from(&api_base, &address).await?;
+        let undone = esplora_change_above(&api_base, &address, height).await?;
+        let (_, after) = bitcoin_wallet::get_usage_from(&api_base, &address).await?;
+        if after.confirmed_txs != before.confirmed_txs {
+            continue;
+        }
+        let sats = u64::try_from(i128::from(current) - undone)
+            .map_err(|_| anyhow::anyhow!("Esplora reported inconsistent transactions for {}", address))?;
+        let mut balance = WalletBalance::new(
+            address,
+            format!("{:.8}", sats as f64 / 100_000_000.0),
+            network.to_string(),
+            network.denomination().to_string(),
+        );
+        balance.block = Some(BlockRef { number: height, hash });
+        return Ok(balance);
+    }
+    Err(WalletError::Other(format!("{} kept receiving transactions; try again", address)))
+}
+
+/// Net satoshis `address` gained in transactions mined above `height`
+async fn esplora_change_above(api_base: &str, address: &str, height: u64) -> Result<i128> {
+    let mut change = 0i128;
+    let mut cursor: Option<String> = None;
+    loop {
+        let path = match &cursor {
+            Some(txid) => format!("/address/{}/txs/chain/{}", address, txid),
+            None => format!("/address/{}/txs/chain", address),
+        };
+        let page = esplora_page(api_base, &path).await?;
+        let full = page.len() >= ESPLORA_PAGE;
+        for tx in page {
+            match tx.status.block_height {
+                Some(block) if block > height => {
+                    let (spent, received) = esplora_flows(&tx, address);
+                    change += i128::from(received) - i128::from(spent);
+                }
+                _ => return Ok(change),
+            }
+            cursor = Some(tx.txid);
+        }
+        if !full {
+            return Ok(change);
+        }
+    }
+}
+
+/// Hash of the block at `height` (Esplora's `/block-height/:height`)
+async fn esplora_block_hash(api_base: &str, height: u64) -> Result<String> {
+    let url = format!("{}/block-height/{}", api_base, height);
+    let response = http::send(http::client().get(&url).header("User-Agent", "wallet-balance-cli/0.1.0"))
+        .await
+        .with_context(|| format!("Failed to send request to Esplora API {}", http::loggable(api_base)))?;
+    if response.status == reqwest::StatusCode::NOT_FOUND {
+        return Err(anyhow::anyhow!("Block {} does not exist yet", height));
+    }
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("API failed: {} - {}", response.status, response.text()));
+    }
+    Ok(response.text().trim().to_string())
+}
+
+#[derive(Debug, Deserialize)]
+struct EtherscanResponse {
+    status: String,
+    message: String,
+    result: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EtherscanTx {
+    hash: String,
+    block_number: String,
+    time_stamp: String,
+    from: String,
+    to: String,
+    value: String,
+    #[serde(default)]
+    is_error: String,
+    gas_used: String,
+    gas_price: String,
+}
+
+/// One `txlist` page of the Etherscan V2 API; the cursor is the next page number
+async fn etherscan_history(
+    api: &str,
+    api_key: &str,
+    chain_id: u64,
+    network: Network,
+    address: &str,
+    limit: usize,
+    before: Option<&str>,
+) -> Result<(Vec<Transaction>, Option<String>)> {
+    let page: u64 = match before {
+        Some(cursor) => cursor.parse().with_context(|| format!("Invalid history cursor: {}", cursor))?,
+        None => 1,
+    };
+    let query = [
+        ("chainid", chain_id.to_string()),
+        ("module", "account".to_string()),
+        ("action", "txlist".to_string()),
+        ("address", address.to_string()),
+        ("startblock", "0".to_string()),
+        ("endblock", "99999999".to_string()),
+        ("page", page.to_string()),
+        ("offset", limit.to_string()),
+        ("sort", "desc".to_string()),
+        ("apikey", api_key.to_string()),
+    ];
+    let response = http::send(http::client().get(api).query(&query))
+        .await
+        .context("Failed to send request to the Etherscan API")?;
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("Etherscan API failed: {}", response.status));
+    }
+    let data: EtherscanResponse = response.json().context("Failed to parse JSON from Etherscan")?;
+    // An address without transactions is status 0 with an empty list, not an error
+    if data.status != "1" && !data.result.as_array().is_some_and(Vec::is_empty) {
+        return Err(anyhow::anyhow!("Etherscan API error: {} ({})", data.message, data.result));
+    }
+    let listed: Vec<EtherscanTx> = serde_json::from_value(data.result).context("Failed to parse transactions from Etherscan")?;
+
+    let number = |field: &str, value: &str| -> Result<u128> {
+        value.parse().with_context(|| format!("Invalid {} from Etherscan: {}", field, value))
+    };
+    let mut transactions = Vec::with_capacity(listed.len());
+    for tx in &listed {
+        // A reverted transaction moved no value but still paid for its gas
+        let value = match tx.is_error.as_str() {
+            "1" => 0,
+            _ => number("value", &tx.value)?,
+        };
+        let fee = number("gasUsed", &tx.gas_used)?.saturating_mul(number("gasPrice", &tx.gas_price)?);
+        let (mut received, mut spent) = (0u128, 0u128);
+        if tx.to.eq_ignore_ascii_case(address) {
+            received = value;
+        }
+        if tx.from.eq_ignore_ascii_case(address) {
+            spent = value.saturating_add(fee);
+        }
+        transactions.push(Transaction {
+            hash: tx.hash.clone(),
+            block: Some(number("blockNumber", &tx.block_number)? as u64),
+            timestamp: Some(number("timeStamp", &tx.time_stamp)? as u64),
+            change: units::format_delta(U256::from(spent), U256::from(received), network.decimals()),
+            denomination: network.denomination().to_string(),
+        });
+    }
+    let next = (listed.len() == limit).then(|| (page + 1).to_string());
+    Ok((transactions, next))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransferLog {
+    address: String,
+    topics: Vec<String>,
+    data: String,
+    block_number: String,
+    transaction_hash: String,
+    log_index: String,
+}
+
+/// `keccak256("Transfer(address,address,uint256)")`
+const TRANSFER_TOPIC: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+/// ERC-20 `Transfer` logs touching `address` in the `LOG_WINDOW` blocks below the cursor
+///
+/// The cursor is the block the scan stopped below, so the next page starts there.
+async fn log_history(
+    rpc_url: &str,
+    address: &str,
+    limit: usize,
+    before: Option<&str>,
+) -> Result<(Vec<Transaction>, Option<String>)> {
+    let end = match before {
+        Some(cursor) => cursor
+            .parse::<u64>()
+            .with_context(|| format!("Invalid history cursor: {}", cursor))?
+            .saturating_sub(1),
+        None => evm_rpc::block_number(rpc_url).await?,
+    };
+    let start = end.saturating_sub(LOG_WINDOW - 1);
+    let topic = format!("0x{:0>64}", address.trim_start_matches("0x").to_lowercase());
+    let filter = |topics: serde_json::Value| {
+        json!({
+            "fromBlock": format!("0x{:x}", start),
+            "toBlock": format!("0x{:x}", end),
+            "topics": topics,
+        })
+    };
+    let (sent, received) = tokio::try_join!(
+        evm_rpc::call(rpc_url, "eth_getLogs", vec![filter(json!([TRANSFER_TOPIC, topic]))]),
+        evm_rpc::call(rpc_url, "eth_getLogs", vec![filter(json!([TRANSFER_TOPIC, null, topic]))]),
+    )?;
+    let mut logs: Vec<TransferLog> = serde_json::from_value(sent).context("Failed to parse Transfer logs")?;
+    logs.extend(serde_json::from_value::<Vec<TransferLog>>(received).context("Failed to parse Transfer logs")?);
+
+    let mut entries = Vec::with_capacity(logs.len());
+    for log in logs {
+        // ERC-721 transfers share the signature but index the token id; they carry no amount
+        if log.topics.len() != 3 {
+            continue;
+        }
+        let block = evm_rpc::parse_quantity(&json!(log.block_number))? as u64;
+        let index = evm_rpc::parse_quantity(&json!(log.log_index))? as u64;
+        entries.push((block, index, log));
+    }
+    // A transfer to oneself is in both lists
+    entries.sort_by_key(|&(block, index, _)| std::cmp::Reverse((block, index)));
+    entries.dedup_by(|a, b| (a.0, a.1) == (b.0, b.1));
+
+    // Finish the block the limit falls in, so the block cursor loses nothing
+    let mut cut = entries.len().min(limit);
+    while cut > 0 && cut < entries.len() && entries[cut].0 == entries[cut - 1].0 {
+        cut += 1;
+    }
+    let truncated = cut < entries.len();
+    entries.truncate(cut);
+
+    let mut tokens: HashMap<String, crate::TokenInfo> = HashMap::new();
+    let mut transactions = Vec::with_capacity(entries.len());
+    for (block, _, log) in entries {
+        let contract = log.address.to_lowercase();
+        if !tokens.contains_key(&contract) {
+            let info = evm_rpc::erc20_token(rpc_url, &contract).await?;
+            tokens.insert(contract.clone(), info);
+        }
+        let token = &tokens[&contract];
+        let amount = evm_rpc::parse_quantity_u256(&json!(log.data))?;
+        let from_self = log.topics[1].eq_ignore_ascii_case(&topic);
+        let to_self = log.topics[2].eq_ignore_ascii_case(&topic);
+        let (spent, received) = match (from_self, to_self) {
+            (true, true) => (amount, amount),
+            (true, false) => (amount, U256::ZERO),
+            _ => (U256::ZERO, amount),
+        };
+        transactions.push(Transaction {
+            hash: log.transaction_hash,
+            block: Some(block),
+            timestamp: None,
+            change: units::format_delta(spent, received, token.decimals),
+            denomination: token.symbol.clone(),
+        });
+    }
+
+    let next = match (truncated, transactions.last()) {
+        (true, Some(last)) => last.block.map(|block| block.to_string()),
+        _ => (start > 0).then(|| start.to_string()),
+    };
+    Ok((transactions, next))
+}
@@ -0,0 +1,428 @@
+//! Shared HTTP transport
+//!
+//! Every wallet module builds its requests on the shared `client()`, so
+//! connections to a provider are pooled and reused across lookups, and sends
+//! them through `send`, which reads the full response body and records
+//! per-provider accounting (requests, bytes, latency) for the session report
+//! shown with `-v`. `send` also applies the shared `RetryPolicy`: connection
+//! failures, rate limits (429) and gateway errors (502-504) are retried with
+//! exponential backoff and jitter. Each attempt is cut off after the
+//! `--timeout`, so an unresponsive endpoint fails with a timeout error instead
+//! of hanging the command.
+//!
+//! Every attempt is logged at `info` level with its method, URL, status and
+//! latency, and every retry with its delay, for `-v`. Query strings, user
+//! names and passwords are left out of the logged URL, since API keys often
+//! travel in them. Each `send` runs in an `http_request` span carrying the
+//! provider, method, URL, final status and number of retries, which the
+//! `telemetry` module exports.
+
+use anyhow::{Context, Result};
+use reqwest::{RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+use crate::WalletError;
+
+/// Accounting for one provider (API host) over the current session
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProviderStats {
+    pub requests: u64,
+    pub errors: u64,
+    pub retries: u64,
+    pub cache_hits: u64,
+    /// Response body bytes received
+    pub bytes: u64,
+    /// Sum of request latencies, including body download
+    pub total_latency: Duration,
+}
+
+static SESSION_STATS: Mutex<BTreeMap<String, ProviderStats>> = Mutex::new(BTreeMap::new());
+
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// The HTTP client shared by every module; cheap to call, created on first use
+pub fn client() -> &'static reqwest::Client {
+    CLIENT.get_or_init(reqwest::Client::new)
+}
+
+/// How `send` retries failed requests (`--retries`, `--retry-delay`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each one after it
+    pub base_delay: Duration,
+    /// Add up to half the delay at random, so concurrent lookups do not retry in lockstep
+    pub jitter: bool,
+}
+
+/// Longest wait honoured from a `Retry-After` header
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+impl RetryPolicy {
+    pub const DEFAULT: RetryPolicy = RetryPolicy {
+        retries: 3,
+        base_delay: Duration::from_secs(1),
+        jitter: true,
+    };
+
+    /// Delay before retry `attempt` (1-based): `base_delay`, then twice that, and so on
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self.base_delay * (1 << attempt.saturating_sub(1).min(5));
+        match self.jitter {
+            true => delay + delay.mul_f64(random_fraction() / 2.0),
+            false => delay,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::DEFAULT
+    }
+}
+
+static RETRY_POLICY: RwLock<RetryPolicy> = RwLock::new(RetryPolicy::DEFAULT);
+
+/// Install the retry policy `send` applies to every request
+pub fn set_retry_policy(policy: RetryPolicy) {
+    *RETRY_POLICY.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = policy;
+}
+
+/// The installed retry policy
+pub fn retry_policy() -> RetryPolicy {
+    *RETRY_POLICY.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Default cut-off for one attempt of a request
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Per-attempt timeout in milliseconds; 0 means none
+static TIMEOUT_MS: AtomicU64 = AtomicU64::new(DEFAULT_TIMEOUT.as_millis() as u64);
+
+/// Set how long `send` waits for one attempt of a request; `Duration::ZERO` waits indefinitely
+pub fn set_timeout(timeout: Duration) {
+    TIMEOUT_MS.store(timeout.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// The per-attempt timeout, or `None` when disabled
+pub fn timeout() -> Option<Duration> {
+    match TIMEOUT_MS.load(Ordering::Relaxed) {
+        0 => None,
+        ms => Some(Duration::from_millis(ms)),
+    }
+}
+
+/// Parse a duration such as `500ms`, `2s` or a bare number of seconds (`--retry-delay`, `--timeout`)
+pub fn parse_duration(s: &str) -> Result<Duration, WalletError> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("Invalid duration: {} (expected e.g. 500ms or 2s)", s))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(number)),
+        "" | "s" => Ok(Duration::from_secs(number)),
+        _ => Err(WalletError::ParseError(format!("Invalid duration unit: {} (expected ms or s)", unit))),
+    }
+}
+
+/// Uniform-ish value in `[0, 1)` from the std hasher's random keys; good enough for jitter
+pub(crate) fn random_fraction() -> f64 {
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Whether a response with `status` is worth retrying
+fn retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// A fully buffered HTTP response
+#[derive(Debug)]
+pub(crate) struct HttpResponse {
+    pub status: StatusCode,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Deserialize the body as JSON
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_slice(&self.body).context("Response body is not valid JSON")
+    }
+
+    /// Body as (lossy) UTF-8 text, for error messages
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Why an attempt produced no response
+#[derive(Debug)]
+enum AttemptError {
+    Transport(reqwest::Error),
+    /// A timeout injected by `chaos`
+    #[cfg(feature = "chaos")]
+    InjectedTimeout,
+}
+
+impl AttemptError {
+    fn is_timeout(&self) -> bool {
+        match self {
+            AttemptError::Transport(e) => e.is_timeout(),
+            #[cfg(feature = "chaos")]
+            AttemptError::InjectedTimeout => true,
+        }
+    }
+
+    /// Whether the `RetryPolicy` covers this failure: no connection, a timeout or a request that could not be sent
+    fn is_retryable(&self) -> bool {
+        match self {
+            AttemptError::Transport(e) => e.is_connect() || e.is_timeout() || e.is_request(),
+            #[cfg(feature = "chaos")]
+            AttemptError::InjectedTimeout => true,
+        }
+    }
+}
+
+/// Kind of failure only, for the request log: reqwest's own message repeats the URL with its query
+impl std::fmt::Display for AttemptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttemptError::Transport(e) if e.is_timeout() => write!(f, "timed out"),
+            AttemptError::Transport(e) if e.is_connect() => write!(f, "connection failed"),
+            AttemptError::Transport(e) if e.is_body() || e.is_decode() => write!(f, "response body failed"),
+            AttemptError::Transport(_) => write!(f, "request failed"),
+            #[cfg(feature = "chaos")]
+            AttemptError::InjectedTimeout => write!(f, "timed out (injected)"),
+        }
+    }
+}
+
+/// The fault the installed `chaos::Chaos` picked for the next attempt, if any
+#[cfg(feature = "chaos")]
+fn injected_fault() -> Option<crate::chaos::Fault> {
+    crate::chaos::roll()
+}
+
+#[cfg(not(feature = "chaos"))]
+fn injected_fault() -> Option<std::convert::Infallible> {
+    None
+}
+
+/// What an attempt replaced by `fault` returns; an injected timeout takes the full per-attempt `timeout`
+#[cfg(feature = "chaos")]
+async fn fault_outcome(
+    fault: crate::chaos::Fault,
+    timeout: Option<Duration>,
+) -> std::result::Result<(HttpResponse, Option<Duration>), AttemptError> {
+    use crate::chaos::{Fault, MALFORMED_BODY};
+
+    let response = |status, body: &[u8]| Ok((HttpResponse { status, body: body.to_vec() }, None));
+    match fault {
+        Fault::RateLimited => response(StatusCode::TOO_MANY_REQUESTS, b"Too Many Requests (chaos)"),
+        Fault::Malformed => response(StatusCode::OK, MALFORMED_BODY),
+        Fault::Timeout => {
+            tokio::time::sleep(timeout.unwrap_or_default()).await;
+            Err(AttemptError::InjectedTimeout)
+        }
+    }
+}
+
+#[cfg(not(feature = "chaos"))]
+async fn fault_outcome(
+    fault: std::convert::Infallible,
+    _timeout: Option<Duration>,
+) -> std::result::Result<(HttpResponse, Option<Duration>), AttemptError> {
+    match fault {}
+}
+
+/// Send a request, buffer its body and record it against the target host
+///
+/// Failures the `RetryPolicy` covers are retried; each attempt counts as a
+/// request and each retry as a retry in the accounting. A `Retry-After`
+/// header (in seconds, up to a minute) lengthens the wait before the next one.
+/// An attempt that outlasts `timeout()` fails with a "timed out" error.
+/// With the `chaos` feature, an installed `chaos::Chaos` may replace an
+/// attempt with an injected fault.
+pub(crate) async fn send(request: RequestBuilder) -> Result<HttpResponse> {
+    let (client, request) = request.build_split();
+    let request = request.context("Failed to build HTTP request")?;
+    let provider = request.url().host_str().unwrap_or("unknown").to_string();
+    let method = request.method().clone();
+    let endpoint = loggable_url(request.url());
+    let span = tracing::info_span!(
+        "http_request",
+        %provider,
+        %method,
+        url = %endpoint,
+        status = tracing::field::Empty,
+        retries = 0
+    );
+    send_with_retries(client, request, provider, method, endpoint).instrument(span).await
+}
+
+/// The attempts of one `send`, inside its `http_request` span
+async fn send_with_retries(
+    client: reqwest::Client,
+    mut request: reqwest::Request,
+    provider: String,
+    method: reqwest::Method,
+    endpoint: String,
+) -> Result<HttpResponse> {
+    let span = tracing::Span::current();
+    let policy = retry_policy();
+    let timeout = timeout();
+    if request.timeout().is_none() {
+        *request.timeout_mut() = timeout;
+    }
+
+    let mut attempt = 0;
+    let mut pending = Some(request);
+    loop {
+        let request = pending.take().expect("a request is pending for every attempt");
+        // Streaming bodies cannot be replayed; such a request gets one attempt
+        let retry = match attempt < policy.retries {
+            true => request.try_clone(),
+            false => None,
+        };
+
+        let started = Instant::now();
+        let result = match injected_fault() {
+            Some(fault) => fault_outcome(fault, timeout).await,
+            None => async {
+                let response = client.execute(request).await?;
+                let status = response.status();
+                let retry_after = response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok()?.trim().parse::<u64>().ok())
+                    .map(Duration::from_secs);
+                let body = response.bytes().await?;
+                Ok((
+                    HttpResponse {
+                        status,
+                        body: body.to_vec(),
+                    },
+                    retry_after,
+                ))
+            }
+            .await
+            .map_err(AttemptError::Transport),
+        };
+
+        let elapsed = started.elapsed();
+        let elapsed_ms = elapsed.as_millis() as u64;
+        match &result {
+            Ok((response, _)) => {
+                let status = response.status.as_u16();
+                span.record("status", status);
+                tracing::info!(%method, url = %endpoint, status, elapsed_ms, attempt = attempt + 1, "request");
+            }
+            Err(e) => {
+                let attempt = attempt + 1;
+                tracing::info!(%method, url = %endpoint, error = %e, elapsed_ms, attempt, "request failed")
+            }
+        }
+        with_stats(&provider, |stats| {
+            stats.requests += 1;
+            stats.total_latency += elapsed;
+            match &result {
+                Ok((response, _)) => stats.bytes += response.body.len() as u64,
+                Err(_) => stats.errors += 1,
+            }
+        });
+
+        let finish = |result: std::result::Result<(HttpResponse, _), AttemptError>| match result {
+            Ok((response, _)) if response.status == StatusCode::TOO_MANY_REQUESTS => Err(WalletError::RateLimited {
+                provider: provider.clone(),
+            }
+            .into()),
+            Ok((response, _)) => Ok(response),
+            Err(e) if e.is_timeout() => Err(WalletError::NetworkUnreachable(format!(
+                "Request to {} timed out after {:?} (see --timeout)",
+                provider,
+                timeout.unwrap_or_default()
+            ))
+            .into()),
+            Err(AttemptError::Transport(e)) => Err(e.into()),
+            #[cfg(feature = "chaos")]
+            Err(AttemptError::InjectedTimeout) => unreachable!("an injected timeout is a timeout"),
+        };
+        let wait = match &result {
+            Ok((response, retry_after)) if retryable_status(response.status) => {
+                retry_after.map(|after| after.min(MAX_RETRY_AFTER))
+            }
+            Ok(_) => return finish(result),
+            Err(e) if e.is_retryable() => None,
+            Err(_) => return finish(result),
+        };
+        let Some(next) = retry else {
+            return finish(result);
+        };
+
+        attempt += 1;
+        span.record("retries", attempt);
+        with_stats(&provider, |stats| stats.retries += 1);
+        let delay = policy.backoff(attempt).max(wait.unwrap_or_default());
+        let delay_ms = delay.as_millis() as u64;
+        tracing::info!(url = %endpoint, retry = attempt, of = policy.retries, delay_ms, "retrying");
+        tokio::time::sleep(delay).await;
+        pending = Some(next);
+    }
+}
+
+/// `url` as `loggable_url` shows it, or as given when it does not parse
+pub(crate) fn loggable(url: &str) -> String {
+    reqwest::Url::parse(url).map_or_else(|_| url.to_string(), |url| loggable_url(&url))
+}
+
+/// `url` without its query, fragment or credentials
+fn loggable_url(url: &reqwest::Url) -> String {
+    let mut url = url.clone();
+    url.set_query(None);
+    url.set_fragment(None);
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url.to_string()
+}
+
+/// Delay before retry `attempt` (1-based) under the default policy, without jitter: 1s, 2s, 4s, ...
+pub fn rate_limit_backoff(attempt: u32) -> Duration {
+    RetryPolicy {
+        jitter: false,
+        ..RetryPolicy::DEFAULT
+    }
+    .backoff(attempt)
+}
+
+/// Snapshot of the accounting recorded so far, keyed by provider host
+pub fn session_stats() -> BTreeMap<String, ProviderStats> {
+    SESSION_STATS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+/// Count a lookup answered by the cache `cache` instead of a provider
+pub(crate) fn record_cache_hit(cache: &str) {
+    with_stats(cache, |stats| stats.cache_hits += 1);
+}
+
+fn with_stats(provider: &str, update: impl FnOnce(&mut ProviderStats)) {
+    let mut stats = SESSION_STATS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    update(stats.entry(provider.to_string()).or_default());
+}
@@ -0,0 +1,462 @@
+//! Message catalog for user-facing CLI text
+//!
+//! Every message the CLI prints is looked up here by key, so translations live
+//! in one table instead of being scattered through `main.rs`. Templates use
+//! `{}` placeholders filled in order. Decorations (emoji, rules) are added by
+//! the caller and are not part of the catalog.
+//!
+//! Details that come from the library (provider errors, parse errors) are
+//! appended untranslated, so a ticket always carries the original wording.
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::WalletError;
+
+/// Supported output languages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+    #[default]
+    En,
+    Es,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::En, Lang::Es];
+}
+
+impl std::str::FromStr for Lang {
+    type Err = WalletError;
+
+    /// Accepts `en`, `es` and locale forms such as `es_ES.UTF-8` or `en-GB`
+    fn from_str(s: &str) -> Result<Self, WalletError> {
+        let language = s.split(['_', '-', '.']).next().unwrap_or_default();
+        match language.to_lowercase().as_str() {
+            "en" => Ok(Lang::En),
+            "es" => Ok(Lang::Es),
+            _ => Err(WalletError::ParseError(format!("Unsupported language: {} (expected en or es)", s))),
+        }
+    }
+}
+
+macro_rules! catalog {
+    ($($key:ident => $en:literal, $es:literal;)*) => {
+        /// Catalog keys
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Msg {
+            $($key,)*
+        }
+
+        impl Msg {
+            /// Every key, for catalog consistency checks
+            pub const ALL: &'static [Msg] = &[$(Msg::$key,)*];
+
+            /// Raw template for `lang`
+            pub fn template(self, lang: Lang) -> &'static str {
+                match (self, lang) {
+                    $(
+                        (Msg::$key, Lang::En) => $en,
+                        (Msg::$key, Lang::Es) => $es,
+                    )*
+                }
+            }
+        }
+    };
+}
+
+catalog! {
+    // Generic
+    Error => "Error", "Error";
+    Success => "Success!", "¡Éxito!";
+    Warning => "Warning", "Aviso";
+    SupportedNetworks => "Supported networks: {}", "Redes admitidas: {}";
+    Stripped => "Stripped {} from address input", "Se eliminó {} de la dirección introducida";
+    ExchangeAddress => "{} is a shared {} wallet: its balance is the exchange's, not what your {} account holds",
+        "{} es un monedero compartido de {}: su saldo es del exchange, no lo que tiene tu cuenta de {}";
+    BurnAddress => "{} is a {}: nothing sent to it can be spent, so it is left out of totals and attestations",
+        "{} es una {}: lo que se envía ahí no se puede gastar, así que queda fuera de los totales y las atestaciones";
+    VanityAddress => "{} is a {}: keys from weak vanity generators such as Profanity have been recovered by attackers",
+        "{} es una {}: atacantes han recuperado claves de generadores de direcciones personalizadas débiles como Profanity";
+    LookalikeAddress => "{} is NOT your address book entry \"{}\" ({}): it only matches at the start and end, the pattern of an address poisoning scam. Compare every character before sending",
+        "{} NO es la entrada \"{}\" de tu libreta de direcciones ({}): solo coincide al principio y al final, el patrón de una estafa de envenenamiento de direcciones. Compara cada carácter antes de enviar";
+    ShuttingDown => "Shutting down...", "Deteniendo...";
+
+    // Balance output labels
+    LabelNetwork => "Network", "Red";
+    LabelAddress => "Address", "Dirección";
+    LabelBalance => "Balance", "Saldo";
+    LabelBlock => "Block", "Bloque";
+    LabelHeight => "Height", "Altura";
+    LabelFees => "Fees", "Comisiones";
+    LabelMempool => "Mempool", "Mempool";
+    LabelValue => "Value", "Valor";
+    LabelTotalSupply => "Total supply", "Suministro total";
+    LabelSupplyShare => "Share of supply", "Parte del suministro";
+    LabelChange => "Change", "Cambio";
+    LabelPending => "Pending", "Pendiente";
+    LabelProjected => "Projected", "Proyectado";
+    LabelUnconfirmed => "Unconfirmed", "Sin confirmar";
+    LabelSource => "Source", "Fuente";
+    LabelNeeds => "Needs", "Necesita";
+    LabelFee => "Est. fee", "Comisión est.";
+    LabelRequested => "Requested", "Solicitado";
+    LabelContract => "Contract", "Contrato";
+    LabelInscriptions => "Inscriptions", "Inscripciones";
+    LabelSpendable => "Spendable", "Gastable";
+    LabelInscribed => "Inscribed", "Inscrito";
+    LabelDust => "Dust", "Polvo";
+    LabelUtxos => "UTXOs", "UTXO";
+    LabelDeposit => "Deposit", "Depósito";
+    LabelStatus => "Status", "Estado";
+    LabelStart => "Start", "Inicio";
+    LabelEnd => "End", "Fin";
+    LabelChanges => "Changes", "Cambios";
+    LabelLargest => "Largest", "Mayor";
+    LabelNet => "Net", "Neto";
+    LabelL2Tx => "L2 transaction", "Transacción L2";
+
+    // Balance lookups
+    Fetching => "Fetching {} balance for address: {}", "Consultando saldo de {} para la dirección: {}";
+    FetchingPaymentRequest => "Fetching {} balance for payment request to: {}",
+        "Consultando saldo de {} para la solicitud de pago a: {}";
+    DevMode => "Dev mode: querying local node at {}", "Modo desarrollo: consultando el nodo local en {}";
+    FetchError => "Error fetching balance: {}", "Error al consultar el saldo: {}";
+    PleaseCheck => "Please check:", "Compruebe:";
+    CheckAddress => "Address format is correct", "Que el formato de la dirección sea correcto";
+    CheckNetwork => "Network is spelled correctly", "Que el nombre de la red esté bien escrito";
+    CheckConnectivity => "You have internet connectivity", "Que tenga conexión a internet";
+
+    // Watch mode
+    Watching => "Watching every {} (Ctrl-C to stop)", "Vigilando cada {} (Ctrl-C para detener)";
+    WatchChanged => "changed {} {}", "cambió {} {}";
+    WatchUnchanged => "unchanged", "sin cambios";
+    WatchPollFailed => "Poll failed, retrying at the next interval: {}",
+        "La consulta falló, se reintentará en el siguiente intervalo: {}";
+    WatchSummary => "Watch session summary ({} polls)", "Resumen de la sesión de vigilancia ({} consultas)";
+    WatchNoPolls => "Watch session ended before any poll succeeded",
+        "La sesión de vigilancia terminó sin ninguna consulta correcta";
+
+    // Batch lookups
+    FetchingBatch => "Fetching {} balances", "Consultando {} saldos";
+    Total => "Total {}:", "Total {}:";
+    GrandTotal => "Grand total:", "Total general:";
+    NoTarget => "no target", "sin objetivo";
+    OnTarget => "on target", "en el objetivo";
+    TradeBuy => "buy {}", "comprar {}";
+    TradeSell => "sell {}", "vender {}";
+    PortfolioValue => "Portfolio value:", "Valor de la cartera:";
+    BatchFailures => "{} of {} lookups failed", "Fallaron {} de {} consultas";
+    SkippedRow => "Skipped: not configured", "Omitida: no configurada";
+    SkippedTitle => "Skipped networks (not configured):", "Redes omitidas (no configuradas):";
+    SkippedNetwork => "{} needs {}", "{} necesita {}";
+    SlaTimedOut => "{} of {} lookups did not finish within the {} SLA", "{} de {} consultas no terminaron dentro del SLA de {}";
+    SlaExceeded => "the lookup did not finish within the {} SLA", "la consulta no terminó dentro del SLA de {}";
+    BatchConflict => "{} cannot be combined with several addresses",
+        "{} no se puede combinar con varias direcciones";
+    BatchTokenUri => "token payment URIs cannot be checked in a batch: {}",
+        "las URI de pago de tokens no se pueden consultar en lote: {}";
+
+    // Argument errors
+    VerifyNeedsLive => "--verify needs the live provider", "--verify requiere el proveedor live";
+    InvalidPaymentUri => "invalid payment URI: {}", "URI de pago no válida: {}";
+    NetworkRequired => "--network is required unless --address is a payment URI or default_network is configured",
+        "--network es obligatorio salvo que --address sea una URI de pago o se configure default_network";
+    AddressRequired => "--address is required unless default_address is configured",
+        "--address es obligatorio salvo que se configure default_address";
+    UriNetworkMismatch => "payment URI is for {} but --network is {}",
+        "la URI de pago es para {} pero --network es {}";
+    TokenIdWithUri => "--token-contract cannot be combined with a payment URI",
+        "--token-contract no se puede combinar con un URI de pago";
+    SupplyShareNeedsToken => "--supply-share needs --token or --token-contract",
+        "--supply-share requiere --token o --token-contract";
+    SinceBlockNeedsToken => "--since-block needs --token or --token-contract",
+        "--since-block requiere --token o --token-contract";
+    ChangeSinceBlock => "{} since block {} (was {})", "{} desde el bloque {} (antes {})";
+    WatchWithUri => "--watch cannot be combined with a payment URI",
+        "--watch no se puede combinar con un URI de pago";
+    NeedsWithUri => "--needs cannot be combined with a payment URI (the URI amount is used)",
+        "--needs no se puede combinar con una URI de pago (se usa el importe de la URI)";
+    HistoricalWithUri => "{} cannot be combined with a payment URI",
+        "{} no se puede combinar con un URI de pago";
+    BlockUnsupported => "--block reads EVM balances; {} is not an EVM network (use --at-height on Bitcoin)",
+        "--block lee saldos EVM; {} no es una red EVM (use --at-height en Bitcoin)";
+    AtHeightUnsupported => "--at-height reads Bitcoin balances; {} is not a Bitcoin network (use --block on EVM networks)",
+        "--at-height lee saldos de Bitcoin; {} no es una red Bitcoin (use --block en redes EVM)";
+    AtHeightNeedsLive => "--at-height needs the live provider", "--at-height necesita el proveedor live";
+    ConfirmationsTokens => "--confirmations is not supported for token balances",
+        "--confirmations no está disponible para saldos de tokens";
+    OutputConflict => "{} cannot be combined with --output {}", "{} no se puede combinar con --output {}";
+    InvalidNeeds => "invalid --needs amount: {}", "importe de --needs no válido: {}";
+
+    // Verification
+    VerifyFailed => "could not verify balance: {}", "no se pudo verificar el saldo: {}";
+    AtBlock => "at block {}", "en el bloque {}";
+    AtLatestBlocks => "at each provider's latest block", "en el último bloque de cada proveedor";
+    Verified => "Verified: {} and {} agree {}", "Verificado: {} y {} coinciden {}";
+    Divergence => "DIVERGENCE {}:", "DISCREPANCIA {}:";
+
+    // Pending transactions
+    PendingFailed => "Could not simulate pending transactions: {}",
+        "No se pudieron simular las transacciones pendientes: {}";
+    PendingTransactions => "{} transaction(s) touching this address", "{} transacción(es) que afectan a esta dirección";
+    PendingBalanceUnreadable => "Could not interpret balance: {}", "No se pudo interpretar el saldo: {}";
+    ProjectedBalance => "{} (confirmed {})", "{} (confirmado {})";
+    BalanceUnreadable => "could not interpret balance: {}", "no se pudo interpretar el saldo: {}";
+
+    // --needs and payment requests
+    FeeEstimateFailed => "could not estimate fees: {}", "no se pudieron estimar las comisiones: {}";
+    Covered => "Balance covers the required amount", "El saldo cubre el importe necesario";
+    Insufficient => "Insufficient balance: short by {}", "Saldo insuficiente: faltan {}";
+    RequestedHeld => "Wallet already holds the requested amount", "La cartera ya tiene el importe solicitado";
+    RequestedShort => "Wallet holds less than the requested amount", "La cartera tiene menos del importe solicitado";
+    RequestedCompareFailed => "Could not compare balance with requested amount: {}",
+        "No se pudo comparar el saldo con el importe solicitado: {}";
+
+    // Attestation
+    ProofValid => "Proof is valid: the wallet is included under root {}",
+        "Prueba válida: la cartera está incluida bajo la raíz {}";
+    FetchingSnapshot => "Reading the balances of {} holders in {} token(s) at block {}...", "Leyendo los saldos de {} titulares en {} token(s) en el bloque {}...";
+    SnapshotFailed => "Snapshot incomplete: {}", "Instantánea incompleta: {}";
+    ProofInvalid => "Proof does not match root {}", "La prueba no coincide con la raíz {}";
+
+    // Servers
+    JsonlFailed => "JSONL session failed: {}", "La sesión JSONL falló: {}";
+    ServingJsonl => "Serving JSONL queries on {}", "Atendiendo consultas JSONL en {}";
+    ServingHttp => "Serving HTTP on {}", "Atendiendo HTTP en {}";
+    ApiKeysRequired => "API keys accepted for lookups: {}", "Claves de API aceptadas para consultas: {}";
+    WatchingSubscriptions => "Balance-change webhooks: {} (polled every {}s)", "Webhooks de cambios de saldo: {} (consultados cada {} s)";
+    SubscriptionsUnavailable => "Cannot load the webhook subscriptions: {}", "No se pueden cargar las suscripciones de webhooks: {}";
+    ExportingSpans => "Exporting request spans to {}", "Exportando spans de peticiones a {}";
+    TelemetryNeedsOtel => "Not exporting spans to {}: this build lacks the otel feature",
+        "No se exportan spans a {}: esta compilación no incluye la característica otel";
+    ServerFailed => "Server failed: {}", "El servidor falló: {}";
+    ConfigReloaded => "Configuration reloaded", "Configuración recargada";
+    ConfigReloadFailed => "Configuration reload failed, keeping the previous settings: {}",
+        "No se pudo recargar la configuración, se mantiene la anterior: {}";
+    ListenFailed => "Failed to listen on {}: {}", "No se pudo escuchar en {}: {}";
+    UdsUnavailable => "Unix domain sockets are not available on this platform ({})",
+        "Los sockets de dominio Unix no están disponibles en esta plataforma ({})";
+
+    // Ordinals
+    FetchingOrdinals => "Fetching inscriptions and BRC-20 balances for address: {}",
+        "Consultando inscripciones y saldos BRC-20 de la dirección: {}";
+    OrdinalsNeedLive => "ordinals needs the live provider", "ordinals necesita el proveedor live";
+    InscribedIn => "{} in {} UTXO(s)", "{} en {} UTXO(s)";
+    DustIn => "{} in {} UTXO(s) of at most {} sats, not counted as spendable",
+        "{} en {} UTXO(s) de como máximo {} sats, no contados como gastables";
+    DustWarning => "These UTXOs look like a dusting attack: spending them with your other coins links your addresses",
+        "Estos UTXO parecen un ataque de polvo: gastarlos con sus otras monedas vincula sus direcciones";
+    InscribedWarning => "These UTXOs carry inscriptions: do not spend them as plain sats",
+        "Estos UTXO llevan inscripciones: no los gaste como sats normales";
+
+    // Pricing
+    PricingFailed => "Could not convert to fiat: {}", "No se pudo convertir a moneda fiduciaria: {}";
+
+    // Tokens
+    FetchingRunes => "Fetching rune balances for address: {}", "Consultando saldos de runas de la dirección: {}";
+    TokensNeedLive => "tokens needs the live provider", "tokens necesita el proveedor live";
+    TokensUnsupported => "tokens lists runes on bitcoin only; use --token-contract for {} tokens",
+        "tokens solo lista runas en bitcoin; use --token-contract para tokens de {}";
+    NoRunes => "No runes held", "No tiene runas";
+
+    // UTXO listing
+    FetchingUtxos => "Fetching unspent outputs for address: {}", "Consultando las salidas no gastadas de la dirección: {}";
+    UtxosNeedLive => "--utxos needs the live provider", "--utxos necesita el proveedor live";
+    UtxosUnsupported => "--utxos lists Bitcoin outputs; {} is not a Bitcoin network",
+        "--utxos lista salidas de Bitcoin; {} no es una red Bitcoin";
+    UtxoSummary => "{} unspent, {}", "{} sin gastar, {}";
+    NoUtxos => "No unspent outputs", "No hay salidas sin gastar";
+    IncludePendingNeedsLive => "--include-pending needs the live provider", "--include-pending necesita el proveedor live";
+    IncludePendingUnsupported => "--include-pending reads the Bitcoin mempool; {} is not a Bitcoin network",
+        "--include-pending lee el mempool de Bitcoin; {} no es una red Bitcoin";
+
+    // Transaction history
+    FetchingHistory => "Fetching {} transactions for address: {}", "Consultando las transacciones de {} de la dirección: {}";
+    HistoryNeedsLive => "history needs the live provider", "history necesita el proveedor live";
+    NoTransactions => "No transactions", "No hay transacciones";
+    Unconfirmed => "unconfirmed", "sin confirmar";
+    HistoryMore => "Older transactions: --before {}", "Transacciones anteriores: --before {}";
+
+    // Rollup deposits
+    FetchingDeposit => "Looking up L1 deposit: {}", "Consultando el depósito en L1: {}";
+    DepositNeedsLive => "deposit-status needs the live provider", "deposit-status necesita el proveedor live";
+    DepositCredited => "Credited on {} in block {}", "Acreditado en {} en el bloque {}";
+    DepositPending => "Not credited on {} yet", "Todavía no acreditado en {}";
+
+    // Unstoppable Domains
+    ResolvedName => "Resolved {} to {}", "{} se resolvió como {}";
+    ResolveFailed => "Could not resolve {}: {}", "No se pudo resolver {}: {}";
+
+    // Test networks
+    NoTestnet => "{} has no test network this tool can query", "{} no tiene una red de pruebas que esta herramienta pueda consultar";
+
+    // Custom EVM chains
+    CustomEvmNeedsRpcUrl => "--network custom-evm needs --rpc-url with the chain's JSON-RPC endpoint",
+        "--network custom-evm necesita --rpc-url con el endpoint JSON-RPC de la cadena";
+    RpcUrlNeedsCustomEvm => "--rpc-url only applies to --network custom-evm; set rpc.<network> in the config file for the built-in chains",
+        "--rpc-url solo se aplica a --network custom-evm; configure rpc.<red> en el archivo de configuración para las cadenas integradas";
+
+    // Output sinks
+    OutputToStreaming => "--output-to cannot be used with --watch, --jsonl or serve, which stream to stdout",
+        "--output-to no puede usarse con --watch, --jsonl ni serve, que escriben continuamente en stdout";
+    OutputDeliveryFailed => "Could not deliver the output to {}: {}", "No se pudo entregar la salida a {}: {}";
+    PseudonymizeProtocol => "--pseudonymize cannot be used with --jsonl or serve, whose replies must name the queried address",
+        "--pseudonymize no puede usarse con --jsonl ni serve, cuyas respuestas deben nombrar la dirección consultada";
+
+    // Cold storage audit
+    AuditTitle => "Cold storage audit: {} ({})", "Auditoría de almacenamiento en frío: {} ({})";
+    AuditAddress => "Address format", "Formato de dirección";
+    AuditChecksum => "Checksum", "Suma de verificación";
+    AuditType => "Address type", "Tipo de dirección";
+    AuditBalance => "Balance", "Saldo";
+    AuditActivity => "Activity", "Actividad";
+    AuditScreening => "Screening", "Revisión";
+    AuditCrossCheck => "Explorer cross-check", "Contraste con otro explorador";
+    AuditWarn => "WARN", "AVISO";
+    AuditFail => "FAIL", "FALLO";
+    AuditSkip => "SKIPPED", "OMITIDO";
+    AuditPassed => "Result: PASS", "Resultado: APROBADO";
+    AuditFailed => "Result: FAIL", "Resultado: FALLIDO";
+    AuditSummary => "({} checks, {} failed, {} warnings)", "({} comprobaciones, {} fallidas, {} avisos)";
+
+    // Balance assertions
+    AssertionsTitle => "Balance assertions: {}", "Aserciones de saldo: {}";
+    AssertionsSummary => "({} assertions, {} failed)", "({} aserciones, {} fallidas)";
+
+    // Address tools
+    DeriveFailed => "Error deriving addresses: {}", "Error al derivar direcciones: {}";
+    ScanningXpub => "Scanning extended key on {} (gap limit {})", "Explorando la clave extendida en {} (límite de huecos {})";
+    XpubScanned => "{} addresses scanned, {} used", "{} direcciones exploradas, {} usadas";
+    NoUsedAddresses => "No used addresses found", "No se encontraron direcciones usadas";
+    XpubNeedsLive => "xpub needs the live provider", "xpub necesita el proveedor live";
+    XpubUnsupported => "{} is not a Bitcoin network; xpub scans bitcoin, bitcoin-testnet4 or bitcoin-signet",
+        "{} no es una red Bitcoin; xpub explora bitcoin, bitcoin-testnet4 o bitcoin-signet";
+    ConvertFailed => "Error converting address: {}", "Error al convertir la dirección: {}";
+
+    // Config files
+    LabelConfig => "Config", "Configuración";
+    LabelData => "Data", "Datos";
+    LabelCache => "Cache", "Caché";
+    ConfigCreated => "Created default config at {}", "Se creó la configuración predeterminada en {}";
+    ConfigMigrated => "Moved legacy config {} to {}", "Se movió la configuración antigua {} a {}";
+    ConfigExists => "Config file already exists at {} (use --force to overwrite)",
+        "El archivo de configuración ya existe en {} (use --force para sobrescribirlo)";
+    ConfigFailed => "Error initializing config: {}", "Error al inicializar la configuración: {}";
+
+    // State bundles
+    NoPassphrase => "A passphrase is needed: set {} or pass --passphrase-file",
+        "Se necesita una frase de contraseña: defina {} o use --passphrase-file";
+    StateExported => "Exported {} files to {}", "Se exportaron {} archivos a {}";
+    StateImported => "Restored {} files from {}", "Se restauraron {} archivos desde {}";
+    StateExportFailed => "Error exporting the local state: {}", "Error al exportar el estado local: {}";
+    StateImportFailed => "Error importing the local state: {}", "Error al importar el estado local: {}";
+
+    // Usage statistics
+    StatsTitle => "Usage statistics since {} (local only, never sent anywhere)",
+        "Estadísticas de uso desde {} (solo locales, nunca se envían)";
+    StatsStoredIn => "Stored in {}", "Guardadas en {}";
+    StatsEmpty => "No usage recorded yet", "Aún no hay uso registrado";
+    StatsCleared => "Usage statistics cleared", "Estadísticas de uso borradas";
+    FetchingChainStats => "Fetching {} chain summary", "Consultando el resumen de la cadena {}";
+    FeeForTarget => "{} {} ({} blocks)", "{} {} ({} bloques)";
+    GasPriceFee => "{} {} gas price", "{} {} de precio del gas";
+    MempoolSize => "{} transactions, {} vB", "{} transacciones, {} vB";
+    MempoolCount => "{} pending transactions", "{} transacciones pendientes";
+    MempoolUnavailable => "not exposed by this provider", "este proveedor no lo expone";
+    StatsFailed => "could not read usage statistics: {}", "no se pudieron leer las estadísticas de uso: {}";
+
+    // Table headers
+    HeaderPath => "PATH", "RUTA";
+    HeaderType => "TYPE", "TIPO";
+    HeaderAddress => "ADDRESS", "DIRECCIÓN";
+    HeaderNetwork => "NETWORK", "RED";
+    HeaderLabel => "LABEL", "ETIQUETA";
+    HeaderCommand => "COMMAND", "COMANDO";
+    HeaderRuns => "RUNS", "EJECUCIONES";
+    HeaderQueries => "QUERIES", "CONSULTAS";
+    HeaderBalance => "BALANCE", "SALDO";
+    HeaderHeld => "HELD", "EN CARTERA";
+    HeaderTarget => "TARGET", "OBJETIVO";
+    HeaderDrift => "DRIFT", "DESVIACIÓN";
+    HeaderTrade => "TRADE", "OPERACIÓN";
+    HeaderTokenId => "TOKEN ID", "ID DE TOKEN";
+    HeaderSymbol => "SYMBOL", "SÍMBOLO";
+    HeaderDecimals => "DECIMALS", "DECIMALES";
+    HeaderContract => "CONTRACT", "CONTRATO";
+    HeaderTicker => "TICKER", "TICKER";
+    HeaderRune => "RUNE", "RUNA";
+    HeaderRuneId => "RUNE ID", "ID DE RUNA";
+    HeaderAvailable => "AVAILABLE", "DISPONIBLE";
+    HeaderTransferable => "TRANSFERABLE", "TRANSFERIBLE";
+    HeaderOverall => "OVERALL", "TOTAL";
+    HeaderOutpoint => "OUTPOINT", "SALIDA";
+    HeaderValue => "VALUE", "VALOR";
+    HeaderTxid => "TXID", "TXID";
+    HeaderVout => "VOUT", "VOUT";
+    HeaderConfirmations => "CONFIRMATIONS", "CONFIRMACIONES";
+    HeaderTime => "TIME (UTC)", "HORA (UTC)";
+    HeaderBlock => "BLOCK", "BLOQUE";
+    HeaderHash => "HASH", "HASH";
+    HeaderChange => "CHANGE", "CAMBIO";
+    HeaderInscriptions => "INSCRIPTIONS", "INSCRIPCIONES";
+    HeaderProvider => "PROVIDER", "PROVEEDOR";
+    HeaderRequests => "REQUESTS", "PETICIONES";
+    HeaderErrors => "ERRORS", "ERRORES";
+    HeaderRetries => "RETRIES", "REINTENTOS";
+    HeaderCacheHits => "CACHE HITS", "ACIERTOS CACHÉ";
+    HeaderBytes => "BYTES", "BYTES";
+    HeaderLatency => "LATENCY", "LATENCIA";
+    HeaderRole => "ROLE", "FUNCIÓN";
+    HeaderEndpoint => "ENDPOINT", "ENDPOINT";
+    HeaderHeight => "HEIGHT", "ALTURA";
+    HeaderStatus => "STATUS", "ESTADO";
+
+    // Endpoint health check
+    CheckingEndpoints => "Checking {} endpoints", "Comprobando {} endpoints";
+    NoEndpoints => "No endpoints to check for {} (custom-evm needs --rpc-url)",
+        "No hay endpoints que comprobar para {} (custom-evm necesita --rpc-url)";
+    EndpointUp => "up", "activo";
+    EndpointDown => "down: {}", "caído: {}";
+    EndpointsAnswered => "{} of {} endpoints answered", "Respondieron {} de {} endpoints";
+    NoEndpointAnswered => "None of the {} endpoints answered: check this machine's internet connection, proxy or firewall",
+        "Ninguno de los {} endpoints respondió: revisa la conexión a internet, el proxy o el cortafuegos de esta máquina";
+
+    // Session report
+    SessionReport => "Session report", "Informe de la sesión";
+    NoRequests => "No network requests were made", "No se realizaron peticiones de red";
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+/// Set the language used by `tr` for the rest of the process
+pub fn set_language(lang: Lang) {
+    LANGUAGE.store(lang as u8, Ordering::Relaxed);
+}
+
+/// Language currently used by `tr`
+pub fn language() -> Lang {
+    Lang::ALL
+        .get(LANGUAGE.load(Ordering::Relaxed) as usize)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Render `msg` in the current language, filling `{}` placeholders from `args` in order
+pub fn tr(msg: Msg, args: &[&dyn Display]) -> String {
+    format_message(msg, language(), args)
+}
+
+/// Render `msg` in `lang`, filling `{}` placeholders from `args` in order
+pub fn format_message(msg: Msg, lang: Lang, args: &[&dyn Display]) -> String {
+    let mut pieces = msg.template(lang).split("{}");
+    let mut rendered = pieces.next().unwrap_or_default().to_string();
+    let mut args = args.iter();
+    for piece in pieces {
+        match args.next() {
+            Some(arg) => rendered.push_str(&arg.to_string()),
+            None => rendered.push_str("{}"),
+        }
+        rendered.push_str(piece);
+    }
+    rendered
+}
@@ -0,0 +1,208 @@
+//! Newline-delimited JSON query protocol
+//!
+//! A long-running `wallet-balance --jsonl` child reads one query object per
+//! line and answers each with one result object on its own line, in input
+//! order. Callers that issue many lookups keep a single process alive instead
+//! of paying process start-up for every query.
+//!
+//! Query:  `{"id": 1, "network": "ethereum", "address": "0x..."}`
+//! Result: `{"id": 1, "ok": true, "balance": {...}}` or
+//!         `{"id": 1, "ok": false, "error": "..."}`
+//!
+//! `id` is optional and echoed back verbatim. The same protocol is served over
+//! a Unix domain socket by `serve_unix`, one session per connection.
+//!
+//! A line with a `command` instead of a query controls the process:
+//! `{"id": 2, "command": "config reload"}` re-reads the config file (see
+//! `config::reload`) and is answered with `{"id": 2, "ok": true}`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
+use std::path::Path;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::{config, sanitize, BalanceSource, Network, WalletBalance, WalletError};
+
+/// One balance query
+#[derive(Debug, Clone, Deserialize)]
+pub struct Query {
+    /// Caller-chosen correlation id, echoed in the reply
+    #[serde(default)]
+    pub id: Option<Value>,
+    pub network: String,
+    pub address: String,
+}
+
+/// One control command
+#[derive(Debug, Clone, Deserialize)]
+pub struct Command {
+    #[serde(default)]
+    pub id: Option<Value>,
+    pub command: String,
+}
+
+/// Answer to one query line
+#[derive(Debug, Clone, Serialize)]
+pub struct Reply {
+    pub id: Option<Value>,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<WalletBalance>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Reply {
+    fn success(id: Option<Value>, balance: WalletBalance) -> Self {
+        Reply {
+            id,
+            ok: true,
+            balance: Some(balance),
+            error: None,
+        }
+    }
+
+    fn done(id: Option<Value>) -> Self {
+        Reply {
+            id,
+            ok: true,
+            balance: None,
+            error: None,
+        }
+    }
+
+    fn failure(id: Option<Value>, error: &anyhow::Error) -> Self {
+        Reply {
+            id,
+            ok: false,
+            balance: None,
+            error: Some(format!("{:#}", error)),
+        }
+    }
+}
+
+/// Answer a single query line
+///
+/// Malformed lines produce an error reply (with a `null` id) rather than
+/// ending the session.
+pub async fn handle_line(line: &str, source: &BalanceSource) -> Reply {
+    let value: Value = match serde_json::from_str(line).context("Invalid query line") {
+        Ok(value) => value,
+        Err(e) => return Reply::failure(None, &e),
+    };
+    if value.get("command").is_some() {
+        return match serde_json::from_value::<Command>(value).context("Invalid command line") {
+            Ok(command) => match run_command(&command.command) {
+                Ok(()) => Reply::done(command.id),
+                Err(e) => Reply::failure(command.id, &e),
+            },
+            Err(e) => Reply::failure(None, &e),
+        };
+    }
+
+    let query: Query = match serde_json::from_value(value).context("Invalid query line") {
+        Ok(query) => query,
+        Err(e) => return Reply::failure(None, &e),
+    };
+
+    match answer(&query, source).await {
+        Ok(balance) => Reply::success(query.id, balance),
+        Err(e) => Reply::failure(query.id, &e),
+    }
+}
+
+fn run_command(command: &str) -> Result<()> {
+    match command.trim() {
+        "config reload" => Ok(config::reload()?),
+        other => Err(anyhow::anyhow!("Unknown command: {}", other)),
+    }
+}
+
+async fn answer(query: &Query, source: &BalanceSource) -> Result<WalletBalance> {
+    let network: Network = query.network.parse()?;
+    let address = sanitize::sanitize_address(&query.address).address;
+    Ok(source.get_balance(network, &address).await?)
+}
+
+/// Serve queries from `reader` until EOF, writing one reply per non-empty line
+///
+/// # Arguments
+///
+/// * `reader` - Source of newline-delimited query objects
+/// * `writer` - Destination for the reply objects; flushed after every line
+/// * `source` - Where balances are fetched from
+pub async fn serve<R, W>(reader: R, mut writer: W, source: BalanceSource) -> Result<(), WalletError>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut lines = reader.lines();
+    while let Some(line) = lines.next_line().await.context("Failed to read query")? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = handle_line(&line, &source).await;
+        let mut encoded = serde_json::to_vec(&reply).context("Failed to encode reply")?;
+        encoded.push(b'\n');
+        writer.write_all(&encoded).await.context("Failed to write reply")?;
+        writer.flush().await.context("Failed to write reply")?;
+    }
+    Ok(())
+}
+
+/// Serve the protocol on a Unix domain socket until `shutdown` completes
+///
+/// Each connection is an independent session handled on its own task. A stale
+/// socket file left by a previous run is replaced; the socket file is removed
+/// again on shutdown.
+///
+/// # Arguments
+///
+/// * `path` - Filesystem path of the socket to create
+/// * `source` - Where balances are fetched from
+/// * `shutdown` - Future that ends the accept loop when it completes
+#[cfg(unix)]
+pub async fn serve_unix(
+    path: &Path,
+    source: BalanceSource,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), WalletError> {
+    use std::os::unix::fs::FileTypeExt;
+    use tokio::net::UnixListener;
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(WalletError::Other(format!("{} exists and is not a socket", path.display())));
+        }
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove stale socket {}", path.display()))?;
+    }
+
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to bind socket {}", path.display()))?;
+
+    tokio::pin!(shutdown);
+    let result = loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let stream = match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => break Err(anyhow::Error::new(e).context("Failed to accept connection")),
+                };
+                let source = source.clone();
+                tokio::spawn(async move {
+                    let (read, write) = stream.into_split();
+                    // A client hanging up mid-session only ends its own session
+                    let _ = serve(BufReader::new(read), write, source).await;
+                });
+            }
+            _ = &mut shutdown => break Ok(()),
+        }
+    };
+
+    let _ = std::fs::remove_file(path);
+    Ok(result?)
+}
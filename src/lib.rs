@@ -2,14 +2,89 @@
 //!
 //! This library provides functionality to check cryptocurrency wallet balances
 //! across multiple blockchain networks.
+//!
+//! Without the default `std` feature only `core`, the no_std validation
+//! rules, is built.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod core;
+
+/// Mark every item given as part of the `std` build
+macro_rules! std_only {
+    ($($item:item)*) => {
+        $(#[cfg(feature = "std")] $item)*
+    };
+}
 
+std_only! {
+pub mod address;
+pub mod address_flags;
+pub mod api_keys;
+pub mod attest;
+pub mod assertions;
+pub mod audit;
+pub mod erc1155;
+pub mod error;
+pub mod exchanges;
+pub mod tokens;
+pub mod snapshot;
+pub mod config;
 pub mod bitcoin_wallet;
+pub mod bitcoin_hd;
+pub mod chain_stats;
+pub mod ordinals;
+pub mod runes;
+pub mod evm_wallet;
 pub mod ethereum_wallet;
 pub mod base_wallet;
+pub mod address_book;
 pub mod arbitrum_wallet;
+pub mod optimism_wallet;
 pub mod polygon_wallet;
 pub mod tron_wallet;
+pub mod unstoppable;
+pub mod dogecoin_wallet;
+pub mod litecoin_wallet;
+pub mod sanitize;
+pub mod schema;
+pub mod payment_uri;
+pub mod pricing;
+pub mod portfolio;
+pub mod drift;
+pub mod pseudonym;
+pub mod units;
+pub mod fees;
+pub mod graphql;
+pub mod history;
+pub mod pending;
+pub mod mock;
+pub mod dev;
+pub mod deposit;
+pub mod endpoints;
+#[cfg(feature = "test-support")]
+pub mod testing;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+#[cfg(feature = "otel")]
+pub mod telemetry;
+pub mod jsonl;
+pub mod cache;
+pub mod server;
+pub mod subscriptions;
+pub mod sink;
+pub mod state;
+pub mod verify;
+pub mod watch;
+pub mod http;
+pub mod provider;
+pub mod usage;
+pub mod i18n;
+mod evm_rpc;
 use anyhow::Result;
+pub use error::WalletError;
 use serde::{Deserialize, Serialize};
 
 /// Represents a wallet balance with amount and denomination
@@ -19,6 +94,94 @@ pub struct WalletBalance {
     pub balance: String,
     pub network: String,
     pub denomination: String,
+    /// Block the balance was read at, when the read was pinned to one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block: Option<BlockRef>,
+    /// Value of the balance in `fiat_currency`, when converted with `pricing::convert`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fiat_value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fiat_currency: Option<String>,
+    /// ERC-20 `totalSupply()` in whole tokens, when requested with the balance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_supply: Option<String>,
+    /// The balance as a percentage of `total_supply`, to 4 decimal places
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supply_share: Option<String>,
+    /// The balance at an earlier block and the change since, when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<BalanceSince>,
+    /// Signed net change from unconfirmed mempool transactions (e.g. `+0.001`), when requested (Bitcoin)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unconfirmed_balance: Option<String>,
+}
+
+/// A balance at an earlier block, and how the current balance differs from it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BalanceSince {
+    pub block: u64,
+    pub balance: String,
+    /// Signed difference, e.g. `+0.5`, `-2` or `0`
+    pub change: String,
+}
+
+/// A specific block, identified by height and hash
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlockRef {
+    pub number: u64,
+    pub hash: String,
+}
+
+/// Block an EVM balance is read at (`--block`): a height or one of the JSON-RPC block tags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockTag {
+    Number(u64),
+    Latest,
+    Safe,
+    Finalized,
+    Earliest,
+    Pending,
+}
+
+impl BlockTag {
+    /// The block parameter of a JSON-RPC call
+    pub fn param(&self) -> String {
+        match self {
+            BlockTag::Number(number) => format!("0x{:x}", number),
+            BlockTag::Latest => "latest".to_string(),
+            BlockTag::Safe => "safe".to_string(),
+            BlockTag::Finalized => "finalized".to_string(),
+            BlockTag::Earliest => "earliest".to_string(),
+            BlockTag::Pending => "pending".to_string(),
+        }
+    }
+}
+
+impl std::str::FromStr for BlockTag {
+    type Err = WalletError;
+
+    /// Parse a decimal or `0x` hex block number, or `latest`, `safe`, `finalized`, `earliest` or `pending`
+    fn from_str(s: &str) -> Result<Self, WalletError> {
+        let s = s.trim();
+        let number = match s.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => s.parse().ok(),
+        };
+        if let Some(number) = number {
+            return Ok(BlockTag::Number(number));
+        }
+        match s.to_lowercase().as_str() {
+            "latest" => Ok(BlockTag::Latest),
+            "safe" => Ok(BlockTag::Safe),
+            "finalized" => Ok(BlockTag::Finalized),
+            "earliest" => Ok(BlockTag::Earliest),
+            "pending" => Ok(BlockTag::Pending),
+            _ => Err(WalletError::ParseError(format!(
+                "Invalid block: {} (expected a number or latest, safe, finalized, earliest or pending)",
+                s
+            ))),
+        }
+    }
 }
 
 impl WalletBalance {
@@ -29,46 +192,747 @@ impl WalletBalance {
             balance,
             network,
             denomination,
+            block: None,
+            fiat_value: None,
+            fiat_currency: None,
+            total_supply: None,
+            supply_share: None,
+            since: None,
+            unconfirmed_balance: None,
         }
     }
 }
 
-/// Network enum for supported blockchain networks
+/// Get the native balance of `address` on `network`
+///
+/// Dispatches to the network's provider in `provider::registry()`.
+///
+/// With a `cache` installed, a balance fetched within its TTL is answered from it.
+#[tracing::instrument(level = "info", name = "balance", skip_all, fields(%network))]
+pub async fn get_balance(network: Network, address: &str) -> Result<WalletBalance, WalletError> {
+    require_configured(network)?;
+    let registry = provider::registry();
+    let provider = registry.get(network)?;
+    // An address that does not normalize is left to fail the lookup with the provider's error
+    let key = cache::active()
+        .and_then(|_| provider.normalize_address(address).ok())
+        .map(|address| cache::balance_key(network, &address));
+    if let Some(key) = &key {
+        if let Some(cached) = cache::cached_balance(key).await {
+            return Ok(cached);
+        }
+    }
+    let balance = provider.get_balance(address).await?;
+    if let Some(key) = &key {
+        cache::store_balance(key, &balance).await;
+    }
+    Ok(balance)
+}
+
+/// Fail with `NotConfigured` when `network` has no endpoint to query
+fn require_configured(network: Network) -> Result<()> {
+    if network == Network::CustomEvm && evm_wallet::custom_chain().is_none() {
+        return Err(NotConfigured { network, needs: "--rpc-url" }.into());
+    }
+    Ok(())
+}
+
+/// Get the native balance of `address` on an EVM `network`, `confirmations` blocks below the tip
+///
+/// The read is pinned to a single block so the returned `WalletBalance`
+/// carries that block's number and hash; with `confirmations > 0` a shallow
+/// reorg of the newest blocks cannot change the result. Reads at the tip go
+/// through the installed `cache`, if any.
+#[tracing::instrument(level = "info", name = "balance", skip_all, fields(%network, confirmations))]
+pub async fn get_balance_with_confirmations(
+    network: Network,
+    address: &str,
+    confirmations: u64,
+) -> Result<WalletBalance, WalletError> {
+    require_configured(network)?;
+    let rpc_url = evm_rpc::rpc_url(network).ok_or_else(|| {
+        anyhow::anyhow!("Block-pinned reads are only supported on EVM networks, not {}", network)
+    })?;
+    let address = normalize_address(network, address)?;
+    // Only reads at the tip: deeper ones are rarer and pinned to an older block on purpose
+    let key = cache::active()
+        .filter(|_| confirmations == 0)
+        .map(|_| cache::pinned_balance_key(network, &address));
+    if let Some(key) = &key {
+        if let Some(cached) = cache::cached_balance(key).await {
+            return Ok(cached);
+        }
+    }
+    let balance = get_evm_balance_pinned(&rpc_url, network, &address, confirmations).await?;
+    if let Some(key) = &key {
+        cache::store_balance(key, &balance).await;
+    }
+    Ok(balance)
+}
+
+/// Pinned EVM read shared by the live and `--dev` sources
+pub(crate) async fn get_evm_balance_pinned(
+    rpc_url: &str,
+    network: Network,
+    address: &str,
+    confirmations: u64,
+) -> Result<WalletBalance> {
+    let block = evm_rpc::block_at_depth(rpc_url, confirmations).await?;
+    let wei = evm_rpc::get_balance_at(rpc_url, address, Some(block.number)).await?;
+
+    let mut balance = WalletBalance::new(
+        address.to_string(),
+        units::format_units_u256(wei, network.decimals()),
+        network.to_string(),
+        network.denomination().to_string(),
+    );
+    balance.block = Some(block);
+    Ok(balance)
+}
+
+/// Get the native balance of `address` on EVM `network` as of `block`
+///
+/// Block tags are resolved to a height first, and the balance is read at that
+/// height, so `block` in the result names exactly the block the amount is
+/// from. `pending` has no settled block: it is read as is and `block` is left
+/// unset. Reads far below the tip need an archive node behind the RPC
+/// endpoint.
+pub async fn get_balance_at_block(
+    network: Network,
+    address: &str,
+    block: BlockTag,
+) -> Result<WalletBalance, WalletError> {
+    require_configured(network)?;
+    let rpc_url = evm_rpc::rpc_url(network).ok_or_else(|| {
+        anyhow::anyhow!("Block-pinned reads are only supported on EVM networks, not {}", network)
+    })?;
+    let address = normalize_address(network, address)?;
+    Ok(get_evm_balance_at_block(&rpc_url, network, &address, block).await?)
+}
+
+/// `get_balance_at_block` shared by the live and `--dev` sources
+pub(crate) async fn get_evm_balance_at_block(
+    rpc_url: &str,
+    network: Network,
+    address: &str,
+    block: BlockTag,
+) -> Result<WalletBalance> {
+    let (wei, block) = match block {
+        BlockTag::Pending => (evm_rpc::get_balance_at_tag(rpc_url, address, &block.param()).await?, None),
+        _ => {
+            let block = evm_rpc::block_by_tag(rpc_url, &block.param()).await?;
+            (evm_rpc::get_balance_at(rpc_url, address, Some(block.number)).await?, Some(block))
+        }
+    };
+
+    let mut balance = WalletBalance::new(
+        address.to_string(),
+        units::format_units_u256(wei, network.decimals()),
+        network.to_string(),
+        network.denomination().to_string(),
+    );
+    balance.block = block;
+    Ok(balance)
+}
+
+/// Apply the network module's address normalization and validation rules
+pub(crate) fn normalize_address(network: Network, address: &str) -> Result<String> {
+    Ok(provider::registry().get(network)?.normalize_address(address)?)
+}
+
+/// Where balances are fetched from
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum BalanceSource {
+    /// Query the public network APIs
+    #[default]
+    Live,
+    /// Deterministic offline balances from the `mock` module
+    Mock,
+    /// A local developer node (`--dev`); `None` uses the network's default local port
+    Dev { node_url: Option<String> },
+}
+
+impl BalanceSource {
+    /// Get the native balance of `address` on `network` from this source
+    pub async fn get_balance(&self, network: Network, address: &str) -> Result<WalletBalance, WalletError> {
+        match self {
+            BalanceSource::Live => get_balance(network, address).await,
+            BalanceSource::Mock => mock::get_balance(network, address),
+            BalanceSource::Dev { node_url } => dev::get_balance(network, node_url.as_deref(), address).await,
+        }
+    }
+
+    /// Like `get_balance`, but EVM reads are pinned `confirmations` blocks below the tip
+    ///
+    /// Mock balances have no blocks, and non-EVM networks only support
+    /// `confirmations == 0`.
+    pub async fn get_balance_with_confirmations(
+        &self,
+        network: Network,
+        address: &str,
+        confirmations: u64,
+    ) -> Result<WalletBalance, WalletError> {
+        let is_evm = evm_rpc::rpc_url(network).is_some();
+        if !is_evm && confirmations > 0 {
+            return Err(WalletError::UnsupportedNetwork(format!(
+                "--confirmations is only supported on EVM networks, not {}",
+                network
+            )));
+        }
+        match self {
+            BalanceSource::Live if is_evm => get_balance_with_confirmations(network, address, confirmations).await,
+            BalanceSource::Dev { node_url } if is_evm => {
+                let address = ethereum_wallet::normalize_address(address)?;
+                let node_url = node_url.as_deref().unwrap_or(dev::DEFAULT_EVM_NODE);
+                Ok(get_evm_balance_pinned(node_url, network, &address, confirmations).await?)
+            }
+            _ => self.get_balance(network, address).await,
+        }
+    }
+
+    /// Like `get_balance`, but read as of `block` (EVM networks)
+    ///
+    /// Mock balances have no blocks, so the mock source ignores `block`.
+    pub async fn get_balance_at_block(
+        &self,
+        network: Network,
+        address: &str,
+        block: BlockTag,
+    ) -> Result<WalletBalance, WalletError> {
+        if evm_rpc::rpc_url(network).is_none() {
+            return Err(WalletError::UnsupportedNetwork(format!(
+                "--block is only supported on EVM networks, not {}",
+                network
+            )));
+        }
+        match self {
+            BalanceSource::Live => get_balance_at_block(network, address, block).await,
+            BalanceSource::Dev { node_url } => {
+                let address = ethereum_wallet::normalize_address(address)?;
+                let node_url = node_url.as_deref().unwrap_or(dev::DEFAULT_EVM_NODE);
+                Ok(get_evm_balance_at_block(node_url, network, &address, block).await?)
+            }
+            BalanceSource::Mock => self.get_balance(network, address).await,
+        }
+    }
+
+    /// Fetch several balances concurrently, returning the results in input order
+    pub async fn get_balances(
+        &self,
+        queries: &[(Network, String)],
+        confirmations: u64,
+    ) -> Vec<Result<WalletBalance, WalletError>> {
+        self.get_balances_within(queries, confirmations, None).await
+    }
+
+    /// `get_balances`, giving up on the lookups still running once `deadline` passes
+    ///
+    /// Lookups that finished in time keep their results; the others fail with
+    /// `LookupTimedOut`, so the caller can tell them from ordinary errors.
+    pub async fn get_balances_within(
+        &self,
+        queries: &[(Network, String)],
+        confirmations: u64,
+        deadline: Option<tokio::time::Instant>,
+    ) -> Vec<Result<WalletBalance, WalletError>> {
+        let mut results: Vec<Option<Result<WalletBalance, WalletError>>> = queries.iter().map(|_| None).collect();
+        let mut tasks = tokio::task::JoinSet::new();
+        let (sender, mut received) = tokio::sync::mpsc::unbounded_channel();
+
+        // Live Tron and Bitcoin lookups each go through one batch that respects the provider's rate limit
+        let mut batched = vec![false; queries.len()];
+        // Cache keys of the batched lookups the cache could not answer, to store what the batch fetches
+        let mut cache_keys: Vec<Option<String>> = vec![None; queries.len()];
+        if *self == BalanceSource::Live && confirmations == 0 {
+            for network in [Network::Tron, Network::Bitcoin] {
+                let mut indices = Vec::new();
+                for index in (0..queries.len()).filter(|&index| queries[index].0 == network) {
+                    let key = cache::active()
+                        .and_then(|_| normalize_address(network, &queries[index].1).ok())
+                        .map(|address| cache::balance_key(network, &address));
+                    if let Some(key) = key {
+                        if let Some(cached) = cache::cached_balance(&key).await {
+                            results[index] = Some(Ok(cached));
+                            batched[index] = true;
+                            continue;
+                        }
+                        cache_keys[index] = Some(key);
+                    }
+                    indices.push(index);
+                }
+                if indices.is_empty() {
+                    continue;
+                }
+                let addresses: Vec<String> = indices.iter().map(|&index| queries[index].1.clone()).collect();
+                for &index in &indices {
+                    batched[index] = true;
+                }
+                let sender = sender.clone();
+                let report = move |position: usize, result| {
+                    let _ = sender.send((indices[position], result));
+                };
+                tasks.spawn(async move {
+                    match network {
+                        Network::Tron => tron_wallet::stream_balances(&addresses, report).await,
+                        _ => bitcoin_wallet::stream_balances(&addresses, report).await,
+                    }
+                });
+            }
+        }
+
+        for (index, (network, address)) in queries.iter().cloned().enumerate() {
+            if batched[index] {
+                continue;
+            }
+            let (source, sender) = (self.clone(), sender.clone());
+            tasks.spawn(async move {
+                let result = source
+                    .get_balance_with_confirmations(network, &address, confirmations)
+                    .await;
+                let _ = sender.send((index, result));
+            });
+        }
+        // Only the tasks hold senders now, so the channel closes once they have all finished
+        drop(sender);
+
+        let collect = async {
+            while let Some((index, result)) = received.recv().await {
+                results[index] = Some(result);
+            }
+        };
+        let timed_out = match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline, collect).await.is_err(),
+            None => {
+                collect.await;
+                false
+            }
+        };
+        tasks.abort_all();
+
+        for (index, key) in cache_keys.iter().enumerate() {
+            if let (Some(key), Some(Ok(balance))) = (key, &results[index]) {
+                cache::store_balance(key, balance).await;
+            }
+        }
+        results
+            .into_iter()
+            .map(|result| match (result, timed_out) {
+                (Some(result), _) => result,
+                (None, true) => Err(WalletError::TimedOut(LookupTimedOut)),
+                (None, false) => Err(WalletError::Other("Balance lookup task failed".to_string())),
+            })
+            .collect()
+    }
+}
+
+/// Error of a lookup abandoned because its batch ran out of time (`get_balances_within`)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LookupTimedOut;
+
+impl std::fmt::Display for LookupTimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Lookup timed out before the deadline")
+    }
+}
+
+impl std::error::Error for LookupTimedOut {}
+
+/// Error of a lookup on a network that needs an endpoint or key nobody has set
+///
+/// Batch reports list these networks as skipped rather than failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotConfigured {
+    pub network: Network,
+    /// Flag or config key that enables the network
+    pub needs: &'static str,
+}
+
+impl std::fmt::Display for NotConfigured {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is not configured (needs {})", self.network, self.needs)
+    }
+}
+
+impl std::error::Error for NotConfigured {}
+
+impl std::str::FromStr for BalanceSource {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, WalletError> {
+        match s.to_lowercase().as_str() {
+            "live" => Ok(BalanceSource::Live),
+            "mock" => Ok(BalanceSource::Mock),
+            _ => Err(WalletError::ParseError(format!("Unsupported provider: {} (expected live or mock)", s))),
+        }
+    }
+}
+
+/// Symbol and decimals an ERC-20 contract reports
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenInfo {
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+/// An ERC-20 balance and the decimals it was scaled by
+#[derive(Debug, Clone)]
+pub struct TokenBalance {
+    /// Balance in whole tokens, denominated in the token's symbol
+    pub balance: WalletBalance,
+    pub decimals: u32,
+}
+
+/// Get the ERC-20 balance of `wallet` for the `token` contract on an EVM `network`
+///
+/// The balance is scaled by the token's `decimals()` and denominated in its
+/// `symbol()`.
+pub async fn get_token_balance(network: Network, token: &str, wallet: &str) -> Result<TokenBalance, WalletError> {
+    let (rpc_url, token, wallet) = token_query(network, token, wallet)?;
+
+    let (units, info) = tokio::try_join!(
+        evm_rpc::erc20_balance(&rpc_url, &token, &wallet),
+        evm_rpc::erc20_token(&rpc_url, &token),
+    )?;
+    Ok(TokenBalance {
+        balance: WalletBalance::new(
+            wallet,
+            units::format_units_u256(units, info.decimals),
+            network.to_string(),
+            info.symbol,
+        ),
+        decimals: info.decimals,
+    })
+}
+
+/// `get_token_balance`, plus the token's `totalSupply()` and the wallet's share of it
+///
+/// Sets `total_supply` and `supply_share` on the balance. A token reporting a
+/// zero supply gets no share.
+pub async fn get_token_balance_with_supply(
+    network: Network,
+    token: &str,
+    wallet: &str,
+) -> Result<TokenBalance, WalletError> {
+    let (rpc_url, token, wallet) = token_query(network, token, wallet)?;
+
+    let (units, supply, info) = tokio::try_join!(
+        evm_rpc::erc20_balance(&rpc_url, &token, &wallet),
+        evm_rpc::erc20_total_supply(&rpc_url, &token),
+        evm_rpc::erc20_token(&rpc_url, &token),
+    )?;
+    let mut balance = WalletBalance::new(
+        wallet,
+        units::format_units_u256(units, info.decimals),
+        network.to_string(),
+        info.symbol,
+    );
+    balance.total_supply = Some(units::format_units_u256(supply, info.decimals));
+    balance.supply_share = units::percent_of(units, supply);
+    Ok(TokenBalance {
+        balance,
+        decimals: info.decimals,
+    })
+}
+
+/// `get_token_balance`, plus the balance at `block` and the change since it
+///
+/// Two `balanceOf` calls, one at the latest block and one at `block`, so an
+/// expected transfer can be confirmed without reading the transfer history.
+pub async fn get_token_balance_since(
+    network: Network,
+    token: &str,
+    wallet: &str,
+    block: u64,
+) -> Result<TokenBalance, WalletError> {
+    let (rpc_url, token, wallet) = token_query(network, token, wallet)?;
+
+    let (current, earlier, info) = tokio::try_join!(
+        evm_rpc::erc20_balance(&rpc_url, &token, &wallet),
+        evm_rpc::erc20_balance_at(&rpc_url, &token, &wallet, block),
+        evm_rpc::erc20_token(&rpc_url, &token),
+    )?;
+    let mut balance = WalletBalance::new(
+        wallet,
+        units::format_units_u256(current, info.decimals),
+        network.to_string(),
+        info.symbol,
+    );
+    balance.since = Some(BalanceSince {
+        block,
+        balance: units::format_units_u256(earlier, info.decimals),
+        change: units::format_delta(earlier, current, info.decimals),
+    });
+    Ok(TokenBalance {
+        balance,
+        decimals: info.decimals,
+    })
+}
+
+/// RPC endpoint of `network` and the normalized `token` and `wallet` addresses
+fn token_query(network: Network, token: &str, wallet: &str) -> Result<(String, String, String)> {
+    let rpc_url = evm_rpc::rpc_url(network)
+        .ok_or_else(|| anyhow::anyhow!("Token balances are only supported on EVM networks, not {}", network))?;
+    let registry = provider::registry();
+    let provider = registry.get(network)?;
+    Ok((rpc_url, provider.normalize_address(token)?, provider.normalize_address(wallet)?))
+}
+
+/// Get the symbol and decimals of the `token` contract on an EVM `network`
+pub async fn get_token_info(network: Network, token: &str) -> Result<TokenInfo, WalletError> {
+    let rpc_url = evm_rpc::rpc_url(network)
+        .ok_or_else(|| anyhow::anyhow!("Token balances are only supported on EVM networks, not {}", network))?;
+    let token = provider::registry().get(network)?.normalize_address(token)?;
+    Ok(evm_rpc::erc20_token(&rpc_url, &token).await?)
+}
+
+/// Network enum for supported blockchain networks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Network {
     Bitcoin,
+    BitcoinTestnet4,
+    BitcoinSignet,
     Ethereum,
     Base,
     Arbitrum,
+    Optimism,
     Polygon,
+    Bsc,
+    Avalanche,
+    Fantom,
+    Gnosis,
+    /// Any other EVM chain, reached through the endpoint given with `--rpc-url`
+    CustomEvm,
     Tron,
+    Dogecoin,
+    Litecoin,
+    Sepolia,
+    BaseSepolia,
+    ArbitrumSepolia,
+    OptimismSepolia,
+    PolygonAmoy,
+    BscTestnet,
+    AvalancheFuji,
+    FantomTestnet,
+    GnosisChiado,
+    TronShasta,
+}
+
+impl Network {
+    /// Every supported network, in display order
+    pub const ALL: [Network; 26] = [
+        Network::Bitcoin,
+        Network::BitcoinTestnet4,
+        Network::BitcoinSignet,
+        Network::Ethereum,
+        Network::Base,
+        Network::Arbitrum,
+        Network::Optimism,
+        Network::Polygon,
+        Network::Bsc,
+        Network::Avalanche,
+        Network::Fantom,
+        Network::Gnosis,
+        Network::CustomEvm,
+        Network::Tron,
+        Network::Dogecoin,
+        Network::Litecoin,
+        Network::Sepolia,
+        Network::BaseSepolia,
+        Network::ArbitrumSepolia,
+        Network::OptimismSepolia,
+        Network::PolygonAmoy,
+        Network::BscTestnet,
+        Network::AvalancheFuji,
+        Network::FantomTestnet,
+        Network::GnosisChiado,
+        Network::TronShasta,
+    ];
+
+    /// Ticker of the network's native asset
+    pub fn denomination(&self) -> &'static str {
+        if let Some(chain) = evm_wallet::chain(*self) {
+            return chain.symbol;
+        }
+        match self {
+            Network::Bitcoin => "BTC",
+            Network::BitcoinTestnet4 => "tBTC",
+            Network::BitcoinSignet => "sBTC",
+            Network::Tron => "TRX",
+            Network::TronShasta => "tTRX",
+            Network::Dogecoin => "DOGE",
+            Network::Litecoin => "LTC",
+            _ => unreachable!("EVM networks are in the chain table"),
+        }
+    }
+
+    /// Number of decimal places of the native asset's base unit
+    pub fn decimals(&self) -> u32 {
+        if let Some(chain) = evm_wallet::chain(*self) {
+            return chain.decimals;
+        }
+        match self {
+            Network::Bitcoin
+            | Network::BitcoinTestnet4
+            | Network::BitcoinSignet
+            | Network::Dogecoin
+            | Network::Litecoin => 8,
+            Network::Tron | Network::TronShasta => 6,
+            _ => unreachable!("EVM networks are in the chain table"),
+        }
+    }
+
+    /// Human-readable network name used in CLI messages
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Network::Bitcoin => "Bitcoin",
+            Network::BitcoinTestnet4 => "Bitcoin Testnet4",
+            Network::BitcoinSignet => "Bitcoin Signet",
+            Network::Ethereum => "Ethereum",
+            Network::Base => "Base L2",
+            Network::Arbitrum => "Arbitrum L2",
+            Network::Optimism => "Optimism L2",
+            Network::Polygon => "Polygon",
+            Network::Bsc => "BNB Smart Chain",
+            Network::Avalanche => "Avalanche C-Chain",
+            Network::Fantom => "Fantom",
+            Network::Gnosis => "Gnosis",
+            Network::CustomEvm => "Custom EVM",
+            Network::Tron => "Tron",
+            Network::Dogecoin => "Dogecoin",
+            Network::Litecoin => "Litecoin",
+            Network::Sepolia => "Ethereum Sepolia",
+            Network::BaseSepolia => "Base Sepolia",
+            Network::ArbitrumSepolia => "Arbitrum Sepolia",
+            Network::OptimismSepolia => "Optimism Sepolia",
+            Network::PolygonAmoy => "Polygon Amoy",
+            Network::BscTestnet => "BNB Smart Chain Testnet",
+            Network::AvalancheFuji => "Avalanche Fuji",
+            Network::FantomTestnet => "Fantom Testnet",
+            Network::GnosisChiado => "Gnosis Chiado",
+            Network::TronShasta => "Tron Shasta",
+        }
+    }
+
+    /// The test network `--testnet` switches this network to, if it has one
+    pub fn testnet(&self) -> Option<Network> {
+        match self {
+            Network::Bitcoin => Some(Network::BitcoinTestnet4),
+            Network::Ethereum => Some(Network::Sepolia),
+            Network::Base => Some(Network::BaseSepolia),
+            Network::Arbitrum => Some(Network::ArbitrumSepolia),
+            Network::Optimism => Some(Network::OptimismSepolia),
+            Network::Polygon => Some(Network::PolygonAmoy),
+            Network::Bsc => Some(Network::BscTestnet),
+            Network::Avalanche => Some(Network::AvalancheFuji),
+            Network::Fantom => Some(Network::FantomTestnet),
+            Network::Gnosis => Some(Network::GnosisChiado),
+            Network::Tron => Some(Network::TronShasta),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a test network, whose coins have no value
+    pub fn is_testnet(&self) -> bool {
+        // Signet is the one test network --testnet does not switch to
+        *self == Network::BitcoinSignet || Network::ALL.iter().any(|network| network.testnet() == Some(*self))
+    }
+
+    /// Whether this network speaks TronGrid's API, with `T` Base58Check addresses
+    pub fn is_tron(&self) -> bool {
+        matches!(self, Network::Tron | Network::TronShasta)
+    }
+
+    /// Whether this is an EVM network, with `0x` hex addresses
+    pub fn is_evm(&self) -> bool {
+        evm_wallet::chain(*self).is_some()
+    }
+
+    /// The Bitcoin chain behind this network, or `None` for non-Bitcoin networks
+    pub fn bitcoin_chain(&self) -> Option<bitcoin_wallet::Chain> {
+        match self {
+            Network::Bitcoin => Some(bitcoin_wallet::Chain::Mainnet),
+            Network::BitcoinTestnet4 => Some(bitcoin_wallet::Chain::Testnet4),
+            Network::BitcoinSignet => Some(bitcoin_wallet::Chain::Signet),
+            _ => None,
+        }
+    }
+
+    /// Look up an EVM network by its EIP-155 chain id
+    pub fn from_chain_id(chain_id: u64) -> Option<Network> {
+        evm_wallet::chain_by_id(chain_id).map(|chain| chain.network)
+    }
 }
 
 impl std::fmt::Display for Network {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Network::Bitcoin => write!(f, "bitcoin"),
+            Network::BitcoinTestnet4 => write!(f, "bitcoin-testnet4"),
+            Network::BitcoinSignet => write!(f, "bitcoin-signet"),
             Network::Ethereum => write!(f, "ethereum"),
             Network::Base => write!(f, "base"),
             Network::Arbitrum => write!(f, "arbitrum"),
+            Network::Optimism => write!(f, "optimism"),
             Network::Polygon => write!(f, "polygon"),
+            Network::Bsc => write!(f, "bsc"),
+            Network::Avalanche => write!(f, "avalanche"),
+            Network::Fantom => write!(f, "fantom"),
+            Network::Gnosis => write!(f, "gnosis"),
+            Network::CustomEvm => write!(f, "custom-evm"),
             Network::Tron => write!(f, "tron"),
+            Network::Dogecoin => write!(f, "dogecoin"),
+            Network::Litecoin => write!(f, "litecoin"),
+            Network::Sepolia => write!(f, "sepolia"),
+            Network::BaseSepolia => write!(f, "base-sepolia"),
+            Network::ArbitrumSepolia => write!(f, "arbitrum-sepolia"),
+            Network::OptimismSepolia => write!(f, "optimism-sepolia"),
+            Network::PolygonAmoy => write!(f, "polygon-amoy"),
+            Network::BscTestnet => write!(f, "bsc-testnet"),
+            Network::AvalancheFuji => write!(f, "avalanche-fuji"),
+            Network::FantomTestnet => write!(f, "fantom-testnet"),
+            Network::GnosisChiado => write!(f, "gnosis-chiado"),
+            Network::TronShasta => write!(f, "tron-shasta"),
         }
     }
 }
 
 impl std::str::FromStr for Network {
-    type Err = anyhow::Error;
+    type Err = WalletError;
 
-    fn from_str(s: &str) -> Result<Self> {
+    fn from_str(s: &str) -> Result<Self, WalletError> {
         match s.to_lowercase().as_str() {
             "bitcoin" | "btc" => Ok(Network::Bitcoin),
+            "bitcoin-testnet4" | "testnet4" | "bitcoin-testnet" => Ok(Network::BitcoinTestnet4),
+            "bitcoin-signet" | "signet" => Ok(Network::BitcoinSignet),
             "ethereum" | "eth" => Ok(Network::Ethereum),
             "base" => Ok(Network::Base),
             "arbitrum" | "arb" => Ok(Network::Arbitrum),
+            "optimism" | "op" => Ok(Network::Optimism),
             "polygon" | "matic" => Ok(Network::Polygon),
+            "bsc" | "bnb" => Ok(Network::Bsc),
+            "avalanche" | "avax" => Ok(Network::Avalanche),
+            "fantom" | "ftm" => Ok(Network::Fantom),
+            "gnosis" | "xdai" => Ok(Network::Gnosis),
+            "custom-evm" => Ok(Network::CustomEvm),
             "tron" | "trx" => Ok(Network::Tron),
-            _ => Err(anyhow::anyhow!("Unsupported network: {}", s)),
+            "dogecoin" | "doge" => Ok(Network::Dogecoin),
+            "litecoin" | "ltc" => Ok(Network::Litecoin),
+            "sepolia" | "ethereum-sepolia" => Ok(Network::Sepolia),
+            "base-sepolia" => Ok(Network::BaseSepolia),
+            "arbitrum-sepolia" => Ok(Network::ArbitrumSepolia),
+            "optimism-sepolia" | "op-sepolia" => Ok(Network::OptimismSepolia),
+            "polygon-amoy" | "amoy" => Ok(Network::PolygonAmoy),
+            "mumbai" | "polygon-mumbai" => Err(WalletError::UnsupportedNetwork(
+                "Polygon Mumbai has been shut down; use polygon-amoy, its replacement".to_string(),
+            )),
+            "bsc-testnet" => Ok(Network::BscTestnet),
+            "avalanche-fuji" | "fuji" => Ok(Network::AvalancheFuji),
+            "fantom-testnet" => Ok(Network::FantomTestnet),
+            "gnosis-chiado" | "chiado" => Ok(Network::GnosisChiado),
+            "tron-shasta" | "shasta" => Ok(Network::TronShasta),
+            _ => Err(WalletError::UnsupportedNetwork(format!("Unsupported network: {}", s))),
         }
     }
 }
+
+} // std_only!
@@ -0,0 +1,82 @@
+//! Litecoin wallet balance checking functionality
+//!
+//! Litecoin shares Bitcoin's address and UTXO model, and litecoinspace.org
+//! serves the same Esplora API as Blockstream, so balances are fetched with
+//! the Bitcoin module's Esplora client.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::provider::BalanceProvider;
+use crate::{bitcoin_wallet, config, Network, WalletBalance, WalletError};
+
+/// Default Esplora API, unless `[litecoin] api` names another
+pub(crate) const LITECOINSPACE_API: &str = "https://litecoinspace.org/api";
+
+/// Base58 prefixes: `L` (P2PKH), `M` (P2SH) and legacy `3` P2SH
+const BASE58_PREFIXES: [char; 3] = ['L', 'M', '3'];
+const BECH32_PREFIX: &str = "ltc1";
+
+/// Get Litecoin wallet balance for a given address
+///
+/// # Arguments
+///
+/// * `address` - Litecoin address to check
+///
+/// # Returns
+///
+/// Returns a `WalletBalance` containing the balance in LTC
+#[tracing::instrument(level = "debug", name = "litecoin_balance", skip_all, fields(address = %address))]
+pub async fn get_balance(address: &str) -> Result<WalletBalance, WalletError> {
+    let address = address.trim();
+    validate_address(address)?;
+
+    let balance_litoshi = bitcoin_wallet::get_balance_sats_from(config::active().litecoin_api(), address).await?;
+    let balance_ltc = balance_litoshi as f64 / 100_000_000.0;
+
+    Ok(WalletBalance::new(
+        address.to_string(),
+        format!("{:.8}", balance_ltc),
+        "litecoin".to_string(),
+        "LTC".to_string(),
+    ))
+}
+
+pub(crate) fn validate_address(address: &str) -> Result<(), WalletError> {
+    if address.is_empty() {
+        return Err(WalletError::InvalidAddress("Litecoin address cannot be empty".to_string()));
+    }
+
+    if address.len() < 26 || address.len() > 62 {
+        return Err(WalletError::InvalidAddress("Invalid Litecoin address length".to_string()));
+    }
+
+    if !address.starts_with(BASE58_PREFIXES) && !address.to_lowercase().starts_with(BECH32_PREFIX) {
+        return Err(WalletError::InvalidAddress(format!(
+            "Invalid Litecoin address format (must start with L, M, 3, or {})",
+            BECH32_PREFIX
+        )));
+    }
+
+    Ok(())
+}
+
+/// `BalanceProvider` for Litecoin
+pub struct LitecoinProvider;
+
+#[async_trait]
+impl BalanceProvider for LitecoinProvider {
+    fn network(&self) -> Network {
+        Network::Litecoin
+    }
+
+    fn normalize_address(&self, address: &str) -> Result<String, WalletError> {
+        let address = address.trim();
+        validate_address(address)?;
+        Ok(address.to_string())
+    }
+
+    async fn get_balance(&self, address: &str) -> Result<WalletBalance, WalletError> {
+        get_balance(address).await
+    }
+}
@@ -2,85 +2,4004 @@
 //!
 //! Command-line tool to check cryptocurrency wallet balances
 
-use clap::Parser;
+use clap::{ArgAction, ArgGroup, Parser, Subcommand};
+use std::io::Write;
 use std::process;
-use wallet_balance::{bitcoin_wallet, ethereum_wallet, base_wallet, arbitrum_wallet, polygon_wallet,
-    tron_wallet, Network};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use anyhow::Context;
+use tracing_subscriber::fmt::{format, FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+use wallet_balance::address::{self, AddressFormat};
+use wallet_balance::api_keys::Tenants;
+use wallet_balance::subscriptions::Subscriptions;
+use wallet_balance::chain_stats::MempoolStats;
+use wallet_balance::payment_uri::{self, PaymentRequest};
+#[cfg(feature = "otel")]
+use tracing_subscriber::{reload, Registry};
+#[cfg(feature = "otel")]
+use wallet_balance::telemetry::{Telemetry, TelemetryLayer};
+use wallet_balance::i18n::{self, Lang, Msg};
+use wallet_balance::{address_book, address_flags, assertions, attest, audit, bitcoin_hd, bitcoin_wallet, cache, chain_stats, config, deposit, dev, drift, endpoints, erc1155, evm_wallet, exchanges, fees, history, http, jsonl, ordinals, portfolio, provider, pseudonym, runes, server, sink, state, tokens, pending, pricing, sanitize, schema, snapshot, subscriptions, units, unstoppable, usage, verify, watch, BalanceSource, BlockTag, LookupTimedOut, Network, NotConfigured, WalletBalance, WalletError};
+
+/// Render a catalog message in the `--lang` language
+macro_rules! t {
+    ($key:ident $(, $arg:expr)* $(,)?) => {
+        i18n::tr(Msg::$key, &[$(&$arg),*])
+    };
+}
+
+/// `std::println!` through `Stdout`, so `--output-to` and `--pseudonymize` see everything printed
+macro_rules! println {
+    ($($arg:tt)*) => {
+        writeln!(Stdout, $($arg)*).expect("failed printing to stdout")
+    };
+}
 
 #[derive(Parser)]
 #[command(name = "wallet-balance")]
 #[command(author = "Venkata Edara")]
 #[command(version = "0.1.0")]
 #[command(about = "Check cryptocurrency wallet balances", long_about = None)]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
+#[command(after_help = EXIT_CODES_HELP)]
 struct Cli {
-    /// Network to check (bitcoin, ethereum)
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Network to check (bitcoin, ethereum); optional when ADDRESS is a payment URI or default_network is configured
     #[arg(short, long, value_name = "NETWORK")]
-    network: String,
+    network: Option<String>,
+
+    /// Query the test network of --network instead (bitcoin-testnet4, sepolia, polygon-amoy, tron-shasta, ...)
+    #[arg(long, requires = "network")]
+    testnet: bool,
 
-    /// Wallet address to check
+    /// JSON-RPC endpoint of the chain queried with `--network custom-evm`
+    #[arg(long, value_name = "URL")]
+    rpc_url: Option<String>,
+
+    /// Native coin symbol of the `--network custom-evm` chain
+    #[arg(long, value_name = "SYMBOL", default_value = "ETH", requires = "rpc_url")]
+    symbol: String,
+
+    /// Wallet address to check, or a BIP-21/EIP-681 payment URI; repeat or comma-separate to check several
     #[arg(short, long, value_name = "ADDRESS")]
-    address: String,
+    address: Vec<String>,
+
+    /// Serve newline-delimited JSON queries from stdin until EOF
+    #[arg(long, conflicts_with_all = ["network", "address"])]
+    jsonl: bool,
+
+    /// Check that the balance covers AMOUNT; exits with code 10 if it does not
+    #[arg(long, value_name = "AMOUNT")]
+    needs: Option<String>,
+
+    /// Add an estimated transfer fee to the --needs amount
+    #[arg(long, requires = "needs", conflicts_with = "dev")]
+    with_fees: bool,
+
+    /// Project the balance after pending mempool transactions (EVM networks)
+    #[arg(long, conflicts_with = "dev")]
+    simulate_pending: bool,
+
+    /// Read EVM balances N blocks below the tip, so shallow reorgs cannot change them
+    #[arg(long, value_name = "N", default_value_t = 0, conflicts_with = "simulate_pending")]
+    confirmations: u64,
+
+    /// Cross-check the balance against an independent provider at the same block
+    #[arg(long, conflicts_with = "dev")]
+    verify: bool,
+
+    /// Token contract to read the balance from (EVM networks): ERC-20, or ERC-1155 with --token-id
+    #[arg(
+        long,
+        value_name = "CONTRACT",
+        conflicts_with_all = ["needs", "verify", "simulate_pending", "confirmations"]
+    )]
+    token_contract: Option<String>,
+
+    /// Known ERC-20 token to read the balance of, by symbol (e.g. USDC); see --list-tokens
+    #[arg(
+        long,
+        value_name = "SYMBOL",
+        conflicts_with_all = ["token_contract", "needs", "verify", "simulate_pending", "confirmations"]
+    )]
+    token: Option<String>,
+
+    /// Print the JSON Schema of the --output json documents and exit
+    #[arg(long, conflicts_with_all = ["address", "jsonl", "list_tokens", "watch"])]
+    emit_schema: bool,
+
+    /// List the tokens --token knows, for --network or every network
+    #[arg(long, conflicts_with_all = ["address", "jsonl"])]
+    list_tokens: bool,
+
+    /// Also read the token's totalSupply() and show the balance as a share of it (ERC-20)
+    #[arg(long, conflicts_with = "token_id")]
+    supply_share: bool,
+
+    /// Also read the token balance at block N and show the change since (ERC-20)
+    #[arg(long, value_name = "N", conflicts_with_all = ["supply_share", "token_id"])]
+    since_block: Option<u64>,
+
+    /// ERC-1155 token ids to read in one balanceOfBatch call, comma-separated (decimal or 0x hex)
+    #[arg(long, value_name = "ID", value_delimiter = ',', requires = "token_contract")]
+    token_id: Vec<String>,
+
+    /// Also show each balance's value in a fiat currency (usd, eur), priced by CoinGecko
+    #[arg(long, value_name = "CURRENCY", conflicts_with_all = ["token_contract", "token", "list_tokens"])]
+    convert: Option<pricing::FiatCurrency>,
+
+    /// List the unspent outputs (txid, vout, value, confirmations) instead of the balance (Bitcoin networks)
+    #[arg(
+        long,
+        conflicts_with_all = ["jsonl", "watch", "needs", "verify", "simulate_pending", "token_contract", "token", "convert", "confirmations"]
+    )]
+    utxos: bool,
+
+    /// Also show the net change from unconfirmed mempool transactions (Bitcoin networks)
+    #[arg(
+        long,
+        conflicts_with_all = ["jsonl", "watch", "utxos", "simulate_pending", "token_contract", "token", "confirmations"]
+    )]
+    include_pending: bool,
+
+    /// Read the balance as of a block: a number, or latest, safe, finalized, earliest or pending (EVM networks)
+    #[arg(
+        long,
+        value_name = "BLOCK",
+        conflicts_with_all = ["jsonl", "watch", "needs", "verify", "simulate_pending", "token_contract", "token", "convert", "confirmations", "utxos", "include_pending"]
+    )]
+    block: Option<BlockTag>,
+
+    /// Read the confirmed balance as of block height N (Bitcoin networks)
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = ["jsonl", "watch", "needs", "verify", "simulate_pending", "token_contract", "token", "convert", "confirmations", "utxos", "include_pending", "block"]
+    )]
+    at_height: Option<u64>,
+
+    /// Keep running and re-fetch the balance every --interval, printing each change
+    #[arg(
+        long,
+        conflicts_with_all = ["jsonl", "list_tokens", "needs", "verify", "simulate_pending", "token_contract", "token"]
+    )]
+    watch: bool,
+
+    /// Cap the lookups' total runtime (e.g. 30s, 500ms): unfinished ones are reported as timed out, exit code 12
+    #[arg(long, value_name = "DURATION", value_parser = http::parse_duration, conflicts_with_all = ["jsonl", "watch"])]
+    sla: Option<Duration>,
+
+    /// Time between --watch polls, e.g. 30s, 5m or 1h
+    #[arg(long, value_name = "DURATION", default_value = watch::DEFAULT_INTERVAL, value_parser = watch::parse_interval, requires = "watch")]
+    interval: Duration,
+
+    /// With --watch, print a poll only when the balance differs from the previous one
+    #[arg(long, requires = "watch")]
+    changes_only: bool,
+
+    /// With --watch, stop after this long (e.g. 90m) and print the session summary, as Ctrl-C does
+    #[arg(long, value_name = "DURATION", value_parser = watch::parse_interval, requires = "watch")]
+    duration: Option<Duration>,
+
+    /// Rounding profile for the printed balance: full, exchange (8 dp, 6 for stablecoins/TRX) or display (4 dp)
+    #[arg(long, value_name = "PROFILE", default_value = "full")]
+    precision: units::Precision,
+
+    /// Balance source: `live` network APIs, or deterministic offline `mock` data
+    #[arg(long, value_name = "PROVIDER", default_value = "live", global = true)]
+    provider: BalanceSource,
+
+    /// Query a local node (regtest Esplora, anvil/hardhat, java-tron) with relaxed checks
+    #[arg(long, global = true, conflicts_with = "provider")]
+    dev: bool,
+
+    /// Local node URL for --dev (default: 127.0.0.1:3002 Bitcoin, :8545 EVM, :8090 Tron)
+    #[arg(long, value_name = "URL", global = true, requires = "dev")]
+    node_url: Option<String>,
+
+    /// Language for messages, labels and table headers (en, es)
+    #[arg(long, value_name = "LANG", default_value = "en", global = true)]
+    lang: Lang,
+
+    /// Plain `key: value` output without emoji, rules or column alignment
+    #[arg(long, global = true)]
+    plain: bool,
+
+    /// Output format: decorated `text`, `json` documents on stdout (errors included), or `csv` rows
+    #[arg(long, value_name = "FORMAT", default_value = "text", global = true)]
+    output: OutputFormat,
+
+    /// Deliver the output to a file, webhook URL (POST), s3:// or gs:// object instead of stdout
+    #[arg(long, value_name = "TARGET", global = true)]
+    output_to: Option<sink::Sink>,
+
+    /// Replace every address in the output with a pseudonym salted with SALT (stable across runs)
+    #[arg(long, value_name = "SALT", global = true)]
+    pseudonymize: Option<String>,
+
+    /// Config file to use instead of the one in the standard location
+    #[arg(long, value_name = "PATH", global = true)]
+    config: Option<std::path::PathBuf>,
+
+    /// Cache live balances in none, memory, disk, redis://HOST or memcached://HOST, instead of `[cache]`'s
+    #[arg(long, value_name = "BACKEND", global = true)]
+    cache: Option<cache::CacheSpec>,
+
+    /// How long a cached balance is used (e.g. 30s, 5m), instead of `[cache]`'s ttl (default 30s)
+    #[arg(long, value_name = "DURATION", value_parser = watch::parse_interval, global = true)]
+    cache_ttl: Option<Duration>,
+
+    /// Give up on a request attempt after this long (e.g. 500ms, 30s); 0 waits indefinitely
+    #[arg(long, value_name = "DURATION", default_value = "10s", value_parser = http::parse_duration, global = true)]
+    timeout: Duration,
+
+    /// Retries of a request that failed to connect, timed out or was throttled (429, 502-504); 0 disables
+    #[arg(long, value_name = "N", default_value_t = http::RetryPolicy::DEFAULT.retries, global = true)]
+    retries: u32,
+
+    /// Delay before the first retry, doubled for each one after it (e.g. 500ms, 2s)
+    #[arg(long, value_name = "DURATION", default_value = "1s", value_parser = http::parse_duration, global = true)]
+    retry_delay: Duration,
+
+    /// Retry after exactly the backoff delay, without random jitter
+    #[arg(long, global = true)]
+    no_retry_jitter: bool,
+
+    /// Inject faults into HTTP requests at these odds, e.g. 429=0.2,timeout=0.05,malformed=0.05
+    #[cfg(feature = "chaos")]
+    #[arg(long, value_name = "SPEC", global = true, hide = true)]
+    chaos: Option<wallet_balance::chaos::Chaos>,
+
+    /// Log request URLs, retries and timings and print a per-provider request report when finished;
+    /// -vv adds the span of each lookup with its duration
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Print only the balance, without progress lines, warnings or notices (errors still go to stderr)
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
+impl Cli {
+    /// Balance source selected by `--provider`, or the local node with `--dev`
+    fn source(&self) -> BalanceSource {
+        if self.dev {
+            BalanceSource::Dev {
+                node_url: self.node_url.clone(),
+            }
+        } else {
+            self.provider.clone()
+        }
+    }
+}
+
+/// Output format selected with `--output`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+        })
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(anyhow::anyhow!("Unsupported output format: {} (expected text, json or csv)", s)),
+        }
+    }
+}
+
+/// Exit code when the address is malformed or missing (clap also exits 2 on bad arguments)
+const EXIT_INVALID_ADDRESS: i32 = 2;
+
+/// Exit code when the network is unknown or does not offer what was asked of it
+const EXIT_UNSUPPORTED_NETWORK: i32 = 3;
+
+/// Exit code when the provider could not be reached or answered with an error
+const EXIT_NETWORK_FAILURE: i32 = 4;
+
+/// Exit code when the provider still throttled the lookup after every retry
+const EXIT_RATE_LIMITED: i32 = 5;
+
+/// Exit code when `--needs` is not covered by the balance
+///
+/// Kept clear of the low codes so error classes can claim those.
+const EXIT_INSUFFICIENT_FUNDS: i32 = 10;
+
+/// Exit code when `--verify` finds the providers disagree
+const EXIT_VERIFY_MISMATCH: i32 = 11;
+
+/// Exit code when `--sla` ran out before every lookup finished
+const EXIT_SLA_EXCEEDED: i32 = 12;
+
+/// Exit code when a check of `audit` fails
+const EXIT_AUDIT_FAILED: i32 = 13;
+
+/// Exit code when an assertion of `check --assertions` does not hold
+const EXIT_ASSERTION_FAILED: i32 = 14;
+
+/// Exit code table shown at the end of `--help`
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+   0  Success
+   1  Any other failure
+   2  Invalid or missing address, or invalid arguments
+   3  Unsupported network, or an option the network does not offer
+   4  Network or API failure (provider unreachable or timed out, JSON-RPC error)
+   5  Rate limited by the provider after every retry
+  10  --needs is not covered by the balance
+  11  --verify found the providers disagree
+  12  --sla ran out before every lookup finished
+  13  An audit check failed
+  14  An assertion of check --assertions does not hold";
+
+/// Exit code for a failed lookup, by the class of the error
+fn exit_code(e: &WalletError) -> i32 {
+    match e {
+        WalletError::InvalidAddress(_) => EXIT_INVALID_ADDRESS,
+        WalletError::UnsupportedNetwork(_) => EXIT_UNSUPPORTED_NETWORK,
+        WalletError::NetworkUnreachable(_) | WalletError::RpcError { .. } => EXIT_NETWORK_FAILURE,
+        WalletError::RateLimited { .. } => EXIT_RATE_LIMITED,
+        WalletError::TimedOut(_) => EXIT_SLA_EXCEEDED,
+        _ => 1,
+    }
+}
+
+/// Exit code for a run where several lookups failed: their shared class, or 1 when they differ
+fn batch_exit_code<'a>(errors: impl IntoIterator<Item = &'a WalletError>) -> i32 {
+    let mut codes = errors.into_iter().map(exit_code);
+    let first = codes.next().unwrap_or(1);
+    match codes.all(|code| code == first) {
+        true => first,
+        false => 1,
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Preview addresses derived from an xpub/ypub/zpub or descriptor (no balance fetch)
+    Derive {
+        /// Extended public key or descriptor, e.g. `wpkh(xpub.../0/*)`
+        #[arg(value_name = "KEY")]
+        key: String,
+
+        /// Path template for plain keys; descriptors use their own path
+        #[arg(short, long, default_value = bitcoin_hd::DEFAULT_PATH)]
+        path: String,
+
+        /// Index range substituted for `*` (end exclusive)
+        #[arg(short, long, default_value = "0..10", value_parser = parse_range)]
+        range: std::ops::Range<u32>,
+    },
+
+    /// Total the balance of an xpub/ypub/zpub or descriptor over its used receive and change addresses
+    Xpub {
+        /// Extended public key or descriptor, e.g. `zpub...` or `wpkh(xpub.../0/*)`
+        #[arg(value_name = "KEY")]
+        key: String,
+
+        /// Unused addresses in a row after which a chain is considered exhausted
+        #[arg(short, long, default_value_t = bitcoin_hd::DEFAULT_GAP_LIMIT)]
+        gap_limit: u32,
+
+        /// Bitcoin network of the key
+        #[arg(short, long, default_value = "bitcoin")]
+        network: Network,
+    },
+
+    /// Convert an address between equivalent encodings
+    ConvertAddress {
+        /// Address to convert
+        #[arg(value_name = "ADDRESS")]
+        address: String,
+
+        /// Target format (tron-base58, tron-hex, cashaddr, bch-legacy,
+        /// xrp-classic, x-address, eip55, lowercase)
+        #[arg(short, long, value_name = "FORMAT")]
+        to: AddressFormat,
+
+        /// Destination tag to embed when converting to an X-address
+        #[arg(long)]
+        tag: Option<u32>,
+    },
+
+    /// Fetch balances for a wallet list and print a merkle attestation with inclusion proofs
+    Attest {
+        /// File with one `<network> <address>` pair per line
+        #[arg(value_name = "FILE")]
+        file: std::path::PathBuf,
+    },
+
+    /// Print every holder's ERC-20 balances at a block as CSV (for airdrop audits)
+    TokenSnapshot {
+        /// File with one holder address per line
+        #[arg(value_name = "FILE")]
+        file: std::path::PathBuf,
+
+        /// Token contract address; repeat or comma-separate to snapshot several tokens
+        #[arg(long, value_name = "CONTRACT", required = true, value_delimiter = ',')]
+        token: Vec<String>,
+
+        /// Block number to read the balances at
+        #[arg(long, value_name = "NUMBER")]
+        block: u64,
+
+        /// EVM network of the token
+        #[arg(short, long, value_name = "NETWORK", default_value = "ethereum")]
+        network: Network,
+
+        /// Archive node to query instead of the network's public endpoint
+        #[arg(long, value_name = "URL")]
+        rpc_url: Option<String>,
+
+        /// Balance reads packed into each Multicall3 request
+        #[arg(long, value_name = "N", default_value_t = snapshot::DEFAULT_BATCH_SIZE)]
+        batch_size: usize,
+
+        /// One `eth_call` per read instead of Multicall3 (for blocks before its deployment)
+        #[arg(long, conflicts_with = "batch_size")]
+        no_multicall: bool,
+    },
+
+    /// Check every wallet of a portfolio file and print a labeled report with totals
+    Portfolio {
+        /// TOML (or `.json`) file listing `{network, address, label}` wallets
+        #[arg(value_name = "FILE")]
+        file: std::path::PathBuf,
+
+        /// Also value each balance and the totals in a fiat currency (usd, eur)
+        #[arg(long, value_name = "CURRENCY")]
+        convert: Option<pricing::FiatCurrency>,
+
+        /// JSON-RPC endpoint of the file's `custom-evm` wallets, which are skipped without one
+        #[arg(long, value_name = "URL")]
+        rpc_url: Option<String>,
+
+        /// Native coin symbol of the `custom-evm` chain
+        #[arg(long, value_name = "SYMBOL", default_value = "ETH", requires = "rpc_url")]
+        symbol: String,
+    },
+
+    /// Compare a portfolio's holdings with target allocations and list the trades that rebalance it
+    Drift {
+        /// Portfolio file whose wallets are checked, as for `portfolio`
+        #[arg(value_name = "FILE")]
+        file: std::path::PathBuf,
+
+        /// TOML file of `[[targets]]`, each a network with an `amount` or a `share` of the value
+        #[arg(long, value_name = "FILE")]
+        targets: std::path::PathBuf,
+
+        /// Value the assets in a fiat currency (usd, eur); needed for `share` targets
+        #[arg(long, value_name = "CURRENCY")]
+        convert: Option<pricing::FiatCurrency>,
+    },
+
+    /// Check an inclusion proof from `attest` against a published root
+    VerifyProof {
+        /// JSON file holding one entry of the attestation's `proofs` list
+        #[arg(value_name = "FILE")]
+        file: std::path::PathBuf,
+
+        /// Published merkle root (hex)
+        #[arg(long, value_name = "HEX")]
+        root: String,
+    },
+
+    /// Manage the configuration file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Check the RPC and explorer endpoints the networks are read from
+    Endpoints {
+        #[command(subcommand)]
+        action: EndpointsAction,
+    },
+
+    /// Move the local state (config, data directory, portfolios) between machines as one encrypted file
+    State {
+        #[command(subcommand)]
+        action: StateAction,
+    },
+
+    /// Show inscriptions, BRC-20 balances and inscribed UTXOs of a Bitcoin address
+    Ordinals {
+        /// Bitcoin mainnet address
+        #[arg(value_name = "ADDRESS")]
+        address: String,
+
+        /// Flag uninscribed UTXOs of at most SATS as dust and leave them out of the spendable total
+        #[arg(long, value_name = "SATS")]
+        ignore_dust: Option<u64>,
+    },
+
+    /// Run the cold storage checklist on an address and print a pass/fail report
+    Audit {
+        /// Address to audit
+        #[arg(value_name = "ADDRESS")]
+        address: String,
+
+        /// Network of the address
+        #[arg(short, long, default_value = "bitcoin")]
+        network: Network,
+    },
+
+    /// Check the balance assertions of a file and print a consolidated pass/fail report
+    Check {
+        /// TOML file listing `[[assertions]]` with `network`, `address` and `min`, `max` or `max_staleness`
+        #[arg(long, value_name = "FILE")]
+        assertions: std::path::PathBuf,
+    },
+
+    /// Show whether an ETH deposit from Ethereum to Arbitrum, Optimism or Base has been credited on L2
+    DepositStatus {
+        /// Hash of the L1 deposit transaction
+        #[arg(value_name = "TX_HASH")]
+        tx: String,
+    },
+
+    /// List an address's recent transactions with the change each made to it, newest first
+    History {
+        /// Address to list the transactions of
+        #[arg(short, long, value_name = "ADDRESS")]
+        address: String,
+
+        /// Network of the address
+        #[arg(short, long)]
+        network: Network,
+
+        /// Transactions per page (Bitcoin lists unconfirmed ones on top of these)
+        #[arg(long, value_name = "N", default_value_t = history::DEFAULT_LIMIT)]
+        limit: usize,
+
+        /// Continue after a previous page, with the cursor it printed
+        #[arg(long, value_name = "CURSOR")]
+        before: Option<String>,
+    },
+
+    /// List the tokens an address holds that its plain balance misses (runes on bitcoin)
+    Tokens {
+        /// Address to list the tokens of
+        #[arg(value_name = "ADDRESS")]
+        address: String,
+
+        /// Network of the address
+        #[arg(short, long, default_value = "bitcoin")]
+        network: Network,
+    },
+
+    /// Show the local usage statistics (never sent anywhere), or a network's chain summary with -n
+    Stats {
+        /// Delete the recorded statistics
+        #[arg(long)]
+        reset: bool,
+
+        /// Show this network's head height, fee rates and mempool size instead
+        #[arg(short, long, value_name = "NETWORK", conflicts_with = "reset")]
+        network: Option<Network>,
+    },
+
+    /// Serve balance queries until SIGTERM or Ctrl-C
+    #[command(group(ArgGroup::new("transport").required(true).args(["uds", "listen"])))]
+    Serve {
+        /// Unix domain socket path for the `--jsonl` protocol
+        #[arg(long, value_name = "PATH")]
+        uds: Option<std::path::PathBuf>,
+
+        /// TCP address for the HTTP server (`/balance`, `/healthz`, `/readyz`)
+        #[arg(long, value_name = "ADDR")]
+        listen: Option<std::net::SocketAddr>,
+
+        /// Seconds each provider probe behind `/readyz` may take
+        #[arg(long, value_name = "SECS", default_value_t = server::DEFAULT_READY_TIMEOUT.as_secs())]
+        ready_timeout: u64,
+
+        /// Also answer GraphQL queries on `/graphql`
+        #[arg(long, requires = "listen")]
+        graphql: bool,
+
+        /// Time between balance lookups for `/subscriptions` webhooks, e.g. 30s, 5m or 1h
+        #[arg(long, value_name = "DURATION", default_value = watch::DEFAULT_INTERVAL, value_parser = watch::parse_interval, requires = "listen")]
+        poll_interval: Duration,
+
+        /// Export request spans to this OpenTelemetry collector (OTLP/HTTP), instead of `[telemetry]`'s
+        #[arg(long, value_name = "URL")]
+        otlp_endpoint: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Create the default config (migrating legacy paths) and print where files live
+    Init {
+        /// Overwrite an existing config file with the defaults
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum EndpointsAction {
+    /// Probe every configured endpoint, fallbacks included, and report its latency and availability
+    Check {
+        /// Only this network's endpoints
+        #[arg(short, long, value_name = "NETWORK")]
+        network: Option<Network>,
+    },
+}
+
+#[derive(Subcommand)]
+enum StateAction {
+    /// Bundle the config file, the data directory and portfolio files into an encrypted archive
+    Export {
+        /// Archive to write
+        #[arg(value_name = "FILE")]
+        file: std::path::PathBuf,
+
+        /// Also bundle this portfolio file (repeatable)
+        #[arg(long, value_name = "FILE")]
+        portfolio: Vec<std::path::PathBuf>,
+
+        /// Read the passphrase from this file instead of WALLET_BALANCE_STATE_PASSPHRASE
+        #[arg(long, value_name = "FILE")]
+        passphrase_file: Option<std::path::PathBuf>,
+    },
+
+    /// Restore an archive made by `state export` onto this machine
+    Import {
+        /// Archive to read
+        #[arg(value_name = "FILE")]
+        file: std::path::PathBuf,
+
+        /// Directory the bundled portfolio files are written to
+        #[arg(long, value_name = "DIR", default_value = ".")]
+        portfolio_dir: std::path::PathBuf,
+
+        /// Replace files that already exist instead of refusing to import
+        #[arg(long)]
+        force: bool,
+
+        /// Read the passphrase from this file instead of WALLET_BALANCE_STATE_PASSPHRASE
+        #[arg(long, value_name = "FILE")]
+        passphrase_file: Option<std::path::PathBuf>,
+    },
+}
+
+/// Verbosity from `-v`, read by `exit` when printing the session report
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+/// Set by `--quiet`, read by the output helpers
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--plain`, read by the output helpers
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--output` (0 text, 1 json, 2 csv), read by the output helpers
+static OUTPUT: AtomicU8 = AtomicU8::new(0);
+
+/// Set by `--convert`: CSV rows carry fiat_value and fiat_currency columns
+static FIAT_COLUMNS: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--supply-share`: CSV rows carry total_supply and supply_share columns
+static SUPPLY_COLUMNS: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--since-block`: CSV rows carry since_block, since_balance and balance_change columns
+static SINCE_COLUMNS: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--include-pending`: CSV rows carry an unconfirmed_balance column
+static PENDING_COLUMNS: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--output-to` (or `output_to`): the destination and everything printed so far, delivered by `exit`
+static CAPTURE: Mutex<Option<(sink::Sink, Vec<u8>)>> = Mutex::new(None);
+
+/// Set by `--pseudonymize`: the salt, and output held back until its line is complete
+static PSEUDONYMIZE: Mutex<Option<(String, Vec<u8>)>> = Mutex::new(None);
+
+/// stdout, or the `CAPTURE` buffer while a destination is set
+///
+/// With `--pseudonymize`, output is passed on a line at a time so that an
+/// address printed in pieces is still recognized whole.
+struct Stdout;
+
+impl Stdout {
+    fn emit(bytes: &[u8]) -> std::io::Result<()> {
+        match capture().as_mut() {
+            Some((_, buffer)) => {
+                buffer.extend_from_slice(bytes);
+                Ok(())
+            }
+            None => std::io::stdout().write_all(bytes),
+        }
+    }
+}
+
+impl Write for Stdout {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut pseudonymize = PSEUDONYMIZE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let Some((salt, pending)) = pseudonymize.as_mut() else {
+            drop(pseudonymize);
+            Stdout::emit(buf)?;
+            return Ok(buf.len());
+        };
+        pending.extend_from_slice(buf);
+        if let Some(end) = pending.iter().rposition(|&byte| byte == b'\n') {
+            let lines: Vec<u8> = pending.drain(..=end).collect();
+            Stdout::emit(pseudonym::pseudonymize_text(salt, &String::from_utf8_lossy(&lines)).as_bytes())?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if let Some((salt, pending)) = PSEUDONYMIZE.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_mut() {
+            let rest = std::mem::take(pending);
+            Stdout::emit(pseudonym::pseudonymize_text(salt, &String::from_utf8_lossy(&rest)).as_bytes())?;
+        }
+        match capture().is_some() {
+            true => Ok(()),
+            false => std::io::stdout().flush(),
+        }
+    }
+}
+
+fn capture() -> std::sync::MutexGuard<'static, Option<(sink::Sink, Vec<u8>)>> {
+    CAPTURE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
 }
 
+/// Command and networks of this run, added to the local usage file by `exit`
+static USAGE: Mutex<Option<(&'static str, Vec<Network>)>> = Mutex::new(None);
+
 #[tokio::main]
 async fn main() {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    cli.address = split_addresses(&cli.address);
+    VERBOSITY.store(cli.verbose, Ordering::Relaxed);
+    QUIET.store(cli.quiet, Ordering::Relaxed);
+    init_logging(cli.verbose, cli.quiet);
+    i18n::set_language(cli.lang);
+    PLAIN.store(cli.plain, Ordering::Relaxed);
+    OUTPUT.store(cli.output as u8, Ordering::Relaxed);
+    http::set_timeout(cli.timeout);
+    http::set_retry_policy(http::RetryPolicy {
+        retries: cli.retries,
+        base_delay: cli.retry_delay,
+        jitter: !cli.no_retry_jitter,
+    });
+    #[cfg(feature = "chaos")]
+    wallet_balance::chaos::set(cli.chaos);
+    let converting = match &cli.command {
+        Some(Command::Portfolio { convert, .. }) => convert.is_some(),
+        _ => cli.convert.is_some(),
+    };
+    FIAT_COLUMNS.store(converting, Ordering::Relaxed);
+    SUPPLY_COLUMNS.store(cli.supply_share, Ordering::Relaxed);
+    SINCE_COLUMNS.store(cli.since_block.is_some(), Ordering::Relaxed);
+    PENDING_COLUMNS.store(cli.include_pending, Ordering::Relaxed);
 
-    // Parse network
-    let network: Network = match cli.network.parse() {
-        Ok(n) => n,
+    // `config init` and `state` must keep working when the existing file does not parse
+    let local_files = matches!(cli.command, Some(Command::Config { .. } | Command::State { .. }));
+    if !local_files {
+        if let Err(e) = config::load_active(cli.config.as_deref()) {
+            print_error(format!("{:#}", e));
+            exit(exit_code(&e));
+        }
+    }
+
+    // Streaming modes always write to stdout; a profile's output_to only applies to the others
+    let streaming = cli.watch || cli.jsonl || matches!(cli.command, Some(Command::Serve { .. }));
+    if streaming && cli.output_to.is_some() {
+        print_error(t!(OutputToStreaming));
+        exit(1);
+    }
+    if let Some(salt) = cli.pseudonymize.take() {
+        if cli.jsonl || matches!(cli.command, Some(Command::Serve { .. })) {
+            print_error(t!(PseudonymizeProtocol));
+            exit(1);
+        }
+        if let Err(e) = pseudonym::validate_salt(&salt) {
+            print_error(format!("{:#}", e));
+            exit(exit_code(&e));
+        }
+        *PSEUDONYMIZE.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some((salt, Vec::new()));
+    }
+    if !local_files {
+        install_cache(&cli);
+    }
+    if !streaming && !local_files {
+        // `Config::from_toml` has already rejected an unparseable output_to
+        let target = cli.output_to.take().or_else(|| config::active().output_to().ok().flatten());
+        *capture() = target.map(|sink| (sink, Vec::new()));
+    }
+
+    let custom_evm = cli.command.is_none() && requested_network(&cli) == Some(Network::CustomEvm);
+    match &cli.rpc_url {
+        Some(rpc_url) if custom_evm => {
+            if let Err(e) = evm_wallet::set_custom_chain(rpc_url, &cli.symbol) {
+                print_error(format!("{:#}", e));
+                exit(exit_code(&e));
+            }
+        }
+        Some(_) => {
+            print_error(t!(RpcUrlNeedsCustomEvm));
+            exit(1);
+        }
+        None if custom_evm => {
+            print_error(t!(CustomEvmNeedsRpcUrl));
+            exit(1);
+        }
+        None => {}
+    }
+
+    if cli.command.is_none() && !cli.jsonl && !cli.list_tokens && !cli.emit_schema && cli.address.is_empty() {
+        match config::active().default_address() {
+            Some(default) => cli.address = split_addresses(&[default.to_string()]),
+            None => {
+                print_error(t!(AddressRequired));
+                exit(EXIT_INVALID_ADDRESS);
+            }
+        }
+    }
+
+    let command = match &cli.command {
+        Some(Command::Derive { .. }) => "derive",
+        Some(Command::Xpub { .. }) => "xpub",
+        Some(Command::ConvertAddress { .. }) => "convert-address",
+        Some(Command::Attest { .. }) => "attest",
+        Some(Command::TokenSnapshot { .. }) => "token-snapshot",
+        Some(Command::Portfolio { .. }) => "portfolio",
+        Some(Command::Drift { .. }) => "drift",
+        Some(Command::VerifyProof { .. }) => "verify-proof",
+        Some(Command::Config { .. }) => "config",
+        Some(Command::Endpoints { .. }) => "endpoints",
+        Some(Command::Ordinals { .. }) => "ordinals",
+        Some(Command::Tokens { .. }) => "tokens",
+        Some(Command::Audit { .. }) => "audit",
+        Some(Command::Check { .. }) => "check",
+        Some(Command::DepositStatus { .. }) => "deposit-status",
+        Some(Command::History { .. }) => "history",
+        Some(Command::Serve { .. }) => "serve",
+        Some(Command::Stats { network: Some(_), .. }) => "chain-stats",
+        // Looking at the statistics is not usage worth recording
+        Some(Command::Stats { .. }) => "",
+        // Nor is moving the state, which would write usage.json over the one being imported
+        Some(Command::State { .. }) => "",
+        None if cli.jsonl => "jsonl",
+        None if cli.list_tokens => "list-tokens",
+        None if cli.emit_schema => "emit-schema",
+        None if cli.address.len() > 1 => "batch",
+        None => "balance",
+    };
+    if !command.is_empty() {
+        *usage_session() = Some((command, Vec::new()));
+    }
+
+    let source = cli.source();
+    match cli.command {
+        Some(Command::Derive { key, path, range }) => run_derive(&key, &path, range),
+        Some(Command::Xpub { key, gap_limit, network }) => run_xpub(&key, gap_limit, network, source).await,
+        Some(Command::ConvertAddress { address, to, tag }) => run_convert(&address, to, tag),
+        Some(Command::Attest { file }) => run_attest(&file, source).await,
+        Some(Command::TokenSnapshot {
+            file,
+            token,
+            block,
+            network,
+            rpc_url,
+            batch_size,
+            no_multicall,
+        }) => {
+            let fetch = match no_multicall {
+                true => snapshot::Fetch::Direct,
+                false => snapshot::Fetch::Multicall { batch_size },
+            };
+            run_token_snapshot(&file, &token, block, network, rpc_url.as_deref(), fetch).await
+        }
+        Some(Command::Portfolio {
+            file,
+            convert,
+            rpc_url,
+            symbol,
+        }) => {
+            if let Some(rpc_url) = rpc_url {
+                if let Err(e) = evm_wallet::set_custom_chain(&rpc_url, &symbol) {
+                    print_error(format!("{:#}", e));
+                    exit(exit_code(&e));
+                }
+            }
+            run_portfolio(&file, source, convert, cli.precision).await
+        }
+        Some(Command::Drift { file, targets, convert }) => run_drift(&file, &targets, source, convert).await,
+        Some(Command::VerifyProof { file, root }) => run_verify_proof(&file, &root),
+        Some(Command::Config {
+            action: ConfigAction::Init { force },
+        }) => run_config_init(force),
+        Some(Command::Endpoints {
+            action: EndpointsAction::Check { network },
+        }) => run_endpoints_check(network).await,
+        Some(Command::State {
+            action:
+                StateAction::Export {
+                    file,
+                    portfolio,
+                    passphrase_file,
+                },
+        }) => run_state_export(&file, &portfolio, passphrase_file.as_deref(), cli.config.as_deref()),
+        Some(Command::State {
+            action:
+                StateAction::Import {
+                    file,
+                    portfolio_dir,
+                    force,
+                    passphrase_file,
+                },
+        }) => run_state_import(&file, &portfolio_dir, force, passphrase_file.as_deref(), cli.config.as_deref()),
+        Some(Command::Ordinals { address, ignore_dust }) => run_ordinals(&address, ignore_dust, source).await,
+        Some(Command::Tokens { address, network }) => run_tokens(&address, network, source).await,
+        Some(Command::Audit { address, network }) => run_audit(&address, network, source).await,
+        Some(Command::Check { assertions }) => run_check(&assertions, source).await,
+        Some(Command::DepositStatus { tx }) => run_deposit_status(&tx, source).await,
+        Some(Command::History {
+            address,
+            network,
+            limit,
+            before,
+        }) => run_history(&address, network, limit, before.as_deref(), source).await,
+        Some(Command::Stats { network: Some(network), .. }) => run_chain_stats(network).await,
+        Some(Command::Stats { reset, .. }) => run_stats(reset),
+        Some(Command::Serve {
+            uds,
+            listen,
+            ready_timeout,
+            graphql,
+            poll_interval,
+            otlp_endpoint,
+        }) => {
+            start_telemetry(otlp_endpoint);
+            match (uds, listen) {
+                (Some(path), _) => run_serve_uds(path, source).await,
+                (None, Some(addr)) => {
+                    run_serve_http(addr, source, Duration::from_secs(ready_timeout), graphql, poll_interval).await
+                }
+                (None, None) => unreachable!("clap requires --uds or --listen"),
+            }
+        }
+        None if cli.jsonl => run_jsonl(source).await,
+        None if cli.list_tokens => run_list_tokens(&cli),
+        None if cli.emit_schema => run_emit_schema(),
+        None => run_balance(&cli).await,
+    }
+
+    exit(0);
+}
+
+/// Exit the process, delivering captured output and printing the session report first when `-v` was given
+/// Split comma-separated address lists, leaving payment URIs whole
+///
+/// A BIP-21 or EIP-681 `label` or `message` may hold commas, so an argument
+/// with a scheme (`bitcoin:`, `ethereum:`) is one address however it reads.
+fn split_addresses(values: &[String]) -> Vec<String> {
+    values
+        .iter()
+        .flat_map(|value| match value.contains(':') {
+            true => vec![value.trim().to_string()],
+            false => value.split(',').map(|address| address.trim().to_string()).collect(),
+        })
+        .collect()
+}
+
+fn exit(code: i32) -> ! {
+    let _ = Stdout.flush();
+    let code = deliver_output(code);
+    if VERBOSITY.load(Ordering::Relaxed) > 0 {
+        print_session_report();
+    }
+    record_usage();
+    #[cfg(feature = "otel")]
+    if let Some(telemetry) = TELEMETRY.get() {
+        telemetry.shutdown();
+    }
+    process::exit(code);
+}
+
+/// Deliver the `--output-to` capture, if any; a failed delivery turns a successful exit into 1
+fn deliver_output(code: i32) -> i32 {
+    let Some((sink, body)) = capture().take() else {
+        return code;
+    };
+    // Nothing printed (say, an error reported on stderr) must not overwrite the last delivery
+    if body.is_empty() {
+        return code;
+    }
+    let content_type = match output() {
+        OutputFormat::Text => "text/plain; charset=utf-8",
+        OutputFormat::Json => "application/json",
+        OutputFormat::Csv => "text/csv",
+    };
+    // `exit` may run inside the runtime, which cannot block on a future itself
+    let target = sink.clone();
+    let delivered = std::thread::spawn(move || {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(anyhow::Error::from)
+            .and_then(|runtime| Ok(runtime.block_on(target.deliver(body, content_type))?))
+    })
+    .join()
+    .unwrap_or_else(|_| Err(anyhow::anyhow!("delivery thread panicked")));
+
+    match delivered {
+        Ok(()) => code,
         Err(e) => {
-            eprintln!("Error: {}", e);
-            eprintln!("Supported networks: bitcoin, ethereum");
-            process::exit(1);
+            print_error(t!(OutputDeliveryFailed, sink, format!("{:#}", e)));
+            if code == 0 { 1 } else { code }
         }
+    }
+}
+
+fn usage_session() -> std::sync::MutexGuard<'static, Option<(&'static str, Vec<Network>)>> {
+    USAGE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Count `network` as queried in this run's usage statistics
+fn note_network(network: Network) {
+    if let Some((_, networks)) = usage_session().as_mut() {
+        networks.push(network);
+    }
+}
+
+/// Add this run to the local usage file; failures never affect the command
+fn record_usage() {
+    let Some((command, networks)) = usage_session().take() else {
+        return;
     };
+    if let Ok(path) = usage::default_path() {
+        let _ = usage::record(&path, command, &networks, &http::session_stats());
+    }
+}
 
-    // Fetch balance based on network
-    let result = match network {
-        Network::Bitcoin => {
-            println!("Fetching Bitcoin balance for address: {}", cli.address);
-            bitcoin_wallet::get_balance(&cli.address).await
+/// Print the built-in token registry, optionally for one network
+fn run_list_tokens(cli: &Cli) {
+    let network = requested_network(cli);
+    let known: Vec<_> = tokens::REGISTRY
+        .iter()
+        .filter(|token| network.is_none_or(|network| token.network == network))
+        .collect();
+
+    match output() {
+        OutputFormat::Json => {
+            let rows: Vec<_> = known
+                .iter()
+                .map(|token| {
+                    serde_json::json!({
+                        "network": token.network.to_string(),
+                        "symbol": token.symbol,
+                        "contract": token.contract,
+                        "decimals": token.decimals,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&rows).expect("JSON values serialize"));
         }
-        Network::Ethereum => {
-            println!("Fetching Ethereum balance for address: {}", cli.address);
-            ethereum_wallet::get_balance(&cli.address).await
+        OutputFormat::Csv => {
+            println!("network,symbol,contract,decimals");
+            for token in &known {
+                println!("{},{},{},{}", token.network, token.symbol, token.contract, token.decimals);
+            }
         }
-         Network::Base => {  // NEW: Add this match arm
-            println!("Fetching Base L2 balance for address: {}", cli.address);
-            base_wallet::get_balance(&cli.address).await
+        OutputFormat::Text if plain() => {
+            for token in &known {
+                let record = format_record(&[
+                    (Msg::HeaderNetwork, token.network.to_string()),
+                    (Msg::HeaderSymbol, token.symbol.to_string()),
+                    (Msg::HeaderDecimals, token.decimals.to_string()),
+                    (Msg::HeaderContract, token.contract.to_string()),
+                ]);
+                println!("\n{}", record);
+            }
         }
-          Network::Arbitrum => {  // NEW: Add this match arm
-            println!("Fetching Arbitrum L2 balance for address: {}", cli.address);
-            arbitrum_wallet::get_balance(&cli.address).await
-          }
-        Network::Polygon => {  // NEW: Add this match arm
-                println!("Fetching Polygon balance for address: {}", cli.address);
-                polygon_wallet::get_balance(&cli.address).await
+        OutputFormat::Text => {
+            println!("{:<10} {:<8} {:>8}  {}", t!(HeaderNetwork), t!(HeaderSymbol), t!(HeaderDecimals), t!(HeaderContract));
+            for token in &known {
+                println!("{:<10} {:<8} {:>8}  {}", token.network.to_string(), token.symbol, token.decimals, token.contract);
             }
-            Network::Tron => {  // NEW: Add this match arm
-                println!("Fetching Tron balance for address: {}", cli.address);
-                tron_wallet::get_balance(&cli.address).await
+        }
+    }
+}
+
+fn run_stats(reset: bool) {
+    let path = match usage::default_path() {
+        Ok(path) => path,
+        Err(e) => {
+            print_error(t!(StatsFailed, format!("{:#}", e)));
+            exit(1);
+        }
+    };
+
+    if reset {
+        match std::fs::remove_file(&path) {
+            Ok(()) => println!("{}", marked("✅", t!(StatsCleared))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => println!("{}", t!(StatsEmpty)),
+            Err(e) => {
+                print_error(t!(StatsFailed, e));
+                exit(1);
             }
+        }
+        return;
+    }
+
+    let stats = match usage::UsageStats::load(&path) {
+        Ok(stats) => stats,
+        Err(e) => {
+            print_error(t!(StatsFailed, format!("{:#}", e)));
+            exit(1);
+        }
     };
 
-    // Display result
-    match result {
-        Ok(balance) => {
-            println!("\n✅ Success!");
-            println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-            println!("Network:  {}", balance.network.to_uppercase());
-            println!("Address:  {}", balance.address);
-            println!("Balance:  {} {}", balance.balance, balance.denomination);
-            println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    if output() == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&stats).expect("usage stats serialize"));
+        return;
+    }
+    if stats.is_empty() {
+        println!("{}", t!(StatsEmpty));
+        println!("{}", t!(StatsStoredIn, path.display()));
+        return;
+    }
+
+    println!("{}", t!(StatsTitle, usage::format_date(stats.since)));
+    println!("{}", t!(StatsStoredIn, path.display()));
+    let counters = [
+        (Msg::HeaderCommand, Msg::HeaderRuns, &stats.commands),
+        (Msg::HeaderNetwork, Msg::HeaderQueries, &stats.networks),
+    ];
+    for (name_header, count_header, counts) in counters {
+        if plain() {
+            for (name, count) in counts {
+                println!("\n{}", format_record(&[(name_header, name.clone()), (count_header, count.to_string())]));
+            }
+        } else {
+            println!("\n{:<28} {:>10}", i18n::tr(name_header, &[]), i18n::tr(count_header, &[]));
+            for (name, count) in counts {
+                println!("{:<28} {:>10}", name, count);
+            }
+        }
+    }
+
+    if plain() {
+        for (provider, entry) in &stats.providers {
+            let record = format_record(&[
+                (Msg::HeaderProvider, provider.clone()),
+                (Msg::HeaderRequests, entry.requests.to_string()),
+                (Msg::HeaderErrors, entry.errors.to_string()),
+            ]);
+            println!("\n{}", record);
         }
+    } else if !stats.providers.is_empty() {
+        println!("\n{:<28} {:>10} {:>10}", t!(HeaderProvider), t!(HeaderRequests), t!(HeaderErrors));
+        for (provider, entry) in &stats.providers {
+            println!("{:<28} {:>10} {:>10}", provider, entry.requests, entry.errors);
+        }
+    }
+}
+
+/// `stats -n`: head height, fee rates and mempool size of `network`
+async fn run_chain_stats(network: Network) {
+    note_network(network);
+    status_line(t!(FetchingChainStats, network.display_name()));
+    let stats = match chain_stats::get_chain_stats(network).await {
+        Ok(stats) => stats,
         Err(e) => {
-            eprintln!("\n❌ Error fetching balance: {}", e);
-            eprintln!("\nPlease check:");
-            eprintln!("  • Address format is correct");
-            eprintln!("  • Network is spelled correctly");
-            eprintln!("  • You have internet connectivity");
-            process::exit(1);
+            print_error(format!("{:#}", e));
+            exit(exit_code(&e));
         }
+    };
+
+    match output() {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&stats).expect("chain stats serialize")),
+        OutputFormat::Csv => {
+            println!("network,height,fee_rate,fee_unit,target_blocks,mempool_tx_count,mempool_vsize");
+            let (tx_count, vsize) = match &stats.mempool {
+                Some(mempool) => (mempool.tx_count.to_string(), mempool.vsize.map(|vsize| vsize.to_string())),
+                None => (String::new(), None),
+            };
+            for fee in &stats.fees {
+                let target = fee.target_blocks.map(|target| target.to_string()).unwrap_or_default();
+                println!(
+                    "{}",
+                    csv_line(&[
+                        &stats.network,
+                        &stats.height.to_string(),
+                        &fee.rate,
+                        &fee.unit,
+                        &target,
+                        &tx_count,
+                        vsize.as_deref().unwrap_or_default(),
+                    ])
+                );
+            }
+        }
+        OutputFormat::Text => {
+            let fees: Vec<String> = stats
+                .fees
+                .iter()
+                .map(|fee| match fee.target_blocks {
+                    Some(target) => t!(FeeForTarget, fee.rate, fee.unit, target),
+                    None => t!(GasPriceFee, fee.rate, fee.unit),
+                })
+                .collect();
+            let mempool = match &stats.mempool {
+                Some(MempoolStats { tx_count, vsize: Some(vsize) }) => t!(MempoolSize, tx_count, vsize),
+                Some(MempoolStats { tx_count, vsize: None }) => t!(MempoolCount, tx_count),
+                None => t!(MempoolUnavailable),
+            };
+            println!("\n{}", marked("✅", t!(Success)));
+            print_rule();
+            println!("{} {}", label(Msg::LabelNetwork), stats.network.to_uppercase());
+            println!("{} {}", label(Msg::LabelHeight), stats.height);
+            println!("{} {}", label(Msg::LabelFees), fees.join(", "));
+            println!("{} {}", label(Msg::LabelMempool), mempool);
+            print_rule();
+        }
+    }
+}
+
+/// Log target of the CLI's own notices and warnings, which are printed as bare lines
+const CLI_TARGET: &str = "cli";
+
+/// Send log events to stderr: the CLI's notices and warnings unless `--quiet`, and the library's
+/// request log from `-v` (`-vv` adds lookup spans and their durations, `-vvv` everything)
+fn init_logging(verbose: u8, quiet: bool) {
+    use tracing_subscriber::filter::{filter_fn, FilterExt, LevelFilter, Targets};
+    use tracing_subscriber::fmt::format::FmtSpan;
+    use tracing_subscriber::prelude::*;
+
+    let (cli, library) = match (quiet, verbose) {
+        (true, _) => (LevelFilter::ERROR, LevelFilter::ERROR),
+        (false, 0) => (LevelFilter::INFO, LevelFilter::WARN),
+        (false, 1) => (LevelFilter::INFO, LevelFilter::INFO),
+        (false, 2) => (LevelFilter::INFO, LevelFilter::DEBUG),
+        (false, _) => (LevelFilter::TRACE, LevelFilter::TRACE),
+    };
+    let spans = match verbose >= 2 {
+        true => FmtSpan::CLOSE,
+        false => FmtSpan::NONE,
+    };
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .with_span_events(spans)
+        .event_format(CliFormat {
+            library: format().compact().without_time().with_ansi(false),
+        });
+    // Only our own events: the HTTP stack's are noise at any of these levels. Spans only from -vv,
+    // so the request lines of -v stay short
+    let targets = Targets::new()
+        .with_target(CLI_TARGET, cli)
+        .with_target("wallet_balance", library)
+        .and(filter_fn(move |metadata| verbose >= 2 || !metadata.is_span()));
+    let registry = tracing_subscriber::registry();
+    // An empty slot until `start_telemetry`; exported spans do not depend on -v
+    #[cfg(feature = "otel")]
+    let registry = {
+        let (slot, handle) = reload::Layer::new(None);
+        let _ = TELEMETRY_LAYER.set(handle);
+        registry.with(slot.with_filter(Targets::new().with_target("wallet_balance", LevelFilter::INFO)))
+    };
+    let _ = registry.with(layer.with_filter(targets)).try_init();
+}
+
+/// Exporter started by `start_telemetry`, flushed by `exit`
+#[cfg(feature = "otel")]
+static TELEMETRY: std::sync::OnceLock<Telemetry> = std::sync::OnceLock::new();
+
+/// Where `start_telemetry` puts the exporter's layer in the subscriber `init_logging` installs
+#[cfg(feature = "otel")]
+static TELEMETRY_LAYER: std::sync::OnceLock<reload::Handle<Option<TelemetryLayer<Registry>>, Registry>> =
+    std::sync::OnceLock::new();
+
+/// Open the cache of `--cache`, or of `[cache]`, for live lookups to go through
+fn install_cache(cli: &Cli) {
+    let config = config::active();
+    // `Config::from_toml` has already checked the backend and ttl
+    let spec = cli.cache.clone().unwrap_or_else(|| config.cache().unwrap_or(cache::CacheSpec::None));
+    let ttl = cli.cache_ttl.unwrap_or_else(|| config.cache_ttl().unwrap_or(cache::DEFAULT_TTL));
+    match spec.open() {
+        Ok(backend) => cache::set(backend, ttl),
+        Err(e) => {
+            print_error(format!("{:#}", e));
+            exit(exit_code(&e));
+        }
+    }
+}
+
+/// Export request spans to `endpoint`, or to `[telemetry] otlp_endpoint`, for a long-running mode
+fn start_telemetry(endpoint: Option<String>) {
+    let config = config::active();
+    // `Config::from_toml` has already checked the endpoint
+    let Some(endpoint) = endpoint.or_else(|| config.otlp_endpoint().ok().flatten().map(str::to_string)) else {
+        return;
+    };
+    #[cfg(feature = "otel")]
+    {
+        let telemetry = match Telemetry::start(&endpoint, config.service_name()) {
+            Ok(telemetry) => telemetry,
+            Err(e) => {
+                print_error(format!("{:#}", e));
+                exit(exit_code(&e));
+            }
+        };
+        if let Some(handle) = TELEMETRY_LAYER.get() {
+            let _ = handle.modify(|slot| *slot = Some(telemetry.layer()));
+        }
+        let _ = TELEMETRY.set(telemetry);
+        notice(t!(ExportingSpans, endpoint));
+    }
+    #[cfg(not(feature = "otel"))]
+    warn(t!(TelemetryNeedsOtel, endpoint));
+}
+
+/// Event format printing the CLI's notices as they are and library events in the compact format
+struct CliFormat {
+    library: format::Format<format::Compact, ()>,
+}
+
+impl<S, N> FormatEvent<S, N> for CliFormat
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        context: &FmtContext<'_, S, N>,
+        mut writer: format::Writer<'_>,
+        event: &tracing::Event<'_>,
+    ) -> std::fmt::Result {
+        if event.metadata().target() != CLI_TARGET {
+            return self.library.format_event(context, writer, event);
+        }
+        let mut message = EventMessage(String::new());
+        event.record(&mut message);
+        writeln!(writer, "{}", message.0)
+    }
+}
+
+/// The `message` field of an event
+struct EventMessage(String);
+
+impl tracing::field::Visit for EventMessage {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+fn print_session_report() {
+    let stats = http::session_stats();
+    eprintln!("\n{}", t!(SessionReport));
+    if stats.is_empty() {
+        eprintln!("  {}", t!(NoRequests));
+        return;
+    }
+    if plain() {
+        for (provider, entry) in stats {
+            let record = format_record(&[
+                (Msg::HeaderProvider, provider.to_string()),
+                (Msg::HeaderRequests, entry.requests.to_string()),
+                (Msg::HeaderErrors, entry.errors.to_string()),
+                (Msg::HeaderRetries, entry.retries.to_string()),
+                (Msg::HeaderCacheHits, entry.cache_hits.to_string()),
+                (Msg::HeaderBytes, entry.bytes.to_string()),
+                (Msg::HeaderLatency, format!("{}ms", entry.total_latency.as_millis())),
+            ]);
+            eprintln!("\n{}", record);
+        }
+        return;
+    }
+    eprintln!(
+        "  {:<28} {:>8} {:>7} {:>7} {:>10} {:>10} {:>12}",
+        t!(HeaderProvider),
+        t!(HeaderRequests),
+        t!(HeaderErrors),
+        t!(HeaderRetries),
+        t!(HeaderCacheHits),
+        t!(HeaderBytes),
+        t!(HeaderLatency)
+    );
+    for (provider, entry) in stats {
+        eprintln!(
+            "  {:<28} {:>8} {:>7} {:>7} {:>10} {:>10} {:>10}ms",
+            provider,
+            entry.requests,
+            entry.errors,
+            entry.retries,
+            entry.cache_hits,
+            entry.bytes,
+            entry.total_latency.as_millis()
+        );
+    }
+}
+
+async fn run_balance(cli: &Cli) {
+    // `main` has filled in default_address when --address was left out
+    // Checked here rather than by clap: a global flag cannot conflict with top-level-only ones
+    if cli.output != OutputFormat::Text {
+        for (flag, given) in [
+            ("--needs", cli.needs.is_some()),
+            ("--verify", cli.verify),
+            ("--simulate-pending", cli.simulate_pending),
+        ] {
+            if given {
+                print_error(t!(OutputConflict, flag, cli.output));
+                exit(1);
+            }
+        }
+    }
+
+    let address = match cli.address.as_slice() {
+        [address] => address,
+        addresses => return run_batch(cli, addresses).await,
+    };
+    let source = cli.source();
+    if cli.verify && source == BalanceSource::Mock {
+        print_error(t!(VerifyNeedsLive));
+        exit(1);
+    }
+
+    let cleaned = sanitize::clean_text(address);
+    warn_stripped(&cleaned.stripped);
+
+    let requested_network = requested_network(cli);
+
+    // Local dev chains have chain ids of their own; --dev trusts -n instead
+    let parsed = match (cli.dev, requested_network) {
+        (true, Some(network)) => payment_uri::parse_with_fallback(&cleaned.address, network),
+        _ => payment_uri::parse(&cleaned.address),
+    };
+    let request = match parsed {
+        Ok(request) => request,
+        Err(e) => {
+            print_error(t!(InvalidPaymentUri, e));
+            exit(1);
+        }
+    };
+
+    // Parse network, falling back to the one implied by a payment URI
+    let network: Network = match (requested_network, &request) {
+        (Some(network), _) => network,
+        (None, Some(request)) => request.network,
+        (None, None) => default_network(),
+    };
+
+    note_network(network);
+    if cli.supply_share && cli.token.is_none() && cli.token_contract.is_none() {
+        print_error(t!(SupplyShareNeedsToken));
+        exit(1);
+    }
+    if cli.since_block.is_some() && cli.token.is_none() && cli.token_contract.is_none() {
+        print_error(t!(SinceBlockNeedsToken));
+        exit(1);
+    }
+    let token_extra = match (cli.supply_share, cli.since_block) {
+        (true, _) => TokenExtra::Supply,
+        (false, Some(block)) => TokenExtra::SinceBlock(block),
+        (false, None) => TokenExtra::Nothing,
+    };
+    if let Some(symbol) = &cli.token {
+        if request.is_some() {
+            print_error(t!(TokenIdWithUri));
+            exit(1);
+        }
+        let known = match tokens::lookup(network, symbol) {
+            Ok(known) => known,
+            Err(e) => {
+                print_error(format!("{:#}", e));
+                exit(exit_code(&e));
+            }
+        };
+        let address = sanitize::sanitize_address(&cleaned.address);
+        warn_stripped(&address.stripped);
+        warn_exchange(network, &address.address);
+        warn_lookalike(network, &address.address);
+        return run_erc20(&source, network, known.contract, &address.address, cli.precision, token_extra).await;
+    }
+    if let Some(contract) = &cli.token_contract {
+        if request.is_some() {
+            print_error(t!(TokenIdWithUri));
+            exit(1);
+        }
+        let address = sanitize::sanitize_address(&cleaned.address);
+        warn_stripped(&address.stripped);
+        warn_exchange(network, &address.address);
+        warn_lookalike(network, &address.address);
+        if cli.token_id.is_empty() {
+            return run_erc20(&source, network, contract, &address.address, cli.precision, token_extra).await;
+        }
+        return run_erc1155(&source, network, contract, &address.address, &cli.token_id).await;
+    }
+    if let Some(mut request) = request {
+        if cli.dev {
+            request.network = network;
+        }
+        if request.network != network {
+            print_error(t!(UriNetworkMismatch, request.network, network));
+            exit(1);
+        }
+        if cli.needs.is_some() {
+            print_error(t!(NeedsWithUri));
+            exit(1);
+        }
+        if cli.watch {
+            print_error(t!(WatchWithUri));
+            exit(1);
+        }
+        for (flag, given) in [("--block", cli.block.is_some()), ("--at-height", cli.at_height.is_some())] {
+            if given {
+                print_error(t!(HistoricalWithUri, flag));
+                exit(1);
+            }
+        }
+        return run_payment_request(&source, request, cli.confirmations, cli.precision, cli.convert).await;
+    }
+
+    let sanitized = sanitize::sanitize_address(&cleaned.address);
+    warn_stripped(&sanitized.stripped);
+    let resolved = resolve_name(network, sanitized.address).await;
+    let address = resolved.as_str();
+    warn_exchange(network, address);
+    warn_lookalike(network, address);
+    if cli.utxos {
+        return run_utxos(&source, network, address).await;
+    }
+    let pending_chain = match (cli.include_pending, network.bitcoin_chain()) {
+        (false, _) => None,
+        (true, None) => {
+            print_error(t!(IncludePendingUnsupported, network));
+            exit(EXIT_UNSUPPORTED_NETWORK);
+        }
+        (true, Some(_)) if source != BalanceSource::Live => {
+            print_error(t!(IncludePendingNeedsLive));
+            exit(1);
+        }
+        (true, chain) => chain,
+    };
+    if cli.block.is_some() && !network.is_evm() {
+        print_error(t!(BlockUnsupported, network));
+        exit(EXIT_UNSUPPORTED_NETWORK);
+    }
+    if cli.at_height.is_some() {
+        if network.bitcoin_chain().is_none() {
+            print_error(t!(AtHeightUnsupported, network));
+            exit(EXIT_UNSUPPORTED_NETWORK);
+        }
+        if source != BalanceSource::Live {
+            print_error(t!(AtHeightNeedsLive));
+            exit(1);
+        }
+    }
+
+    status_line(t!(Fetching, network.display_name(), address));
+    if let BalanceSource::Dev { node_url } = &source {
+        let node_url = node_url.as_deref().unwrap_or_else(|| dev::default_node_url(network));
+        notice(marked("🛠 ", t!(DevMode, node_url)));
+    }
+    if cli.watch {
+        start_telemetry(None);
+        return run_watch(&source, network, address, cli).await;
+    }
+    let lookup = async {
+        match (pending_chain, cli.block, cli.at_height) {
+            (Some(chain), _, _) => bitcoin_wallet::get_balance_with_pending_on(chain, address).await,
+            (None, Some(block), _) => source.get_balance_at_block(network, address, block).await,
+            (None, None, Some(height)) => history::get_balance_at_height(network, address, height).await,
+            (None, None, None) => source.get_balance_with_confirmations(network, address, cli.confirmations).await,
+        }
+    };
+    let fetched = match cli.sla {
+        Some(sla) => tokio::time::timeout(sla, lookup)
+            .await
+            .unwrap_or_else(|_| Err(LookupTimedOut.into())),
+        None => lookup.await,
+    };
+    let mut balance = match fetched {
+        Ok(balance) => balance,
+        Err(WalletError::TimedOut(_)) => {
+            print_error(t!(SlaExceeded, format!("{:?}", cli.sla.unwrap_or_default())));
+            exit(EXIT_SLA_EXCEEDED);
+        }
+        Err(e) => exit_with_fetch_error(e),
+    };
+    convert_to_fiat([&mut balance], cli.convert).await;
+    print_balance(&balance, network.decimals(), cli.precision);
+
+    if cli.verify && !print_verification(network, &balance.address).await {
+        exit(EXIT_VERIFY_MISMATCH);
+    }
+
+    if cli.simulate_pending {
+        print_pending_projection(&balance, network).await;
+    }
+
+    if let Some(needs) = &cli.needs {
+        if !check_needs(&balance, network, needs, cli.with_fees).await {
+            exit(EXIT_INSUFFICIENT_FUNDS);
+        }
+    }
+}
+
+/// Poll the balance every `--interval` until Ctrl-C or `--duration`, printing the first result and each poll or change
+///
+/// Failed polls are reported on stderr and retried at the next tick. The
+/// session summary is printed when watching stops.
+async fn run_watch(source: &BalanceSource, network: Network, address: &str, cli: &Cli) {
+    status_line(t!(Watching, humanize_interval(cli.interval)));
+    if output() == OutputFormat::Csv {
+        println!("{}", csv_header());
+    }
+
+    reload_on_hangup(|| {});
+    let duration = cli.duration;
+    let shutdown = async move {
+        match duration {
+            Some(duration) => tokio::select! {
+                _ = shutdown_signal() => {}
+                _ = tokio::time::sleep(duration) => {}
+            },
+            None => shutdown_signal().await,
+        }
+    };
+    tokio::pin!(shutdown);
+    let mut ticker = tokio::time::interval(cli.interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut previous: Option<WalletBalance> = None;
+    let mut session = watch::Session::new(network.decimals());
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = ticker.tick() => {}
+        }
+        let fetched = tokio::select! {
+            _ = &mut shutdown => break,
+            fetched = source.get_balance_with_confirmations(network, address, cli.confirmations) => fetched,
+        };
+        let mut balance = match fetched {
+            Ok(balance) => balance,
+            Err(e) => {
+                warn(t!(WatchPollFailed, format!("{:#}", e)));
+                continue;
+            }
+        };
+        convert_to_fiat([&mut balance], cli.convert).await;
+
+        let change = match &previous {
+            Some(previous) => match watch::balance_change(previous, &balance, network.decimals()) {
+                Ok(change) => change,
+                Err(e) => {
+                    warn(t!(WatchPollFailed, format!("{:#}", e)));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let first = previous.is_none();
+        if first || change.is_some() || !cli.changes_only {
+            print_watch_poll(&balance, network, cli.precision, first, change.as_ref());
+        }
+        // balance_change parsed this amount already, so recording it cannot fail
+        let _ = session.record(&balance);
+        previous = Some(balance);
+    }
+    print_watch_summary(session.summary());
+}
+
+/// Summary of a `--watch` session; on stderr with CSV output, so the rows stay one table
+fn print_watch_summary(summary: Option<watch::SessionSummary>) {
+    let Some(summary) = summary else {
+        warn(t!(WatchNoPolls));
+        return;
+    };
+    if output() == OutputFormat::Json {
+        println!("{}", serde_json::json!({ "session_summary": summary }));
+        return;
+    }
+
+    let amount = |value: &str| format!("{} {}", value, summary.denomination);
+    let mut lines = vec![
+        format!("\n{}", marked("📊", t!(WatchSummary, summary.polls))),
+        format!("{} {}", label(Msg::LabelStart), amount(&summary.start_balance)),
+        format!("{} {}", label(Msg::LabelEnd), amount(&summary.end_balance)),
+        format!("{} {}", label(Msg::LabelChanges), summary.changes),
+    ];
+    if let Some(largest) = &summary.largest_change {
+        lines.push(format!("{} {}", label(Msg::LabelLargest), amount(largest)));
+    }
+    lines.push(format!("{} {}", label(Msg::LabelNet), amount(&summary.net_delta)));
+    let text = lines.join("\n");
+    match output() {
+        OutputFormat::Csv => eprintln!("{}", text),
+        _ => println!("{}", text),
+    }
+}
+
+/// One `--watch` line: the full balance block the first time, then a timestamped amount
+fn print_watch_poll(
+    balance: &WalletBalance,
+    network: Network,
+    precision: units::Precision,
+    first: bool,
+    change: Option<&watch::BalanceChange>,
+) {
+    let shown = rounded(balance, network.decimals(), precision);
+    match output() {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string(&shown).expect("balances serialize"));
+        }
+        OutputFormat::Csv => println!("{}", csv_row(&shown)),
+        OutputFormat::Text if first => print_balance(balance, network.decimals(), precision),
+        OutputFormat::Text => {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default();
+            let summary = match change {
+                Some(change) => t!(WatchChanged, change.delta, shown.denomination),
+                None => t!(WatchUnchanged),
+            };
+            println!("[{} UTC] {} {} ({})", watch::format_time(now), shown.balance, shown.denomination, summary);
+        }
+    }
+}
+
+/// `30s`, `5m` or `1h`, whichever unit divides the interval
+fn humanize_interval(interval: Duration) -> String {
+    match interval.as_secs() {
+        secs if secs % 3_600 == 0 => format!("{}h", secs / 3_600),
+        secs if secs % 60 == 0 => format!("{}m", secs / 60),
+        secs => format!("{}s", secs),
+    }
+}
+
+/// What an ERC-20 lookup reads besides the balance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenExtra {
+    Nothing,
+    /// `--supply-share`: the token's totalSupply()
+    Supply,
+    /// `--since-block`: the balance at this block
+    SinceBlock(u64),
+}
+
+/// ERC-20 balance of `address` in the `contract` token
+async fn run_erc20(
+    source: &BalanceSource,
+    network: Network,
+    contract: &str,
+    address: &str,
+    precision: units::Precision,
+    extra: TokenExtra,
+) {
+    status_line(t!(Fetching, network.display_name(), address));
+    let (balance, decimals) = match token_balance(source, network, contract, address, extra).await {
+        Ok(found) => found,
+        Err(e) => exit_with_fetch_error(e),
+    };
+    print_balance(&balance, decimals, precision);
+}
+
+/// ERC-20 balance from `source` and the token's decimals; only live providers serve token balances
+async fn token_balance(
+    source: &BalanceSource,
+    network: Network,
+    contract: &str,
+    address: &str,
+    extra: TokenExtra,
+) -> Result<(WalletBalance, u32), WalletError> {
+    let token = match (source, extra) {
+        (BalanceSource::Live, TokenExtra::Nothing) => wallet_balance::get_token_balance(network, contract, address).await,
+        (BalanceSource::Live, TokenExtra::Supply) => {
+            wallet_balance::get_token_balance_with_supply(network, contract, address).await
+        }
+        (BalanceSource::Live, TokenExtra::SinceBlock(block)) => {
+            wallet_balance::get_token_balance_since(network, contract, address, block).await
+        }
+        (BalanceSource::Mock, _) => {
+            Err(WalletError::Other("The mock provider does not serve token balances".to_string()))
+        }
+        (BalanceSource::Dev { .. }, _) => Err(WalletError::Other("Dev mode does not serve token balances".to_string())),
+    }?;
+    Ok((token.balance, token.decimals))
+}
+
+/// ERC-1155 balances of several token ids, read with one `balanceOfBatch` call
+async fn run_erc1155(source: &BalanceSource, network: Network, contract: &str, address: &str, token_ids: &[String]) {
+    let ids = match token_ids.iter().map(|id| erc1155::parse_token_id(id)).collect::<Result<Vec<_>, _>>() {
+        Ok(ids) => ids,
+        Err(e) => {
+            print_error(format!("{:#}", e));
+            exit(exit_code(&e));
+        }
+    };
+
+    status_line(t!(Fetching, network.display_name(), address));
+    let result = match source {
+        BalanceSource::Live => erc1155::get_balances(network, contract, address, &ids).await,
+        BalanceSource::Mock => Err(WalletError::Other("The mock provider does not serve token balances".to_string())),
+        BalanceSource::Dev { .. } => Err(WalletError::Other("Dev mode does not serve token balances".to_string())),
+    };
+    let balances = match result {
+        Ok(balances) => balances,
+        Err(e) => exit_with_fetch_error(e),
+    };
+
+    match output() {
+        OutputFormat::Json => {
+            let rows: Vec<_> = balances
+                .iter()
+                .map(|entry| serde_json::json!({ "id": entry.id.to_string(), "balance": entry.balance.to_string() }))
+                .collect();
+            let document = serde_json::json!({
+                "network": network.to_string(),
+                "address": address,
+                "contract": contract.to_lowercase(),
+                "balances": rows,
+            });
+            println!("{}", serde_json::to_string_pretty(&document).expect("JSON values serialize"));
+        }
+        OutputFormat::Csv => {
+            println!("{}", csv_header());
+            for entry in &balances {
+                let row = WalletBalance::new(
+                    address.to_string(),
+                    entry.balance.to_string(),
+                    network.to_string(),
+                    format!("#{}", entry.id),
+                );
+                println!("{}", csv_row(&row));
+            }
+        }
+        OutputFormat::Text => {
+            println!("\n{}", marked("✅", t!(Success)));
+            print_rule();
+            println!("{} {}", label(Msg::LabelNetwork), network.to_string().to_uppercase());
+            println!("{} {}", label(Msg::LabelAddress), address);
+            println!("{} {}", label(Msg::LabelContract), contract.to_lowercase());
+            print_rule();
+            if !plain() {
+                println!("{:<20} {}", t!(HeaderTokenId), t!(HeaderBalance));
+            }
+            for entry in &balances {
+                if plain() {
+                    println!(
+                        "\n{}",
+                        format_record(&[
+                            (Msg::HeaderTokenId, entry.id.to_string()),
+                            (Msg::HeaderBalance, entry.balance.to_string()),
+                        ])
+                    );
+                } else {
+                    println!("{:<20} {}", entry.id, entry.balance);
+                }
+            }
+        }
+    }
+}
+
+/// Network given with `--network`, switched to its test network by `--testnet`, exiting on an unknown name
+fn requested_network(cli: &Cli) -> Option<Network> {
+    let network: Network = cli.network.as_deref().map(|name| match name.parse() {
+        Ok(n) => n,
+        Err(e) => {
+            print_error(e);
+            let supported: Vec<String> = provider::registry().networks().iter().map(|n| n.to_string()).collect();
+            notice(t!(SupportedNetworks, supported.join(", ")));
+            exit(EXIT_UNSUPPORTED_NETWORK);
+        }
+    })?;
+    match (cli.testnet, network.testnet()) {
+        (false, _) => Some(network),
+        (true, Some(testnet)) => Some(testnet),
+        (true, None) if network.is_testnet() => Some(network),
+        (true, None) => {
+            print_error(t!(NoTestnet, network));
+            exit(EXIT_UNSUPPORTED_NETWORK);
+        }
+    }
+}
+
+/// Configured default_network for an address without `--network`, exiting when there is none
+fn default_network() -> Network {
+    match config::active().default_network() {
+        Ok(Some(network)) => network,
+        // `Config::from_toml` has already rejected an unknown name
+        Ok(None) | Err(_) => {
+            print_error(t!(NetworkRequired));
+            exit(1);
+        }
+    }
+}
+
+/// Check several addresses concurrently and print a table with per-network totals
+async fn run_batch(cli: &Cli, entries: &[String]) {
+    for (flag, given) in [
+        ("--needs", cli.needs.is_some()),
+        ("--verify", cli.verify),
+        ("--simulate-pending", cli.simulate_pending),
+        ("--token-contract", cli.token_contract.is_some()),
+        ("--token", cli.token.is_some()),
+        ("--watch", cli.watch),
+        ("--utxos", cli.utxos),
+        ("--include-pending", cli.include_pending),
+        ("--block", cli.block.is_some()),
+        ("--at-height", cli.at_height.is_some()),
+    ] {
+        if given {
+            print_error(t!(BatchConflict, flag));
+            exit(1);
+        }
+    }
+
+    let requested_network = requested_network(cli);
+    let mut queries = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let cleaned = sanitize::clean_text(entry);
+        warn_stripped(&cleaned.stripped);
+
+        let parsed = match (cli.dev, requested_network) {
+            (true, Some(network)) => payment_uri::parse_with_fallback(&cleaned.address, network),
+            _ => payment_uri::parse(&cleaned.address),
+        };
+        let query = match (parsed, requested_network) {
+            (Ok(Some(request)), _) if request.token_contract.is_some() => {
+                print_error(t!(BatchTokenUri, entry));
+                exit(1);
+            }
+            (Ok(Some(request)), Some(network)) if cli.dev => (network, request.address),
+            (Ok(Some(request)), _) => (request.network, request.address),
+            (Ok(None), Some(network)) => {
+                let sanitized = sanitize::sanitize_address(&cleaned.address);
+                warn_stripped(&sanitized.stripped);
+                (network, sanitized.address)
+            }
+            (Ok(None), None) => {
+                let sanitized = sanitize::sanitize_address(&cleaned.address);
+                warn_stripped(&sanitized.stripped);
+                (default_network(), sanitized.address)
+            }
+            (Err(e), _) => {
+                print_error(t!(InvalidPaymentUri, e));
+                exit(1);
+            }
+        };
+        queries.push(query);
+    }
+
+    for (network, address) in queries.iter_mut() {
+        *address = resolve_name(*network, std::mem::take(address)).await;
+    }
+    for (network, address) in &queries {
+        note_network(*network);
+        warn_exchange(*network, address);
+        warn_lookalike(*network, address);
+    }
+    let flags: Vec<_> = queries
+        .iter()
+        .map(|(network, address)| flag_address(*network, address))
+        .collect();
+    status_line(t!(FetchingBatch, queries.len()));
+    let deadline = cli.sla.map(|sla| tokio::time::Instant::now() + sla);
+    let mut results = cli.source().get_balances_within(&queries, cli.confirmations, deadline).await;
+    convert_to_fiat(results.iter_mut().filter_map(|result| result.as_mut().ok()), cli.convert).await;
+    let failures = results.iter().filter(|result| result.is_err()).count();
+    let timed_out = results
+        .iter()
+        .filter(|result| result.as_ref().is_err_and(|e| matches!(e, WalletError::TimedOut(_))))
+        .count();
+
+    match output() {
+        OutputFormat::Json => {
+            let rows: Vec<_> = queries
+                .iter()
+                .zip(&results)
+                .map(|((network, address), result)| match result {
+                    Ok(balance) => serde_json::to_value(rounded(balance, network.decimals(), cli.precision))
+                        .expect("balances serialize"),
+                    Err(e @ WalletError::TimedOut(_)) => serde_json::json!({
+                        "network": network.to_string(),
+                        "address": address,
+                        "error": format!("{:#}", e),
+                        "timed_out": true,
+                    }),
+                    Err(e) => serde_json::json!({
+                        "network": network.to_string(),
+                        "address": address,
+                        "error": format!("{:#}", e),
+                    }),
+                })
+                .zip(&flags)
+                .map(|(mut row, flag)| {
+                    if let Some(flag) = flag {
+                        row["warnings"] = serde_json::json!([flag.to_string()]);
+                    }
+                    row
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&rows).expect("JSON values serialize"));
+        }
+        OutputFormat::Csv => {
+            println!("{}", csv_header());
+            for ((network, address), result) in queries.iter().zip(&results) {
+                match result {
+                    Ok(balance) => println!("{}", csv_row(&rounded(balance, network.decimals(), cli.precision))),
+                    Err(e) => print_error(format!("{} {}: {:#}", network, address, e)),
+                }
+            }
+        }
+        // One amount per line in input order, empty where the lookup failed
+        OutputFormat::Text if quiet() => {
+            for ((network, address), result) in queries.iter().zip(&results) {
+                match result {
+                    Ok(balance) => println!("{}", rounded(balance, network.decimals(), cli.precision).balance),
+                    Err(e) => {
+                        println!();
+                        report_error(format!("{} {}: {:#}", network, address, e));
+                    }
+                }
+            }
+        }
+        OutputFormat::Text => print_batch_table(&queries, &results, &flags, cli.precision),
+    }
+
+    if timed_out > 0 {
+        let sla = format!("{:?}", cli.sla.unwrap_or_default());
+        warn(t!(SlaTimedOut, timed_out, queries.len(), sla));
+        exit(EXIT_SLA_EXCEEDED);
+    }
+    if failures > 0 {
+        warn(t!(BatchFailures, failures, queries.len()));
+        exit(batch_exit_code(results.iter().filter_map(|result| result.as_ref().err())));
+    }
+}
+
+/// Text table of batch results, followed by a total per network that leaves out burn addresses
+fn print_batch_table(
+    queries: &[(Network, String)],
+    results: &[Result<WalletBalance, WalletError>],
+    flags: &[Option<address_flags::AddressFlag>],
+    precision: units::Precision,
+) {
+    let mut totals: std::collections::BTreeMap<Network, u128> = std::collections::BTreeMap::new();
+    if !plain() {
+        println!("\n{:<18} {:<44} {}", t!(HeaderNetwork), t!(HeaderAddress), t!(HeaderBalance));
+    }
+    for (((network, address), result), flag) in queries.iter().zip(results).zip(flags) {
+        let burn = flag.as_ref().is_some_and(address_flags::AddressFlag::is_burn);
+        let shown = match result {
+            Ok(balance) => {
+                if let Some(held) = units::parse_units(&balance.balance, network.decimals()).ok().filter(|_| !burn) {
+                    let total = totals.entry(*network).or_default();
+                    *total = total.saturating_add(held);
+                }
+                let balance = rounded(balance, network.decimals(), precision);
+                match (&balance.fiat_value, &balance.fiat_currency) {
+                    (Some(value), Some(currency)) => {
+                        format!("{} {} ({} {})", balance.balance, balance.denomination, value, currency)
+                    }
+                    _ => format!("{} {}", balance.balance, balance.denomination),
+                }
+            }
+            Err(e) => format!("{}: {}", t!(Error), e),
+        };
+        if plain() {
+            println!(
+                "\n{}",
+                format_record(&[
+                    (Msg::HeaderNetwork, network.to_string()),
+                    (Msg::HeaderAddress, address.clone()),
+                    (Msg::HeaderBalance, shown),
+                ])
+            );
+        } else {
+            println!("{:<18} {:<44} {}", network.to_string(), address, shown);
+        }
+    }
+
+    println!();
+    for (network, total) in totals {
+        let formatted = units::format_units(total, network.decimals());
+        let amount = precision
+            .apply(&formatted, network.decimals(), network.denomination())
+            .unwrap_or(formatted);
+        println!("{} {} {}", t!(Total, network), amount, network.denomination());
+    }
+}
+
+/// Print the cross-provider check and return whether the providers agree
+async fn print_verification(network: Network, address: &str) -> bool {
+    let verification = match verify::verify_balance(network, address).await {
+        Ok(verification) => verification,
+        Err(e) => {
+            print_error(t!(VerifyFailed, format!("{:#}", e)));
+            exit(1);
+        }
+    };
+
+    let decimals = network.decimals();
+    let at = match verification.block {
+        Some(block) => t!(AtBlock, block),
+        None => t!(AtLatestBlocks),
+    };
+    if verification.agrees() {
+        println!(
+            "{}",
+            t!(Verified, verification.primary_source, verification.secondary_source, at)
+        );
+    } else {
+        println!("{}", warning(t!(Divergence, at)));
+        for (source, amount) in [
+            (&verification.primary_source, verification.primary),
+            (&verification.secondary_source, verification.secondary),
+        ] {
+            let amount = format!("{} {}", units::format_units_u256(amount, decimals), network.denomination());
+            if plain() {
+                println!("{}: {}", source, amount);
+            } else {
+                println!("  {:<32} {}", source, amount);
+            }
+        }
+    }
+    verification.agrees()
+}
+
+async fn print_pending_projection(balance: &WalletBalance, network: Network) {
+    let projection = match pending::scan_pending(network, &balance.address).await {
+        Ok(projection) => projection,
+        Err(e) => {
+            warn(t!(PendingFailed, e));
+            return;
+        }
+    };
+    let decimals = network.decimals();
+    let confirmed = match units::parse_units(&balance.balance, decimals) {
+        Ok(confirmed) => confirmed,
+        Err(e) => {
+            warn(t!(PendingBalanceUnreadable, e));
+            return;
+        }
+    };
+
+    let projected = units::format_units(projection.projected_balance(confirmed), decimals);
+    println!("{} {}", label(Msg::LabelPending), t!(PendingTransactions, projection.tx_count));
+    println!(
+        "{} {}",
+        label(Msg::LabelProjected),
+        t!(
+            ProjectedBalance,
+            format!("{} {}", projected, balance.denomination),
+            format!("{} {}", balance.balance, balance.denomination)
+        )
+    );
+}
+
+/// Print whether `balance` covers `needs` (plus fees) and return the verdict
+async fn check_needs(balance: &WalletBalance, network: Network, needs: &str, with_fees: bool) -> bool {
+    let decimals = network.decimals();
+    let required = match units::parse_units(needs, decimals) {
+        Ok(required) => required,
+        Err(e) => {
+            print_error(t!(InvalidNeeds, e));
+            exit(1);
+        }
+    };
+    let fee = if with_fees {
+        match fees::estimate_transfer_fee(network).await {
+            Ok(fee) => fee,
+            Err(e) => {
+                print_error(t!(FeeEstimateFailed, e));
+                exit(1);
+            }
+        }
+    } else {
+        0
+    };
+    let held = match units::parse_units(&balance.balance, decimals) {
+        Ok(held) => held,
+        Err(e) => {
+            print_error(t!(BalanceUnreadable, e));
+            exit(1);
+        }
+    };
+
+    println!(
+        "{} {} {}",
+        label(Msg::LabelNeeds),
+        units::format_units(required, decimals),
+        balance.denomination
+    );
+    if with_fees {
+        println!(
+            "{} {} {}",
+            label(Msg::LabelFee),
+            units::format_units(fee, decimals),
+            balance.denomination
+        );
+    }
+
+    let sufficient = held >= required.saturating_add(fee);
+    if sufficient {
+        println!("{}", marked("✅", t!(Covered)));
+    } else {
+        let shortfall = required.saturating_add(fee) - held;
+        let shortfall = format!("{} {}", units::format_units(shortfall, decimals), balance.denomination);
+        println!("{}", marked("❌", t!(Insufficient, shortfall)));
+    }
+    sufficient
+}
+
+async fn run_payment_request(
+    source: &BalanceSource,
+    request: PaymentRequest,
+    confirmations: u64,
+    precision: units::Precision,
+    convert: Option<pricing::FiatCurrency>,
+) {
+    if output() == OutputFormat::Text {
+        println!(
+            "{}",
+            t!(FetchingPaymentRequest, request.network.display_name(), request.address)
+        );
+    }
+    warn_exchange(request.network, &request.address);
+    warn_lookalike(request.network, &request.address);
+
+    if request.token_contract.is_some() && confirmations > 0 {
+        print_error(t!(ConfirmationsTokens));
+        exit(1);
+    }
+
+    let result = match &request.token_contract {
+        Some(token) => token_balance(source, request.network, token, &request.address, TokenExtra::Nothing).await,
+        None => source
+            .get_balance_with_confirmations(request.network, &request.address, confirmations)
+            .await
+            .map(|balance| (balance, request.network.decimals())),
+    };
+
+    let (mut balance, decimals) = match result {
+        Ok(found) => found,
+        Err(e) => exit_with_fetch_error(e),
+    };
+    // Token amounts have no CoinGecko id here; clap keeps --convert off the token flags
+    if request.token_contract.is_none() {
+        convert_to_fiat([&mut balance], convert).await;
+    }
+    print_balance(&balance, decimals, precision);
+
+    // The JSON document or CSV row is the balance alone; the requested amount is in the URI
+    if output() != OutputFormat::Text {
+        return;
+    }
+    if let Some(amount) = request.amount {
+        println!(
+            "{} {} {}",
+            label(Msg::LabelRequested),
+            units::format_units(amount, decimals),
+            balance.denomination
+        );
+        match units::parse_units(&balance.balance, decimals) {
+            Ok(held) if held >= amount => println!("{}", marked("✅", t!(RequestedHeld))),
+            Ok(_) => println!("{}", warning(t!(RequestedShort))),
+            Err(e) => warn(t!(RequestedCompareFailed, e)),
+        }
+    }
+}
+
+async fn run_attest(file: &std::path::Path, source: BalanceSource) {
+    let wallets = match std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))
+        .and_then(|text| Ok(attest::parse_wallet_list(&text)?))
+    {
+        Ok(wallets) => wallets,
+        Err(e) => {
+            print_error(format!("{:#}", e));
+            exit(1);
+        }
+    };
+
+    let mut leaves = Vec::with_capacity(wallets.len());
+    for (network, address) in wallets {
+        // Coins at a burn address back nothing, so they are not attested to
+        if flag_address(network, &address).is_some_and(|flag| flag.is_burn()) {
+            continue;
+        }
+        note_network(network);
+        notice(t!(Fetching, network.display_name(), address));
+        let leaf = source
+            .get_balance(network, &address)
+            .await
+            .and_then(|balance| attest::Leaf::from_balance(network, &balance));
+        match leaf {
+            Ok(leaf) => leaves.push(leaf),
+            Err(e) => exit_with_fetch_error(e),
+        }
+    }
+
+    match attest::attest(&leaves) {
+        Ok(attestation) => println!(
+            "{}",
+            serde_json::to_string_pretty(&attestation).expect("attestation serializes")
+        ),
+        Err(e) => {
+            print_error(format!("{:#}", e));
+            exit(exit_code(&e));
+        }
+    }
+}
+
+/// Print the versioned JSON Schema of the balance output
+fn run_emit_schema() {
+    if output() == OutputFormat::Csv {
+        print_error(t!(OutputConflict, "--emit-schema", OutputFormat::Csv));
+        exit(1);
+    }
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&schema::balance_output_schema()).expect("schemas serialize")
+    );
+}
+
+/// Check the wallets of a portfolio file and print them with per-network (and fiat) totals
+async fn run_portfolio(
+    file: &std::path::Path,
+    source: BalanceSource,
+    convert: Option<pricing::FiatCurrency>,
+    precision: units::Precision,
+) {
+    let entries = match portfolio::load(file) {
+        Ok(entries) => entries,
+        Err(e) => {
+            print_error(format!("{:#}", e));
+            exit(exit_code(&e));
+        }
+    };
+    let queries: Vec<(Network, String)> = entries.iter().map(|entry| (entry.network, entry.address.clone())).collect();
+    for (network, _) in &queries {
+        note_network(*network);
+    }
+    status_line(t!(FetchingBatch, queries.len()));
+
+    let mut results = source.get_balances(&queries, 0).await;
+    convert_to_fiat(results.iter_mut().filter_map(|result| result.as_mut().ok()), convert).await;
+    let mut skipped: Vec<NotConfigured> = Vec::new();
+    for not_configured in results.iter().filter_map(|result| match result {
+        Err(WalletError::NotConfigured(not_configured)) => Some(not_configured),
+        _ => None,
+    }) {
+        if !skipped.contains(not_configured) {
+            skipped.push(*not_configured);
+        }
+    }
+    let failures = results
+        .iter()
+        .filter(|result| result.as_ref().is_err_and(|e| !matches!(e, WalletError::NotConfigured(_))))
+        .count();
+    let totals = match portfolio::totals(
+        entries
+            .iter()
+            .zip(&results)
+            .filter_map(|(entry, result)| result.as_ref().ok().map(|balance| (entry.network, balance))),
+    ) {
+        Ok(totals) => totals,
+        Err(e) => {
+            print_error(format!("{:#}", e));
+            exit(exit_code(&e));
+        }
+    };
+
+    match output() {
+        OutputFormat::Json => {
+            let wallets: Vec<_> = entries
+                .iter()
+                .zip(&results)
+                .map(|(entry, result)| {
+                    let mut row = match result {
+                        Ok(balance) => serde_json::to_value(rounded(balance, entry.network.decimals(), precision))
+                            .expect("balances serialize"),
+                        Err(e) => serde_json::json!({
+                            "network": entry.network.to_string(),
+                            "address": entry.address,
+                            "error": format!("{:#}", e),
+                            "skipped": matches!(e, WalletError::NotConfigured(_)),
+                        }),
+                    };
+                    row["label"] = serde_json::Value::String(entry.label.clone());
+                    row
+                })
+                .collect();
+            let skipped: Vec<_> = skipped
+                .iter()
+                .map(|skip| serde_json::json!({ "network": skip.network.to_string(), "needs": skip.needs }))
+                .collect();
+            let report = serde_json::json!({ "wallets": wallets, "totals": totals, "skipped": skipped });
+            println!("{}", serde_json::to_string_pretty(&report).expect("JSON values serialize"));
+        }
+        OutputFormat::Csv => {
+            println!("label,{}", csv_header());
+            for (entry, result) in entries.iter().zip(&results) {
+                match result {
+                    Ok(balance) => {
+                        let row = csv_row(&rounded(balance, entry.network.decimals(), precision));
+                        println!("{},{}", csv_line(&[entry.label.as_str()]), row);
+                    }
+                    Err(WalletError::NotConfigured(_)) => {}
+                    Err(e) => print_error(format!("{} {} {}: {:#}", entry.label, entry.network, entry.address, e)),
+                }
+            }
+            for skip in &skipped {
+                warn(t!(SkippedNetwork, skip.network, skip.needs));
+            }
+        }
+        OutputFormat::Text => {
+            print_portfolio_table(&entries, &results, &totals, precision);
+            if !skipped.is_empty() {
+                println!("\n{}", t!(SkippedTitle));
+                for skip in &skipped {
+                    println!("  {} {}", bullet(), t!(SkippedNetwork, skip.network, skip.needs));
+                }
+            }
+        }
+    }
+
+    if failures > 0 {
+        warn(t!(BatchFailures, failures, queries.len()));
+        let errors = results.iter().filter_map(|result| result.as_ref().err());
+        exit(batch_exit_code(errors.filter(|e| !matches!(e, WalletError::NotConfigured(_)))));
+    }
+}
+
+fn print_portfolio_table(
+    entries: &[portfolio::PortfolioEntry],
+    results: &[Result<WalletBalance, WalletError>],
+    totals: &portfolio::Totals,
+    precision: units::Precision,
+) {
+    if !plain() {
+        println!(
+            "\n{:<20} {:<18} {:<44} {}",
+            t!(HeaderLabel),
+            t!(HeaderNetwork),
+            t!(HeaderAddress),
+            t!(HeaderBalance)
+        );
+    }
+    for (entry, result) in entries.iter().zip(results) {
+        let shown = match result {
+            Ok(balance) => {
+                let balance = rounded(balance, entry.network.decimals(), precision);
+                match (&balance.fiat_value, &balance.fiat_currency) {
+                    (Some(value), Some(currency)) => {
+                        format!("{} {} ({} {})", balance.balance, balance.denomination, value, currency)
+                    }
+                    _ => format!("{} {}", balance.balance, balance.denomination),
+                }
+            }
+            Err(WalletError::NotConfigured(_)) => t!(SkippedRow),
+            Err(e) => format!("{}: {}", t!(Error), e),
+        };
+        if plain() {
+            println!(
+                "\n{}",
+                format_record(&[
+                    (Msg::HeaderLabel, entry.label.clone()),
+                    (Msg::HeaderNetwork, entry.network.to_string()),
+                    (Msg::HeaderAddress, entry.address.clone()),
+                    (Msg::HeaderBalance, shown),
+                ])
+            );
+        } else {
+            println!("{:<20} {:<18} {:<44} {}", entry.label, entry.network.to_string(), entry.address, shown);
+        }
+    }
+
+    println!();
+    for total in &totals.networks {
+        let network: Network = total.network.parse().expect("totals name known networks");
+        let amount = precision
+            .apply(&total.balance, network.decimals(), &total.denomination)
+            .unwrap_or_else(|_| total.balance.clone());
+        match (&total.fiat_value, &totals.fiat_currency) {
+            (Some(value), Some(currency)) => {
+                println!("{} {} {} ({} {})", t!(Total, network), amount, total.denomination, value, currency)
+            }
+            _ => println!("{} {} {}", t!(Total, network), amount, total.denomination),
+        }
+    }
+    if let (Some(value), Some(currency)) = (&totals.fiat_value, &totals.fiat_currency) {
+        println!("{} {} {}", t!(GrandTotal), value, currency);
+    }
+}
+
+async fn run_drift(
+    file: &std::path::Path,
+    targets_file: &std::path::Path,
+    source: BalanceSource,
+    convert: Option<pricing::FiatCurrency>,
+) {
+    let loaded = portfolio::load(file).and_then(|entries| Ok((entries, drift::load(targets_file)?)));
+    let (entries, targets) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            print_error(format!("{:#}", e));
+            exit(exit_code(&e));
+        }
+    };
+    let queries: Vec<(Network, String)> = entries.iter().map(|entry| (entry.network, entry.address.clone())).collect();
+    for (network, _) in &queries {
+        note_network(*network);
+    }
+    status_line(t!(FetchingBatch, queries.len()));
+
+    // Drift measured from part of the holdings would ask for the wrong trades
+    let results = source.get_balances(&queries, 0).await;
+    let errors: Vec<&WalletError> = results.iter().filter_map(|result| result.as_ref().err()).collect();
+    if !errors.is_empty() {
+        for (entry, result) in entries.iter().zip(&results) {
+            if let Err(e) = result {
+                print_error(format!("{} {} {}: {:#}", entry.label, entry.network, entry.address, e));
+            }
+        }
+        exit(batch_exit_code(errors));
+    }
+    let prices = match convert {
+        Some(currency) => {
+            let networks = entries.iter().map(|entry| entry.network).chain(targets.iter().map(|entry| entry.network));
+            match drift::fetch_prices(networks, currency).await {
+                Ok(prices) => Some(prices),
+                Err(e) => {
+                    warn(t!(PricingFailed, format!("{:#}", e)));
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+    let report = portfolio::totals(
+        entries
+            .iter()
+            .zip(&results)
+            .filter_map(|(entry, result)| result.as_ref().ok().map(|balance| (entry.network, balance))),
+    )
+    .and_then(|totals| drift::report(&totals, &targets, prices.as_ref()));
+    let report = match report {
+        Ok(report) => report,
+        Err(e) => {
+            print_error(format!("{:#}", e));
+            exit(exit_code(&e));
+        }
+    };
+
+    match output() {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report).expect("drift reports serialize")),
+        OutputFormat::Csv => {
+            println!("network,denomination,held,target,held_share,target_share,drift,side,trade,trade_value");
+            for asset in &report.assets {
+                let trade = asset.trade.as_ref();
+                let side = trade.map(|trade| match trade.side {
+                    drift::Side::Buy => "buy",
+                    drift::Side::Sell => "sell",
+                });
+                println!(
+                    "{}",
+                    csv_line(&[
+                        asset.network.as_str(),
+                        asset.denomination.as_str(),
+                        asset.held.as_str(),
+                        asset.target.as_deref().unwrap_or_default(),
+                        asset.held_share.as_deref().unwrap_or_default(),
+                        asset.target_share.as_deref().unwrap_or_default(),
+                        asset.drift.as_deref().unwrap_or_default(),
+                        side.unwrap_or_default(),
+                        trade.map(|trade| trade.amount.as_str()).unwrap_or_default(),
+                        trade.and_then(|trade| trade.fiat_value.as_deref()).unwrap_or_default(),
+                    ])
+                );
+            }
+        }
+        OutputFormat::Text => print_drift_table(&report),
+    }
+}
+
+fn print_drift_table(report: &drift::DriftReport) {
+    let with_share = |amount: &str, share: Option<&String>, denomination: &str| match share {
+        Some(share) => format!("{} {} ({}%)", amount, denomination, share),
+        None => format!("{} {}", amount, denomination),
+    };
+    if !plain() {
+        println!(
+            "\n{:<18} {:<30} {:<30} {:<24} {}",
+            t!(HeaderNetwork),
+            t!(HeaderHeld),
+            t!(HeaderTarget),
+            t!(HeaderDrift),
+            t!(HeaderTrade)
+        );
+    }
+    for asset in &report.assets {
+        let held = with_share(&asset.held, asset.held_share.as_ref(), &asset.denomination);
+        let target = match &asset.target {
+            Some(target) => with_share(target, asset.target_share.as_ref(), &asset.denomination),
+            None => t!(NoTarget),
+        };
+        let drift = asset.drift.clone().unwrap_or_else(|| "-".to_string());
+        let trade = match (&asset.trade, &asset.target) {
+            (Some(trade), _) => {
+                let amount = match (&trade.fiat_value, &report.fiat_currency) {
+                    (Some(value), Some(currency)) => format!("{} {} ({} {})", trade.amount, asset.denomination, value, currency),
+                    _ => format!("{} {}", trade.amount, asset.denomination),
+                };
+                match trade.side {
+                    drift::Side::Buy => t!(TradeBuy, amount),
+                    drift::Side::Sell => t!(TradeSell, amount),
+                }
+            }
+            (None, Some(_)) => t!(OnTarget),
+            (None, None) => "-".to_string(),
+        };
+        if plain() {
+            println!(
+                "\n{}",
+                format_record(&[
+                    (Msg::HeaderNetwork, asset.network.clone()),
+                    (Msg::HeaderHeld, held),
+                    (Msg::HeaderTarget, target),
+                    (Msg::HeaderDrift, drift),
+                    (Msg::HeaderTrade, trade),
+                ])
+            );
+        } else {
+            println!("{:<18} {:<30} {:<30} {:<24} {}", asset.network, held, target, drift, trade);
+        }
+    }
+    if let (Some(value), Some(currency)) = (&report.portfolio_value, &report.fiat_currency) {
+        println!("\n{} {} {}", t!(PortfolioValue), value, currency);
+    }
+}
+
+async fn run_token_snapshot(
+    file: &std::path::Path,
+    tokens: &[String],
+    block: u64,
+    network: Network,
+    rpc_url: Option<&str>,
+    fetch: snapshot::Fetch,
+) {
+    let prepared = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))
+        .and_then(|text| Ok(snapshot::parse_holder_list(&text)?))
+        .and_then(|holders| {
+            let tokens = tokens
+                .iter()
+                .map(|token| snapshot::normalize_address(token).with_context(|| format!("Invalid token contract {}", token)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let rpc_url = match rpc_url {
+                Some(url) => url.to_string(),
+                None => snapshot::default_rpc_url(network)?,
+            };
+            Ok((holders, tokens, rpc_url))
+        });
+    let (holders, tokens, rpc_url) = match prepared {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            print_error(format!("{:#}", e));
+            exit(1);
+        }
+    };
+
+    note_network(network);
+    notice(t!(FetchingSnapshot, holders.len(), tokens.len(), block));
+
+    // Rows are written as each batch arrives; JSON output is one object per line
+    let json = output() == OutputFormat::Json;
+    let mut out = std::io::BufWriter::new(Stdout);
+    let streamed = async {
+        if !json {
+            writeln!(out, "{}", snapshot::CSV_HEADER)?;
+        }
+        snapshot::stream(&rpc_url, &tokens, block, &holders, fetch, |row| {
+            match json {
+                true => writeln!(out, "{}", serde_json::to_string(&row).expect("snapshot rows serialize"))?,
+                false => writeln!(out, "{}", row.csv_line())?,
+            }
+            Ok(())
+        })
+        .await
+        .map_err(anyhow::Error::from)
+    }
+    .await;
+    let flushed = out.flush();
+    drop(out);
+
+    if let Err(e) = streamed.and_then(|rows| flushed.map(|()| rows).map_err(Into::into)) {
+        print_error(t!(SnapshotFailed, format!("{:#}", e)));
+        exit(1);
+    }
+}
+
+fn run_verify_proof(file: &std::path::Path, root: &str) {
+    let verified = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))
+        .and_then(|text| serde_json::from_str(&text).context("Proof file is not a valid inclusion proof"))
+        .and_then(|proof: attest::InclusionProof| Ok(attest::verify_proof(&proof, root)?));
+
+    match verified {
+        Ok(true) => println!("{}", marked("✅", t!(ProofValid, root))),
+        Ok(false) => {
+            println!("{}", marked("❌", t!(ProofInvalid, root)));
+            exit(1);
+        }
+        Err(e) => {
+            print_error(format!("{:#}", e));
+            exit(1);
+        }
+    }
+}
+
+fn run_config_init(force: bool) {
+    let paths = match config::ConfigPaths::discover() {
+        Ok(paths) => paths,
+        Err(e) => {
+            report_error(marked("❌", t!(ConfigFailed, e)));
+            exit(1);
+        }
+    };
+
+    match config::init(&paths, force) {
+        Ok(config::InitOutcome::Created) => println!("{}", marked("✅", t!(ConfigCreated, paths.config_file.display()))),
+        Ok(config::InitOutcome::Migrated { from }) => println!(
+            "{}",
+            marked("✅", t!(ConfigMigrated, from.display(), paths.config_file.display()))
+        ),
+        Ok(config::InitOutcome::AlreadyExists) => println!("{}", t!(ConfigExists, paths.config_file.display())),
+        Err(e) => {
+            report_error(marked("❌", t!(ConfigFailed, format!("{:#}", e))));
+            exit(1);
+        }
+    }
+
+    println!("{} {}", label(Msg::LabelConfig), paths.config_file.display());
+    println!("{} {}", label(Msg::LabelData), paths.data_dir.display());
+    println!("{} {}", label(Msg::LabelCache), paths.cache_dir.display());
+}
+
+/// The passphrase of a state bundle, from `passphrase_file` or `state::PASSPHRASE_ENV`
+fn state_passphrase(passphrase_file: Option<&std::path::Path>) -> String {
+    let passphrase = match passphrase_file {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(text) => text.trim_end_matches(['\r', '\n']).to_string(),
+            Err(e) => {
+                print_error(format!("Failed to read {}: {}", path.display(), e));
+                exit(1);
+            }
+        },
+        None => std::env::var(state::PASSPHRASE_ENV).unwrap_or_default(),
+    };
+    if passphrase.is_empty() {
+        print_error(t!(NoPassphrase, state::PASSPHRASE_ENV));
+        exit(1);
+    }
+    passphrase
+}
+
+/// The config file a state bundle is read from and restored to: `--config`, or the standard location
+fn state_paths(config_path: Option<&std::path::Path>) -> (config::ConfigPaths, std::path::PathBuf) {
+    match config::ConfigPaths::discover() {
+        Ok(paths) => {
+            let config_file = config_path.map_or_else(|| paths.config_file.clone(), |path| path.to_path_buf());
+            (paths, config_file)
+        }
+        Err(e) => {
+            report_error(marked("❌", t!(ConfigFailed, e)));
+            exit(1);
+        }
+    }
+}
+
+/// Write the config file, data directory and `portfolios` to the encrypted archive `file`
+fn run_state_export(
+    file: &std::path::Path,
+    portfolios: &[std::path::PathBuf],
+    passphrase_file: Option<&std::path::Path>,
+    config_path: Option<&std::path::Path>,
+) {
+    let passphrase = state_passphrase(passphrase_file);
+    let (paths, config_file) = state_paths(config_path);
+    let exported = state::Bundle::collect(&paths, &config_file, portfolios).and_then(|bundle| {
+        let archive = bundle.seal(&passphrase)?;
+        std::fs::write(file, archive).with_context(|| format!("Failed to write {}", file.display()))?;
+        Ok(bundle)
+    });
+    match exported {
+        Ok(bundle) => {
+            println!("{}", marked("✅", t!(StateExported, bundle.files.len(), file.display())));
+            for bundled in &bundle.files {
+                println!("  {:<10} {}", bundled.kind.to_string(), bundled.name);
+            }
+        }
+        Err(e) => {
+            report_error(marked("❌", t!(StateExportFailed, format!("{:#}", e))));
+            exit(exit_code(&e));
+        }
+    }
+}
+
+/// Decrypt the archive `file` and restore its files onto this machine
+fn run_state_import(
+    file: &std::path::Path,
+    portfolio_dir: &std::path::Path,
+    force: bool,
+    passphrase_file: Option<&std::path::Path>,
+    config_path: Option<&std::path::Path>,
+) {
+    let passphrase = state_passphrase(passphrase_file);
+    let (paths, config_file) = state_paths(config_path);
+    let restored = std::fs::read(file)
+        .with_context(|| format!("Failed to read {}", file.display()))
+        .map_err(WalletError::from)
+        .and_then(|archive| state::Bundle::open(&archive, &passphrase))
+        .and_then(|bundle| bundle.restore(&paths, &config_file, portfolio_dir, force));
+    match restored {
+        Ok(targets) => {
+            println!("{}", marked("✅", t!(StateImported, targets.len(), file.display())));
+            for target in targets {
+                println!("  {}", target.display());
+            }
+        }
+        Err(e) => {
+            report_error(marked("❌", t!(StateImportFailed, format!("{:#}", e))));
+            exit(exit_code(&e));
+        }
+    }
+}
+
+/// Probe every endpoint (or `network`'s) and print a table of their latency and availability
+async fn run_endpoints_check(network: Option<Network>) {
+    let networks = match network {
+        Some(network) => vec![network],
+        None => Network::ALL.to_vec(),
+    };
+    let endpoints = endpoints::configured(&networks);
+    if endpoints.is_empty() {
+        print_error(t!(NoEndpoints, network.map(|network| network.to_string()).unwrap_or_default()));
+        exit(EXIT_UNSUPPORTED_NETWORK);
+    }
+    // One attempt each, so the latency is the endpoint's and a flaky one shows up as down
+    http::set_retry_policy(http::RetryPolicy {
+        retries: 0,
+        ..http::retry_policy()
+    });
+    status_line(t!(CheckingEndpoints, endpoints.len()));
+    let health = endpoints::check(endpoints).await;
+    let answered = health.iter().filter(|endpoint| endpoint.ok).count();
+
+    match output() {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&health).expect("endpoint health serializes"));
+        }
+        OutputFormat::Csv => {
+            println!("network,role,kind,url,ok,latency_ms,height,error");
+            for endpoint in &health {
+                println!(
+                    "{}",
+                    csv_line(&[
+                        &endpoint.network,
+                        &endpoint.role,
+                        &endpoint.kind.to_string(),
+                        &endpoint.url,
+                        &endpoint.ok.to_string(),
+                        &endpoint.latency_ms.to_string(),
+                        &endpoint.height.map(|height| height.to_string()).unwrap_or_default(),
+                        endpoint.error.as_deref().unwrap_or_default(),
+                    ])
+                );
+            }
+        }
+        OutputFormat::Text => {
+            if !plain() {
+                println!(
+                    "\n{:<18} {:<11} {:<48} {:>9} {:>10}  {}",
+                    t!(HeaderNetwork),
+                    t!(HeaderRole),
+                    t!(HeaderEndpoint),
+                    t!(HeaderLatency),
+                    t!(HeaderHeight),
+                    t!(HeaderStatus)
+                );
+            }
+            for endpoint in &health {
+                let status = match &endpoint.error {
+                    None => marked("✅", t!(EndpointUp)),
+                    Some(e) => marked("❌", t!(EndpointDown, e)),
+                };
+                let height = endpoint.height.map(|height| height.to_string()).unwrap_or_default();
+                if plain() {
+                    let record = format_record(&[
+                        (Msg::HeaderNetwork, endpoint.network.clone()),
+                        (Msg::HeaderRole, endpoint.role.clone()),
+                        (Msg::HeaderEndpoint, endpoint.url.clone()),
+                        (Msg::HeaderLatency, format!("{}ms", endpoint.latency_ms)),
+                        (Msg::HeaderHeight, height),
+                        (Msg::HeaderStatus, status),
+                    ]);
+                    println!("\n{}", record);
+                } else {
+                    println!(
+                        "{:<18} {:<11} {:<48} {:>7}ms {:>10}  {}",
+                        endpoint.network, endpoint.role, endpoint.url, endpoint.latency_ms, height, status
+                    );
+                }
+            }
+            println!();
+            match answered {
+                // Nothing answering at all points at this machine's connection rather than the endpoints
+                0 if health.len() > 1 => println!("{}", warning(t!(NoEndpointAnswered, health.len()))),
+                _ => println!("{}", t!(EndpointsAnswered, answered, health.len())),
+            }
+        }
+    }
+
+    if answered < health.len() {
+        exit(EXIT_NETWORK_FAILURE);
+    }
+}
+
+async fn run_jsonl(source: BalanceSource) {
+    let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+    if let Err(e) = jsonl::serve(stdin, tokio::io::stdout(), source).await {
+        report_error(marked("❌", t!(JsonlFailed, e)));
+        exit(1);
+    }
+}
+
+async fn run_serve_uds(path: std::path::PathBuf, source: BalanceSource) {
+    #[cfg(unix)]
+    {
+        notice(t!(ServingJsonl, path.display()));
+        reload_on_hangup(|| {});
+        if let Err(e) = jsonl::serve_unix(&path, source, shutdown_signal()).await {
+            report_error(marked("❌", t!(ServerFailed, format!("{:#}", e))));
+            exit(1);
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = source;
+        report_error(marked("❌", t!(UdsUnavailable, path.display())));
+        exit(1);
+    }
+}
+
+async fn run_serve_http(
+    addr: std::net::SocketAddr,
+    source: BalanceSource,
+    ready_timeout: Duration,
+    graphql: bool,
+    poll_interval: Duration,
+) {
+    let listener = match tokio::net::TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            report_error(marked("❌", t!(ListenFailed, addr, e)));
+            exit(1);
+        }
+    };
+
+    // `Config::from_toml` has already checked the keys
+    let tenants = Tenants::new(config::active().api_keys().unwrap_or_default());
+    notice(t!(ServingHttp, addr));
+    if !tenants.is_empty() {
+        notice(t!(ApiKeysRequired, tenants.len()));
+    }
+    let subscriptions = match subscriptions::default_path().and_then(|path| Subscriptions::open(&path)) {
+        Ok(subscriptions) => subscriptions,
+        Err(e) => {
+            report_error(marked("❌", t!(SubscriptionsUnavailable, format!("{:#}", e))));
+            exit(1);
+        }
+    };
+    subscriptions.set_policy(config::active().webhook_policy().unwrap_or_default());
+    notice(t!(WatchingSubscriptions, subscriptions.list(None).len(), poll_interval.as_secs()));
+    tokio::spawn(subscriptions.clone().run(source.clone(), poll_interval));
+    let (reloaded, reloaded_subscriptions) = (tenants.clone(), subscriptions.clone());
+    reload_on_hangup(move || {
+        reloaded.set_keys(config::active().api_keys().unwrap_or_default());
+        reloaded_subscriptions.set_policy(config::active().webhook_policy().unwrap_or_default());
+    });
+    let served = server::serve_http(listener, source, ready_timeout, graphql, tenants, subscriptions, shutdown_signal());
+    if let Err(e) = served.await {
+        report_error(marked("❌", t!(ServerFailed, format!("{:#}", e))));
+        exit(1);
+    }
+}
+
+/// Re-read the config file on every SIGHUP, for the rest of the process, calling `on_reload` after each success
+fn reload_on_hangup(on_reload: impl Fn() + Send + 'static) {
+    #[cfg(not(unix))]
+    let _ = on_reload;
+    #[cfg(unix)]
+    tokio::spawn(async move {
+        let Ok(mut hangup) = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) else {
+            return;
+        };
+        while hangup.recv().await.is_some() {
+            match config::reload() {
+                Ok(()) => {
+                    on_reload();
+                    notice(t!(ConfigReloaded));
+                }
+                Err(e) => warn(t!(ConfigReloadFailed, format!("{:#}", e))),
+            }
+        }
+    });
+}
+
+/// Resolve on Ctrl-C, or on SIGTERM where the platform has it
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    notice(t!(ShuttingDown));
+}
+
+/// The address an Unstoppable Domains name resolves to on `network`, or `input` when it is no name
+async fn resolve_name(network: Network, input: String) -> String {
+    if !unstoppable::is_domain(&input) {
+        return input;
+    }
+    match unstoppable::resolve(&input, network).await {
+        Ok(address) => {
+            notice(t!(ResolvedName, input, address));
+            address
+        }
+        Err(e) => {
+            print_error(t!(ResolveFailed, input, format!("{:#}", e)));
+            exit(1);
+        }
+    }
+}
+
+/// Warn on stderr when `address` is a known exchange wallet, whose balance is not any one customer's
+fn warn_exchange(network: Network, address: &str) {
+    if let Some(exchange) = exchanges::identify(network, address) {
+        security_warning(t!(ExchangeAddress, address, exchange, exchange));
+    }
+}
+
+/// Warn on stderr when `address` looks like a burn or vanity address, and return the flag
+fn flag_address(network: Network, address: &str) -> Option<address_flags::AddressFlag> {
+    let flag = address_flags::inspect(network, address)?;
+    match flag.is_burn() {
+        true => security_warning(t!(BurnAddress, address, flag)),
+        false => security_warning(t!(VanityAddress, address, flag)),
+    }
+    Some(flag)
+}
+
+/// Warn, before any balance is printed, when `address` imitates an address book entry
+fn warn_lookalike(network: Network, address: &str) {
+    if let Some(entry) = address_book::find_lookalike(network, address) {
+        security_warning(t!(LookalikeAddress, address, entry.name, entry.address));
+    }
+}
+
+fn warn_stripped(stripped: &[String]) {
+    for item in stripped {
+        warn(t!(Stripped, item));
+    }
+}
+
+/// Print `message` after the localized "Error:" prefix
+///
+/// With `--output json` the error is a `{"error": ...}` document on stdout instead.
+fn print_error(message: impl std::fmt::Display) {
+    if output() == OutputFormat::Json {
+        println!("{}", serde_json::json!({ "error": message.to_string() }));
+    } else {
+        report_error(format!("{}: {}", t!(Error), message));
+    }
+}
+
+fn plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Warn on stderr through the log, so `--quiet` silences it
+fn warn(text: String) {
+    tracing::warn!(target: CLI_TARGET, "{}", warning(text));
+}
+
+/// Warning about where funds would go (lookalike, exchange, burn or vanity address) on stderr
+///
+/// Logged at the error level, so `--quiet` keeps it as it keeps errors.
+fn security_warning(text: String) {
+    tracing::error!(target: CLI_TARGET, "{}", warning(text));
+}
+
+/// Error on stderr through the log; `--quiet` keeps errors
+fn report_error(text: impl std::fmt::Display) {
+    tracing::error!(target: CLI_TARGET, "{}", text);
+}
+
+/// Progress or status notice on stderr through the log, so `--quiet` silences it
+fn notice(text: impl std::fmt::Display) {
+    tracing::info!(target: CLI_TARGET, "{}", text);
+}
+
+/// Progress line of the text output on stdout, left out by `--quiet`
+fn status_line(text: impl std::fmt::Display) {
+    if output() == OutputFormat::Text && !quiet() {
+        println!("{}", text);
+    }
+}
+
+fn output() -> OutputFormat {
+    match OUTPUT.load(Ordering::Relaxed) {
+        1 => OutputFormat::Json,
+        2 => OutputFormat::Csv,
+        _ => OutputFormat::Text,
+    }
+}
+
+/// Header line of `--output csv`
+const CSV_HEADER: &str = "address,network,balance,denomination";
+
+/// Header of `--output csv` balance rows, with the fiat columns under `--convert`
+/// the supply columns under `--supply-share`, the change columns under `--since-block`
+/// and the unconfirmed column under `--include-pending`
+fn csv_header() -> String {
+    let mut header = CSV_HEADER.to_string();
+    if FIAT_COLUMNS.load(Ordering::Relaxed) {
+        header.push_str(",fiat_value,fiat_currency");
+    }
+    if SUPPLY_COLUMNS.load(Ordering::Relaxed) {
+        header.push_str(",total_supply,supply_share");
+    }
+    if SINCE_COLUMNS.load(Ordering::Relaxed) {
+        header.push_str(",since_block,since_balance,balance_change");
+    }
+    if PENDING_COLUMNS.load(Ordering::Relaxed) {
+        header.push_str(",unconfirmed_balance");
+    }
+    header
+}
+
+/// Attach `--convert` fiat values; a pricing failure is a warning and the balances still print
+async fn convert_to_fiat<'a>(
+    balances: impl IntoIterator<Item = &'a mut WalletBalance>,
+    currency: Option<pricing::FiatCurrency>,
+) {
+    let Some(currency) = currency else {
+        return;
+    };
+    if let Err(e) = pricing::convert(balances, currency).await {
+        warn(t!(PricingFailed, format!("{:#}", e)));
+    }
+}
+
+/// One `--output csv` row, quoting fields that contain separators or quotes
+fn csv_row(balance: &WalletBalance) -> String {
+    let mut fields = vec![
+        balance.address.as_str(),
+        balance.network.as_str(),
+        balance.balance.as_str(),
+        balance.denomination.as_str(),
+    ];
+    if FIAT_COLUMNS.load(Ordering::Relaxed) {
+        fields.push(balance.fiat_value.as_deref().unwrap_or_default());
+        fields.push(balance.fiat_currency.as_deref().unwrap_or_default());
+    }
+    if SUPPLY_COLUMNS.load(Ordering::Relaxed) {
+        fields.push(balance.total_supply.as_deref().unwrap_or_default());
+        fields.push(balance.supply_share.as_deref().unwrap_or_default());
+    }
+    let since_block = balance.since.as_ref().map(|since| since.block.to_string()).unwrap_or_default();
+    if SINCE_COLUMNS.load(Ordering::Relaxed) {
+        fields.push(&since_block);
+        fields.push(balance.since.as_ref().map_or("", |since| since.balance.as_str()));
+        fields.push(balance.since.as_ref().map_or("", |since| since.change.as_str()));
+    }
+    if PENDING_COLUMNS.load(Ordering::Relaxed) {
+        fields.push(balance.unconfirmed_balance.as_deref().unwrap_or_default());
+    }
+    csv_line(&fields)
+}
+
+/// Join `fields` into a CSV line, quoting those that need it
+fn csv_line(fields: &[&str]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Localized field label with its colon, padded so values line up unless `--plain`
+fn label(key: Msg) -> String {
+    let label = format!("{}:", i18n::tr(key, &[]));
+    if plain() {
+        label
+    } else {
+        format!("{:<9}", label)
+    }
+}
+
+/// Prefix `text` with a status glyph, or leave it bare with `--plain`
+fn marked(glyph: &str, text: String) -> String {
+    if plain() {
+        text
+    } else {
+        format!("{} {}", glyph, text)
+    }
+}
+
+/// Warning line; `--plain` spells out the prefix instead of using an emoji
+fn warning(text: String) -> String {
+    if plain() {
+        format!("{}: {}", t!(Warning), text)
+    } else {
+        format!("⚠️  {}", text)
+    }
+}
+
+fn bullet() -> &'static str {
+    if plain() {
+        "-"
+    } else {
+        "•"
+    }
+}
+
+/// Horizontal rule around the balance block, omitted with `--plain`
+fn print_rule() {
+    if !plain() {
+        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    }
+}
+
+/// One table row as `key: value` lines, keyed by lowercased column header
+fn format_record(fields: &[(Msg, String)]) -> String {
+    fields
+        .iter()
+        .map(|(header, value)| format!("{}: {}", i18n::tr(*header, &[]).to_lowercase(), value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Print the balance block, rounding the amount for `precision` (with `decimals` places in the asset)
+fn print_balance(balance: &WalletBalance, decimals: u32, precision: units::Precision) {
+    let balance = &rounded(balance, decimals, precision);
+    match output() {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(balance).expect("balances serialize"));
+            return;
+        }
+        OutputFormat::Csv => {
+            println!("{}\n{}", csv_header(), csv_row(balance));
+            return;
+        }
+        OutputFormat::Text if quiet() => {
+            println!("{}", balance.balance);
+            return;
+        }
+        OutputFormat::Text => {}
+    }
+    println!("\n{}", marked("✅", t!(Success)));
+    print_rule();
+    println!("{} {}", label(Msg::LabelNetwork), balance.network.to_uppercase());
+    println!("{} {}", label(Msg::LabelAddress), balance.address);
+    println!("{} {} {}", label(Msg::LabelBalance), balance.balance, balance.denomination);
+    if let Some(unconfirmed) = &balance.unconfirmed_balance {
+        println!("{} {} {}", label(Msg::LabelUnconfirmed), unconfirmed, balance.denomination);
+    }
+    if let (Some(value), Some(currency)) = (&balance.fiat_value, &balance.fiat_currency) {
+        println!("{} {} {}", label(Msg::LabelValue), value, currency);
+    }
+    if let Some(supply) = &balance.total_supply {
+        println!("{} {} {}", label(Msg::LabelTotalSupply), supply, balance.denomination);
+    }
+    if let Some(share) = &balance.supply_share {
+        println!("{} {}%", label(Msg::LabelSupplyShare), share);
+    }
+    if let Some(since) = &balance.since {
+        println!(
+            "{} {}",
+            label(Msg::LabelChange),
+            t!(ChangeSinceBlock, format!("{} {}", since.change, balance.denomination), since.block, since.balance)
+        );
+    }
+    if let Some(block) = &balance.block {
+        println!("{} {} ({})", label(Msg::LabelBlock), block.number, block.hash);
+    }
+    print_rule();
+}
+
+/// Copy of `balance` with the amount rounded for `precision`
+fn rounded(balance: &WalletBalance, decimals: u32, precision: units::Precision) -> WalletBalance {
+    let mut rounded = balance.clone();
+    if let Ok(amount) = precision.apply(&balance.balance, decimals, &balance.denomination) {
+        rounded.balance = amount;
+    }
+    rounded
+}
+
+fn exit_with_fetch_error(e: WalletError) -> ! {
+    if output() == OutputFormat::Json {
+        print_error(format!("{:#}", e));
+        exit(exit_code(&e));
+    }
+    report_error(format!("\n{}", marked("❌", t!(FetchError, format!("{:#}", e)))));
+    notice(format!("\n{}", t!(PleaseCheck)));
+    for hint in [t!(CheckAddress), t!(CheckNetwork), t!(CheckConnectivity)] {
+        notice(format!("  {} {}", bullet(), hint));
+    }
+    exit(exit_code(&e));
+}
+
+fn run_derive(key: &str, path: &str, range: std::ops::Range<u32>) {
+    match bitcoin_hd::derive_addresses(key, path, range) {
+        Ok(addresses) => {
+            if plain() {
+                for (index, derived) in addresses.into_iter().enumerate() {
+                    if index > 0 {
+                        println!();
+                    }
+                    println!(
+                        "{}",
+                        format_record(&[
+                            (Msg::HeaderPath, derived.path),
+                            (Msg::HeaderType, derived.address_type.to_string()),
+                            (Msg::HeaderAddress, derived.address),
+                        ])
+                    );
+                }
+                return;
+            }
+            println!("{:<12} {:<12} {}", t!(HeaderPath), t!(HeaderType), t!(HeaderAddress));
+            for derived in addresses {
+                println!(
+                    "{:<12} {:<12} {}",
+                    derived.path,
+                    derived.address_type.to_string(),
+                    derived.address
+                );
+            }
+        }
+        Err(e) => {
+            report_error(marked("❌", t!(DeriveFailed, e)));
+            exit(1);
+        }
+    }
+}
+
+async fn run_ordinals(address: &str, ignore_dust: Option<u64>, source: BalanceSource) {
+    if source != BalanceSource::Live {
+        print_error(t!(OrdinalsNeedLive));
+        exit(1);
+    }
+    note_network(Network::Bitcoin);
+    status_line(t!(FetchingOrdinals, address));
+    let report = match ordinals::get_report(address, ignore_dust).await {
+        Ok(report) => report,
+        Err(e) => exit_with_fetch_error(e),
+    };
+    if output() == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report).expect("ordinals report serializes"));
+        return;
+    }
+
+    let btc = |sats: u64| format!("{} BTC", units::format_units(sats.into(), 8));
+    println!("\n{}", marked("✅", t!(Success)));
+    print_rule();
+    println!("{} {}", label(Msg::LabelAddress), report.address);
+    println!("{} {}", label(Msg::LabelInscriptions), report.inscription_count);
+    println!("{} {}", label(Msg::LabelSpendable), btc(report.spendable_sats));
+    println!(
+        "{} {}",
+        label(Msg::LabelInscribed),
+        t!(InscribedIn, btc(report.inscribed_sats), report.inscribed_utxos.len())
+    );
+    if let Some(threshold) = report.dust_threshold {
+        println!(
+            "{} {}",
+            label(Msg::LabelDust),
+            t!(DustIn, btc(report.dust_sats), report.dust_utxos.len(), threshold)
+        );
+    }
+    print_rule();
+
+    if !report.brc20.is_empty() {
+        if !plain() {
+            println!(
+                "\n{:<10} {:>24} {:>24} {:>24}",
+                t!(HeaderTicker),
+                t!(HeaderAvailable),
+                t!(HeaderTransferable),
+                t!(HeaderOverall)
+            );
+        }
+        for token in &report.brc20 {
+            if plain() {
+                let record = format_record(&[
+                    (Msg::HeaderTicker, token.ticker.clone()),
+                    (Msg::HeaderAvailable, token.available_balance.clone()),
+                    (Msg::HeaderTransferable, token.transferrable_balance.clone()),
+                    (Msg::HeaderOverall, token.overall_balance.clone()),
+                ]);
+                println!("\n{}", record);
+            } else {
+                println!(
+                    "{:<10} {:>24} {:>24} {:>24}",
+                    token.ticker, token.available_balance, token.transferrable_balance, token.overall_balance
+                );
+            }
+        }
+    }
+
+    if !report.inscribed_utxos.is_empty() {
+        tracing::warn!(target: CLI_TARGET, "\n{}", warning(t!(InscribedWarning)));
+        if !plain() {
+            println!("\n{:<68} {:>14} {}", t!(HeaderOutpoint), t!(HeaderValue), t!(HeaderInscriptions));
+        }
+        for utxo in &report.inscribed_utxos {
+            if plain() {
+                let record = format_record(&[
+                    (Msg::HeaderOutpoint, utxo.outpoint.clone()),
+                    (Msg::HeaderValue, btc(utxo.value)),
+                    (Msg::HeaderInscriptions, utxo.inscriptions.join(", ")),
+                ]);
+                println!("\n{}", record);
+            } else {
+                println!("{:<68} {:>14} {}", utxo.outpoint, btc(utxo.value), utxo.inscriptions.join(", "));
+            }
+        }
+    }
+
+    if !report.dust_utxos.is_empty() {
+        tracing::warn!(target: CLI_TARGET, "\n{}", warning(t!(DustWarning)));
+        if !plain() {
+            println!("\n{:<68} {:>14}", t!(HeaderOutpoint), t!(HeaderValue));
+        }
+        for utxo in &report.dust_utxos {
+            if plain() {
+                let record = format_record(&[
+                    (Msg::HeaderOutpoint, utxo.outpoint.clone()),
+                    (Msg::HeaderValue, btc(utxo.value)),
+                ]);
+                println!("\n{}", record);
+            } else {
+                println!("{:<68} {:>14}", utxo.outpoint, btc(utxo.value));
+            }
+        }
+    }
+}
+
+/// Print the cold storage checklist of `address` as markdown, JSON or CSV
+async fn run_audit(address: &str, network: Network, source: BalanceSource) {
+    note_network(network);
+    let report = audit::run(network, address, &source).await;
+
+    match output() {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&report).expect("audit report serializes"));
+        }
+        OutputFormat::Csv => {
+            println!("check,status,detail");
+            for check in &report.checks {
+                let status = serde_json::to_value(check.status).expect("status serializes");
+                println!("{}", csv_line(&[check.id, status.as_str().unwrap_or_default(), &check.detail]));
+            }
+        }
+        OutputFormat::Text => {
+            println!("# {}\n", t!(AuditTitle, report.address, report.network));
+            for check in &report.checks {
+                let title = match check.id {
+                    "address" => t!(AuditAddress),
+                    "checksum" => t!(AuditChecksum),
+                    "type" => t!(AuditType),
+                    "balance" => t!(AuditBalance),
+                    "activity" => t!(AuditActivity),
+                    "screening" => t!(AuditScreening),
+                    _ => t!(AuditCrossCheck),
+                };
+                let (box_mark, status) = match check.status {
+                    audit::CheckStatus::Pass => ("x", String::new()),
+                    audit::CheckStatus::Warn => ("x", format!("{}: ", t!(AuditWarn))),
+                    audit::CheckStatus::Fail => (" ", format!("{}: ", t!(AuditFail))),
+                    audit::CheckStatus::Skip => (" ", format!("{}: ", t!(AuditSkip))),
+                };
+                println!("- [{}] **{}**: {}{}", box_mark, title, status, check.detail);
+            }
+            let result = if report.passed { t!(AuditPassed) } else { t!(AuditFailed) };
+            println!(
+                "\n**{}** {}",
+                result,
+                t!(
+                    AuditSummary,
+                    report.checks.len(),
+                    report.count(audit::CheckStatus::Fail),
+                    report.count(audit::CheckStatus::Warn)
+                )
+            );
+        }
+    }
+
+    if !report.passed {
+        exit(EXIT_AUDIT_FAILED);
+    }
+}
+
+/// Evaluate an assertions file and print each assertion's outcome
+async fn run_check(file: &std::path::Path, source: BalanceSource) {
+    let assertions = match assertions::load(file) {
+        Ok(assertions) => assertions,
+        Err(e) => {
+            print_error(format!("{:#}", e));
+            exit(exit_code(&e));
+        }
+    };
+    for assertion in &assertions {
+        note_network(assertion.network);
+    }
+    let report = assertions::evaluate(&source, &assertions).await;
+
+    match output() {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&report).expect("assertion report serializes"));
+        }
+        OutputFormat::Csv => {
+            println!("name,network,address,balance,denomination,tip_age_secs,passed,failures");
+            for result in &report.assertions {
+                let tip_age = result.tip_age_secs.map(|age| age.to_string()).unwrap_or_default();
+                println!(
+                    "{}",
+                    csv_line(&[
+                        &result.name,
+                        &result.network,
+                        &result.address,
+                        result.balance.as_deref().unwrap_or_default(),
+                        &result.denomination,
+                        &tip_age,
+                        if result.passed { "true" } else { "false" },
+                        &result.failures.join("; "),
+                    ])
+                );
+            }
+        }
+        OutputFormat::Text => {
+            println!("# {}\n", t!(AssertionsTitle, file.display()));
+            for result in &report.assertions {
+                let balance = match &result.balance {
+                    Some(balance) => format!("{} {}", balance, result.denomination),
+                    None => "-".to_string(),
+                };
+                match result.passed {
+                    true => println!("- [x] **{}** ({}): {}", result.name, result.network, balance),
+                    false => println!(
+                        "- [ ] **{}** ({}): {}: {}",
+                        result.name,
+                        result.network,
+                        t!(AuditFail),
+                        result.failures.join("; ")
+                    ),
+                }
+            }
+            let failed = report.assertions.iter().filter(|result| !result.passed).count();
+            let outcome = if report.passed { t!(AuditPassed) } else { t!(AuditFailed) };
+            println!("\n**{}** {}", outcome, t!(AssertionsSummary, report.assertions.len(), failed));
+        }
+    }
+
+    if !report.passed {
+        exit(EXIT_ASSERTION_FAILED);
+    }
+}
+
+/// Whether an L1 deposit to a rollup has been credited, and the recipient's L2 balance
+async fn run_deposit_status(tx: &str, source: BalanceSource) {
+    if source != BalanceSource::Live {
+        print_error(t!(DepositNeedsLive));
+        exit(1);
+    }
+    status_line(t!(FetchingDeposit, tx.trim()));
+    let status = match deposit::status(tx).await {
+        Ok(status) => status,
+        Err(e) => exit_with_fetch_error(e),
+    };
+    note_network(status.l2_balance.network.parse().unwrap_or(Network::Ethereum));
+
+    match output() {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&status).expect("deposit status serializes"));
+        }
+        OutputFormat::Csv => {
+            println!("l1_tx,network,recipient,amount,l2_tx,credited,l2_block,l2_balance");
+            let l2_block = status.l2_block.map(|block| block.to_string()).unwrap_or_default();
+            println!(
+                "{}",
+                csv_line(&[
+                    &status.l1_tx,
+                    &status.network,
+                    &status.recipient,
+                    &status.amount,
+                    &status.l2_tx,
+                    &status.credited.to_string(),
+                    &l2_block,
+                    &status.l2_balance.balance,
+                ])
+            );
+        }
+        OutputFormat::Text => {
+            let state = match status.l2_block {
+                Some(block) if status.credited => t!(DepositCredited, status.network, block),
+                _ => t!(DepositPending, status.network),
+            };
+            println!("\n{}", marked("✅", t!(Success)));
+            print_rule();
+            println!("{} {}", label(Msg::LabelNetwork), status.network);
+            println!("{} {}", label(Msg::LabelAddress), status.recipient);
+            println!("{} {} ETH", label(Msg::LabelDeposit), status.amount);
+            println!("{} {}", label(Msg::LabelStatus), state);
+            println!("{} {}", label(Msg::LabelL2Tx), status.l2_tx);
+            println!("{} {} {}", label(Msg::LabelBalance), status.l2_balance.balance, status.l2_balance.denomination);
+            print_rule();
+        }
+    }
+}
+
+/// A page of an address's transactions, with the cursor of the next one
+async fn run_history(address: &str, network: Network, limit: usize, before: Option<&str>, source: BalanceSource) {
+    if source != BalanceSource::Live {
+        print_error(t!(HistoryNeedsLive));
+        exit(1);
+    }
+    note_network(network);
+    status_line(t!(FetchingHistory, network.display_name(), address.trim()));
+    let page = match history::get_history(network, address, limit, before).await {
+        Ok(page) => page,
+        Err(e) => exit_with_fetch_error(e),
+    };
+
+    match output() {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&page).expect("history pages serialize"));
+        }
+        OutputFormat::Csv => {
+            println!("hash,block,timestamp,change,denomination");
+            for tx in &page.transactions {
+                let block = tx.block.map(|block| block.to_string()).unwrap_or_default();
+                let timestamp = tx.timestamp.map(|timestamp| timestamp.to_string()).unwrap_or_default();
+                println!("{}", csv_line(&[&tx.hash, &block, &timestamp, &tx.change, &tx.denomination]));
+            }
+        }
+        OutputFormat::Text => {
+            println!("\n{}", marked("✅", t!(Success)));
+            print_rule();
+            println!("{} {}", label(Msg::LabelAddress), page.address);
+            println!("{} {}", label(Msg::LabelSource), page.source);
+            print_rule();
+            if page.transactions.is_empty() {
+                println!("{}", t!(NoTransactions));
+            } else if !plain() {
+                println!("\n{:<20} {:>10} {:<66} {:>24}", t!(HeaderTime), t!(HeaderBlock), t!(HeaderHash), t!(HeaderChange));
+            }
+            for tx in &page.transactions {
+                let time = tx
+                    .timestamp
+                    .map(|timestamp| format!("{} {}", usage::format_date(timestamp), watch::format_time(timestamp)))
+                    .unwrap_or_default();
+                let block = match tx.block {
+                    Some(block) => block.to_string(),
+                    None => t!(Unconfirmed),
+                };
+                let change = format!("{} {}", tx.change, tx.denomination);
+                if plain() {
+                    let record = format_record(&[
+                        (Msg::HeaderTime, time),
+                        (Msg::HeaderBlock, block),
+                        (Msg::HeaderHash, tx.hash.clone()),
+                        (Msg::HeaderChange, change),
+                    ]);
+                    println!("\n{}", record);
+                } else {
+                    println!("{:<20} {:>10} {:<66} {:>24}", time, block, tx.hash, change);
+                }
+            }
+            if let Some(next) = &page.next {
+                println!("\n{}", t!(HistoryMore, next));
+            }
+        }
+    }
+}
+
+/// Unspent outputs of a Bitcoin address with their confirmations
+async fn run_utxos(source: &BalanceSource, network: Network, address: &str) {
+    let Some(chain) = network.bitcoin_chain() else {
+        print_error(t!(UtxosUnsupported, network));
+        exit(EXIT_UNSUPPORTED_NETWORK);
+    };
+    if *source != BalanceSource::Live {
+        print_error(t!(UtxosNeedLive));
+        exit(1);
+    }
+    status_line(t!(FetchingUtxos, address));
+    let fetched = tokio::try_join!(bitcoin_wallet::get_utxos_on(chain, address), bitcoin_wallet::get_tip_height_on(chain));
+    let (mut utxos, tip) = match fetched {
+        Ok(fetched) => fetched,
+        Err(e) => exit_with_fetch_error(e),
+    };
+    // Oldest first, with mempool outputs last
+    utxos.sort_by_key(|utxo| (utxo.status.block_height.unwrap_or(u64::MAX), utxo.txid.clone(), utxo.vout));
+
+    match output() {
+        OutputFormat::Json => {
+            let listed: Vec<_> = utxos
+                .iter()
+                .map(|utxo| {
+                    serde_json::json!({
+                        "txid": utxo.txid,
+                        "vout": utxo.vout,
+                        "value": utxo.value,
+                        "confirmations": utxo.confirmations(tip),
+                    })
+                })
+                .collect();
+            let document = serde_json::json!({
+                "network": network.to_string(),
+                "address": address,
+                "tip_height": tip,
+                "utxos": listed,
+            });
+            println!("{}", serde_json::to_string_pretty(&document).expect("JSON values serialize"));
+        }
+        OutputFormat::Csv => {
+            println!("txid,vout,value,confirmations");
+            for utxo in &utxos {
+                println!("{},{},{},{}", utxo.txid, utxo.vout, utxo.value, utxo.confirmations(tip));
+            }
+        }
+        OutputFormat::Text => {
+            let amount = |sats: u64| format!("{} {}", units::format_units(sats.into(), 8), network.denomination());
+            println!("\n{}", marked("✅", t!(Success)));
+            print_rule();
+            println!("{} {}", label(Msg::LabelAddress), address);
+            println!(
+                "{} {}",
+                label(Msg::LabelUtxos),
+                t!(UtxoSummary, utxos.len(), amount(utxos.iter().map(|utxo| utxo.value).sum()))
+            );
+            print_rule();
+            if utxos.is_empty() {
+                println!("{}", t!(NoUtxos));
+                return;
+            }
+            if !plain() {
+                println!("\n{:<64} {:>5} {:>20} {:>13}", t!(HeaderTxid), t!(HeaderVout), t!(HeaderValue), t!(HeaderConfirmations));
+            }
+            for utxo in &utxos {
+                if plain() {
+                    let record = format_record(&[
+                        (Msg::HeaderTxid, utxo.txid.clone()),
+                        (Msg::HeaderVout, utxo.vout.to_string()),
+                        (Msg::HeaderValue, amount(utxo.value)),
+                        (Msg::HeaderConfirmations, utxo.confirmations(tip).to_string()),
+                    ]);
+                    println!("\n{}", record);
+                } else {
+                    println!("{:<64} {:>5} {:>20} {:>13}", utxo.txid, utxo.vout, amount(utxo.value), utxo.confirmations(tip));
+                }
+            }
+        }
+    }
+}
+
+/// Token balances a plain balance lookup cannot see; on bitcoin these are runes
+async fn run_tokens(address: &str, network: Network, source: BalanceSource) {
+    if network != Network::Bitcoin {
+        print_error(t!(TokensUnsupported, network));
+        exit(EXIT_UNSUPPORTED_NETWORK);
+    }
+    if source != BalanceSource::Live {
+        print_error(t!(TokensNeedLive));
+        exit(1);
+    }
+    note_network(network);
+    status_line(t!(FetchingRunes, address));
+    let address = address.trim();
+    let runes = match runes::get_balances(address).await {
+        Ok(runes) => runes,
+        Err(e) => exit_with_fetch_error(e),
+    };
+
+    match output() {
+        OutputFormat::Json => {
+            let document = serde_json::json!({
+                "network": network.to_string(),
+                "address": address,
+                "runes": runes,
+            });
+            println!("{}", serde_json::to_string_pretty(&document).expect("JSON values serialize"));
+        }
+        OutputFormat::Csv => {
+            println!("{}", csv_header());
+            for rune in &runes {
+                let row = WalletBalance::new(address.to_string(), rune.balance.clone(), network.to_string(), rune.name.clone());
+                println!("{}", csv_row(&row));
+            }
+        }
+        OutputFormat::Text => {
+            println!("\n{}", marked("✅", t!(Success)));
+            print_rule();
+            println!("{} {}", label(Msg::LabelAddress), address);
+            print_rule();
+            if runes.is_empty() {
+                println!("{}", t!(NoRunes));
+                return;
+            }
+            if !plain() {
+                println!("\n{:<32} {:<16} {:>24}", t!(HeaderRune), t!(HeaderRuneId), t!(HeaderBalance));
+            }
+            for rune in &runes {
+                if plain() {
+                    let record = format_record(&[
+                        (Msg::HeaderRune, rune.name.clone()),
+                        (Msg::HeaderRuneId, rune.id.clone()),
+                        (Msg::HeaderBalance, rune.balance.clone()),
+                    ]);
+                    println!("\n{}", record);
+                } else {
+                    println!("{:<32} {:<16} {:>24}", rune.name, rune.id, rune.balance);
+                }
+            }
+        }
+    }
+}
+
+/// Balance of every used address of an extended key, and their total
+async fn run_xpub(key: &str, gap_limit: u32, network: Network, source: BalanceSource) {
+    let Some(chain) = network.bitcoin_chain() else {
+        print_error(t!(XpubUnsupported, network));
+        exit(EXIT_UNSUPPORTED_NETWORK);
+    };
+    if source != BalanceSource::Live {
+        print_error(t!(XpubNeedsLive));
+        exit(1);
+    }
+    if let Err(e) = bitcoin_hd::derive_addresses(key, bitcoin_hd::DEFAULT_PATH, 0..1) {
+        report_error(marked("❌", t!(DeriveFailed, e)));
+        exit(1);
+    }
+    note_network(network);
+    status_line(t!(ScanningXpub, network, gap_limit));
+    let scan = match bitcoin_hd::scan(key, chain, gap_limit).await {
+        Ok(scan) => scan,
+        Err(e) => exit_with_fetch_error(e),
+    };
+
+    match output() {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&scan).expect("xpub scan serializes"));
+        }
+        OutputFormat::Csv => {
+            println!("{}", csv_header());
+            for used in &scan.addresses {
+                let row = WalletBalance::new(
+                    used.derived.address.clone(),
+                    used.balance.clone(),
+                    scan.network.clone(),
+                    network.denomination().to_string(),
+                );
+                println!("{}", csv_row(&row));
+            }
+        }
+        OutputFormat::Text => {
+            println!("\n{}", marked("✅", t!(Success)));
+            print_rule();
+            println!("{} {}", label(Msg::LabelNetwork), scan.network);
+            println!("{} {} {}", label(Msg::LabelBalance), scan.balance, network.denomination());
+            print_rule();
+            if scan.addresses.is_empty() {
+                println!("{}", t!(NoUsedAddresses));
+            } else if !plain() {
+                println!("\n{:<12} {:<12} {:<64} {:>16}", t!(HeaderPath), t!(HeaderType), t!(HeaderAddress), t!(HeaderBalance));
+            }
+            for used in &scan.addresses {
+                if plain() {
+                    let record = format_record(&[
+                        (Msg::HeaderPath, used.derived.path.clone()),
+                        (Msg::HeaderType, used.derived.address_type.to_string()),
+                        (Msg::HeaderAddress, used.derived.address.clone()),
+                        (Msg::HeaderBalance, used.balance.clone()),
+                    ]);
+                    println!("\n{}", record);
+                } else {
+                    println!(
+                        "{:<12} {:<12} {:<64} {:>16}",
+                        used.derived.path,
+                        used.derived.address_type.to_string(),
+                        used.derived.address,
+                        used.balance
+                    );
+                }
+            }
+            println!("\n{}", t!(XpubScanned, scan.scanned, scan.addresses.len()));
+        }
+    }
+}
+
+fn run_convert(input: &str, target: AddressFormat, tag: Option<u32>) {
+    match address::convert(input, target, tag) {
+        Ok(converted) => println!("{}", converted),
+        Err(e) => {
+            report_error(marked("❌", t!(ConvertFailed, e)));
+            exit(1);
+        }
+    }
+}
+
+/// Parse an index range such as `0..20` (end exclusive)
+fn parse_range(s: &str) -> Result<std::ops::Range<u32>, String> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| "expected a range like 0..20".to_string())?;
+    let start: u32 = start.trim().parse().map_err(|_| format!("invalid range start '{}'", start))?;
+    let end: u32 = end.trim().parse().map_err(|_| format!("invalid range end '{}'", end))?;
+    if start >= end {
+        return Err("range end must be greater than start".to_string());
     }
+    Ok(start..end)
 }
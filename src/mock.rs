@@ -0,0 +1,58 @@
+//! Deterministic mock balance provider
+//!
+//! Returns pseudo-random balances derived from a hash of the network and
+//! address, so demos, tutorials and CI smoke tests of downstream tooling get
+//! stable output with no network access. Addresses are still validated with
+//! the same rules as the live modules.
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+
+use crate::{units, Network, WalletBalance, WalletError};
+
+/// Mock balances stay below this many whole coins
+const MAX_WHOLE_UNITS: u128 = 1_000;
+
+/// Get a deterministic mock balance for `address` on `network`
+///
+/// # Returns
+///
+/// Returns a `WalletBalance` formatted the same way as the live module's output
+pub fn get_balance(network: Network, address: &str) -> Result<WalletBalance, WalletError> {
+    let address = crate::normalize_address(network, address)?;
+
+    let digest = Sha256::digest(format!("{}:{}", network, address).as_bytes());
+    let seed = u128::from_be_bytes(digest[..16].try_into().expect("digest is 32 bytes"));
+    let decimals = network.decimals();
+    let scale = 10u128.checked_pow(decimals).ok_or_else(|| {
+        WalletError::ParseError(format!("{} decimals do not fit in 128 bits for a mock balance", decimals))
+    })?;
+    // Fewer whole units when the full range would overflow
+    let units_value = seed % MAX_WHOLE_UNITS.saturating_mul(scale);
+
+    // Match the live modules: fixed decimals for BTC/TRX/DOGE/LTC, trimmed for EVM chains
+    let balance = match network {
+        Network::Bitcoin
+        | Network::BitcoinTestnet4
+        | Network::BitcoinSignet
+        | Network::Tron
+        | Network::TronShasta
+        | Network::Dogecoin
+        | Network::Litecoin => {
+            format!(
+                "{}.{:0width$}",
+                units_value / scale,
+                units_value % scale,
+                width = decimals as usize
+            )
+        }
+        _ => units::format_units(units_value, decimals),
+    };
+
+    Ok(WalletBalance::new(
+        address,
+        balance,
+        network.to_string(),
+        network.denomination().to_string(),
+    ))
+}
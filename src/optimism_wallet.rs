@@ -0,0 +1,46 @@
+//! Optimism L2 wallet balance checking functionality
+//!
+//! This module provides functions to check Optimism L2 wallet balances
+//! using the Optimism Foundation's public RPC endpoint. The
+//! requests are made by `evm_wallet`, which serves every EVM chain.
+
+use anyhow::Result;
+
+use crate::evm_wallet::{self, EvmChain};
+use crate::{Network, TokenInfo, WalletBalance, WalletError};
+
+fn chain() -> &'static EvmChain {
+    evm_wallet::chain(Network::Optimism).expect("Optimism is in the EVM chain table")
+}
+
+/// Get Optimism wallet balance for a given address
+///
+/// # Arguments
+///
+/// * `address` - Ethereum address to check on Optimism network
+///
+/// # Returns
+///
+/// Returns a `WalletBalance` containing the balance in ETH
+pub async fn get_balance(address: &str) -> Result<WalletBalance, WalletError> {
+    evm_wallet::get_balance(chain(), address).await
+}
+
+/// Get ERC20 token balance of a wallet on Optimism
+///
+/// # Arguments
+///
+/// * `token_address` - ERC20 token contract address (0x prefixed)
+/// * `wallet_address` - Wallet address to check balance for (0x prefixed)
+///
+/// # Returns
+///
+/// Returns token balance as a decimal string, scaled by the token's own `decimals()`
+pub async fn get_erc20_balance(token_address: &str, wallet_address: &str) -> Result<String, WalletError> {
+    evm_wallet::get_erc20_balance(chain(), token_address, wallet_address).await
+}
+
+/// Symbol and decimals of an ERC20 token on Optimism
+pub async fn get_erc20_token(token_address: &str) -> Result<TokenInfo, WalletError> {
+    evm_wallet::get_erc20_token(chain(), token_address).await
+}
@@ -0,0 +1,197 @@
+//! Ordinals and BRC-20 awareness for Bitcoin addresses
+//!
+//! Inscriptions live on individual satoshis, so a UTXO that carries one is
+//! worth far more (or at least something different) than its sat value and
+//! must not be spent as ordinary change. `get_report` combines Hiro's Ordinals
+//! API (inscriptions and BRC-20 balances) with the address's UTXOs from
+//! Esplora, and splits the confirmed sats into spendable and inscribed.
+//!
+//! With a dust threshold, tiny uninscribed UTXOs are split off as well: dusting
+//! attacks send them so that spending them alongside other coins links the
+//! addresses, so they are listed and left out of the spendable total.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::bitcoin_wallet::{self, Utxo};
+use crate::http;
+use crate::WalletError;
+
+pub(crate) const HIRO_ORDINALS_API: &str = "https://api.hiro.so/ordinals/v1";
+
+/// Page size of Hiro's inscription listing (its maximum)
+const INSCRIPTION_PAGE_SIZE: u64 = 60;
+
+/// One inscription held by the address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Inscription {
+    pub id: String,
+    pub number: i64,
+    /// `txid:vout` of the UTXO currently carrying the inscription
+    pub output: String,
+}
+
+/// A BRC-20 ticker balance, as decimal strings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Brc20Balance {
+    pub ticker: String,
+    pub available_balance: String,
+    pub transferrable_balance: String,
+    pub overall_balance: String,
+}
+
+/// A UTXO carrying at least one inscription
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InscribedUtxo {
+    pub outpoint: String,
+    /// Value in satoshis
+    pub value: u64,
+    /// Ids of the inscriptions it carries
+    pub inscriptions: Vec<String>,
+}
+
+/// An uninscribed UTXO at or below the dust threshold
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DustUtxo {
+    pub outpoint: String,
+    /// Value in satoshis
+    pub value: u64,
+}
+
+/// Ordinals view of one address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OrdinalsReport {
+    pub address: String,
+    pub inscription_count: u64,
+    pub brc20: Vec<Brc20Balance>,
+    pub inscribed_utxos: Vec<InscribedUtxo>,
+    /// Sats in UTXOs without inscriptions, less any dust
+    pub spendable_sats: u64,
+    /// Sats in UTXOs carrying inscriptions
+    pub inscribed_sats: u64,
+    /// Dust threshold in sats, when one was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dust_threshold: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dust_utxos: Vec<DustUtxo>,
+    /// Sats in `dust_utxos`
+    pub dust_sats: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Page<T> {
+    total: u64,
+    results: Vec<T>,
+}
+
+/// Build the ordinals report for a Bitcoin mainnet `address`
+///
+/// With `dust_threshold`, uninscribed UTXOs of at most that many sats are
+/// reported as dust and not counted as spendable.
+pub async fn get_report(address: &str, dust_threshold: Option<u64>) -> Result<OrdinalsReport, WalletError> {
+    let address = address.trim();
+    bitcoin_wallet::validate_address(address)?;
+
+    let api_base = bitcoin_wallet::Chain::Mainnet.api_base();
+    let (inscriptions, brc20, utxos) = tokio::try_join!(
+        get_inscriptions(address),
+        get_brc20_balances(address),
+        bitcoin_wallet::get_utxos_from(&api_base, address),
+    )?;
+
+    let inscription_count = inscriptions.len() as u64;
+    let (inscribed_utxos, spendable_sats) = classify_utxos(&utxos, &inscriptions);
+    let dust_utxos = match dust_threshold {
+        Some(threshold) => find_dust(&utxos, &inscribed_utxos, threshold),
+        None => Vec::new(),
+    };
+    let dust_sats = dust_utxos.iter().map(|utxo| utxo.value).sum();
+    Ok(OrdinalsReport {
+        address: address.to_string(),
+        inscription_count,
+        brc20,
+        inscribed_sats: inscribed_utxos.iter().map(|utxo| utxo.value).sum(),
+        inscribed_utxos,
+        spendable_sats: spendable_sats.saturating_sub(dust_sats),
+        dust_threshold,
+        dust_utxos,
+        dust_sats,
+    })
+}
+
+/// The UTXOs of `utxos` worth at most `threshold` sats, other than the `inscribed` ones
+///
+/// Inscriptions usually sit on UTXOs of a few hundred sats; those are
+/// reported as inscribed, not as dust.
+pub fn find_dust(utxos: &[Utxo], inscribed: &[InscribedUtxo], threshold: u64) -> Vec<DustUtxo> {
+    utxos
+        .iter()
+        .filter(|utxo| utxo.value <= threshold)
+        .map(|utxo| DustUtxo {
+            outpoint: utxo.outpoint(),
+            value: utxo.value,
+        })
+        .filter(|dust| !inscribed.iter().any(|utxo| utxo.outpoint == dust.outpoint))
+        .collect()
+}
+
+/// Split `utxos` into those carrying one of `inscriptions` and the spendable sats of the rest
+pub fn classify_utxos(utxos: &[Utxo], inscriptions: &[Inscription]) -> (Vec<InscribedUtxo>, u64) {
+    let mut by_output: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for inscription in inscriptions {
+        by_output.entry(inscription.output.as_str()).or_default().push(inscription.id.clone());
+    }
+
+    let mut inscribed = Vec::new();
+    let mut spendable = 0u64;
+    for utxo in utxos {
+        let outpoint = utxo.outpoint();
+        match by_output.get(outpoint.as_str()) {
+            Some(ids) => inscribed.push(InscribedUtxo {
+                outpoint,
+                value: utxo.value,
+                inscriptions: ids.clone(),
+            }),
+            None => spendable = spendable.saturating_add(utxo.value),
+        }
+    }
+    (inscribed, spendable)
+}
+
+/// Every inscription held by `address`, following Hiro's pagination
+async fn get_inscriptions(address: &str) -> Result<Vec<Inscription>> {
+    let mut inscriptions = Vec::new();
+    loop {
+        let url = format!(
+            "{}/inscriptions?address={}&limit={}&offset={}",
+            HIRO_ORDINALS_API,
+            address,
+            INSCRIPTION_PAGE_SIZE,
+            inscriptions.len()
+        );
+        let page: Page<Inscription> = get_json(&url).await?;
+        let fetched = page.results.len();
+        inscriptions.extend(page.results);
+        if fetched == 0 || inscriptions.len() as u64 >= page.total {
+            return Ok(inscriptions);
+        }
+    }
+}
+
+async fn get_brc20_balances(address: &str) -> Result<Vec<Brc20Balance>> {
+    let url = format!("{}/brc-20/balances/{}?limit=60", HIRO_ORDINALS_API, address);
+    let page: Page<Brc20Balance> = get_json(&url).await?;
+    Ok(page.results)
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T> {
+    let client = http::client();
+    let response = http::send(client.get(url).header("Accept", "application/json"))
+        .await
+        .context("Failed to send request to Hiro Ordinals API")?;
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("Hiro Ordinals API failed: {} - {}", response.status, response.text()));
+    }
+    response.json().context("Failed to parse JSON from Hiro Ordinals API")
+}
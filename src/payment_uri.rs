@@ -0,0 +1,178 @@
+//! Payment URI parsing
+//!
+//! This module understands BIP-21 (`bitcoin:`) and EIP-681 (`ethereum:`)
+//! payment requests, extracting the payee address, chain, token contract and
+//! requested amount so the CLI can check the payee's balance against it.
+
+use anyhow::{Context, Result};
+
+use crate::{units, Network, WalletError};
+
+/// A parsed payment request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentRequest {
+    /// Network implied by the URI scheme and chain id
+    pub network: Network,
+    /// Address the payment is addressed to (the wallet to check)
+    pub address: String,
+    /// EIP-155 chain id given with `@`, if any
+    pub chain_id: Option<u64>,
+    /// ERC-20 contract for `transfer` requests
+    pub token_contract: Option<String>,
+    /// Requested amount in base units (satoshi, wei or token base units)
+    pub amount: Option<u128>,
+}
+
+/// Parse a BIP-21 or EIP-681 payment URI
+///
+/// # Arguments
+///
+/// * `uri` - Candidate URI, e.g. `bitcoin:1A1z...?amount=0.01`
+///
+/// # Returns
+///
+/// Returns `Ok(None)` when the input is not a payment URI at all, and an error
+/// when it is one but cannot be understood
+pub fn parse(uri: &str) -> Result<Option<PaymentRequest>, WalletError> {
+    Ok(parse_inner(uri, None)?)
+}
+
+/// Like `parse`, but map chain ids this crate does not know (local dev
+/// chains such as anvil's 31337) to `fallback` instead of rejecting them
+pub fn parse_with_fallback(uri: &str, fallback: Network) -> Result<Option<PaymentRequest>, WalletError> {
+    Ok(parse_inner(uri, Some(fallback))?)
+}
+
+fn parse_inner(uri: &str, unknown_chain: Option<Network>) -> Result<Option<PaymentRequest>> {
+    let Some((scheme, rest)) = uri.split_once(':') else {
+        return Ok(None);
+    };
+
+    match scheme.to_lowercase().as_str() {
+        "bitcoin" => parse_bip21(rest).map(Some),
+        "ethereum" => parse_eip681(rest, unknown_chain).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// `bitcoin:<address>[?amount=<btc>][&label=...][&message=...]`
+fn parse_bip21(rest: &str) -> Result<PaymentRequest> {
+    let (address, params) = split_query(rest);
+    if address.is_empty() {
+        return Err(anyhow::anyhow!("BIP-21 URI is missing an address"));
+    }
+
+    let mut amount = None;
+    for (key, value) in params {
+        match key {
+            "amount" => {
+                amount = Some(
+                    units::parse_units(value, Network::Bitcoin.decimals())
+                        .context("Invalid BIP-21 amount")?,
+                )
+            }
+            // BIP-21 requires rejecting URIs with unknown required parameters
+            key if key.starts_with("req-") => {
+                return Err(anyhow::anyhow!(
+                    "Unsupported required BIP-21 parameter '{}'",
+                    key
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    Ok(PaymentRequest {
+        network: Network::Bitcoin,
+        address: address.to_string(),
+        chain_id: None,
+        token_contract: None,
+        amount,
+    })
+}
+
+/// `ethereum:[pay-]<target>[@chain_id][/function_name][?params]`
+fn parse_eip681(rest: &str, unknown_chain: Option<Network>) -> Result<PaymentRequest> {
+    let (path, params) = split_query(rest);
+    let path = path.strip_prefix("pay-").unwrap_or(path);
+
+    let (target, function) = match path.split_once('/') {
+        Some((target, function)) => (target, Some(function)),
+        None => (path, None),
+    };
+    let (target, chain_id) = match target.split_once('@') {
+        Some((target, chain_id)) => (
+            target,
+            Some(
+                chain_id
+                    .parse::<u64>()
+                    .with_context(|| format!("Invalid chain id '{}'", chain_id))?,
+            ),
+        ),
+        None => (target, None),
+    };
+
+    if !target.starts_with("0x") {
+        return Err(anyhow::anyhow!(
+            "EIP-681 target '{}' is not a hex address (ENS names are not supported)",
+            target
+        ));
+    }
+
+    let network = Network::from_chain_id(chain_id.unwrap_or(1))
+        .or(unknown_chain)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported chain id {}", chain_id.unwrap_or(1)))?;
+
+    let param = |name: &str| params.iter().find(|(key, _)| *key == name).map(|(_, value)| *value);
+
+    match function {
+        None => Ok(PaymentRequest {
+            network,
+            address: target.to_string(),
+            chain_id,
+            token_contract: None,
+            amount: param("value")
+                .map(parse_amount)
+                .transpose()
+                .context("Invalid EIP-681 value")?,
+        }),
+        Some("transfer") => {
+            let recipient = param("address")
+                .ok_or_else(|| anyhow::anyhow!("EIP-681 transfer URI is missing the 'address' parameter"))?;
+            Ok(PaymentRequest {
+                network,
+                address: recipient.to_string(),
+                chain_id,
+                token_contract: Some(target.to_string()),
+                amount: param("uint256")
+                    .map(parse_amount)
+                    .transpose()
+                    .context("Invalid EIP-681 uint256 amount")?,
+            })
+        }
+        Some(other) => Err(anyhow::anyhow!(
+            "Unsupported EIP-681 function '{}' (only 'transfer' is supported)",
+            other
+        )),
+    }
+}
+
+/// An EIP-681 number, failing as a `ParseError` rather than a bare message
+fn parse_amount(number: &str) -> Result<u128> {
+    units::parse_scientific(number).map_err(|e| WalletError::ParseError(format!("{:#}", e)).into())
+}
+
+/// Split `path?key=value&...` into the path and its key/value pairs
+fn split_query(rest: &str) -> (&str, Vec<(&str, &str)>) {
+    match rest.split_once('?') {
+        Some((path, query)) => (
+            path,
+            query
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+                .collect(),
+        ),
+        None => (rest, Vec::new()),
+    }
+}
@@ -0,0 +1,111 @@
+//! Pending transaction simulation for EVM networks
+//!
+//! This module inspects the node's pending block for transactions touching an
+//! address and projects the balance after they are included. Many public RPC
+//! endpoints serve a pending block identical to `latest`, in which case the
+//! projection simply equals the confirmed balance.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{evm_rpc, Network, WalletError};
+
+/// Effect of pending transactions on an address, in wei
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PendingProjection {
+    /// Number of pending transactions sending to or from the address
+    pub tx_count: usize,
+    /// Value arriving at the address
+    pub incoming: u128,
+    /// Value leaving the address plus the maximum gas cost it pays
+    pub outgoing: u128,
+}
+
+impl PendingProjection {
+    /// Project a confirmed balance (in wei) past the pending transactions
+    pub fn projected_balance(&self, confirmed: u128) -> u128 {
+        confirmed.saturating_add(self.incoming).saturating_sub(self.outgoing)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PendingBlock {
+    transactions: Vec<PendingTransaction>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PendingTransaction {
+    from: String,
+    to: Option<String>,
+    value: String,
+    gas: String,
+    gas_price: Option<String>,
+    max_fee_per_gas: Option<String>,
+}
+
+/// Scan the pending block of `network` for transactions touching `address`
+///
+/// # Arguments
+///
+/// * `network` - EVM network to inspect
+/// * `address` - 0x-prefixed address
+///
+/// # Returns
+///
+/// Returns a `PendingProjection` summarising incoming and outgoing value
+pub async fn scan_pending(network: Network, address: &str) -> Result<PendingProjection, WalletError> {
+    let rpc_url = evm_rpc::rpc_url(network).ok_or_else(|| {
+        anyhow::anyhow!("Pending simulation is only supported on EVM networks, not {}", network)
+    })?;
+    let address = address.to_lowercase();
+
+    let result = evm_rpc::call(
+        &rpc_url,
+        "eth_getBlockByNumber",
+        vec![json!("pending"), json!(true)],
+    )
+    .await?;
+    if result.is_null() {
+        return Ok(PendingProjection::default());
+    }
+    let block: PendingBlock = serde_json::from_value(result).context("Invalid pending block")?;
+
+    let mut projection = PendingProjection::default();
+    for tx in block.transactions {
+        let from_self = tx.from.to_lowercase() == address;
+        let to_self = tx.to.as_deref().map(str::to_lowercase).as_deref() == Some(address.as_str());
+        if !from_self && !to_self {
+            continue;
+        }
+
+        projection.tx_count += 1;
+        let value = parse_hex(&tx.value)?;
+        if to_self {
+            projection.incoming = projection.incoming.saturating_add(value);
+        }
+        if from_self {
+            // Worst case: the full gas limit at the maximum fee is paid
+            let fee_per_gas = tx
+                .max_fee_per_gas
+                .as_deref()
+                .or(tx.gas_price.as_deref())
+                .map(parse_hex)
+                .transpose()?
+                .unwrap_or(0);
+            let gas_cost = parse_hex(&tx.gas)?.saturating_mul(fee_per_gas);
+            projection.outgoing = projection
+                .outgoing
+                .saturating_add(value)
+                .saturating_add(gas_cost);
+        }
+    }
+
+    Ok(projection)
+}
+
+/// A quantity read at full 256-bit width; beyond `u128` it saturates, like the sums it goes into
+fn parse_hex(value: &str) -> Result<u128> {
+    Ok(evm_rpc::parse_quantity_u256(&json!(value))?.to_u128().unwrap_or(u128::MAX))
+}
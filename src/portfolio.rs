@@ -0,0 +1,168 @@
+//! Portfolio files: a saved list of labeled wallets
+//!
+//! `portfolio <FILE>` checks every wallet in the file at once and adds the
+//! balances up per network. The file is TOML, or JSON when its name ends in
+//! `.json`:
+//!
+//! ```toml
+//! [[wallets]]
+//! network = "bitcoin"
+//! address = "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"
+//! label = "Cold storage"
+//! ```
+//!
+//! JSON files hold the same `{"wallets": [...]}` document, or just the array.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::{units, Network, WalletBalance, WalletError};
+
+/// One wallet of a portfolio
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortfolioEntry {
+    pub network: Network,
+    pub address: String,
+    /// Name shown in reports; the address when the file gives none
+    pub label: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawEntry {
+    network: String,
+    address: String,
+    label: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPortfolio {
+    #[serde(default)]
+    wallets: Vec<RawEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawJson {
+    Document(RawPortfolio),
+    List(Vec<RawEntry>),
+}
+
+/// Parse the TOML text of a portfolio file
+pub fn from_toml(text: &str) -> Result<Vec<PortfolioEntry>, WalletError> {
+    let raw: RawPortfolio = toml::from_str(text).context("Invalid portfolio file")?;
+    Ok(validate(raw.wallets)?)
+}
+
+/// Parse the JSON text of a portfolio file
+pub fn from_json(text: &str) -> Result<Vec<PortfolioEntry>, WalletError> {
+    let wallets = match serde_json::from_str(text).context("Invalid portfolio file")? {
+        RawJson::Document(raw) => raw.wallets,
+        RawJson::List(wallets) => wallets,
+    };
+    Ok(validate(wallets)?)
+}
+
+/// Read the portfolio file at `path`, as JSON when it ends in `.json` and TOML otherwise
+pub fn load(path: &Path) -> Result<Vec<PortfolioEntry>, WalletError> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let is_json = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let parsed = match is_json {
+        true => from_json(&text),
+        false => from_toml(&text),
+    };
+    Ok(parsed.with_context(|| path.display().to_string())?)
+}
+
+fn validate(wallets: Vec<RawEntry>) -> Result<Vec<PortfolioEntry>> {
+    if wallets.is_empty() {
+        return Err(anyhow::anyhow!("The portfolio lists no wallets"));
+    }
+    wallets
+        .into_iter()
+        .enumerate()
+        .map(|(index, raw)| {
+            let network = raw
+                .network
+                .parse()
+                .with_context(|| format!("Wallet {}: invalid network", index + 1))?;
+            let address = raw.address.trim().to_string();
+            let label = raw
+                .label
+                .map(|label| label.trim().to_string())
+                .filter(|label| !label.is_empty())
+                .unwrap_or_else(|| address.clone());
+            Ok(PortfolioEntry { network, address, label })
+        })
+        .collect()
+}
+
+/// Sum of a portfolio's balances on one network
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NetworkTotal {
+    pub network: String,
+    pub balance: String,
+    pub denomination: String,
+    /// Fiat value of the total, when every balance on the network was priced
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fiat_value: Option<String>,
+}
+
+/// Per-network totals and, with fiat conversion, the grand total
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Totals {
+    pub networks: Vec<NetworkTotal>,
+    /// Sum of every priced balance
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fiat_value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fiat_currency: Option<String>,
+}
+
+/// Add up `balances` per network, and their fiat values where they have one
+///
+/// Amounts are summed in base units. Networks are listed in `Network` order.
+pub fn totals<'a>(balances: impl IntoIterator<Item = (Network, &'a WalletBalance)>) -> Result<Totals, WalletError> {
+    // Per network: base units, fiat sum, and whether every balance was priced
+    let mut sums: BTreeMap<Network, (u128, f64, bool)> = BTreeMap::new();
+    let mut fiat_total: Option<(f64, String)> = None;
+    for (network, balance) in balances {
+        let held = units::parse_units(&balance.balance, network.decimals())?;
+        let fiat = match (&balance.fiat_value, &balance.fiat_currency) {
+            (Some(value), Some(currency)) => {
+                let value: f64 = value.parse().with_context(|| format!("Invalid fiat value: {}", value))?;
+                let total = fiat_total.get_or_insert_with(|| (0.0, currency.clone()));
+                total.0 += value;
+                Some(value)
+            }
+            _ => None,
+        };
+
+        let sum = sums.entry(network).or_insert((0, 0.0, true));
+        sum.0 = sum.0.saturating_add(held);
+        match fiat {
+            Some(value) => sum.1 += value,
+            None => sum.2 = false,
+        }
+    }
+
+    let priced = fiat_total.is_some();
+    Ok(Totals {
+        networks: sums
+            .into_iter()
+            .map(|(network, (held, fiat, all_priced))| NetworkTotal {
+                network: network.to_string(),
+                balance: units::format_units(held, network.decimals()),
+                denomination: network.denomination().to_string(),
+                fiat_value: (priced && all_priced).then(|| format!("{:.2}", fiat)),
+            })
+            .collect(),
+        fiat_value: fiat_total.as_ref().map(|(value, _)| format!("{:.2}", value)),
+        fiat_currency: fiat_total.map(|(_, currency)| currency),
+    })
+}
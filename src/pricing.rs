@@ -0,0 +1,148 @@
+//! Fiat prices from CoinGecko
+//!
+//! `--convert usd` shows each balance next to its value in a fiat currency,
+//! priced with CoinGecko's public `simple/price` endpoint. All networks in one
+//! command are priced with a single request. Testnet coins have no market
+//! price and are left unconverted.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use crate::{http, Network, WalletBalance, WalletError};
+
+pub(crate) const COINGECKO_API: &str = "https://api.coingecko.com/api/v3";
+
+/// Fiat currency a balance can be converted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FiatCurrency {
+    Usd,
+    Eur,
+}
+
+impl FiatCurrency {
+    /// CoinGecko's `vs_currencies` code
+    fn code(&self) -> &'static str {
+        match self {
+            FiatCurrency::Usd => "usd",
+            FiatCurrency::Eur => "eur",
+        }
+    }
+}
+
+impl fmt::Display for FiatCurrency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code().to_uppercase())
+    }
+}
+
+impl std::str::FromStr for FiatCurrency {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, WalletError> {
+        match s.to_lowercase().as_str() {
+            "usd" => Ok(FiatCurrency::Usd),
+            "eur" => Ok(FiatCurrency::Eur),
+            _ => Err(WalletError::ParseError(format!("Unsupported currency: {} (expected usd or eur)", s))),
+        }
+    }
+}
+
+/// CoinGecko id of a network's native coin, or `None` for testnets and custom chains
+pub fn coingecko_id(network: Network) -> Option<&'static str> {
+    match network {
+        Network::Bitcoin => Some("bitcoin"),
+        Network::BitcoinTestnet4
+        | Network::BitcoinSignet
+        | Network::Sepolia
+        | Network::BaseSepolia
+        | Network::ArbitrumSepolia
+        | Network::OptimismSepolia
+        | Network::PolygonAmoy
+        | Network::BscTestnet
+        | Network::AvalancheFuji
+        | Network::FantomTestnet
+        | Network::GnosisChiado
+        | Network::TronShasta => None,
+        // ETH on the L2s is bridged ETH
+        Network::Ethereum | Network::Base | Network::Arbitrum | Network::Optimism => Some("ethereum"),
+        Network::Polygon => Some("polygon-ecosystem-token"),
+        Network::Bsc => Some("binancecoin"),
+        Network::Avalanche => Some("avalanche-2"),
+        Network::Fantom => Some("fantom"),
+        Network::Gnosis => Some("xdai"),
+        // An arbitrary chain's coin cannot be priced by its symbol alone
+        Network::CustomEvm => None,
+        Network::Tron => Some("tron"),
+        Network::Dogecoin => Some("dogecoin"),
+        Network::Litecoin => Some("litecoin"),
+    }
+}
+
+/// Prices in `currency` keyed by CoinGecko id, from a `simple/price` response `body`
+///
+/// Ids CoinGecko did not price are missing from the map.
+pub fn parse_prices(body: &str, currency: FiatCurrency) -> Result<HashMap<String, f64>, WalletError> {
+    let prices: HashMap<String, HashMap<String, f64>> =
+        serde_json::from_str(body).context("Failed to parse prices from CoinGecko")?;
+    Ok(prices
+        .into_iter()
+        .filter_map(|(id, quotes)| quotes.get(currency.code()).map(|price| (id, *price)))
+        .collect())
+}
+
+/// Current prices of the coins `ids` in `currency`
+pub async fn get_prices(ids: &[&str], currency: FiatCurrency) -> Result<HashMap<String, f64>, WalletError> {
+    let url = format!(
+        "{}/simple/price?ids={}&vs_currencies={}",
+        COINGECKO_API,
+        ids.join(","),
+        currency.code()
+    );
+
+    let client = http::client();
+    let response = http::send(client.get(&url).header("Accept", "application/json"))
+        .await
+        .context("Failed to send request to CoinGecko")?;
+    if !response.status.is_success() {
+        return Err(WalletError::Other(format!("CoinGecko API failed: {} - {}", response.status, response.text())));
+    }
+    parse_prices(&response.text(), currency)
+}
+
+/// Value of a decimal `balance` at `price`, rounded to cents
+pub fn fiat_value(balance: &str, price: f64) -> Result<String, WalletError> {
+    let amount: f64 = balance
+        .parse()
+        .with_context(|| format!("Invalid balance amount: {}", balance))?;
+    Ok(format!("{:.2}", amount * price))
+}
+
+/// Set `fiat_value` and `fiat_currency` on every balance whose coin has a price
+///
+/// One request prices every network involved. Balances on testnets, or with a
+/// network CoinGecko returned no price for, are left unconverted.
+pub async fn convert<'a>(
+    balances: impl IntoIterator<Item = &'a mut WalletBalance>,
+    currency: FiatCurrency,
+) -> Result<(), WalletError> {
+    let mut balances: Vec<&mut WalletBalance> = balances.into_iter().collect();
+    let id_of = |balance: &WalletBalance| balance.network.parse().ok().and_then(coingecko_id);
+
+    let ids: BTreeSet<&str> = balances.iter().filter_map(|balance| id_of(balance)).collect();
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let prices = get_prices(&ids.into_iter().collect::<Vec<_>>(), currency).await?;
+
+    for balance in balances.iter_mut() {
+        let Some(price) = id_of(balance).and_then(|id| prices.get(id)) else {
+            continue;
+        };
+        balance.fiat_value = Some(fiat_value(&balance.balance, *price)?);
+        balance.fiat_currency = Some(currency.to_string());
+    }
+    Ok(())
+}
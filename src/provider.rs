@@ -0,0 +1,106 @@
+//! The `BalanceProvider` trait and the registry of per-network providers
+//!
+//! Each network module implements `BalanceProvider` next to its request code,
+//! and `ProviderRegistry::with_defaults` registers one provider per `Network`.
+//! Live lookups (`get_balance`, address normalization) go through `registry()`,
+//! so adding a chain means writing its module and one `register` call here.
+//! The registry can be replaced while lookups are in flight (`set_registry`);
+//! a lookup keeps the registry it started with until it finishes.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::{
+    bitcoin_wallet, dogecoin_wallet, evm_wallet, litecoin_wallet, tron_wallet, Network, WalletBalance, WalletError,
+};
+
+/// Source of native balances for one network
+#[async_trait]
+pub trait BalanceProvider: Send + Sync {
+    /// Network this provider serves
+    fn network(&self) -> Network;
+
+    /// Apply the network's address normalization and validation rules
+    fn normalize_address(&self, address: &str) -> Result<String, WalletError>;
+
+    /// Get the native balance of `address`
+    async fn get_balance(&self, address: &str) -> Result<WalletBalance, WalletError>;
+}
+
+/// Providers keyed by the network they serve
+pub struct ProviderRegistry {
+    providers: HashMap<Network, Box<dyn BalanceProvider>>,
+}
+
+impl ProviderRegistry {
+    /// A registry without any providers
+    pub fn empty() -> Self {
+        ProviderRegistry {
+            providers: HashMap::new(),
+        }
+    }
+
+    /// The public-API provider of every supported network
+    pub fn with_defaults() -> Self {
+        let mut registry = ProviderRegistry::empty();
+        registry.register(bitcoin_wallet::BitcoinProvider(bitcoin_wallet::Chain::Mainnet));
+        registry.register(bitcoin_wallet::BitcoinProvider(bitcoin_wallet::Chain::Testnet4));
+        registry.register(bitcoin_wallet::BitcoinProvider(bitcoin_wallet::Chain::Signet));
+        for chain in evm_wallet::CHAINS {
+            registry.register(evm_wallet::EvmProvider(chain.network));
+        }
+        registry.register(evm_wallet::EvmProvider(Network::CustomEvm));
+        registry.register(tron_wallet::TronProvider(Network::Tron));
+        registry.register(tron_wallet::TronProvider(Network::TronShasta));
+        registry.register(dogecoin_wallet::DogecoinProvider);
+        registry.register(litecoin_wallet::LitecoinProvider);
+        registry
+    }
+
+    /// Add `provider` for its network, returning the provider it replaces
+    pub fn register(&mut self, provider: impl BalanceProvider + 'static) -> Option<Box<dyn BalanceProvider>> {
+        self.providers.insert(provider.network(), Box::new(provider))
+    }
+
+    /// Provider registered for `network`
+    pub fn get(&self, network: Network) -> Result<&dyn BalanceProvider, WalletError> {
+        self.providers
+            .get(&network)
+            .map(|provider| provider.as_ref())
+            .ok_or_else(|| WalletError::UnsupportedNetwork(format!("No balance provider registered for {}", network)))
+    }
+
+    /// Networks with a provider, in `Network` order
+    pub fn networks(&self) -> Vec<Network> {
+        let mut networks: Vec<Network> = self.providers.keys().copied().collect();
+        networks.sort();
+        networks
+    }
+}
+
+impl Default for ProviderRegistry {
+    fn default() -> Self {
+        ProviderRegistry::with_defaults()
+    }
+}
+
+static REGISTRY: RwLock<Option<Arc<ProviderRegistry>>> = RwLock::new(None);
+
+/// The registry used for live lookups, `ProviderRegistry::with_defaults` until one is installed
+pub fn registry() -> Arc<ProviderRegistry> {
+    if let Some(registry) = REGISTRY.read().unwrap_or_else(|poisoned| poisoned.into_inner()).as_ref() {
+        return registry.clone();
+    }
+    REGISTRY
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get_or_insert_with(|| Arc::new(ProviderRegistry::with_defaults()))
+        .clone()
+}
+
+/// Replace the registry used for live lookups
+pub fn set_registry(registry: ProviderRegistry) {
+    *REGISTRY.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Arc::new(registry));
+}
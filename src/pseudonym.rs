@@ -0,0 +1,70 @@
+//! Salted pseudonyms for addresses in shared reports (`--pseudonymize`)
+//!
+//! Every address in the output is replaced with `anon_` and the first 16 hex
+//! digits of HMAC-SHA256(salt, address). The same salt always maps an address
+//! to the same pseudonym, so reports from several runs can still be joined,
+//! while someone without the salt cannot confirm a guessed address.
+//!
+//! Addresses are recognized by their encoding rather than by where they were
+//! printed: `0x` plus 40 hex digits, Base58Check with a 20-byte payload
+//! (Bitcoin, Litecoin and Dogecoin legacy addresses, Tron) and bech32/bech32m
+//! segwit addresses. Token contracts are addresses too and are replaced alike.
+
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::address;
+use crate::WalletError;
+
+/// Check that `salt` can key pseudonyms
+pub fn validate_salt(salt: &str) -> Result<(), WalletError> {
+    if salt.trim().is_empty() {
+        return Err(WalletError::Other("--pseudonymize needs a non-empty salt".to_string()));
+    }
+    Ok(())
+}
+
+/// The pseudonym of `address` under `salt`
+///
+/// EVM and bech32 addresses are case-insensitive and are lowercased first, so
+/// a checksummed and a lowercase spelling get the same pseudonym.
+pub fn pseudonym(salt: &str, address: &str) -> String {
+    let canonical = match address.starts_with("0x") || address::decode_segwit(address).is_ok() {
+        true => address.to_lowercase(),
+        false => address.to_string(),
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(salt.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(canonical.as_bytes());
+    format!("anon_{}", &hex::encode(mac.finalize().into_bytes())[..16])
+}
+
+/// Whether `token` is encoded like an address
+pub fn is_address(token: &str) -> bool {
+    if let Some(hex) = token.strip_prefix("0x") {
+        return hex.len() == 40 && hex.bytes().all(|b| b.is_ascii_hexdigit());
+    }
+    if (25..=35).contains(&token.len()) && address::decode_base58check(token).is_ok_and(|data| data.len() == 21) {
+        return true;
+    }
+    token.contains('1') && address::decode_segwit(token).is_ok()
+}
+
+/// Replace every address in `text` with its pseudonym under `salt`
+pub fn pseudonymize_text(salt: &str, text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric()) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len());
+        let token = &rest[..end];
+        match is_address(token) {
+            true => out.push_str(&pseudonym(salt, token)),
+            false => out.push_str(token),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,100 @@
+//! Runes balances for Bitcoin addresses
+//!
+//! Runes are fungible tokens recorded in `OP_RETURN` runestones and carried by
+//! ordinary UTXOs, so nothing about them shows up in a plain sat sum. An
+//! indexer has to replay every runestone to know who holds what; this module
+//! asks Hiro's public Runes API for the balances it has indexed.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{bitcoin_wallet, http, WalletError};
+
+pub(crate) const HIRO_RUNES_API: &str = "https://api.hiro.so/runes/v1";
+
+/// Page size of Hiro's balance listing (its maximum)
+const BALANCE_PAGE_SIZE: u64 = 60;
+
+/// Balance of one rune, already scaled by the rune's divisibility
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuneBalance {
+    /// Rune id as `block:tx` of its etching
+    pub id: String,
+    /// Name with its `•` spacers, e.g. `DOG•GO•TO•THE•MOON`
+    pub name: String,
+    pub balance: String,
+}
+
+/// One page of an address's rune balances
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalancePage {
+    /// Balances across all pages
+    pub total: u64,
+    pub balances: Vec<RuneBalance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HiroPage {
+    total: u64,
+    results: Vec<HiroBalance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HiroBalance {
+    rune: HiroRune,
+    balance: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HiroRune {
+    id: String,
+    spaced_name: String,
+}
+
+/// Parse one page of Hiro's `/addresses/:address/balances` response
+pub fn parse_balance_page(body: &str) -> Result<BalancePage, WalletError> {
+    let page: HiroPage = serde_json::from_str(body).context("Failed to parse JSON from Hiro Runes API")?;
+    Ok(BalancePage {
+        total: page.total,
+        balances: page
+            .results
+            .into_iter()
+            .map(|entry| RuneBalance {
+                id: entry.rune.id,
+                name: entry.rune.spaced_name,
+                balance: entry.balance,
+            })
+            .collect(),
+    })
+}
+
+/// Every rune balance of a Bitcoin mainnet `address`, following Hiro's pagination
+pub async fn get_balances(address: &str) -> Result<Vec<RuneBalance>, WalletError> {
+    let address = address.trim();
+    bitcoin_wallet::validate_address(address)?;
+
+    let client = http::client();
+    let mut balances = Vec::new();
+    loop {
+        let url = format!(
+            "{}/addresses/{}/balances?limit={}&offset={}",
+            HIRO_RUNES_API,
+            address,
+            BALANCE_PAGE_SIZE,
+            balances.len()
+        );
+        let response = http::send(client.get(&url).header("Accept", "application/json"))
+            .await
+            .context("Failed to send request to Hiro Runes API")?;
+        if !response.status.is_success() {
+            return Err(WalletError::Other(format!("Hiro Runes API failed: {} - {}", response.status, response.text())));
+        }
+
+        let page = parse_balance_page(&response.text())?;
+        let fetched = page.balances.len();
+        balances.extend(page.balances);
+        if fetched == 0 || balances.len() as u64 >= page.total {
+            return Ok(balances);
+        }
+    }
+}
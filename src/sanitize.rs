@@ -0,0 +1,126 @@
+//! Address input sanitation
+//!
+//! Pasted and QR-scanned addresses often carry invisible characters, quotes
+//! or a payment URI wrapper. This module strips them and records what was
+//! removed so the CLI can tell the user.
+
+/// Result of cleaning a raw address argument
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanitizedAddress {
+    /// The cleaned address
+    pub address: String,
+    /// Human-readable descriptions of everything that was stripped
+    pub stripped: Vec<String>,
+}
+
+/// Characters that render as nothing but break address matching
+const INVISIBLE_CHARS: &[char] = &[
+    '\u{00AD}', // soft hyphen
+    '\u{200B}', // zero width space
+    '\u{200C}', // zero width non-joiner
+    '\u{200D}', // zero width joiner
+    '\u{200E}', // left-to-right mark
+    '\u{200F}', // right-to-left mark
+    '\u{202A}', '\u{202B}', '\u{202C}', '\u{202D}', '\u{202E}', // bidi embeddings/overrides
+    '\u{2060}', // word joiner
+    '\u{2066}', '\u{2067}', '\u{2068}', '\u{2069}', // bidi isolates
+    '\u{FEFF}', // byte order mark / zero width no-break space
+];
+
+const QUOTE_PAIRS: &[(char, char)] = &[
+    ('"', '"'),
+    ('\'', '\''),
+    ('`', '`'),
+    ('\u{201C}', '\u{201D}'), // “ ”
+    ('\u{2018}', '\u{2019}'), // ‘ ’
+    ('<', '>'),
+];
+
+/// URI schemes recognised in front of an address (BIP-21, EIP-681 and friends)
+const URI_SCHEMES: &[&str] = &[
+    "bitcoin", "ethereum", "litecoin", "dogecoin", "tron", "polygon", "arbitrum", "base",
+];
+
+/// Strip invisible characters, whitespace, quotes and payment URI wrappers
+///
+/// # Arguments
+///
+/// * `input` - Raw address as typed or pasted by the user
+///
+/// # Returns
+///
+/// Returns a `SanitizedAddress` with the cleaned address and a list of what was removed
+pub fn sanitize_address(input: &str) -> SanitizedAddress {
+    let SanitizedAddress {
+        mut address,
+        mut stripped,
+    } = clean_text(input);
+
+    if let Some((scheme, rest)) = address.split_once(':') {
+        if URI_SCHEMES.contains(&scheme.to_lowercase().as_str()) {
+            stripped.push(format!("URI prefix '{}:'", scheme));
+            address = strip_uri_suffix(rest, &mut stripped);
+        }
+    }
+
+    SanitizedAddress { address, stripped }
+}
+
+/// Strip invisible characters, whitespace and surrounding quotes only
+///
+/// Payment URIs are left intact so they can be parsed by `payment_uri`.
+pub fn clean_text(input: &str) -> SanitizedAddress {
+    let mut stripped = Vec::new();
+
+    let visible: String = input.chars().filter(|c| !INVISIBLE_CHARS.contains(c)).collect();
+    if visible.chars().count() != input.chars().count() {
+        stripped.push(format!(
+            "{} invisible character(s)",
+            input.chars().count() - visible.chars().count()
+        ));
+    }
+
+    // Addresses never contain whitespace, so line breaks from wrapped pastes go too
+    let mut address: String = visible.chars().filter(|c| !c.is_whitespace()).collect();
+    if address.len() != visible.len() {
+        stripped.push("whitespace".to_string());
+    }
+
+    while let Some(&(open, close)) = QUOTE_PAIRS
+        .iter()
+        .find(|(open, close)| address.starts_with(*open) && address.ends_with(*close) && address.len() > 1)
+    {
+        address = address[open.len_utf8()..address.len() - close.len_utf8()].to_string();
+        stripped.push(format!("surrounding quotes {}{}", open, close));
+    }
+
+    SanitizedAddress { address, stripped }
+}
+
+/// Reduce the part after `scheme:` to the bare target address
+fn strip_uri_suffix(rest: &str, stripped: &mut Vec<String>) -> String {
+    let (path, query) = match rest.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (rest, None),
+    };
+    if let Some(query) = query {
+        stripped.push(format!("query parameters '?{}'", query));
+    }
+
+    // EIP-681: [pay-]<target>[@chain_id][/function_name]
+    let mut target = path;
+    if let Some(without_pay) = target.strip_prefix("pay-") {
+        stripped.push("'pay-' prefix".to_string());
+        target = without_pay;
+    }
+    if let Some((address, function)) = target.split_once('/') {
+        stripped.push(format!("function call '/{}'", function));
+        target = address;
+    }
+    if let Some((address, chain_id)) = target.split_once('@') {
+        stripped.push(format!("chain id '@{}'", chain_id));
+        target = address;
+    }
+
+    target.to_string()
+}
@@ -0,0 +1,133 @@
+//! JSON Schema of the `--output json` documents
+//!
+//! `--emit-schema` prints `balance_output_schema()` so integrators can
+//! generate types for, and validate, what the CLI prints. The schema is
+//! versioned: bump `SCHEMA_VERSION` whenever a field is added, removed or
+//! changes meaning, so consumers can tell which envelope they were built for.
+
+use serde_json::{json, Value};
+
+use crate::Network;
+
+/// Version of the JSON output envelope described by `balance_output_schema`
+///
+/// - 1: initial envelope
+/// - 2: `total_supply` and `supply_share` on token balances (`--supply-share`)
+/// - 3: `timed_out` on batch lookup errors (`--sla`)
+/// - 4: `since` on token balances (`--since-block`)
+/// - 5: `unconfirmed_balance` on Bitcoin balances (`--include-pending`)
+/// - 6: `warnings` on batch rows whose address looks like a burn or vanity address
+pub const SCHEMA_VERSION: u32 = 6;
+
+/// `$id` of the schema for `version`
+pub fn schema_id(version: u32) -> String {
+    format!("https://github.com/venkata-rust/wallet-balance-cli/schemas/v{}/balance-output.json", version)
+}
+
+/// Schema of a balance lookup's JSON output: one balance, an array for a batch, or an error
+pub fn balance_output_schema() -> Value {
+    let networks: Vec<String> = Network::ALL.iter().map(|network| network.to_string()).collect();
+    let decimal = json!({ "type": "string", "pattern": "^[0-9]+(\\.[0-9]+)?$" });
+    let warnings = json!({
+        "type": "array",
+        "items": { "type": "string" },
+        "description": "In a batch, why the address looks like a burn or vanity address"
+    });
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": schema_id(SCHEMA_VERSION),
+        "title": "wallet-balance JSON output",
+        "description": "Output of a balance lookup with --output json",
+        "version": SCHEMA_VERSION,
+        "oneOf": [
+            { "$ref": "#/$defs/balance" },
+            { "type": "array", "items": { "oneOf": [{ "$ref": "#/$defs/balance" }, { "$ref": "#/$defs/lookupError" }] } },
+            { "$ref": "#/$defs/error" }
+        ],
+        "$defs": {
+            "balance": {
+                "type": "object",
+                "required": ["address", "balance", "network", "denomination"],
+                "additionalProperties": false,
+                "properties": {
+                    "address": { "type": "string" },
+                    "balance": decimal,
+                    "network": { "enum": networks },
+                    "denomination": { "type": "string", "description": "Coin or token symbol" },
+                    "block": {
+                        "type": "object",
+                        "description": "Block the balance was read at, when the read was pinned to one",
+                        "required": ["number", "hash"],
+                        "additionalProperties": false,
+                        "properties": {
+                            "number": { "type": "integer", "minimum": 0 },
+                            "hash": { "type": "string" }
+                        }
+                    },
+                    "fiat_value": {
+                        "type": "string",
+                        "pattern": "^[0-9]+\\.[0-9]{2}$",
+                        "description": "Value in fiat_currency, with --convert"
+                    },
+                    "fiat_currency": { "enum": ["USD", "EUR"] },
+                    "total_supply": {
+                        "type": "string",
+                        "description": "Token totalSupply() in whole tokens, with --supply-share"
+                    },
+                    "supply_share": {
+                        "type": "string",
+                        "pattern": "^[0-9]+\\.[0-9]{4}$",
+                        "description": "Balance as a percentage of total_supply, with --supply-share"
+                    },
+                    "since": {
+                        "type": "object",
+                        "description": "Balance at an earlier block and the change since, with --since-block",
+                        "required": ["block", "balance", "change"],
+                        "additionalProperties": false,
+                        "properties": {
+                            "block": { "type": "integer", "minimum": 0 },
+                            "balance": decimal,
+                            "change": { "type": "string", "pattern": "^([+-][0-9]+(\\.[0-9]+)?|0)$" }
+                        }
+                    },
+                    "unconfirmed_balance": {
+                        "type": "string",
+                        "pattern": "^([+-][0-9]+(\\.[0-9]+)?|0)$",
+                        "description": "Net change from unconfirmed mempool transactions, with --include-pending"
+                    },
+                    "warnings": warnings
+                },
+                "dependentRequired": {
+                    "fiat_value": ["fiat_currency"],
+                    "fiat_currency": ["fiat_value"]
+                }
+            },
+            "lookupError": {
+                "type": "object",
+                "description": "A failed lookup in its slot of a batch",
+                "required": ["network", "address", "error"],
+                "additionalProperties": false,
+                "properties": {
+                    "network": { "enum": networks },
+                    "address": { "type": "string" },
+                    "error": { "type": "string" },
+                    "timed_out": {
+                        "const": true,
+                        "description": "Set when the lookup was cut off by --sla rather than failing"
+                    },
+                    "warnings": warnings
+                }
+            },
+            "error": {
+                "type": "object",
+                "description": "A command that failed as a whole",
+                "required": ["error"],
+                "additionalProperties": false,
+                "properties": {
+                    "error": { "type": "string" }
+                }
+            }
+        }
+    })
+}
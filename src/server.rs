@@ -0,0 +1,335 @@
+//! HTTP server mode
+//!
+//! `wallet-balance serve --listen <addr>` answers balance lookups over HTTP
+//! and exposes the probes an orchestrator such as Kubernetes needs:
+//!
+//! * `GET /balance?network=<network>&address=<address>` - balance as JSON
+//! * `GET /healthz` - the process is alive and serving requests
+//! * `GET /readyz` - every mainnet provider (or those of `?networks=a,b`) answered a cheap probe within the budget
+//! * `POST /graphql` - balance, token and history queries (with `--graphql`, see `graphql`)
+//! * `POST /subscriptions` - register a webhook for balance changes of an address (see `subscriptions`)
+//! * `GET /subscriptions`, `DELETE /subscriptions/:id` - list and remove them
+//!
+//! With API keys configured, `/balance`, `/graphql` and `/subscriptions` need
+//! one of them and hold it to its quota and networks (see `api_keys`); a key
+//! only sees the subscriptions it registered.
+
+use anyhow::{Context, Result};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::task::JoinSet;
+
+use crate::api_keys::{ApiKey, Denied, Tenants};
+use crate::subscriptions::Subscriptions;
+use crate::{
+    bitcoin_wallet, config, dogecoin_wallet, evm_rpc, evm_wallet, graphql, sanitize, tron_wallet, BalanceSource,
+    Network, WalletError,
+};
+
+/// Default time allowed for each provider probe behind `/readyz`
+pub const DEFAULT_READY_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone)]
+struct AppState {
+    source: BalanceSource,
+    ready_timeout: Duration,
+    tenants: Tenants,
+    subscriptions: Subscriptions,
+}
+
+#[derive(Debug, Deserialize)]
+struct BalanceParams {
+    network: String,
+    address: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadyParams {
+    /// Comma-separated networks to probe instead of `ready_networks()`
+    networks: Option<String>,
+}
+
+/// Body of `POST /subscriptions`
+#[derive(Debug, Deserialize)]
+struct SubscriptionRequest {
+    network: String,
+    address: String,
+    url: String,
+    #[serde(default)]
+    secret: Option<String>,
+}
+
+/// Outcome of probing one provider for `/readyz`
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeResult {
+    pub ok: bool,
+    pub latency_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Build the router for `source`, probing providers with `ready_timeout`
+///
+/// `/graphql` is only routed when `graphql` is set. Lookups need one of the
+/// keys of `tenants` unless it has none. Webhooks are registered in
+/// `subscriptions`; polling them is up to the caller (`Subscriptions::run`).
+pub fn router(
+    source: BalanceSource,
+    ready_timeout: Duration,
+    graphql: bool,
+    tenants: Tenants,
+    subscriptions: Subscriptions,
+) -> Router {
+    let mut router = Router::new()
+        .route("/balance", get(balance))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/subscriptions", get(list_subscriptions).post(subscribe))
+        .route("/subscriptions/:id", delete(unsubscribe));
+    if graphql {
+        router = router.route("/graphql", post(graphql_query));
+    }
+    router.with_state(AppState {
+        source,
+        ready_timeout,
+        tenants,
+        subscriptions,
+    })
+}
+
+/// Serve HTTP on `listener` until `shutdown` completes, then drain in-flight requests
+///
+/// # Arguments
+///
+/// * `listener` - Bound TCP listener
+/// * `source` - Where balances are fetched from
+/// * `ready_timeout` - Time allowed for each provider probe behind `/readyz`
+/// * `graphql` - Whether to answer GraphQL queries on `/graphql`
+/// * `tenants` - API keys required for lookups; the server is open when there are none
+/// * `subscriptions` - Registered balance-change webhooks
+/// * `shutdown` - Future that starts graceful shutdown when it completes
+pub async fn serve_http(
+    listener: TcpListener,
+    source: BalanceSource,
+    ready_timeout: Duration,
+    graphql: bool,
+    tenants: Tenants,
+    subscriptions: Subscriptions,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), WalletError> {
+    axum::serve(listener, router(source, ready_timeout, graphql, tenants, subscriptions))
+        .with_graceful_shutdown(shutdown)
+        .await
+        .context("HTTP server failed")?;
+    Ok(())
+}
+
+async fn balance(State(state): State<AppState>, headers: HeaderMap, Query(params): Query<BalanceParams>) -> Response {
+    let key = match admit(&state, &headers) {
+        Ok(key) => key,
+        Err(denied) => return denied_response(&denied),
+    };
+    let network: Network = match params.network.parse() {
+        Ok(network) => network,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e),
+    };
+    if let Some(key) = key.filter(|key| !key.allows(network)) {
+        let message = format!("API key '{}' may not query {}", key.name, network);
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": message }))).into_response();
+    }
+    let address = sanitize::sanitize_address(&params.address).address;
+
+    match state.source.get_balance(network, &address).await {
+        Ok(balance) => Json(balance).into_response(),
+        Err(e) => error_response(lookup_status(&e), &e),
+    }
+}
+
+async fn graphql_query(State(state): State<AppState>, headers: HeaderMap, Json(body): Json<Value>) -> Response {
+    let key = match admit(&state, &headers) {
+        Ok(key) => key,
+        Err(denied) => return denied_response(&denied),
+    };
+    let networks = key.and_then(|key| key.networks);
+    Json(graphql::respond(&state.source, networks.as_deref(), body).await).into_response()
+}
+
+async fn subscribe(State(state): State<AppState>, headers: HeaderMap, Json(request): Json<SubscriptionRequest>) -> Response {
+    let key = match admit(&state, &headers) {
+        Ok(key) => key,
+        Err(denied) => return denied_response(&denied),
+    };
+    let network: Network = match request.network.parse() {
+        Ok(network) => network,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e),
+    };
+    if let Some(key) = key.as_ref().filter(|key| !key.allows(network)) {
+        let message = format!("API key '{}' may not query {}", key.name, network);
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": message }))).into_response();
+    }
+    let address = sanitize::sanitize_address(&request.address).address;
+    let owner = key.map(|key| key.name);
+
+    match state
+        .subscriptions
+        .subscribe(&state.source, network, &address, &request.url, request.secret, owner)
+        .await
+    {
+        Ok(subscription) => (StatusCode::CREATED, Json(subscription.redacted())).into_response(),
+        Err(e) => error_response(lookup_status(&e), &e),
+    }
+}
+
+async fn list_subscriptions(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let owner = match admit(&state, &headers) {
+        Ok(key) => key.map(|key| key.name),
+        Err(denied) => return denied_response(&denied),
+    };
+    let subscriptions: Vec<_> = state.subscriptions.list(owner.as_deref()).iter().map(|s| s.redacted()).collect();
+    Json(json!({ "subscriptions": subscriptions })).into_response()
+}
+
+async fn unsubscribe(State(state): State<AppState>, headers: HeaderMap, Path(id): Path<String>) -> Response {
+    let owner = match admit(&state, &headers) {
+        Ok(key) => key.map(|key| key.name),
+        Err(denied) => return denied_response(&denied),
+    };
+    match state.subscriptions.remove(&id, owner.as_deref()) {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => {
+            let message = format!("No subscription {}", id);
+            (StatusCode::NOT_FOUND, Json(json!({ "error": message }))).into_response()
+        }
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, &e),
+    }
+}
+
+/// The API key a lookup presented, taken from its quota
+fn admit(state: &AppState, headers: &HeaderMap) -> Result<Option<ApiKey>, Denied> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let presented = bearer.or_else(|| headers.get("x-api-key").and_then(|value| value.to_str().ok()));
+    state.tenants.admit(presented.map(str::trim))
+}
+
+fn denied_response(denied: &Denied) -> Response {
+    let body = Json(json!({ "error": denied.to_string() }));
+    match denied {
+        Denied::MissingKey | Denied::UnknownKey => {
+            (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"))], body)
+                .into_response()
+        }
+        Denied::QuotaExceeded { retry_after, .. } => {
+            let seconds = HeaderValue::from(retry_after.as_secs_f64().ceil() as u64);
+            (StatusCode::TOO_MANY_REQUESTS, [(header::RETRY_AFTER, seconds)], body).into_response()
+        }
+    }
+}
+
+async fn healthz() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+async fn readyz(State(state): State<AppState>, Query(params): Query<ReadyParams>) -> Response {
+    let networks = match params.networks.as_deref() {
+        Some(list) => match list.split(',').map(|name| name.trim().parse()).collect::<Result<Vec<Network>, _>>() {
+            Ok(networks) => networks,
+            Err(e) => return error_response(StatusCode::BAD_REQUEST, &e),
+        },
+        None => ready_networks(),
+    };
+    let providers = match state.source {
+        BalanceSource::Live => probe_all(&networks, state.ready_timeout).await,
+        // Mock data has nothing to reach, and local dev nodes are not ours to gate on
+        BalanceSource::Mock | BalanceSource::Dev { .. } => BTreeMap::new(),
+    };
+    let ready = providers.values().all(|probe| probe.ok);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(json!({ "ready": ready, "providers": providers }))).into_response()
+}
+
+/// Networks `/readyz` probes by default: the mainnets
+///
+/// An outage of a public test network says nothing about whether the server
+/// can serve real balances, so those are only probed when asked for by name.
+pub fn ready_networks() -> Vec<Network> {
+    Network::ALL.into_iter().filter(|network| !network.is_testnet()).collect()
+}
+
+/// Probe the providers of `networks` concurrently, each within `timeout`
+///
+/// `custom-evm` is probed only once `--rpc-url` has given it an endpoint.
+pub async fn probe_all(networks: &[Network], timeout: Duration) -> BTreeMap<String, ProbeResult> {
+    let mut probes = JoinSet::new();
+    for &network in networks {
+        if network == Network::CustomEvm && evm_wallet::custom_chain().is_none() {
+            continue;
+        }
+        probes.spawn(async move {
+            let started = Instant::now();
+            let outcome = match tokio::time::timeout(timeout, probe(network)).await {
+                Ok(result) => result,
+                Err(_) => Err(anyhow::anyhow!("No response within {:?}", timeout)),
+            };
+            let result = ProbeResult {
+                ok: outcome.is_ok(),
+                latency_ms: started.elapsed().as_millis(),
+                error: outcome.err().map(|e| format!("{:#}", e)),
+            };
+            (network.to_string(), result)
+        });
+    }
+
+    let mut results = BTreeMap::new();
+    while let Some(joined) = probes.join_next().await {
+        if let Ok((network, result)) = joined {
+            results.insert(network, result);
+        }
+    }
+    results
+}
+
+/// Cheapest request that proves the network's provider is answering
+async fn probe(network: Network) -> Result<()> {
+    match evm_rpc::rpc_url(network) {
+        Some(rpc_url) => evm_rpc::block_number(&rpc_url).await.map(drop),
+        None => match network.bitcoin_chain() {
+            Some(chain) => bitcoin_wallet::get_tip_height_from(&chain.api_base()).await.map(drop),
+            None if network == Network::Dogecoin => dogecoin_wallet::get_tip_height().await.map(drop),
+            None if network == Network::Litecoin => {
+                bitcoin_wallet::get_tip_height_from(config::active().litecoin_api()).await.map(drop)
+            }
+            None => tron_wallet::get_tip_height(network).await.map(drop),
+        },
+    }
+}
+
+/// `400` for a lookup the client got wrong, `502` for one the upstream failed
+fn lookup_status(error: &WalletError) -> StatusCode {
+    match error {
+        WalletError::InvalidAddress(_) | WalletError::ParseError(_) | WalletError::UnsupportedNetwork(_) => {
+            StatusCode::BAD_REQUEST
+        }
+        _ => StatusCode::BAD_GATEWAY,
+    }
+}
+
+fn error_response(status: StatusCode, error: &WalletError) -> Response {
+    (status, Json(json!({ "error": format!("{:#}", error) }))).into_response()
+}
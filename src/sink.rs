@@ -0,0 +1,235 @@
+//! Destinations for command output other than stdout (`--output-to`)
+//!
+//! Scheduled runs usually feed another system, so the output document can be
+//! delivered straight to where that system reads it: a local file, a webhook
+//! (HTTP POST), or an object in S3 or Google Cloud Storage. Object uploads
+//! are single signed `PUT`s (AWS Signature Version 4) with the credentials in
+//! `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`; GCS is reached through its
+//! S3-compatible XML API, with an HMAC key in the same variables.
+
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{http, usage, WalletError};
+
+/// Where the output of a run is delivered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sink {
+    /// Write (or overwrite) a local file
+    File(PathBuf),
+    /// POST the output to an `http://` or `https://` URL
+    Webhook(String),
+    /// Upload to `s3://bucket/key`
+    S3 { bucket: String, key: String },
+    /// Upload to `gs://bucket/key`
+    Gcs { bucket: String, key: String },
+}
+
+impl std::str::FromStr for Sink {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, WalletError> {
+        let s = s.trim();
+        if s.starts_with("http://") || s.starts_with("https://") {
+            return Ok(Sink::Webhook(s.to_string()));
+        }
+        for (scheme, gcs) in [("s3://", false), ("gs://", true)] {
+            let Some(path) = s.strip_prefix(scheme) else {
+                continue;
+            };
+            let (bucket, key) = path
+                .split_once('/')
+                .filter(|(bucket, key)| !bucket.is_empty() && !key.is_empty())
+                .ok_or_else(|| {
+                    WalletError::ParseError(format!("Invalid object URI: {} (expected {}bucket/key)", s, scheme))
+                })?;
+            let (bucket, key) = (bucket.to_string(), key.to_string());
+            return Ok(match gcs {
+                true => Sink::Gcs { bucket, key },
+                false => Sink::S3 { bucket, key },
+            });
+        }
+        if s.is_empty() {
+            return Err(WalletError::ParseError("Output destination cannot be empty".to_string()));
+        }
+        Ok(Sink::File(PathBuf::from(s.strip_prefix("file://").unwrap_or(s))))
+    }
+}
+
+impl std::fmt::Display for Sink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sink::File(path) => write!(f, "{}", path.display()),
+            Sink::Webhook(url) => write!(f, "{}", url),
+            Sink::S3 { bucket, key } => write!(f, "s3://{}/{}", bucket, key),
+            Sink::Gcs { bucket, key } => write!(f, "gs://{}/{}", bucket, key),
+        }
+    }
+}
+
+/// Access key for signed object uploads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Set for temporary credentials (`AWS_SESSION_TOKEN`)
+    pub session_token: Option<String>,
+}
+
+impl Credentials {
+    /// Credentials from the standard `AWS_*` environment variables
+    pub fn from_env() -> Result<Self, WalletError> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.trim().is_empty());
+        match (var("AWS_ACCESS_KEY_ID"), var("AWS_SECRET_ACCESS_KEY")) {
+            (Some(access_key_id), Some(secret_access_key)) => Ok(Credentials {
+                access_key_id,
+                secret_access_key,
+                session_token: var("AWS_SESSION_TOKEN"),
+            }),
+            _ => Err(WalletError::Other(
+                "Uploading needs AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY (an HMAC key for gs://)".to_string(),
+            )),
+        }
+    }
+}
+
+impl Sink {
+    /// Deliver `body`, a document of `content_type`, to this destination
+    pub async fn deliver(&self, body: Vec<u8>, content_type: &str) -> Result<(), WalletError> {
+        match self {
+            Sink::File(path) => {
+                Ok(std::fs::write(path, &body).with_context(|| format!("Failed to write {}", path.display()))?)
+            }
+            Sink::Webhook(url) => {
+                let request = http::client().post(url).header("Content-Type", content_type).body(body);
+                Ok(check_status(http::send(request).await, url)?)
+            }
+            Sink::S3 { bucket, key } => {
+                let region = std::env::var("AWS_REGION")
+                    .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+                    .unwrap_or_else(|_| "us-east-1".to_string());
+                // AWS_ENDPOINT_URL points at an S3-compatible store (MinIO, R2, ...) with path-style URLs
+                let (host, path) = match std::env::var("AWS_ENDPOINT_URL") {
+                    Ok(endpoint) => (endpoint.trim_end_matches('/').to_string(), format!("/{}/{}", bucket, key)),
+                    Err(_) => (format!("https://{}.s3.{}.amazonaws.com", bucket, region), format!("/{}", key)),
+                };
+                Ok(put_object(&host, &path, &region, body, content_type).await?)
+            }
+            Sink::Gcs { bucket, key } => {
+                let path = format!("/{}/{}", bucket, key);
+                Ok(put_object("https://storage.googleapis.com", &path, "auto", body, content_type).await?)
+            }
+        }
+    }
+}
+
+fn check_status(response: Result<http::HttpResponse>, target: &str) -> Result<()> {
+    let response = response?;
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("{} answered {}: {}", target, response.status, response.text()));
+    }
+    Ok(())
+}
+
+/// Signed `PUT` of `body` to `origin` + `path`
+async fn put_object(origin: &str, path: &str, region: &str, body: Vec<u8>, content_type: &str) -> Result<()> {
+    let credentials = Credentials::from_env()?;
+    let host = origin.split("://").nth(1).unwrap_or(origin);
+    let path = uri_encode_path(path);
+    let payload_hash = hex::encode(Sha256::digest(&body));
+    let amz_date = amz_date(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs());
+
+    let mut headers = vec![
+        ("content-type".to_string(), content_type.to_string()),
+        ("host".to_string(), host.to_string()),
+        ("x-amz-content-sha256".to_string(), payload_hash.clone()),
+        ("x-amz-date".to_string(), amz_date.clone()),
+    ];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    let authorization = sigv4_authorization("PUT", &path, &headers, &payload_hash, &amz_date, region, &credentials);
+
+    let url = format!("{}{}", origin, path);
+    let mut request = http::client().put(&url).header("Authorization", authorization).body(body);
+    for (name, value) in headers.iter().filter(|(name, _)| name != "host") {
+        request = request.header(name, value);
+    }
+    check_status(http::send(request).await, &url)
+}
+
+/// `YYYYMMDDTHHMMSSZ` timestamp of SigV4 requests
+fn amz_date(unix_secs: u64) -> String {
+    let secs = unix_secs % 86_400;
+    format!(
+        "{}T{:02}{:02}{:02}Z",
+        usage::format_date(unix_secs).replace('-', ""),
+        secs / 3_600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Percent-encode every path byte except unreserved characters and `/`
+fn uri_encode_path(path: &str) -> String {
+    path.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// `Authorization` header of an AWS Signature Version 4 (`s3` service) request
+///
+/// `path` must already be URI-encoded and the request must have no query
+/// string; `headers` are lowercase names with their values, all of them signed.
+pub fn sigv4_authorization(
+    method: &str,
+    path: &str,
+    headers: &[(String, String)],
+    payload_hash: &str,
+    amz_date: &str,
+    region: &str,
+    credentials: &Credentials,
+) -> String {
+    let mut headers: Vec<&(String, String)> = headers.iter().collect();
+    headers.sort();
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(";");
+    let canonical_request = format!(
+        "{}\n{}\n\n{}\n{}\n{}",
+        method, path, canonical_headers, signed_headers, payload_hash
+    );
+
+    let date = &amz_date[..8];
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = [date, region, "s3", "aws4_request"]
+        .iter()
+        .fold(format!("AWS4{}", credentials.secret_access_key).into_bytes(), |key, part| hmac_sha256(&key, part));
+    let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{},SignedHeaders={},Signature={}",
+        credentials.access_key_id, scope, signed_headers, signature
+    )
+}
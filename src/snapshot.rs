@@ -0,0 +1,296 @@
+//! ERC-20 holder snapshots at a fixed block
+//!
+//! Airdrop eligibility audits need every holder's token balance as of one
+//! block, not as of whenever the script happened to run. `stream` reads
+//! `balanceOf` for each (token, holder) pair with `eth_call` pinned to that
+//! block, which needs an archive node for anything older than the node's
+//! pruning window (usually the last 128 blocks), so `token-snapshot` accepts
+//! `--rpc-url`.
+//!
+//! With `Fetch::Multicall` the reads are packed into Multicall3 `aggregate3`
+//! calls, so tens of thousands of pairs take a few hundred requests. Rows are
+//! handed to the caller batch by batch and never collected, keeping memory
+//! flat however many pairs there are.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::units::U256;
+use crate::{ethereum_wallet, evm_rpc, Network, WalletError};
+
+/// Multicall3 deployment address, identical on every major EVM chain
+pub const MULTICALL3_ADDRESS: &str = "0xca11bde05779ba9e0a8e2e4f5e8c209e6f5f1bdc";
+
+/// `balanceOf` reads packed into one Multicall3 request by default
+pub const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// `balanceOf(address)` selector
+const BALANCE_OF_SELECTOR: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+
+/// `aggregate3((address,bool,bytes)[])` selector
+const AGGREGATE3_SELECTOR: [u8; 4] = [0x82, 0xad, 0x56, 0xcb];
+
+/// Header line of `to_csv` and `SnapshotRow::csv_line`
+pub const CSV_HEADER: &str = "token,block,holder,balance";
+
+/// One holder's balance of one token, in the token's base units
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SnapshotRow {
+    pub token: String,
+    pub block: u64,
+    pub holder: String,
+    /// Raw `balanceOf` value; no decimals are applied
+    #[serde(serialize_with = "serialize_decimal_string")]
+    pub balance: U256,
+}
+
+impl SnapshotRow {
+    /// The row as a CSV line (without the newline)
+    pub fn csv_line(&self) -> String {
+        format!("{},{},{},{}", self.token, self.block, self.holder, self.balance)
+    }
+}
+
+/// JSON numbers lose precision past 2^53, so base units are written as strings
+fn serialize_decimal_string<S: serde::Serializer>(value: &U256, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+/// RPC endpoint of an EVM `network`, from the config file or the public default
+pub fn default_rpc_url(network: Network) -> Result<String, WalletError> {
+    evm_rpc::rpc_url(network)
+        .ok_or_else(|| WalletError::UnsupportedNetwork(format!("Token snapshots need an EVM network, not {}", network)))
+}
+
+/// Normalize and validate an EVM address
+pub fn normalize_address(address: &str) -> Result<String, WalletError> {
+    let normalized = ethereum_wallet::normalize_address(address.trim())?;
+    ethereum_wallet::validate_address(&normalized)?;
+    Ok(normalized)
+}
+
+/// Parse a holder list: one address per line
+///
+/// Blank lines and `#` comments are skipped. A holder listed twice is an
+/// error, since it would be counted twice in the snapshot.
+pub fn parse_holder_list(text: &str) -> Result<Vec<String>, WalletError> {
+    let mut holders = Vec::new();
+    let mut seen = HashSet::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let holder = normalize_address(line).with_context(|| format!("Line {}", index + 1))?;
+        if !seen.insert(holder.clone()) {
+            return Err(WalletError::ParseError(format!("Line {}: {} is listed twice", index + 1, holder)));
+        }
+        holders.push(holder);
+    }
+    if holders.is_empty() {
+        return Err(WalletError::ParseError("The holder list is empty".to_string()));
+    }
+    Ok(holders)
+}
+
+/// How `stream` reads the balances
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fetch {
+    /// One `eth_call` per pair; works on any node and at blocks before Multicall3 was deployed
+    Direct,
+    /// Up to `batch_size` pairs per Multicall3 `aggregate3` call
+    Multicall { batch_size: usize },
+}
+
+/// Read the balance of every holder of every token at `block`
+///
+/// Pairs are visited token by token, each in holder-list order, and handed
+/// to `on_row` as soon as their batch is read. Stops at the first failure;
+/// rows already passed to `on_row` are not retracted, so the caller should
+/// treat the output as incomplete. Returns the number of rows produced.
+pub async fn stream(
+    rpc_url: &str,
+    tokens: &[String],
+    block: u64,
+    holders: &[String],
+    fetch: Fetch,
+    mut on_row: impl FnMut(SnapshotRow) -> Result<()>,
+) -> Result<u64, WalletError> {
+    let batch_size = match fetch {
+        Fetch::Direct => 1,
+        Fetch::Multicall { batch_size } => batch_size.max(1),
+    };
+    let mut pairs = tokens.iter().flat_map(|token| holders.iter().map(move |holder| (token, holder)));
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut produced = 0;
+    loop {
+        batch.clear();
+        batch.extend(pairs.by_ref().take(batch_size));
+        if batch.is_empty() {
+            return Ok(produced);
+        }
+
+        let balances = match fetch {
+            Fetch::Direct => {
+                let (token, holder) = batch[0];
+                vec![evm_rpc::erc20_balance_at(rpc_url, token, holder, block)
+                    .await
+                    .with_context(|| format!("Failed to read the balance of {} at block {}", holder, block))?]
+            }
+            Fetch::Multicall { .. } => multicall_balances(rpc_url, &batch, block).await.with_context(|| {
+                let (token, holder) = batch[0];
+                format!("Failed to read the batch starting at {} / {} at block {}", token, holder, block)
+            })?,
+        };
+
+        for (&(token, holder), balance) in batch.iter().zip(balances) {
+            on_row(SnapshotRow {
+                token: token.clone(),
+                block,
+                holder: holder.clone(),
+                balance,
+            })?;
+            produced += 1;
+        }
+    }
+}
+
+/// `balanceOf` of each `(token, holder)` pair through one `aggregate3` call
+async fn multicall_balances(rpc_url: &str, pairs: &[(&String, &String)], block: u64) -> Result<Vec<U256>> {
+    let calls: Vec<([u8; 20], Vec<u8>)> = pairs
+        .iter()
+        .map(|(token, holder)| Ok((address_bytes(token)?, balance_of_call(holder)?)))
+        .collect::<Result<_>>()?;
+    let data = format!("0x{}", hex::encode(encode_aggregate3(&calls)));
+    let result = evm_rpc::call(
+        rpc_url,
+        "eth_call",
+        vec![
+            serde_json::json!({ "to": MULTICALL3_ADDRESS, "data": data }),
+            serde_json::json!(format!("0x{:x}", block)),
+        ],
+    )
+    .await?;
+    let output = result
+        .as_str()
+        .and_then(|hex_str| hex::decode(hex_str.trim_start_matches("0x")).ok())
+        .ok_or_else(|| anyhow::anyhow!("Multicall returned {} instead of hex data", result))?;
+    if output.is_empty() {
+        return Err(anyhow::anyhow!("No Multicall3 contract at block {}", block));
+    }
+
+    let returned = decode_aggregate3(&output)?;
+    if returned.len() != pairs.len() {
+        return Err(anyhow::anyhow!("Multicall returned {} results for {} calls", returned.len(), pairs.len()));
+    }
+    returned
+        .iter()
+        .zip(pairs)
+        .map(|(return_data, (token, holder))| {
+            decode_uint(return_data).with_context(|| format!("{} did not return a balance for {}", token, holder))
+        })
+        .collect()
+}
+
+fn address_bytes(address: &str) -> Result<[u8; 20]> {
+    let bytes = hex::decode(address.trim_start_matches("0x")).context("Invalid address hex")?;
+    bytes.try_into().map_err(|_| anyhow::anyhow!("Address {} is not 20 bytes", address))
+}
+
+fn balance_of_call(holder: &str) -> Result<Vec<u8>> {
+    let mut data = BALANCE_OF_SELECTOR.to_vec();
+    data.extend([0u8; 12]);
+    data.extend(address_bytes(holder)?);
+    Ok(data)
+}
+
+fn word(value: usize) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+/// ABI-encode `aggregate3` with `allowFailure = false`, so one failing read reverts the batch
+fn encode_aggregate3(calls: &[([u8; 20], Vec<u8>)]) -> Vec<u8> {
+    let mut data = AGGREGATE3_SELECTOR.to_vec();
+    data.extend(word(0x20));
+    data.extend(word(calls.len()));
+
+    // Each tuple is (address, bool, offset of bytes, bytes length, padded bytes)
+    let tuple_len = |call_data: &Vec<u8>| 32 * 4 + call_data.len().div_ceil(32) * 32;
+    let mut offset = 32 * calls.len();
+    for (_, call_data) in calls {
+        data.extend(word(offset));
+        offset += tuple_len(call_data);
+    }
+    for (target, call_data) in calls {
+        let mut address = [0u8; 32];
+        address[12..].copy_from_slice(target);
+        data.extend(address);
+        data.extend(word(0));
+        data.extend(word(0x60));
+        data.extend(word(call_data.len()));
+        data.extend(call_data);
+        data.resize(data.len() + (32 - call_data.len() % 32) % 32, 0);
+    }
+    data
+}
+
+fn read_word(data: &[u8], at: usize) -> Result<usize> {
+    let word = data
+        .get(at..offset(at, 32)?)
+        .ok_or_else(|| anyhow::anyhow!("Multicall result truncated at byte {}", at))?;
+    if word[..24].iter().any(|&b| b != 0) {
+        return Err(anyhow::anyhow!("Multicall result has an out-of-range offset at byte {}", at));
+    }
+    usize::try_from(u64::from_be_bytes(word[24..].try_into().expect("8 bytes")))
+        .map_err(|_| anyhow::anyhow!("Multicall result has an out-of-range offset at byte {}", at))
+}
+
+/// `base + delta`, as an error instead of an overflow when the node sent an absurd offset
+fn offset(base: usize, delta: usize) -> Result<usize> {
+    base.checked_add(delta)
+        .ok_or_else(|| anyhow::anyhow!("Multicall result has an out-of-range offset"))
+}
+
+/// Decode the `(bool success, bytes returnData)[]` returned by `aggregate3`
+fn decode_aggregate3(output: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let array = read_word(output, 0)?;
+    let count = read_word(output, array)?;
+    let elements = offset(array, 32)?;
+    (0..count)
+        .map(|index| {
+            let head = offset(elements, index.checked_mul(32).context("Multicall result has too many calls")?)?;
+            let tuple = offset(elements, read_word(output, head)?)?;
+            if read_word(output, tuple)? != 1 {
+                return Err(anyhow::anyhow!("Call {} in the batch failed", index));
+            }
+            let bytes = offset(tuple, read_word(output, offset(tuple, 32)?)?)?;
+            let len = read_word(output, bytes)?;
+            let start = offset(bytes, 32)?;
+            output
+                .get(start..offset(start, len)?)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| anyhow::anyhow!("Multicall result truncated in call {}", index))
+        })
+        .collect()
+}
+
+/// A 32-byte big-endian `uint256`
+fn decode_uint(data: &[u8]) -> Result<U256> {
+    let word: [u8; 32] = data
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Expected 32 bytes, got {}", data.len()))?;
+    Ok(U256::from_be_bytes(word))
+}
+
+/// CSV document with `CSV_HEADER` and one line per row
+pub fn to_csv(rows: &[SnapshotRow]) -> String {
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for row in rows {
+        csv.push_str(&row.csv_line());
+        csv.push('\n');
+    }
+    csv
+}
@@ -0,0 +1,293 @@
+//! Encrypted bundles of the local state (`state export`, `state import`)
+//!
+//! Moving to a new machine, or backing up the old one, means carrying the
+//! config file (endpoints, keys, the address book), the data directory
+//! (`usage.json`, the `serve` webhook subscriptions) and any portfolio files
+//! kept elsewhere. A `Bundle` holds them all, and `seal` turns it into one
+//! archive encrypted under a passphrase:
+//!
+//! ```text
+//! "WBSTATE\0" | version (1 byte) | PBKDF2 iterations (4 bytes, big-endian) | salt (16) | nonce (12) | ciphertext
+//! ```
+//!
+//! The key is PBKDF2-HMAC-SHA256 of the passphrase and salt, and the
+//! ciphertext is the JSON manifest under ChaCha20-Poly1305 with the header
+//! as associated data, so a wrong passphrase and a corrupted or altered
+//! archive are both refused before anything is written. The manifest also
+//! records the SHA-256 of every file, checked again when it is opened. The
+//! balance cache is left out, since it refills itself; history is read from
+//! the chain each time, so there is nothing of it to carry.
+
+use anyhow::{Context, Result};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{Config, ConfigPaths};
+use crate::WalletError;
+
+/// Archive format written by `seal`; `open` refuses newer ones
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Environment variable holding the passphrase when no `--passphrase-file` is given
+pub const PASSPHRASE_ENV: &str = "WALLET_BALANCE_STATE_PASSPHRASE";
+
+/// Start of every archive
+const MAGIC: &[u8; 8] = b"WBSTATE\0";
+/// PBKDF2 rounds for new archives, per OWASP's recommendation for HMAC-SHA256
+const PBKDF2_ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4 + SALT_LEN + NONCE_LEN;
+
+/// What a bundled file is, which decides where it is restored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileKind {
+    /// The config file
+    Config,
+    /// A file of the data directory
+    Data,
+    /// A portfolio file given to `state export`
+    Portfolio,
+}
+
+impl std::fmt::Display for FileKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileKind::Config => write!(f, "config"),
+            FileKind::Data => write!(f, "data"),
+            FileKind::Portfolio => write!(f, "portfolio"),
+        }
+    }
+}
+
+/// One file of a bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundledFile {
+    pub kind: FileKind,
+    /// File name, without directories
+    pub name: String,
+    /// Hex SHA-256 of `contents`
+    pub sha256: String,
+    pub contents: String,
+}
+
+impl BundledFile {
+    fn new(kind: FileKind, name: &str, contents: String) -> Self {
+        BundledFile {
+            kind,
+            name: name.to_string(),
+            sha256: hex::encode(Sha256::digest(contents.as_bytes())),
+            contents,
+        }
+    }
+}
+
+/// The local state of one machine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bundle {
+    pub format: u8,
+    /// Unix time of the export
+    pub created: u64,
+    /// Version of wallet-balance that made it
+    pub version: String,
+    pub files: Vec<BundledFile>,
+}
+
+impl Bundle {
+    /// Gather `config_file`, the files of `paths.data_dir` and `portfolios`
+    ///
+    /// A config file or data directory that does not exist yet is skipped;
+    /// a portfolio file that cannot be read is an error. Where the config
+    /// file lives in the data directory (macOS), it is bundled once, as the
+    /// config; data files that are not UTF-8 text are left out with a warning.
+    pub fn collect(paths: &ConfigPaths, config_file: &Path, portfolios: &[PathBuf]) -> Result<Self, WalletError> {
+        let mut files = Vec::new();
+        if config_file.is_file() {
+            files.push(BundledFile::new(FileKind::Config, &file_name(config_file)?, read(config_file)?));
+        }
+        if paths.data_dir.is_dir() {
+            let entries = std::fs::read_dir(&paths.data_dir)
+                .with_context(|| format!("Failed to read {}", paths.data_dir.display()))?;
+            let mut data: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                // Half-written files of an interrupted save are not state
+                .filter(|path| path.is_file() && path.extension().is_none_or(|extension| extension != "tmp"))
+                .filter(|path| !same_file(path, config_file))
+                .collect();
+            data.sort();
+            for path in data {
+                let bytes = std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+                match String::from_utf8(bytes) {
+                    Ok(contents) => files.push(BundledFile::new(FileKind::Data, &file_name(&path)?, contents)),
+                    Err(_) => tracing::warn!("Leaving {} out of the bundle: it is not UTF-8 text", path.display()),
+                }
+            }
+        }
+        for path in portfolios {
+            let name = file_name(path)?;
+            if files.iter().any(|file| file.kind == FileKind::Portfolio && file.name == name) {
+                return Err(WalletError::Other(format!("Two portfolio files are named {}", name)));
+            }
+            files.push(BundledFile::new(FileKind::Portfolio, &name, read(path)?));
+        }
+        Ok(Bundle {
+            format: FORMAT_VERSION,
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            files,
+        })
+    }
+
+    /// Encrypt the bundle under `passphrase` into an archive
+    pub fn seal(&self, passphrase: &str) -> Result<Vec<u8>, WalletError> {
+        if passphrase.is_empty() {
+            return Err(WalletError::Other("The passphrase cannot be empty".to_string()));
+        }
+        let random = SystemRandom::new();
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        random
+            .fill(&mut salt)
+            .and_then(|()| random.fill(&mut nonce))
+            .map_err(|_| anyhow::anyhow!("No secure random numbers available"))?;
+
+        let mut archive = Vec::with_capacity(HEADER_LEN);
+        archive.extend_from_slice(MAGIC);
+        archive.push(FORMAT_VERSION);
+        archive.extend_from_slice(&PBKDF2_ITERATIONS.to_be_bytes());
+        archive.extend_from_slice(&salt);
+        archive.extend_from_slice(&nonce);
+
+        let mut contents = serde_json::to_vec(self).expect("bundle serializes");
+        key(passphrase, &salt, PBKDF2_ITERATIONS)?
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(&archive[..]), &mut contents)
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt the bundle"))?;
+        archive.extend_from_slice(&contents);
+        Ok(archive)
+    }
+
+    /// Decrypt and check an archive `seal` made
+    pub fn open(archive: &[u8], passphrase: &str) -> Result<Self, WalletError> {
+        if archive.len() < HEADER_LEN || !archive.starts_with(MAGIC) {
+            return Err(WalletError::ParseError("Not a wallet-balance state bundle".to_string()));
+        }
+        let (header, ciphertext) = archive.split_at(HEADER_LEN);
+        let version = header[MAGIC.len()];
+        if version != FORMAT_VERSION {
+            return Err(WalletError::ParseError(format!(
+                "State bundle format {} is not supported by this version (expected {})",
+                version, FORMAT_VERSION
+            )));
+        }
+        let at = MAGIC.len() + 1;
+        let iterations = u32::from_be_bytes(header[at..at + 4].try_into().expect("4 bytes"));
+        let salt = &header[at + 4..at + 4 + SALT_LEN];
+        let nonce: [u8; NONCE_LEN] = header[at + 4 + SALT_LEN..].try_into().expect("nonce length");
+
+        let mut contents = ciphertext.to_vec();
+        let plaintext = key(passphrase, salt, iterations)?
+            .open_in_place(Nonce::assume_unique_for_key(nonce), Aad::from(header), &mut contents)
+            .map_err(|_| {
+                WalletError::Other("Cannot decrypt the state bundle: wrong passphrase, or the file is damaged".to_string())
+            })?;
+        let bundle: Bundle = serde_json::from_slice(plaintext).context("The state bundle's manifest is invalid")?;
+
+        for file in &bundle.files {
+            if hex::encode(Sha256::digest(file.contents.as_bytes())) != file.sha256 {
+                return Err(WalletError::ParseError(format!("{} in the state bundle fails its checksum", file.name)));
+            }
+            if Path::new(&file.name).file_name() != Some(file.name.as_ref()) {
+                return Err(WalletError::ParseError(format!("Invalid file name in the state bundle: {}", file.name)));
+            }
+            if file.kind == FileKind::Config {
+                Config::from_toml(&file.contents).context("The config file in the state bundle is invalid")?;
+            }
+        }
+        Ok(bundle)
+    }
+
+    /// Where each file is restored: the config to `config_file`, data files
+    /// to `paths.data_dir` and portfolios to `portfolio_dir`
+    pub fn targets(&self, paths: &ConfigPaths, config_file: &Path, portfolio_dir: &Path) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .map(|file| match file.kind {
+                FileKind::Config => config_file.to_path_buf(),
+                FileKind::Data => paths.data_dir.join(&file.name),
+                FileKind::Portfolio => portfolio_dir.join(&file.name),
+            })
+            .collect()
+    }
+
+    /// Write every file to its target and return the targets
+    ///
+    /// Without `overwrite`, nothing is written when any target already
+    /// exists, so an import never half-replaces a machine's state.
+    pub fn restore(
+        &self,
+        paths: &ConfigPaths,
+        config_file: &Path,
+        portfolio_dir: &Path,
+        overwrite: bool,
+    ) -> Result<Vec<PathBuf>, WalletError> {
+        let targets = self.targets(paths, config_file, portfolio_dir);
+        if !overwrite {
+            let existing: Vec<String> = targets
+                .iter()
+                .filter(|target| target.exists())
+                .map(|target| target.display().to_string())
+                .collect();
+            if !existing.is_empty() {
+                return Err(WalletError::Other(format!(
+                    "Would overwrite {} (use --force to replace them)",
+                    existing.join(", ")
+                )));
+            }
+        }
+        for (file, target) in self.files.iter().zip(&targets) {
+            if let Some(parent) = target.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            let staging = target.with_extension("import.tmp");
+            std::fs::write(&staging, &file.contents).with_context(|| format!("Failed to write {}", staging.display()))?;
+            std::fs::rename(&staging, target).with_context(|| format!("Failed to write {}", target.display()))?;
+        }
+        Ok(targets)
+    }
+}
+
+/// The archive key for `passphrase`, `salt` and `iterations` rounds of PBKDF2
+fn key(passphrase: &str, salt: &[u8], iterations: u32) -> Result<LessSafeKey> {
+    // A damaged header must not send the derivation off for hours
+    let iterations = NonZeroU32::new(iterations)
+        .filter(|iterations| iterations.get() <= 10 * PBKDF2_ITERATIONS)
+        .context("The state bundle's header is damaged")?;
+    let mut key = [0u8; 32];
+    ring::pbkdf2::derive(ring::pbkdf2::PBKDF2_HMAC_SHA256, iterations, salt, passphrase.as_bytes(), &mut key);
+    let key = UnboundKey::new(&CHACHA20_POLY1305, &key).map_err(|_| anyhow::anyhow!("Invalid key length"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+fn file_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .with_context(|| format!("{} has no file name", path.display()))
+}
+
+/// Whether `a` and `b` name the same file, through symlinks and `..` too
+fn same_file(a: &Path, b: &Path) -> bool {
+    a == b || matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
+
+fn read(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+}
@@ -0,0 +1,502 @@
+//! Balance-change webhooks of the HTTP server
+//!
+//! Clients of `serve --listen` register a URL for an address with
+//! `POST /subscriptions`, and the server tells them when its balance changes
+//! instead of each of them polling: every `--poll-interval` it looks up each
+//! subscribed address once, however many subscriptions share it, and POSTs a
+//! `BalanceEvent` to every subscription whose last reported balance differs.
+//! A subscription given a `secret` gets the HMAC-SHA256 of each body in the
+//! `X-Wallet-Balance-Signature` header (`sha256=<hex>`), so the receiver can
+//! tell the events are ours.
+//!
+//! A delivery that fails is tried again on the next poll, with the change
+//! still measured from the last balance the receiver was told about.
+//! Subscriptions and those balances are kept in `subscriptions.json` in the
+//! data directory, so a restart neither loses them nor misses what changed
+//! while the server was down.
+//!
+//! The server makes those POSTs from inside the network it runs in, so a
+//! URL whose host resolves to a loopback, private, link-local or otherwise
+//! non-public address (a cloud metadata service, an admin port on
+//! localhost) is refused unless `[webhooks] allowed_hosts` lists the host.
+//! The address is checked when the subscription is made and again before
+//! every delivery, which then connects to exactly the address checked and
+//! follows no redirects. Each API key may hold `[webhooks] max_per_owner`
+//! subscriptions.
+
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::ConfigPaths;
+use crate::{http, watch, BalanceSource, Network, WalletBalance, WalletError};
+
+/// File name of the subscription list inside the data directory
+pub const SUBSCRIPTIONS_FILE_NAME: &str = "subscriptions.json";
+
+/// Header carrying the signature of an event for a subscription with a secret
+pub const SIGNATURE_HEADER: &str = "X-Wallet-Balance-Signature";
+
+/// Subscriptions one API key may hold when `[webhooks]` sets no `max_per_owner`
+pub const DEFAULT_MAX_PER_OWNER: usize = 100;
+
+/// Where webhooks may point, and how many each API key may register
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookPolicy {
+    /// Hosts (names or IP addresses) accepted even though they resolve to a non-public address
+    pub allowed_hosts: Vec<String>,
+    /// Subscriptions per API key; on a server without keys, in total
+    pub max_per_owner: usize,
+}
+
+impl Default for WebhookPolicy {
+    fn default() -> Self {
+        WebhookPolicy {
+            allowed_hosts: Vec::new(),
+            max_per_owner: DEFAULT_MAX_PER_OWNER,
+        }
+    }
+}
+
+impl WebhookPolicy {
+    fn allows_host(&self, host: &str) -> bool {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        self.allowed_hosts.iter().any(|allowed| {
+            allowed.trim().trim_start_matches('[').trim_end_matches(']').eq_ignore_ascii_case(host)
+        })
+    }
+}
+
+/// One registered webhook
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subscription {
+    pub id: String,
+    pub network: String,
+    pub address: String,
+    /// Where events are POSTed
+    pub url: String,
+    /// Key the events are signed with; never shown back through the API
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// Name of the API key that registered it; only that key may see or remove it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Unix time of registration
+    pub created: u64,
+    /// Balance the receiver was last told about (at registration, the balance then)
+    pub balance: String,
+    pub denomination: String,
+}
+
+impl std::fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Subscription")
+            .field("id", &self.id)
+            .field("network", &self.network)
+            .field("address", &self.address)
+            .field("url", &self.url)
+            .field("owner", &self.owner)
+            .field("balance", &self.balance)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Subscription {
+    /// The subscription without its secret, as the API shows it
+    pub fn redacted(&self) -> Subscription {
+        Subscription {
+            secret: None,
+            ..self.clone()
+        }
+    }
+}
+
+/// Body of a webhook POST
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BalanceEvent {
+    /// Always `balance_change`
+    pub event: &'static str,
+    pub subscription: String,
+    pub network: String,
+    pub address: String,
+    pub previous: String,
+    pub current: String,
+    /// Signed difference, e.g. `+0.5` or `-0.0001`
+    pub delta: String,
+    pub denomination: String,
+    /// Unix time of the poll that saw the change
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// File the list is saved to after every change; kept in memory only when `None`
+    path: Option<PathBuf>,
+    subscriptions: Vec<Subscription>,
+    /// Registrations so far, so ids made in the same instant still differ
+    issued: u64,
+    policy: WebhookPolicy,
+}
+
+/// The server's subscriptions; clones share them
+#[derive(Debug, Clone, Default)]
+pub struct Subscriptions {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Subscriptions {
+    /// The subscriptions saved at `path`, saving there from now on; a missing file means there are none yet
+    pub fn open(path: &Path) -> Result<Self, WalletError> {
+        let subscriptions = match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)
+                .with_context(|| format!("{} is not a subscriptions file", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(WalletError::Other(format!("Failed to read {}: {}", path.display(), e))),
+        };
+        Ok(Subscriptions {
+            inner: Arc::new(Mutex::new(Inner {
+                path: Some(path.to_path_buf()),
+                subscriptions,
+                issued: 0,
+                policy: WebhookPolicy::default(),
+            })),
+        })
+    }
+
+    /// Check new subscriptions and deliveries against `policy` from now on
+    pub fn set_policy(&self, policy: WebhookPolicy) {
+        self.lock().policy = policy;
+    }
+
+    /// Register `url` for changes of `address` on `network`, on behalf of API key `owner`
+    ///
+    /// The address is looked up through `source` first, which rejects one the
+    /// network does not accept and gives the balance later changes are
+    /// measured from. A URL the policy does not allow, or an owner already
+    /// at its limit, is refused with `ParseError`.
+    pub async fn subscribe(
+        &self,
+        source: &BalanceSource,
+        network: Network,
+        address: &str,
+        url: &str,
+        secret: Option<String>,
+        owner: Option<String>,
+    ) -> Result<Subscription, WalletError> {
+        let url = url.trim();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(WalletError::ParseError(format!(
+                "Invalid webhook URL: {} (expected an http:// or https:// URL)",
+                url
+            )));
+        }
+        let policy = self.lock().policy.clone();
+        check_limit(&self.lock(), owner.as_deref())?;
+        resolve(url, &policy).await?;
+        let current = source.get_balance(network, address).await?;
+
+        let mut inner = self.lock();
+        // Checked again: other registrations may have landed during the lookup
+        check_limit(&inner, owner.as_deref())?;
+        inner.issued += 1;
+        let created = unix_now();
+        let seed = format!("{}:{}:{}:{}:{:?}", network, current.address, url, inner.issued, SystemTime::now());
+        let subscription = Subscription {
+            id: hex::encode(&Sha256::digest(seed.as_bytes())[..8]),
+            network: network.to_string(),
+            address: current.address,
+            url: url.to_string(),
+            secret: secret.filter(|secret| !secret.is_empty()),
+            owner,
+            created,
+            balance: current.balance,
+            denomination: current.denomination,
+        };
+        inner.subscriptions.push(subscription.clone());
+        save(&inner)?;
+        Ok(subscription)
+    }
+
+    /// Subscriptions of API key `owner`, or all of them when the server is open (`None`)
+    pub fn list(&self, owner: Option<&str>) -> Vec<Subscription> {
+        let inner = self.lock();
+        inner
+            .subscriptions
+            .iter()
+            .filter(|subscription| owner.is_none() || subscription.owner.as_deref() == owner)
+            .cloned()
+            .collect()
+    }
+
+    /// Remove subscription `id` if `owner` may see it; `false` when there is no such subscription
+    pub fn remove(&self, id: &str, owner: Option<&str>) -> Result<bool, WalletError> {
+        let mut inner = self.lock();
+        let before = inner.subscriptions.len();
+        inner
+            .subscriptions
+            .retain(|subscription| subscription.id != id || (owner.is_some() && subscription.owner.as_deref() != owner));
+        if inner.subscriptions.len() == before {
+            return Ok(false);
+        }
+        save(&inner)?;
+        Ok(true)
+    }
+
+    /// Look up every subscribed address once and deliver the changes; returns the number of events delivered
+    ///
+    /// Addresses are looked up one after another, so a long list does not
+    /// set off provider rate limits. Failed lookups and deliveries are logged
+    /// and left for the next poll.
+    pub async fn poll(&self, source: &BalanceSource) -> usize {
+        let watched: BTreeSet<(String, String)> = self
+            .lock()
+            .subscriptions
+            .iter()
+            .map(|subscription| (subscription.network.clone(), subscription.address.clone()))
+            .collect();
+
+        let policy = self.lock().policy.clone();
+        let mut delivered = 0;
+        for (network_name, address) in watched {
+            let Ok(network) = network_name.parse::<Network>() else {
+                continue;
+            };
+            let current = match source.get_balance(network, &address).await {
+                Ok(current) => current,
+                Err(e) => {
+                    tracing::warn!(network = %network, address, error = %format!("{:#}", e), "subscription poll failed");
+                    continue;
+                }
+            };
+            let due: Vec<(Subscription, watch::BalanceChange)> = self
+                .lock()
+                .subscriptions
+                .iter()
+                .filter(|subscription| subscription.network == network_name && subscription.address == address)
+                .filter_map(|subscription| {
+                    let reported = WalletBalance::new(
+                        address.clone(),
+                        subscription.balance.clone(),
+                        network_name.clone(),
+                        subscription.denomination.clone(),
+                    );
+                    let change = watch::balance_change(&reported, &current, network.decimals()).ok()??;
+                    Some((subscription.clone(), change))
+                })
+                .collect();
+
+            for (subscription, change) in due {
+                let event = BalanceEvent {
+                    event: "balance_change",
+                    subscription: subscription.id.clone(),
+                    network: network_name.clone(),
+                    address: address.clone(),
+                    previous: change.previous,
+                    current: change.current,
+                    delta: change.delta,
+                    denomination: current.denomination.clone(),
+                    timestamp: unix_now(),
+                };
+                match deliver(&subscription, &event, &policy).await {
+                    Ok(()) => {
+                        delivered += 1;
+                        let mut inner = self.lock();
+                        let stored = inner.subscriptions.iter_mut().find(|stored| stored.id == subscription.id);
+                        if let Some(stored) = stored {
+                            stored.balance = event.current;
+                        }
+                    }
+                    Err(e) => tracing::warn!(
+                        subscription = %subscription.id,
+                        endpoint = %http::loggable(&subscription.url),
+                        error = %e.root_cause(),
+                        "webhook delivery failed"
+                    ),
+                }
+            }
+        }
+
+        if delivered > 0 {
+            if let Err(e) = save(&self.lock()) {
+                tracing::warn!(error = %e, "failed to save subscriptions");
+            }
+        }
+        delivered
+    }
+
+    /// Poll every `interval` for as long as the server runs
+    pub async fn run(self, source: BalanceSource, interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            self.poll(&source).await;
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Location of the subscriptions file for the current user
+pub fn default_path() -> Result<PathBuf, WalletError> {
+    Ok(ConfigPaths::discover()?.data_dir.join(SUBSCRIPTIONS_FILE_NAME))
+}
+
+/// `sha256=<hex>` signature of `body` under `secret`, as sent in `SIGNATURE_HEADER`
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Refuse another subscription for `owner` once it holds `max_per_owner` of them
+fn check_limit(inner: &Inner, owner: Option<&str>) -> Result<(), WalletError> {
+    let limit = inner.policy.max_per_owner;
+    let held = inner
+        .subscriptions
+        .iter()
+        .filter(|subscription| subscription.owner.as_deref() == owner)
+        .count();
+    if held >= limit {
+        return Err(WalletError::ParseError(format!(
+            "Subscription limit reached ({} per API key); remove one first",
+            limit
+        )));
+    }
+    Ok(())
+}
+
+/// Host and addresses of webhook `url`, refused when any address is not public and the host is not allowed
+async fn resolve(url: &str, policy: &WebhookPolicy) -> Result<(String, Vec<SocketAddr>), WalletError> {
+    let invalid = |reason: &str| WalletError::ParseError(format!("Invalid webhook URL: {} ({})", http::loggable(url), reason));
+    let parsed = reqwest::Url::parse(url).map_err(|e| invalid(&e.to_string()))?;
+    let host = parsed.host_str().ok_or_else(|| invalid("no host"))?.to_string();
+    let port = parsed.port_or_known_default().ok_or_else(|| invalid("no port"))?;
+    let literal = host.trim_start_matches('[').trim_end_matches(']');
+    let addresses: Vec<SocketAddr> = match literal.parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => tokio::net::lookup_host((literal, port))
+            .await
+            .map_err(|e| invalid(&format!("cannot resolve {}: {}", host, e)))?
+            .collect(),
+    };
+    if addresses.is_empty() {
+        return Err(invalid(&format!("{} has no addresses", host)));
+    }
+    if !policy.allows_host(&host) {
+        if let Some(address) = addresses.iter().find(|address| !is_public(address.ip())) {
+            return Err(invalid(&format!(
+                "{} is not a public address; list the host in [webhooks] allowed_hosts to allow it",
+                address.ip()
+            )));
+        }
+    }
+    Ok((host, addresses))
+}
+
+/// Whether `ip` is reachable on the public internet, as opposed to loopback,
+/// private, link-local, shared (CGNAT), reserved or multicast space
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match embedded_v4(ip) {
+            Some(embedded) => is_public_v4(embedded),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+/// The IPv4 address an IPv6 one routes to: IPv4-mapped `::ffff:a.b.c.d`,
+/// IPv4-compatible `::a.b.c.d`, NAT64 `64:ff9b::/96` and 6to4 `2002::/16`
+fn embedded_v4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let segments = ip.segments();
+    let v4 = |high: u16, low: u16| Some(Ipv4Addr::from((u32::from(high) << 16) | u32::from(low)));
+    match segments {
+        [0, 0, 0, 0, 0, 0xffff, high, low] | [0, 0, 0, 0, 0, 0, high, low] => v4(high, low),
+        [0x64, 0xff9b, 0, 0, 0, 0, high, low] => v4(high, low),
+        [0x2002, high, low, ..] => v4(high, low),
+        _ => None,
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 192 && b == 0 && c == 0)
+        || (a == 198 && (b == 18 || b == 19))
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local fc00::/7, link-local fe80::/10 and the old site-local fec0::/10
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+        || (first & 0xffc0) == 0xfec0
+        // Documentation 2001:db8::/32
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8)
+        // Local-use NAT64 64:ff9b:1::/48, which may embed its IPv4 address anywhere
+        || (first == 0x64 && ip.segments()[1] == 0xff9b && ip.segments()[2] == 1))
+}
+
+/// POST `event` to the subscription's URL, signed when it has a secret
+///
+/// The host is resolved and checked against `policy` again, so a name that
+/// has since moved to a private address is refused, and the connection goes
+/// to the address that was checked, without following redirects.
+async fn deliver(subscription: &Subscription, event: &BalanceEvent, policy: &WebhookPolicy) -> Result<()> {
+    let (host, addresses) = resolve(&subscription.url, policy).await?;
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .resolve_to_addrs(&host, &addresses)
+        .build()
+        .context("Failed to build the webhook client")?;
+    let body = serde_json::to_vec(event).expect("balance event serializes");
+    let mut request = client.post(&subscription.url).header("Content-Type", "application/json");
+    if let Some(secret) = &subscription.secret {
+        request = request.header(SIGNATURE_HEADER, signature(secret, &body));
+    }
+    let response = http::send(request.body(body)).await?;
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("Webhook answered {}", response.status));
+    }
+    Ok(())
+}
+
+/// Write the list to its file, if it has one, replacing the previous file atomically
+fn save(inner: &Inner) -> Result<()> {
+    let Some(path) = &inner.path else {
+        return Ok(());
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let staging = path.with_extension("json.tmp");
+    let text = serde_json::to_string_pretty(&inner.subscriptions).expect("subscriptions serialize");
+    std::fs::write(&staging, text).with_context(|| format!("Failed to write {}", staging.display()))?;
+    std::fs::rename(&staging, path).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
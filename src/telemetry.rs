@@ -0,0 +1,94 @@
+//! OpenTelemetry export of the request pipeline (`otel` feature)
+//!
+//! The library's `info` spans describe every lookup: `balance` (and the Tron
+//! and Bitcoin batches) with the network, `rpc` with the JSON-RPC method, and
+//! `http_request` with the provider host, HTTP method, URL, status and number
+//! of retries, each attempt and retry recorded as an event on it. A span's
+//! duration is the latency of that step. `Telemetry` ships them to an
+//! OpenTelemetry collector over OTLP/HTTP, so a long-running `serve` or
+//! `--watch` shows up next to the other services in an existing tracing stack:
+//!
+//! ```no_run
+//! use tracing_subscriber::prelude::*;
+//! use wallet_balance::telemetry::Telemetry;
+//!
+//! let telemetry = Telemetry::start("http://collector:4318", "wallet-balance").unwrap();
+//! tracing_subscriber::registry().with(telemetry.layer()).init();
+//! // ...
+//! telemetry.shutdown();
+//! ```
+//!
+//! Spans are sent in batches from a background thread; `shutdown` sends what
+//! is still queued.
+
+use anyhow::Context;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::WalletError;
+
+/// Path of the trace signal under a collector's OTLP/HTTP base URL
+const TRACES_PATH: &str = "v1/traces";
+
+/// The layer `Telemetry::layer` returns
+pub type TelemetryLayer<S> = OpenTelemetryLayer<S, SdkTracer>;
+
+/// A running span exporter
+#[derive(Debug, Clone)]
+pub struct Telemetry {
+    provider: SdkTracerProvider,
+}
+
+impl Telemetry {
+    /// Export spans to the collector at `endpoint`, reporting them as `service_name`
+    ///
+    /// `endpoint` is the collector's OTLP/HTTP base URL, such as
+    /// `http://localhost:4318`; `/v1/traces` is appended unless it is there.
+    pub fn start(endpoint: &str, service_name: &str) -> Result<Self, WalletError> {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(traces_url(endpoint)?)
+            .build()
+            .context("Failed to create the OTLP exporter")?;
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(Resource::builder().with_service_name(service_name.to_string()).build())
+            .build();
+        Ok(Telemetry { provider })
+    }
+
+    /// A `tracing` layer turning spans into OpenTelemetry spans for this exporter
+    pub fn layer<S>(&self) -> TelemetryLayer<S>
+    where
+        S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+    {
+        tracing_opentelemetry::layer().with_tracer(self.provider.tracer(env!("CARGO_PKG_NAME")))
+    }
+
+    /// Send the spans still queued and stop exporting
+    pub fn shutdown(&self) {
+        if let Err(e) = self.provider.shutdown() {
+            tracing::warn!("Failed to flush spans to the OTLP collector: {}", e);
+        }
+    }
+}
+
+/// The trace endpoint under the collector base URL `endpoint`
+fn traces_url(endpoint: &str) -> Result<String, WalletError> {
+    let url = reqwest::Url::parse(endpoint.trim()).with_context(|| format!("Invalid OTLP endpoint: {}", endpoint))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(WalletError::ParseError(format!(
+            "Invalid OTLP endpoint: {} (expected an http:// or https:// URL)",
+            endpoint
+        )));
+    }
+    let base = url.as_str().trim_end_matches('/');
+    Ok(match base.ends_with(TRACES_PATH) {
+        true => base.to_string(),
+        false => format!("{}/{}", base, TRACES_PATH),
+    })
+}
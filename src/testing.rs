@@ -0,0 +1,140 @@
+//! Integration-test helpers for anvil/hardhat nodes (`test-support` feature)
+//!
+//! Downstream projects can start (or attach to) an `anvil` node, optionally
+//! forking a live chain, set balances with `anvil_setBalance`, and then assert
+//! through the same code path the CLI uses with `--dev`. Snapshots let each
+//! test start from a known state:
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! use wallet_balance::{testing::AnvilNode, Network};
+//!
+//! let node = AnvilNode::spawn(None).await?;
+//! let snapshot = node.snapshot().await?;
+//! node.set_balance("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266", 5 * 10u128.pow(18)).await?;
+//! let balance = node.balance(Network::Ethereum, "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").await?;
+//! assert_eq!(balance.balance, "5");
+//! node.revert(&snapshot).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+use crate::{ethereum_wallet, evm_rpc, BalanceSource, Network, WalletBalance, WalletError};
+
+/// How long `spawn` waits for anvil to start answering RPC calls
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A running anvil node, killed on drop when spawned by this crate
+#[derive(Debug)]
+pub struct AnvilNode {
+    url: String,
+    child: Option<Child>,
+}
+
+impl AnvilNode {
+    /// Attach to an already running node at `url`
+    pub fn connect(url: impl Into<String>) -> Self {
+        AnvilNode {
+            url: url.into(),
+            child: None,
+        }
+    }
+
+    /// Start `anvil` from `PATH` on a free local port, forking `fork_url` if given
+    pub async fn spawn(fork_url: Option<&str>) -> Result<Self, WalletError> {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .context("Failed to find a free port")?
+            .port();
+
+        let mut command = Command::new("anvil");
+        command
+            .args(["--port", &port.to_string(), "--silent"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if let Some(fork_url) = fork_url {
+            command.args(["--fork-url", fork_url]);
+        }
+        let child = command.spawn().context("Failed to start anvil (is it on PATH?)")?;
+
+        let node = AnvilNode {
+            url: format!("http://127.0.0.1:{}", port),
+            child: Some(child),
+        };
+        node.wait_until_ready().await?;
+        Ok(node)
+    }
+
+    /// JSON-RPC URL of the node
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Balance source that queries this node, as `--dev --node-url` would
+    pub fn source(&self) -> BalanceSource {
+        BalanceSource::Dev {
+            node_url: Some(self.url.clone()),
+        }
+    }
+
+    /// Fetch a balance through the crate, exactly as the CLI reports it
+    pub async fn balance(&self, network: Network, address: &str) -> Result<WalletBalance, WalletError> {
+        self.source().get_balance(network, address).await
+    }
+
+    /// Overwrite the native balance of `address` (`anvil_setBalance`), in wei
+    pub async fn set_balance(&self, address: &str, wei: u128) -> Result<(), WalletError> {
+        let address = ethereum_wallet::normalize_address(address)?;
+        self.call("anvil_setBalance", vec![json!(address), json!(format!("0x{:x}", wei))])
+            .await?;
+        Ok(())
+    }
+
+    /// Record the current chain state (`evm_snapshot`) and return its id
+    pub async fn snapshot(&self) -> Result<String, WalletError> {
+        let id = self.call("evm_snapshot", vec![]).await?;
+        id.as_str()
+            .map(str::to_string)
+            .ok_or_else(|| WalletError::ParseError(format!("Unexpected snapshot id {}", id)))
+    }
+
+    /// Restore a state recorded by `snapshot` (`evm_revert`)
+    pub async fn revert(&self, snapshot: &str) -> Result<(), WalletError> {
+        let reverted = self.call("evm_revert", vec![json!(snapshot)]).await?;
+        if reverted != json!(true) {
+            return Err(WalletError::Other(format!("Snapshot {} could not be reverted", snapshot)));
+        }
+        Ok(())
+    }
+
+    async fn call(&self, method: &str, params: Vec<Value>) -> Result<Value> {
+        evm_rpc::call(&self.url, method, params).await
+    }
+
+    async fn wait_until_ready(&self) -> Result<()> {
+        let started = std::time::Instant::now();
+        loop {
+            match evm_rpc::block_number(&self.url).await {
+                Ok(_) => return Ok(()),
+                Err(e) if started.elapsed() > STARTUP_TIMEOUT => {
+                    return Err(e.context("anvil did not become ready in time"))
+                }
+                Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+            }
+        }
+    }
+}
+
+impl Drop for AnvilNode {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Built-in registry of common ERC-20 tokens
+//!
+//! Maps a symbol such as `USDC` to the token's canonical contract on each EVM
+//! network, so `--token USDC` can stand in for a pasted contract address.
+//! Only widely used tokens with one well-known deployment per network are
+//! listed; anything else still needs `--token-contract`.
+
+use anyhow::Result;
+
+use crate::Network;
+use crate::WalletError;
+
+/// A token contract known by symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownToken {
+    pub network: Network,
+    pub symbol: &'static str,
+    pub contract: &'static str,
+    pub decimals: u32,
+}
+
+const fn token(network: Network, symbol: &'static str, contract: &'static str, decimals: u32) -> KnownToken {
+    KnownToken {
+        network,
+        symbol,
+        contract,
+        decimals,
+    }
+}
+
+/// Every known token, grouped by network
+pub const REGISTRY: &[KnownToken] = &[
+    token(Network::Ethereum, "USDC", "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48", 6),
+    token(Network::Ethereum, "USDT", "0xdAC17F958D2ee523a2206206994597C13D831ec7", 6),
+    token(Network::Ethereum, "DAI", "0x6B175474E89094C44Da98b954EedeAC495271d0F", 18),
+    token(Network::Ethereum, "WETH", "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2", 18),
+    token(Network::Ethereum, "WBTC", "0x2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599", 8),
+    token(Network::Base, "USDC", "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913", 6),
+    token(Network::Base, "USDT", "0xfde4C96c8593536E31F229EA8f37b2ADa2699bb2", 6),
+    token(Network::Base, "DAI", "0x50c5725949A6F0c72E6C4a641F24049A917DB0Cb", 18),
+    token(Network::Base, "WETH", "0x4200000000000000000000000000000000000006", 18),
+    token(Network::Arbitrum, "USDC", "0xaf88d065e77c8cC2239327C5EDb3A432268e5831", 6),
+    token(Network::Arbitrum, "USDT", "0xFd086bC7CD5C481DCC9C85ebE478A1C0b69FCbb9", 6),
+    token(Network::Arbitrum, "DAI", "0xDA10009cBd5D07dd0CeCc66161FC93D7c9000da1", 18),
+    token(Network::Arbitrum, "WETH", "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1", 18),
+    token(Network::Arbitrum, "WBTC", "0x2f2a2543B76A4166549F7aaB2e75Bef0aefC5B0f", 8),
+    token(Network::Optimism, "USDC", "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85", 6),
+    token(Network::Optimism, "USDT", "0x94b008aA00579c1307B0EF2c499aD98a8ce58e58", 6),
+    token(Network::Optimism, "DAI", "0xDA10009cBd5D07dd0CeCc66161FC93D7c9000da1", 18),
+    token(Network::Optimism, "WETH", "0x4200000000000000000000000000000000000006", 18),
+    token(Network::Optimism, "OP", "0x4200000000000000000000000000000000000042", 18),
+    token(Network::Polygon, "USDC", "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359", 6),
+    token(Network::Polygon, "USDT", "0xc2132D05D31c914a87C6611C10748AEb04B58e8F", 6),
+    token(Network::Polygon, "DAI", "0x8f3Cf7ad23Cd3CaDbD9735AFf958023239c6A063", 18),
+    token(Network::Polygon, "WETH", "0x7ceB23fD6bC0adD59E62ac25578270cFf1b9f619", 18),
+    token(Network::Polygon, "WBTC", "0x1BFD67037B42Cf73acF2047067bd4F2C47D9BfD6", 8),
+];
+
+/// Known tokens on `network`
+pub fn on_network(network: Network) -> impl Iterator<Item = &'static KnownToken> {
+    REGISTRY.iter().filter(move |token| token.network == network)
+}
+
+/// Look up `symbol` (case-insensitive) on `network`
+pub fn lookup(network: Network, symbol: &str) -> Result<&'static KnownToken, WalletError> {
+    let symbol = symbol.trim();
+    if let Some(token) = on_network(network).find(|token| token.symbol.eq_ignore_ascii_case(symbol)) {
+        return Ok(token);
+    }
+
+    let known: Vec<&str> = on_network(network).map(|token| token.symbol).collect();
+    if known.is_empty() {
+        return Err(WalletError::UnsupportedNetwork(format!("No tokens are registered on {}", network)));
+    }
+    Err(WalletError::Other(format!(
+        "Unknown token {} on {} (known: {}); use --token-contract for other tokens",
+        symbol,
+        network,
+        known.join(", ")
+    )))
+}
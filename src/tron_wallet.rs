@@ -1,12 +1,26 @@
 use anyhow::{Context, Result};
-use reqwest::header::{HeaderMap, HeaderValue};
+use async_trait::async_trait;
 use serde::Deserialize;
-use base58::{FromBase58, ToBase58}; // For Base58Check
+use base58::FromBase58; // For Base58Check
 use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tracing::Instrument;
 
-use crate::WalletBalance;
+use crate::http::HttpResponse;
+use crate::provider::BalanceProvider;
+use crate::{config, http, Network, WalletBalance, WalletError};
 
-const TRON_API_URL: &str = "https://api.trongrid.io"; // Switch to "https://api.shasta.trongrid.io" for testnet (no key needed)
+pub(crate) const TRON_API_URL: &str = "https://api.trongrid.io";
+/// TronGrid for the Shasta testnet (no key needed)
+const SHASTA_API_URL: &str = "https://api.shasta.trongrid.io";
+
+/// TronGrid base URL serving `network` (`Tron` or `TronShasta`)
+pub(crate) fn api_url(network: Network) -> &'static str {
+    match network {
+        Network::TronShasta => SHASTA_API_URL,
+        _ => TRON_API_URL,
+    }
+}
 
 #[derive(Debug, Deserialize)]
 struct AccountResponse {
@@ -19,67 +33,172 @@ struct AccountData {
     balance: Option<u64>,
 }
 
-pub async fn get_balance(address: &str) -> Result<WalletBalance> {
+#[derive(Debug, Deserialize)]
+struct NowBlockResponse {
+    block_header: BlockHeader,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockHeader {
+    raw_data: BlockRawData,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockRawData {
+    number: u64,
+}
+
+/// TronGrid's request budget per second with an API key; keyless clients get far less
+const KEYED_REQUESTS_PER_SECOND: u64 = 15;
+const KEYLESS_REQUESTS_PER_SECOND: u64 = 3;
+
+pub async fn get_balance(address: &str) -> Result<WalletBalance, WalletError> {
+    get_balance_on(Network::Tron, address).await
+}
+
+/// Get the balance of `address` on Tron mainnet or the Shasta testnet
+#[tracing::instrument(level = "debug", name = "tron_balance", skip_all, fields(%network, address = %address))]
+pub async fn get_balance_on(network: Network, address: &str) -> Result<WalletBalance, WalletError> {
     let address = address.trim();
     validate_address(address)?;
 
-    let url = format!("{}/v1/accounts/{}", TRON_API_URL, address);
+    let balance_sun = get_balance_sun_on(network, address).await?;
+    Ok(to_wallet_balance(network, address, balance_sun))
+}
 
-    let client = reqwest::Client::new();
-    let request = client.get(&url);
+/// Balances of several Tron accounts, in input order
+///
+/// TronGrid has no multi-account endpoint, so every account is still one
+/// `/v1/accounts` request (a single page). The batch starts the requests at
+/// the pace TronGrid's rate limit allows, faster with an API key; an account
+/// that still hits a 429 is retried under the shared `http::RetryPolicy`.
+/// Invalid addresses fail without a request.
+pub async fn get_balances(addresses: &[String]) -> Vec<Result<WalletBalance, WalletError>> {
+    let mut results: Vec<Option<Result<WalletBalance, WalletError>>> = addresses.iter().map(|_| None).collect();
+    let (sender, mut received) = tokio::sync::mpsc::unbounded_channel();
+    stream_balances(addresses, move |index, result| {
+        let _ = sender.send((index, result));
+    })
+    .await;
+    while let Some((index, result)) = received.recv().await {
+        results[index] = Some(result);
+    }
+    results
+        .into_iter()
+        .map(|result| result.unwrap_or_else(|| Err(WalletError::Other("Balance lookup task failed".to_string()))))
+        .collect()
+}
+
+/// `get_balances`, handing each result to `report` with its index as soon as it is in
+#[tracing::instrument(
+    level = "info",
+    name = "tron_batch",
+    skip_all,
+    fields(network = "tron", addresses = addresses.len())
+)]
+pub(crate) async fn stream_balances(
+    addresses: &[String],
+    report: impl Fn(usize, Result<WalletBalance, WalletError>) + Clone + Send + Sync + 'static,
+) {
+    let interval = request_interval(config::active().trongrid_api_key().is_some());
+
+    let mut tasks = tokio::task::JoinSet::new();
+    let mut pacing = tokio::time::interval(interval);
+    for (index, address) in addresses.iter().enumerate() {
+        let address = address.trim().to_string();
+        if let Err(e) = validate_address(&address) {
+            report(index, Err(e));
+            continue;
+        }
+        pacing.tick().await;
+        let report = report.clone();
+        tasks.spawn(async move {
+            let result = get_balance_sun(&address)
+                .await
+                .map(|sun| to_wallet_balance(Network::Tron, &address, sun));
+            report(index, result.map_err(WalletError::from));
+        }
+        .in_current_span());
+    }
+    while tasks.join_next().await.is_some() {}
+}
+
+/// Gap between the requests of a batch that keeps it inside TronGrid's rate limit
+pub fn request_interval(has_api_key: bool) -> Duration {
+    let per_second = match has_api_key {
+        true => KEYED_REQUESTS_PER_SECOND,
+        false => KEYLESS_REQUESTS_PER_SECOND,
+    };
+    Duration::from_millis(1_000 / per_second)
+}
+
+fn to_wallet_balance(network: Network, address: &str, balance_sun: u64) -> WalletBalance {
+    let balance_trx = (balance_sun as f64) / 1_000_000.0;
+
+    WalletBalance::new(
+        address.to_string(),
+        format!("{:.6}", balance_trx),
+        network.to_string(),
+        network.denomination().to_string(),
+    )
+}
 
-    let response = request.send().await?;
-    
-    // Log the full response for debugging
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        eprintln!("API Error - Status: {}, Body: {}", status, body); // Or use tracing/log crate
+/// Balance in sun from TronGrid; accounts that were never activated hold zero
+pub(crate) async fn get_balance_sun(address: &str) -> Result<u64> {
+    get_balance_sun_on(Network::Tron, address).await
+}
+
+async fn get_balance_sun_on(network: Network, address: &str) -> Result<u64> {
+    let response = request_account(network, address).await?;
+    balance_from_response(response)
+}
+
+async fn request_account(network: Network, address: &str) -> Result<HttpResponse> {
+    let url = format!("{}/v1/accounts/{}", api_url(network), address);
+
+    let client = http::client();
+    let request = with_api_key(client.get(&url));
+
+    http::send(request).await
+}
+
+fn balance_from_response(response: HttpResponse) -> Result<u64> {
+    if !response.status.is_success() {
+        let status = response.status;
+        let body = response.text();
+        tracing::debug!(%status, %body, "TronGrid error response");
         return Err(anyhow::anyhow!(
             "TronGrid API failed: {} - {}",
             status, body
         ));
     }
 
-    let data: AccountResponse = response.json().await.context("Failed to parse JSON")?;
+    let data: AccountResponse = response.json().context("Failed to parse JSON")?;
 
     if !data.success || data.data.is_empty() {
-        let balance_sun = 0u64;
-        let balance_trx = 0.0;
         // Return zero balance for non-existent accounts (common for new/unfunded wallets)
-        Ok(WalletBalance::new(
-            address.to_string(),
-            format!("{:.6}", balance_trx),
-            "tron".to_string(),
-            "TRX".to_string(),
-        ))
+        Ok(0)
     } else {
-        let balance_sun = data.data[0].balance.unwrap_or(0);
-        let balance_trx = (balance_sun as f64) / 1_000_000.0;
-
-        Ok(WalletBalance::new(
-            address.to_string(),
-            format!("{:.6}", balance_trx),
-            "tron".to_string(),
-            "TRX".to_string(),
-        ))
+        Ok(data.data[0].balance.unwrap_or(0))
     }
 }
 
-fn validate_address(address: &str) -> Result<()> {
+pub(crate) fn validate_address(address: &str) -> Result<(), WalletError> {
     if address.len() != 34 || !address.starts_with('T') {
-        return Err(anyhow::anyhow!("Invalid Tron address: must be 34 chars starting with 'T'"));
+        return Err(WalletError::InvalidAddress(
+            "Invalid Tron address: must be 34 chars starting with 'T'".to_string(),
+        ));
     }
 
     // Full Base58Check validation
     // let decoded = address.from_base58().context("Invalid Base58 encoding")?;
     let decoded = address.from_base58()
-    .map_err(|_| anyhow::anyhow!("Invalid Base58 encoding"))?;
+    .map_err(|_| WalletError::InvalidAddress("Invalid Base58 encoding".to_string()))?;
     if decoded.len() != 25 {
-        return Err(anyhow::anyhow!("Invalid decoded length"));
+        return Err(WalletError::InvalidAddress("Invalid decoded length".to_string()));
     }
     if decoded[0] != 0x41 {
-        return Err(anyhow::anyhow!("Invalid Tron version byte"));
+        return Err(WalletError::InvalidAddress("Invalid Tron version byte".to_string()));
     }
 
     let payload = &decoded[0..21];
@@ -90,12 +209,54 @@ fn validate_address(address: &str) -> Result<()> {
     hasher.update(payload);
     let hash1 = hasher.finalize();
     let mut hasher = Sha256::new();
-    hasher.update(&hash1);
+    hasher.update(hash1);
     let expected_checksum = &hasher.finalize()[..4];
 
     if provided_checksum != expected_checksum {
-        return Err(anyhow::anyhow!("Invalid address checksum"));
+        return Err(WalletError::InvalidAddress("Invalid address checksum".to_string()));
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Attach the configured TronGrid API key, if any
+fn with_api_key(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match config::active().trongrid_api_key() {
+        Some(key) => request.header("TRON-PRO-API-KEY", key),
+        None => request,
+    }
+}
+
+/// Height of the latest block on `network`, used as a cheap reachability probe
+pub(crate) async fn get_tip_height(network: Network) -> Result<u64> {
+    let url = format!("{}/wallet/getnowblock", api_url(network));
+
+    let client = http::client();
+    let response = http::send(with_api_key(client.post(&url))).await?;
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("TronGrid API failed: {}", response.status));
+    }
+
+    let block: NowBlockResponse = response.json().context("Failed to parse JSON")?;
+    Ok(block.block_header.raw_data.number)
+}
+
+/// `BalanceProvider` for Tron mainnet (`Network::Tron`) or the Shasta testnet
+pub struct TronProvider(pub Network);
+
+#[async_trait]
+impl BalanceProvider for TronProvider {
+    fn network(&self) -> Network {
+        self.0
+    }
+
+    fn normalize_address(&self, address: &str) -> Result<String, WalletError> {
+        let address = address.trim();
+        validate_address(address)?;
+        Ok(address.to_string())
+    }
+
+    async fn get_balance(&self, address: &str) -> Result<WalletBalance, WalletError> {
+        get_balance_on(self.0, address).await
+    }
+}
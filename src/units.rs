@@ -0,0 +1,99 @@
+//! Unit conversion helpers
+//!
+//! Balances are carried around as decimal strings. These helpers convert
+//! between those strings and integer base units (satoshi, wei, sun) so that
+//! amounts can be compared without floating point rounding. EVM balances are
+//! 256-bit and are read as `U256`, so no wallet module parses wei by hand.
+//! The conversions themselves live in `core::units`, which builds without std,
+//! and are re-exported here; display rounding (`Precision`) is CLI-only.
+
+use anyhow::Result;
+
+use crate::WalletError;
+
+pub use crate::core::units::{format_delta, format_units, format_units_u256, parse_scientific, parse_units, parse_units_u256, percent_of, U256};
+
+/// Rounding profile for displayed balances (`--precision`)
+///
+/// Profiles other than `Full` truncate rather than round, the way exchanges
+/// show available balances, so a rounded figure never exceeds what is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    /// Every digit the provider reported
+    #[default]
+    Full,
+    /// Exchange statement rules: 8 decimal places, 6 for stablecoins and TRX
+    Exchange,
+    /// 4 decimal places for quick reading
+    Display,
+}
+
+/// Assets that exchanges quote with 6 decimal places
+const SIX_DECIMAL_ASSETS: [&str; 6] = ["USDT", "USDC", "DAI", "TUSD", "BUSD", "TRX"];
+
+impl Precision {
+    /// Decimal places this profile shows for `denomination`, or `None` for all of them
+    pub fn decimal_places(&self, denomination: &str) -> Option<u32> {
+        match self {
+            Precision::Full => None,
+            Precision::Exchange if SIX_DECIMAL_ASSETS.contains(&denomination.to_uppercase().as_str()) => Some(6),
+            Precision::Exchange => Some(8),
+            Precision::Display => Some(4),
+        }
+    }
+
+    /// Re-format `balance` (a decimal string in an asset with `decimals` places) for this profile
+    pub fn apply(&self, balance: &str, decimals: u32, denomination: &str) -> Result<String, WalletError> {
+        match self.decimal_places(denomination) {
+            Some(places) => format_fixed(balance, decimals, places),
+            None => Ok(balance.to_string()),
+        }
+    }
+}
+
+impl std::str::FromStr for Precision {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, WalletError> {
+        match s.to_lowercase().as_str() {
+            "full" => Ok(Precision::Full),
+            "exchange" => Ok(Precision::Exchange),
+            "display" => Ok(Precision::Display),
+            _ => Err(WalletError::ParseError(format!(
+                "Unsupported precision profile: {} (expected full, exchange or display)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Format a decimal balance with exactly `places` fractional digits (truncating extra digits)
+///
+/// Works on the digits rather than base units, so tokens reporting more
+/// decimals than a `u128` can scale (up to 77) and balances above 2^128 format too.
+fn format_fixed(balance: &str, decimals: u32, places: u32) -> Result<String, WalletError> {
+    let balance = balance.trim();
+    let (whole, fraction) = balance.split_once('.').unwrap_or((balance, ""));
+    if (whole.is_empty() && fraction.is_empty())
+        || !whole.chars().all(|c| c.is_ascii_digit())
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(WalletError::ParseError(format!("Invalid amount '{}'", balance)));
+    }
+    if fraction.trim_end_matches('0').len() > decimals as usize {
+        return Err(WalletError::ParseError(format!(
+            "Amount '{}' has more than {} decimal places",
+            balance, decimals
+        )));
+    }
+
+    let whole = match whole.trim_start_matches('0') {
+        "" => "0",
+        digits => digits,
+    };
+    if places == 0 {
+        return Ok(whole.to_string());
+    }
+    let places = places as usize;
+    Ok(format!("{}.{:0<places$.places$}", whole, fraction))
+}
@@ -0,0 +1,86 @@
+//! Unstoppable Domains names (`brad.crypto`, `alice.nft`, ...) resolved to addresses
+//!
+//! Names are looked up with the Resolution API, which needs an API key
+//! (`unstoppable.api_key` or `WALLET_BALANCE_UD_KEY`). A domain holds one
+//! record per coin; EVM networks without a record of their own fall back to
+//! the domain's `crypto.ETH.address`, which controls the same account there.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::{config, http, Network, WalletError};
+
+/// Default Resolution API base URL
+pub const RESOLUTION_API: &str = "https://api.unstoppabledomains.com/resolve";
+
+/// Top-level domains served by Unstoppable Domains
+pub const TLDS: [&str; 11] = [
+    "crypto", "nft", "x", "wallet", "bitcoin", "dao", "888", "blockchain", "zil", "polygon", "unstoppable",
+];
+
+#[derive(Debug, Deserialize)]
+struct DomainResponse {
+    #[serde(default)]
+    records: HashMap<String, String>,
+}
+
+/// Whether `input` is an Unstoppable Domains name rather than an address
+pub fn is_domain(input: &str) -> bool {
+    let input = input.to_lowercase();
+    let Some((name, tld)) = input.rsplit_once('.') else {
+        return false;
+    };
+    TLDS.contains(&tld)
+        && name
+            .split('.')
+            .all(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+}
+
+/// Records holding the address of `network`, most specific first
+pub fn record_keys(network: Network) -> &'static [&'static str] {
+    match network {
+        Network::Bitcoin => &["crypto.BTC.address"],
+        Network::Ethereum => &["crypto.ETH.address"],
+        Network::Polygon => &["crypto.MATIC.version.MATIC.address", "crypto.ETH.address"],
+        Network::Bsc => &["crypto.BNB.version.BEP20.address", "crypto.ETH.address"],
+        Network::Arbitrum | Network::Optimism | Network::Base | Network::Avalanche | Network::Fantom | Network::Gnosis => {
+            &["crypto.ETH.address"]
+        }
+        Network::Tron => &["crypto.TRX.address"],
+        Network::Dogecoin => &["crypto.DOGE.address"],
+        Network::Litecoin => &["crypto.LTC.address"],
+        _ => &[],
+    }
+}
+
+/// Resolve `domain` to its address on `network`
+pub async fn resolve(domain: &str, network: Network) -> Result<String, WalletError> {
+    let keys = record_keys(network);
+    if keys.is_empty() {
+        return Err(WalletError::UnsupportedNetwork(format!("Unstoppable Domains names do not resolve on {}", network)));
+    }
+    let settings = config::active();
+    let api_key = settings.unstoppable_api_key().ok_or_else(|| {
+        anyhow::anyhow!("Resolving {} needs a Resolution API key (unstoppable.api_key or WALLET_BALANCE_UD_KEY)", domain)
+    })?;
+
+    let domain = domain.to_lowercase();
+    let url = format!("{}/domains/{}", settings.unstoppable_api().trim_end_matches('/'), domain);
+    let response = http::send(http::client().get(&url).bearer_auth(api_key))
+        .await
+        .context("Failed to send request to the Unstoppable Domains API")?;
+    if !response.status.is_success() {
+        return Err(WalletError::Other(format!(
+            "Unstoppable Domains API failed: {} - {}",
+            response.status,
+            response.text()
+        )));
+    }
+    let data: DomainResponse = response.json().context("Failed to parse JSON from Unstoppable Domains")?;
+
+    keys.iter()
+        .find_map(|key| data.records.get(*key).map(|address| address.trim()).filter(|address| !address.is_empty()))
+        .map(str::to_string)
+        .ok_or_else(|| WalletError::Other(format!("{} has no {} address record", domain, network)))
+}
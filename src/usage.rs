@@ -0,0 +1,121 @@
+//! Local usage statistics
+//!
+//! Counters of which commands were run, which networks were queried and how
+//! many requests each provider served (and failed), kept in a JSON file in
+//! the data directory. Nothing is ever sent anywhere: the file exists so users
+//! can see their own query patterns and how close they run to provider rate
+//! limits with `wallet-balance stats`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::ConfigPaths;
+use crate::http::ProviderStats;
+use crate::Network;
+use crate::WalletError;
+
+/// File name of the statistics file inside the data directory
+pub const USAGE_FILE_NAME: &str = "usage.json";
+
+/// Request totals for one provider host
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderUsage {
+    pub requests: u64,
+    pub errors: u64,
+}
+
+/// Everything recorded since the file was created (or last reset)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageStats {
+    /// Unix time of the first recorded session
+    #[serde(default)]
+    pub since: u64,
+    #[serde(default)]
+    pub commands: BTreeMap<String, u64>,
+    #[serde(default)]
+    pub networks: BTreeMap<String, u64>,
+    #[serde(default)]
+    pub providers: BTreeMap<String, ProviderUsage>,
+}
+
+impl UsageStats {
+    /// Read the statistics at `path`; a missing file means nothing was recorded yet
+    pub fn load(path: &Path) -> Result<Self, WalletError> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => {
+                Ok(serde_json::from_str(&text).with_context(|| format!("{} is not a usage file", path.display()))?)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(UsageStats::default()),
+            Err(e) => Err(WalletError::Other(format!("Failed to read {}: {}", path.display(), e))),
+        }
+    }
+
+    /// Write the statistics to `path`, replacing the previous file atomically
+    pub fn save(&self, path: &Path) -> Result<(), WalletError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let staging = path.with_extension("json.tmp");
+        std::fs::write(&staging, serde_json::to_string_pretty(self).expect("usage stats serialize"))
+            .with_context(|| format!("Failed to write {}", staging.display()))?;
+        Ok(std::fs::rename(&staging, path).with_context(|| format!("Failed to write {}", path.display()))?)
+    }
+
+    /// Add one session: the command run, the networks it queried and its provider accounting
+    pub fn record_session(&mut self, command: &str, networks: &[Network], providers: &BTreeMap<String, ProviderStats>) {
+        if self.since == 0 {
+            self.since = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default();
+        }
+        *self.commands.entry(command.to_string()).or_default() += 1;
+        for network in networks {
+            *self.networks.entry(network.to_string()).or_default() += 1;
+        }
+        for (provider, stats) in providers {
+            let usage = self.providers.entry(provider.clone()).or_default();
+            usage.requests += stats.requests;
+            usage.errors += stats.errors;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+/// Location of the statistics file for the current user
+pub fn default_path() -> Result<PathBuf, WalletError> {
+    Ok(ConfigPaths::discover()?.data_dir.join(USAGE_FILE_NAME))
+}
+
+/// Load, update and save the file at `path` in one step
+pub fn record(
+    path: &Path,
+    command: &str,
+    networks: &[Network],
+    providers: &BTreeMap<String, ProviderStats>,
+) -> Result<(), WalletError> {
+    let mut stats = UsageStats::load(path)?;
+    stats.record_session(command, networks, providers);
+    stats.save(path)
+}
+
+/// Format a Unix timestamp as a UTC `YYYY-MM-DD` date
+pub fn format_date(unix_secs: u64) -> String {
+    // Days-to-civil conversion from Howard Hinnant's date algorithms
+    let days = (unix_secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
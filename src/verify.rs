@@ -0,0 +1,188 @@
+//! Cross-provider balance verification
+//!
+//! `--verify` re-reads a balance from the primary provider and from an
+//! independent one, pinned to the same block where the APIs allow it, so a
+//! stale, lagging or misbehaving endpoint shows up as a divergence instead of
+//! a silently wrong number.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::units::U256;
+use crate::{bitcoin_wallet, ethereum_wallet, evm_rpc, http, tron_wallet, Network, WalletError};
+
+/// Independent Esplora instance for Bitcoin
+const BITCOIN_SECONDARY_API: &str = "https://mempool.space/api";
+
+/// Independent RPC endpoints, operated separately from the primary ones
+const ETHEREUM_SECONDARY_RPC: &str = "https://ethereum-rpc.publicnode.com";
+const BASE_SECONDARY_RPC: &str = "https://base-rpc.publicnode.com";
+const ARBITRUM_SECONDARY_RPC: &str = "https://arbitrum-one-rpc.publicnode.com";
+const OPTIMISM_SECONDARY_RPC: &str = "https://optimism-rpc.publicnode.com";
+const POLYGON_SECONDARY_RPC: &str = "https://polygon-bor-rpc.publicnode.com";
+const BSC_SECONDARY_RPC: &str = "https://bsc-rpc.publicnode.com";
+const AVALANCHE_SECONDARY_RPC: &str = "https://avalanche-c-chain-rpc.publicnode.com";
+const FANTOM_SECONDARY_RPC: &str = "https://fantom-rpc.publicnode.com";
+const GNOSIS_SECONDARY_RPC: &str = "https://gnosis-rpc.publicnode.com";
+
+/// Tronscan account API (balance in sun)
+const TRONSCAN_API: &str = "https://apilist.tronscanapi.com/api";
+
+/// Result of reading one balance from two providers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    /// Host of the primary provider
+    pub primary_source: String,
+    /// Host of the independent provider
+    pub secondary_source: String,
+    /// Block both reads refer to, when the providers allow pinning one
+    pub block: Option<u64>,
+    /// Balance from the primary provider, in base units
+    pub primary: U256,
+    /// Balance from the independent provider, in base units
+    pub secondary: U256,
+}
+
+impl Verification {
+    /// Whether both providers reported the same balance
+    pub fn agrees(&self) -> bool {
+        self.primary == self.secondary
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TronscanAccount {
+    #[serde(default)]
+    balance: u64,
+}
+
+/// Whether `verify_balance` has an independent provider for `network`
+pub fn is_supported(network: Network) -> bool {
+    match network {
+        Network::Bitcoin | Network::Tron => true,
+        Network::CustomEvm => false,
+        _ => network.is_evm() && !network.is_testnet(),
+    }
+}
+
+/// Read the balance of `address` from the primary and an independent provider
+///
+/// # Arguments
+///
+/// * `network` - Network to verify on
+/// * `address` - Address to check, already normalized for the network
+///
+/// # Returns
+///
+/// Returns a `Verification` holding both readings in base units
+pub async fn verify_balance(network: Network, address: &str) -> Result<Verification, WalletError> {
+    match network {
+        _ if !is_supported(network) => Err(WalletError::UnsupportedNetwork(format!(
+            "Verification is not supported on {}: no independent provider is configured",
+            network
+        ))),
+        Network::Bitcoin => Ok(verify_bitcoin(address).await?),
+        Network::Tron => Ok(verify_tron(address).await?),
+        _ => {
+            let primary_rpc = evm_rpc::rpc_url(network).expect("EVM networks have an RPC URL");
+            Ok(verify_evm(&primary_rpc, secondary_rpc(network), address).await?)
+        }
+    }
+}
+
+fn secondary_rpc(network: Network) -> &'static str {
+    match network {
+        Network::Ethereum => ETHEREUM_SECONDARY_RPC,
+        Network::Base => BASE_SECONDARY_RPC,
+        Network::Arbitrum => ARBITRUM_SECONDARY_RPC,
+        Network::Optimism => OPTIMISM_SECONDARY_RPC,
+        Network::Polygon => POLYGON_SECONDARY_RPC,
+        Network::Bsc => BSC_SECONDARY_RPC,
+        Network::Avalanche => AVALANCHE_SECONDARY_RPC,
+        Network::Fantom => FANTOM_SECONDARY_RPC,
+        Network::Gnosis => GNOSIS_SECONDARY_RPC,
+        _ => unreachable!("not an EVM network"),
+    }
+}
+
+/// Both reads at the lower of the two tips, so a lagging node cannot diverge by timing
+async fn verify_evm(primary_rpc: &str, secondary_rpc: &str, address: &str) -> Result<Verification> {
+    let address = ethereum_wallet::normalize_address(address)?;
+    ethereum_wallet::validate_address(&address)?;
+
+    let (primary_tip, secondary_tip) = tokio::try_join!(
+        evm_rpc::block_number(primary_rpc),
+        evm_rpc::block_number(secondary_rpc),
+    )?;
+    let block = primary_tip.min(secondary_tip);
+
+    let (primary, secondary) = tokio::try_join!(
+        evm_rpc::get_balance_at(primary_rpc, &address, Some(block)),
+        evm_rpc::get_balance_at(secondary_rpc, &address, Some(block)),
+    )
+    .with_context(|| format!("Failed to read balance at block {}", block))?;
+
+    Ok(Verification {
+        primary_source: host(primary_rpc),
+        secondary_source: host(secondary_rpc),
+        block: Some(block),
+        primary,
+        secondary,
+    })
+}
+
+/// Esplora has no historical balance query; reads count as pinned only when both tips match
+async fn verify_bitcoin(address: &str) -> Result<Verification> {
+    bitcoin_wallet::validate_address(address)?;
+    let primary_api = bitcoin_wallet::Chain::Mainnet.api_base();
+    let (primary_tip, secondary_tip, primary, secondary) = tokio::try_join!(
+        bitcoin_wallet::get_tip_height_from(&primary_api),
+        bitcoin_wallet::get_tip_height_from(BITCOIN_SECONDARY_API),
+        bitcoin_wallet::get_balance_sats_from(&primary_api, address),
+        bitcoin_wallet::get_balance_sats_from(BITCOIN_SECONDARY_API, address),
+    )?;
+
+    Ok(Verification {
+        primary_source: host(&primary_api),
+        secondary_source: host(BITCOIN_SECONDARY_API),
+        block: (primary_tip == secondary_tip).then_some(primary_tip),
+        primary: primary.into(),
+        secondary: secondary.into(),
+    })
+}
+
+/// Neither TronGrid nor Tronscan can read at a given block
+async fn verify_tron(address: &str) -> Result<Verification> {
+    tron_wallet::validate_address(address)?;
+    let (primary, secondary) = tokio::try_join!(tron_wallet::get_balance_sun(address), tronscan_balance(address))?;
+
+    Ok(Verification {
+        primary_source: host(tron_wallet::TRON_API_URL),
+        secondary_source: host(TRONSCAN_API),
+        block: None,
+        primary: primary.into(),
+        secondary: secondary.into(),
+    })
+}
+
+async fn tronscan_balance(address: &str) -> Result<u64> {
+    let url = format!("{}/accountv2?address={}", TRONSCAN_API, address);
+
+    let client = http::client();
+    let response = http::send(client.get(&url))
+        .await
+        .context("Failed to send request to Tronscan API")?;
+    if !response.status.is_success() {
+        return Err(anyhow::anyhow!("Tronscan API failed: {}", response.status));
+    }
+
+    let account: TronscanAccount = response.json().context("Failed to parse JSON from Tronscan")?;
+    Ok(account.balance)
+}
+
+fn host(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.to_string())
+}
@@ -0,0 +1,149 @@
+//! Watch mode: poll one balance and report what changed
+//!
+//! `--watch` keeps the CLI running and re-fetches a balance every
+//! `--interval`. Consecutive lookups are compared in base units, so a provider
+//! that reports `1.5` one time and `1.500000` the next is not a change.
+//! A `Session` keeps the totals for the summary printed when watching ends.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::{units, WalletBalance, WalletError};
+
+/// Default gap between polls
+pub const DEFAULT_INTERVAL: &str = "30s";
+
+/// Parse a poll interval such as `30s`, `5m`, `1h` or a bare number of seconds
+pub fn parse_interval(s: &str) -> Result<Duration, WalletError> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("Invalid interval: {} (expected e.g. 30s, 5m or 1h)", s))?;
+    let seconds = match unit {
+        "" | "s" => number,
+        "m" => number * 60,
+        "h" => number * 3_600,
+        _ => return Err(WalletError::ParseError(format!("Invalid interval unit: {} (expected s, m or h)", unit))),
+    };
+    if seconds == 0 {
+        return Err(WalletError::ParseError("Interval must be at least 1s".to_string()));
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+/// A balance that differs from the previous poll
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceChange {
+    pub previous: String,
+    pub current: String,
+    /// Signed difference, e.g. `+0.5` or `-0.0001`
+    pub delta: String,
+}
+
+/// The change from `previous` to `current` (with `decimals` places), or `None` if the amount is the same
+pub fn balance_change(
+    previous: &WalletBalance,
+    current: &WalletBalance,
+    decimals: u32,
+) -> Result<Option<BalanceChange>, WalletError> {
+    let before = units::parse_units(&previous.balance, decimals)?;
+    let after = units::parse_units(&current.balance, decimals)?;
+    Ok(signed_delta(before, after, decimals).map(|delta| BalanceChange {
+        previous: previous.balance.clone(),
+        current: current.balance.clone(),
+        delta,
+    }))
+}
+
+/// `+x` or `-x` from `before` to `after` in whole units, or `None` if they are equal
+fn signed_delta(before: u128, after: u128, decimals: u32) -> Option<String> {
+    match after.cmp(&before) {
+        std::cmp::Ordering::Equal => None,
+        std::cmp::Ordering::Greater => Some(format!("+{}", units::format_units(after - before, decimals))),
+        std::cmp::Ordering::Less => Some(format!("-{}", units::format_units(before - after, decimals))),
+    }
+}
+
+/// Totals of a watch session, fed one successful poll at a time
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    decimals: u32,
+    denomination: String,
+    /// First and latest balance, in base units and as reported
+    start: Option<(u128, String)>,
+    end: Option<(u128, String)>,
+    polls: usize,
+    changes: usize,
+    /// Size in base units and signed amount of the biggest change so far
+    largest: Option<(u128, String)>,
+}
+
+/// What a watch session saw, printed when it ends
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionSummary {
+    pub start_balance: String,
+    pub end_balance: String,
+    pub denomination: String,
+    pub polls: usize,
+    pub changes: usize,
+    /// Signed amount of the biggest single change, if there was any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub largest_change: Option<String>,
+    /// End minus start, `0` when they are equal
+    pub net_delta: String,
+}
+
+impl Session {
+    /// An empty session for an asset with `decimals` places
+    pub fn new(decimals: u32) -> Self {
+        Session {
+            decimals,
+            ..Session::default()
+        }
+    }
+
+    /// Count a successful poll of `balance`
+    ///
+    /// Each poll is compared with the one before, so a change that a failed
+    /// poll hid still counts once.
+    pub fn record(&mut self, balance: &WalletBalance) -> Result<(), WalletError> {
+        let amount = units::parse_units(&balance.balance, self.decimals)?;
+        if let Some((previous, _)) = &self.end {
+            if let Some(delta) = signed_delta(*previous, amount, self.decimals) {
+                self.changes += 1;
+                let size = amount.abs_diff(*previous);
+                if self.largest.as_ref().is_none_or(|(largest, _)| size > *largest) {
+                    self.largest = Some((size, delta));
+                }
+            }
+        }
+        self.start.get_or_insert_with(|| (amount, balance.balance.clone()));
+        self.end = Some((amount, balance.balance.clone()));
+        self.denomination = balance.denomination.clone();
+        self.polls += 1;
+        Ok(())
+    }
+
+    /// The summary so far, or `None` before the first successful poll
+    pub fn summary(&self) -> Option<SessionSummary> {
+        let ((start, start_balance), (end, end_balance)) = (self.start.as_ref()?, self.end.as_ref()?);
+        Some(SessionSummary {
+            start_balance: start_balance.clone(),
+            end_balance: end_balance.clone(),
+            denomination: self.denomination.clone(),
+            polls: self.polls,
+            changes: self.changes,
+            largest_change: self.largest.as_ref().map(|(_, delta)| delta.clone()),
+            net_delta: signed_delta(*start, *end, self.decimals).unwrap_or_else(|| "0".to_string()),
+        })
+    }
+}
+
+/// UTC time of day of `unix_secs` as `HH:MM:SS`
+pub fn format_time(unix_secs: u64) -> String {
+    let seconds = unix_secs % 86_400;
+    format!("{:02}:{:02}:{:02}", seconds / 3_600, seconds % 3_600 / 60, seconds % 60)
+}